@@ -1,8 +1,14 @@
-use crate::{database::Database, translation::TranslationService};
+use crate::{
+    database::Database, jobs::JobManager, offline_dictionary::OfflineDictionaryStore,
+    secrets::SecretStore, translation::TranslationService,
+};
 use std::sync::Mutex;
 
 /// Shared application state registered with Tauri.
 pub struct AppState {
     pub db: Mutex<Database>,
     pub translation_service: Mutex<TranslationService>,
+    pub jobs: JobManager,
+    pub offline_dictionaries: OfflineDictionaryStore,
+    pub secrets: SecretStore,
 }
@@ -0,0 +1,482 @@
+// Database backups and recovery
+// Rotating VACUUM INTO snapshots of trans.db in the backups directory, integrity checks, and the
+// file operations used at startup to quarantine a corrupt database and restore a snapshot
+
+use crate::database::Database;
+use crate::secrets;
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use rusqlite::{params, Connection, OpenFlags, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 备份目录，位于数据库文件旁边
+const BACKUP_DIR: &str = "backups";
+/// 备份文件名中的时间格式（UTC，精确到毫秒，避免同一秒内重名）
+const BACKUP_TIME_FORMAT: &str = "%Y%m%dT%H%M%S%3fZ";
+/// 迁移前备份（见 migrations.rs）文件名中的时间格式
+const MIGRATION_BACKUP_TIME_FORMAT: &str = "%Y%m%d%H%M%S";
+/// 两次自动备份的最短间隔
+const AUTO_BACKUP_INTERVAL_HOURS: i64 = 24;
+/// 检查是否需要自动备份的频率
+const AUTO_BACKUP_CHECK_MINUTES: u64 = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupReason {
+    /// 每天自动备份
+    Auto,
+    /// 用户手动备份
+    Manual,
+    /// 从备份恢复前对当前数据库的备份
+    PreRestore,
+    /// 表结构迁移前的备份
+    PreMigration,
+}
+
+impl BackupReason {
+    fn as_str(&self) -> &'static str {
+        match self {
+            BackupReason::Auto => "auto",
+            BackupReason::Manual => "manual",
+            BackupReason::PreRestore => "pre-restore",
+            BackupReason::PreMigration => "pre-migration",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "auto" => Some(BackupReason::Auto),
+            "manual" => Some(BackupReason::Manual),
+            "pre-restore" => Some(BackupReason::PreRestore),
+            _ => None,
+        }
+    }
+
+    /// 每类备份保留的份数，手动备份和迁移前备份不自动删除
+    fn keep(&self) -> Option<usize> {
+        match self {
+            BackupReason::Auto => Some(7),
+            BackupReason::PreRestore => Some(3),
+            BackupReason::Manual | BackupReason::PreMigration => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupInfo {
+    pub file_name: String,
+    pub reason: BackupReason,
+    pub created_at: String,
+    pub size_bytes: u64,
+    #[serde(skip)]
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IntegrityReport {
+    pub ok: bool,
+    /// SQLite 报告的问题，完整性正常时为空
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DatabaseMode {
+    Normal,
+    /// 数据库损坏，已隔离并从备份恢复（或新建）
+    Recovered,
+    /// 无法打开数据库，使用内存数据库运行，本次会话的数据不会保存
+    Degraded,
+}
+
+/// 启动时数据库的状态，供前端提示用户
+#[derive(Debug, Clone, Serialize)]
+pub struct DatabaseStatus {
+    pub mode: DatabaseMode,
+    pub db_path: String,
+    pub message: Option<String>,
+    /// 恢复所用备份的文件名；没有可用备份而新建数据库时为空
+    pub restored_from: Option<String>,
+    /// 被隔离的损坏文件
+    pub quarantined: Vec<String>,
+    /// 其他需要提示的问题，如配置无法解析已被隔离
+    pub warnings: Vec<String>,
+}
+
+impl DatabaseStatus {
+    pub fn normal(db_path: &Path) -> Self {
+        DatabaseStatus {
+            mode: DatabaseMode::Normal,
+            db_path: db_path.display().to_string(),
+            message: None,
+            restored_from: None,
+            quarantined: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+}
+
+/// quick_check 只检查页面结构，启动时使用；integrity_check 还会核对索引内容
+pub fn check_integrity(conn: &Connection, full: bool) -> Result<IntegrityReport> {
+    let pragma = if full {
+        "integrity_check"
+    } else {
+        "quick_check"
+    };
+    let mut stmt = conn.prepare(&format!("PRAGMA {}", pragma))?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>>>()?;
+    let ok = rows.len() == 1 && rows[0] == "ok";
+    Ok(IntegrityReport {
+        ok,
+        errors: if ok { Vec::new() } else { rows },
+    })
+}
+
+/// 用 VACUUM INTO 生成一致的快照，并按类型删除多余的旧备份
+pub fn create(
+    conn: &Connection,
+    db_path: &Path,
+    reason: BackupReason,
+) -> Result<BackupInfo, String> {
+    let dir = backup_dir(db_path);
+    fs::create_dir_all(&dir).map_err(|e| format!("创建备份目录失败: {}", e))?;
+    let path = dir.join(format!(
+        "{}-{}-{}.db",
+        db_stem(db_path),
+        Utc::now().format(BACKUP_TIME_FORMAT),
+        reason.as_str()
+    ));
+    conn.execute("VACUUM INTO ?1", params![path.to_string_lossy().as_ref()])
+        .map_err(|e| format!("备份数据库失败: {}", e))?;
+
+    if let Some(keep) = reason.keep() {
+        let outdated = list(db_path)?
+            .into_iter()
+            .filter(|backup| backup.reason == reason)
+            .skip(keep);
+        for backup in outdated {
+            if let Err(e) = fs::remove_file(&backup.path) {
+                eprintln!("删除旧备份 {} 失败: {}", backup.file_name, e);
+            }
+        }
+    }
+
+    backup_info(&path, reason).ok_or_else(|| "读取备份文件信息失败".to_string())
+}
+
+/// 距离上次自动备份超过间隔时才备份
+pub fn is_auto_backup_due(db_path: &Path, now: DateTime<Utc>) -> Result<bool, String> {
+    let latest = list(db_path)?
+        .into_iter()
+        .find(|backup| backup.reason == BackupReason::Auto);
+    Ok(latest
+        .and_then(|backup| DateTime::parse_from_rfc3339(&backup.created_at).ok())
+        .is_none_or(|created_at| {
+            now - created_at.with_timezone(&Utc) >= Duration::hours(AUTO_BACKUP_INTERVAL_HOURS)
+        }))
+}
+
+/// 所有备份（包括迁移前备份），按时间从新到旧排列
+pub fn list(db_path: &Path) -> Result<Vec<BackupInfo>, String> {
+    let mut backups = Vec::new();
+    let stem = db_stem(db_path);
+
+    let dir = backup_dir(db_path);
+    if dir.exists() {
+        for entry in fs::read_dir(&dir).map_err(|e| format!("读取备份目录失败: {}", e))? {
+            let path = entry
+                .map_err(|e| format!("读取备份目录失败: {}", e))?
+                .path();
+            let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            let reason = name
+                .strip_prefix(&format!("{}-", stem))
+                .and_then(|rest| rest.strip_suffix(".db"))
+                .and_then(|rest| rest.split_once('-'))
+                .and_then(|(_, reason)| BackupReason::parse(reason));
+            if let Some(info) = reason.and_then(|reason| backup_info(&path, reason)) {
+                backups.push(info);
+            }
+        }
+    }
+
+    if let Some(parent) = db_path.parent() {
+        for entry in fs::read_dir(parent).map_err(|e| format!("读取数据目录失败: {}", e))? {
+            let path = entry
+                .map_err(|e| format!("读取数据目录失败: {}", e))?
+                .path();
+            let is_migration_backup =
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| {
+                        name.starts_with(&format!("{}.v", stem)) && name.ends_with(".bak.db")
+                    });
+            if is_migration_backup {
+                if let Some(info) = backup_info(&path, BackupReason::PreMigration) {
+                    backups.push(info);
+                }
+            }
+        }
+    }
+
+    backups.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    Ok(backups)
+}
+
+/// 按文件名查找备份，只接受 list 列出的文件，防止通过文件名访问其他路径
+pub fn find(db_path: &Path, file_name: &str) -> Result<BackupInfo, String> {
+    list(db_path)?
+        .into_iter()
+        .find(|backup| backup.file_name == file_name)
+        .ok_or_else(|| format!("备份不存在: {}", file_name))
+}
+
+/// 以只读方式打开备份并检查完整性
+pub fn verify(path: &Path) -> Result<(), String> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| format!("打开备份失败: {}", e))?;
+    let report = check_integrity(&conn, false).map_err(|e| format!("检查备份失败: {}", e))?;
+    if report.ok {
+        Ok(())
+    } else {
+        Err(format!("备份已损坏: {}", report.errors.join("; ")))
+    }
+}
+
+/// 清除备份中旧版本明文保存的 API 密钥（api_keys 表和配置中的密钥字段），有改动时
+/// VACUUM 重写文件，避免密钥残留在空闲页中；返回是否清除了密钥
+pub fn scrub_secrets(path: &Path) -> Result<bool, String> {
+    let mut conn = Connection::open(path).map_err(|e| format!("打开备份失败: {}", e))?;
+    let scrubbed =
+        scrub_plaintext_secrets(&mut conn).map_err(|e| format!("清除备份中的密钥失败: {}", e))?;
+    if scrubbed {
+        conn.pragma_update(None, "secure_delete", true)
+            .and_then(|_| conn.execute("VACUUM", []))
+            .map_err(|e| format!("整理备份失败: {}", e))?;
+    }
+    Ok(scrubbed)
+}
+
+fn scrub_plaintext_secrets(conn: &mut Connection) -> Result<bool> {
+    let tx = conn.transaction()?;
+    let has_table = |name: &str| -> Result<bool> {
+        tx.query_row(
+            "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1)",
+            params![name],
+            |row| row.get(0),
+        )
+    };
+
+    let mut scrubbed = false;
+    if has_table("api_keys")? {
+        scrubbed |= tx.execute("DELETE FROM api_keys", [])? > 0;
+    }
+    // 引入配置档案之前配置保存在 user_settings 中
+    for (table, select, update) in [
+        (
+            "user_settings",
+            "SELECT id, value FROM user_settings WHERE key = 'app_config'",
+            "UPDATE user_settings SET value = ?2 WHERE id = ?1",
+        ),
+        (
+            "config_profiles",
+            "SELECT id, config FROM config_profiles WHERE config IS NOT NULL",
+            "UPDATE config_profiles SET config = ?2 WHERE id = ?1",
+        ),
+    ] {
+        if !has_table(table)? {
+            continue;
+        }
+        let configs = tx
+            .prepare(select)?
+            .query_map([], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<Result<Vec<_>>>()?;
+        for (id, config) in configs {
+            if let Some(stripped) = secrets::strip_config_secrets(&config) {
+                tx.execute(update, params![id, stripped])?;
+                scrubbed = true;
+            }
+        }
+    }
+
+    tx.commit()?;
+    Ok(scrubbed)
+}
+
+/// 把损坏的数据库文件（连同 -wal、-shm）改名隔离，返回隔离后的文件路径
+pub fn quarantine(db_path: &Path) -> Result<Vec<PathBuf>, String> {
+    let suffix = format!("corrupt-{}", Utc::now().format(BACKUP_TIME_FORMAT));
+    let mut moved = Vec::new();
+    for path in database_files(db_path) {
+        if !path.exists() {
+            continue;
+        }
+        let target = PathBuf::from(format!("{}.{}", path.display(), suffix));
+        fs::rename(&path, &target).map_err(|e| format!("隔离损坏的数据库失败: {}", e))?;
+        moved.push(target);
+    }
+    Ok(moved)
+}
+
+/// 隔离无法解析的单个文件（如密钥存储文件），返回隔离后的路径
+pub fn quarantine_file(path: &Path) -> Result<PathBuf, String> {
+    let target = PathBuf::from(format!(
+        "{}.corrupt-{}",
+        path.display(),
+        Utc::now().format(BACKUP_TIME_FORMAT)
+    ));
+    fs::rename(path, &target).map_err(|e| format!("隔离损坏的文件失败: {}", e))?;
+    Ok(target)
+}
+
+/// 用备份替换数据库文件；调用前必须关闭该数据库的所有连接
+pub fn restore_file(backup_path: &Path, db_path: &Path) -> Result<(), String> {
+    let temp_path = db_path.with_extension("db.restore");
+    fs::copy(backup_path, &temp_path).map_err(|e| format!("复制备份失败: {}", e))?;
+    for path in database_files(db_path).iter().skip(1) {
+        if path.exists() {
+            fs::remove_file(path).map_err(|e| format!("删除旧的日志文件失败: {}", e))?;
+        }
+    }
+    fs::rename(&temp_path, db_path).map_err(|e| format!("替换数据库文件失败: {}", e))
+}
+
+/// 删除数据库文件及其 -wal、-shm，用于丢弃无法使用的恢复结果
+pub fn remove_database_files(db_path: &Path) -> Result<(), String> {
+    for path in database_files(db_path) {
+        if path.exists() {
+            fs::remove_file(&path).map_err(|e| format!("删除数据库文件失败: {}", e))?;
+        }
+    }
+    Ok(())
+}
+
+/// 启动后立即检查一次，之后每小时检查是否需要自动备份；Database 的克隆共享同一个连接
+pub fn start_auto_backup(db: Database) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match db.backup_if_due() {
+                Ok(Some(backup)) => println!("已自动备份数据库: {}", backup.file_name),
+                Ok(None) => {}
+                Err(err) => eprintln!("自动备份数据库失败: {}", err),
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(
+                AUTO_BACKUP_CHECK_MINUTES * 60,
+            ))
+            .await;
+        }
+    });
+}
+
+fn backup_dir(db_path: &Path) -> PathBuf {
+    db_path.with_file_name(BACKUP_DIR)
+}
+
+fn db_stem(db_path: &Path) -> &str {
+    db_path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("trans")
+}
+
+fn database_files(db_path: &Path) -> [PathBuf; 3] {
+    [
+        db_path.to_path_buf(),
+        PathBuf::from(format!("{}-wal", db_path.display())),
+        PathBuf::from(format!("{}-shm", db_path.display())),
+    ]
+}
+
+/// 备份时间取自文件名，解析失败时使用文件修改时间
+fn backup_info(path: &Path, reason: BackupReason) -> Option<BackupInfo> {
+    let file_name = path.file_name()?.to_str()?.to_string();
+    let metadata = fs::metadata(path).ok()?;
+    let from_name = match reason {
+        BackupReason::PreMigration => file_name
+            .strip_suffix(".bak.db")
+            .and_then(|rest| rest.rsplit_once('-'))
+            .and_then(|(_, time)| {
+                NaiveDateTime::parse_from_str(time, MIGRATION_BACKUP_TIME_FORMAT).ok()
+            }),
+        _ => file_name
+            .strip_suffix(&format!("-{}.db", reason.as_str()))
+            .and_then(|rest| rest.rsplit_once('-'))
+            .and_then(|(_, time)| NaiveDateTime::parse_from_str(time, BACKUP_TIME_FORMAT).ok()),
+    };
+    let created_at = from_name
+        .map(|time| time.and_utc())
+        .or_else(|| metadata.modified().ok().map(DateTime::<Utc>::from))?;
+
+    Some(BackupInfo {
+        file_name,
+        reason,
+        created_at: created_at.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        size_bytes: metadata.len(),
+        path: path.to_path_buf(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scrubs_plaintext_secrets_from_old_backups() {
+        let path = std::env::temp_dir().join(format!(
+            "ai-translate-backup-scrub-{}.db",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        {
+            let conn = Connection::open(&path).unwrap();
+            conn.execute_batch(
+                r#"CREATE TABLE user_settings (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    key TEXT UNIQUE NOT NULL,
+                    value TEXT NOT NULL
+                );
+                CREATE TABLE api_keys (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    service TEXT UNIQUE NOT NULL,
+                    api_key TEXT NOT NULL
+                );
+                INSERT INTO user_settings (key, value) VALUES
+                    ('app_config', '{"translation":{"api_key":"sk-plain","model_id":"m"},"ocr":{"api_key":""}}'),
+                    ('theme', 'dark');
+                INSERT INTO api_keys (service, api_key) VALUES ('OpenAI', 'sk-old');"#,
+            )
+            .unwrap();
+        }
+
+        assert!(scrub_secrets(&path).unwrap());
+        // 已清除的备份不再改动
+        assert!(!scrub_secrets(&path).unwrap());
+
+        let conn = Connection::open(&path).unwrap();
+        let keys: i64 = conn
+            .query_row("SELECT COUNT(*) FROM api_keys", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(keys, 0);
+        let config: serde_json::Value = conn
+            .query_row(
+                "SELECT value FROM user_settings WHERE key = 'app_config'",
+                [],
+                |row| row.get::<_, String>(0),
+            )
+            .map(|value| serde_json::from_str(&value).unwrap())
+            .unwrap();
+        assert_eq!(config["translation"]["api_key"], "");
+        assert_eq!(config["translation"]["model_id"], "m");
+        drop(conn);
+        let raw = fs::read(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        assert!(!raw.windows(8).any(|window| window == b"sk-plain"));
+        assert!(!raw.windows(6).any(|window| window == b"sk-old"));
+    }
+}
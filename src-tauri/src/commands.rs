@@ -3,13 +3,41 @@ use crate::http_client::{configure_http_client, http_client, validate_http_clien
 use crate::system_tray::show_main_window;
 use crate::{
     app_state::AppState,
-    database::{AppConfig, TokenLimitConfig, TranslationRecord},
+    backup::{BackupInfo, BackupReason, DatabaseStatus, IntegrityReport},
+    config_exchange::{self, ConfigFileFormat, ConfigImportReport},
+    database::{AppConfig, HistoryEntry, JobRecord, JobStatus, TagCount, TranslationRecord},
+    dictionary::{self, DictionaryEntry},
+    file_translation::{
+        self,
+        document::{DocumentOutputMode, DocumentTranslationSummary},
+        resource::ResourceTranslationSummary,
+        subtitle::{SubtitleCue, SubtitleOutputMode, SubtitleTranslationSummary},
+    },
+    history_exchange::{self, ExchangeFormat, HistoryImportSummary},
+    history_search::{self, HistoryCursor, HistoryFilter, HistorySearchPage, HistorySort},
+    housekeeping::{self, HistoryDeletion, HousekeepingReport},
+    jobs::{self, JobKind},
+    naming::{self, IdentifierNamingResult},
     ocr_tasks::run_ocr_on_image_data,
+    offline_dictionary::{self, OfflineDictionary, OfflineDictionaryMatch},
     platform,
+    profiles::{self, ConfigProfile},
+    quality::{self, QualityReport},
+    romanization::{self, Romanization},
+    secrets,
     shortcuts::register_shortcuts,
     token_limits::calculate_text_response_tokens,
-    translation::{TranslationRequest, TranslationResult, TranslationService},
+    translation::{
+        MultiTranslationResult, TranslationBackend, TranslationMode, TranslationRequest,
+        TranslationResult, TranslationService,
+    },
+    translation_memory::{self, MemoryEntry, MemoryMatch, MemoryOrigin},
+    vocabulary::{
+        self, ReviewGrade, VocabularyDraft, VocabularyItem, VocabularyKind, VocabularyStats,
+    },
+    writing::{self, WritingOperation, WritingResult, WritingTone},
 };
+use std::path::{Path, PathBuf};
 use serde::Serialize;
 use serde_json::Value;
 use tauri::Emitter;
@@ -43,63 +71,394 @@ fn encode_image_to_png(image: &ScreenshotImage) -> Result<Vec<u8>, String> {
     Ok(cursor.into_inner())
 }
 
+/// 读取当前应用配置
+pub(crate) fn load_app_config(state: &AppState) -> Result<AppConfig, String> {
+    let db = state
+        .db
+        .lock()
+        .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+
+    db.get_app_config()
+        .map_err(|e| format!("获取应用配置失败: {}", e))?
+        .ok_or_else(|| "无法获取配置".to_string())
+}
+
+/// 根据前端传入的 service 参数和数据库配置解析出翻译后端
+pub(crate) fn resolve_translation_backend(
+    state: &AppState,
+    service: &str,
+) -> Result<TranslationBackend, String> {
+    let config = load_app_config(state)?;
+    backend_from_config(state, config, service)
+}
+
+/// 构造翻译后端，此时才从密钥存储中读取 API 密钥
+pub(crate) fn backend_from_config(
+    state: &AppState,
+    config: AppConfig,
+    service: &str,
+) -> Result<TranslationBackend, String> {
+    let mut backend = TranslationBackend::from_config(config, service);
+    if matches!(backend.service, TranslationService::OpenAI) {
+        backend.api_key = resolve_profile_secret(state, secrets::TRANSLATION_API_KEY)?;
+    }
+    Ok(backend)
+}
+
+/// 读取当前配置档案保存的密钥，未保存时返回空字符串
+pub(crate) fn resolve_profile_secret(state: &AppState, key: &str) -> Result<String, String> {
+    let profile_id = {
+        let db = state
+            .db
+            .lock()
+            .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+
+        db.active_profile_id()
+            .map_err(|e| format!("获取当前配置档案失败: {}", e))?
+    };
+    state
+        .secrets
+        .resolve(&secrets::profile_secret_key(profile_id, key))
+}
+
+/// 翻译文本；mode 为 auto（默认）且输入是单词或短语时改用词典模式，结构化词条放在 dictionary 字段
+/// verify 为 true（默认取配置 quality.auto_verify）时回译校验，评估结果放在 quality 字段
+/// romanize 为 true（默认取配置 romanization.annotate_translations）时附带原文和译文的拉丁字母转写
+/// use_memory 为 false 时跳过记忆库，总是请求翻译服务（默认取配置 translation_memory.enabled）
+#[allow(clippy::too_many_arguments)]
 #[tauri::command]
 pub async fn translate_text(
     text: String,
     from_language: Option<String>,
     to_language: String,
     service: String,
+    mode: Option<TranslationMode>,
+    verify: Option<bool>,
+    romanize: Option<bool>,
+    use_memory: Option<bool>,
     state: State<'_, AppState>,
 ) -> Result<TranslationResult, String> {
     let from_lang_value = from_language.unwrap_or_default();
     let to_lang_value = to_language;
 
-    // 根据前端传入的 service 参数决定使用哪个服务
-    // 如果 service 是 "google"，则直接使用 Google 翻译
-    // 否则根据数据库配置使用 AI 服务
-    let (target_service, api_key, base_url, model_id, token_config): (TranslationService, String, String, String, TokenLimitConfig) = {
-        let db = state
-            .db
-            .lock()
-            .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+    let config = load_app_config(&state)?;
+    let verify = verify.unwrap_or(config.quality.auto_verify);
+    let romanize = romanize.unwrap_or(config.romanization.annotate_translations);
+    let is_lookup = match mode.unwrap_or_default() {
+        TranslationMode::Dictionary => true,
+        TranslationMode::Translate => false,
+        TranslationMode::Auto => dictionary::is_dictionary_candidate(&text),
+    };
+    let use_dictionary =
+        is_lookup && (mode == Some(TranslationMode::Dictionary) || config.dictionary.auto_lookup);
+    let memory_config = config.translation_memory.clone();
+    let use_memory = use_memory.unwrap_or(memory_config.enabled);
+    let backend = backend_from_config(&state, config, &service)?;
+
+    // 离线词典命中时直接返回，不发网络请求
+    if use_dictionary {
+        match state
+            .offline_dictionaries
+            .lookup_entry(&text, &from_lang_value, &to_lang_value)
+        {
+            Ok(Some(entry)) => {
+                let translated_text = entry
+                    .senses
+                    .first()
+                    .and_then(|sense| sense.definition.lines().next())
+                    .unwrap_or_default()
+                    .to_string();
+                let result = TranslationResult {
+                    translated_text,
+                    from_lang: from_lang_value,
+                    to_lang: to_lang_value,
+                    service: "Offline".to_string(),
+                    dictionary: Some(entry),
+                    quality: None,
+                    romanization: None,
+                    memory_matches: Vec::new(),
+                };
+                return Ok(with_romanization(result, &text, romanize));
+            }
+            Ok(None) => {}
+            Err(err) => eprintln!("查询离线词典失败: {}", err),
+        }
+    }
 
-        let config = db.get_app_config()
-            .map_err(|e| format!("获取应用配置失败: {}", e))?
-            .ok_or_else(|| "无法获取配置".to_string())?;
-
-        let token_config = config.token_limits.clone();
-        
-        if service == "google" {
-            (
-                TranslationService::Google,
-                "".to_string(),
-                "".to_string(),
-                "".to_string(),
-                token_config,
-            )
-        } else {
-            let translation_config = config.translation;
-            (
-                TranslationService::OpenAI,
-                translation_config.api_key,
-                translation_config.base_url,
-                translation_config.model_id,
-                token_config,
-            )
+    if use_dictionary {
+        match dictionary::lookup(&backend, &text, &from_lang_value, &to_lang_value).await {
+            Ok(entry) => {
+                let result = TranslationResult {
+                    translated_text: entry.primary_translation().unwrap_or_default().to_string(),
+                    from_lang: from_lang_value,
+                    to_lang: to_lang_value,
+                    service: backend.service.label().to_string(),
+                    dictionary: Some(entry),
+                    quality: None,
+                    romanization: None,
+                    memory_matches: Vec::new(),
+                };
+                return Ok(with_romanization(result, &text, romanize));
+            }
+            Err(err) if mode == Some(TranslationMode::Dictionary) => return Err(err),
+            // 自动模式下查词失败时退回普通翻译
+            Err(err) => eprintln!("词典查询失败，改用普通翻译: {}", err),
         }
+    }
+
+    // 记忆库中有用户确认过且足够相似的译文时直接使用，不再请求翻译服务
+    let memory_matches = if use_memory {
+        find_memory_matches(
+            &state,
+            &text,
+            &from_lang_value,
+            &to_lang_value,
+            memory_config.min_score,
+            MEMORY_MATCH_LIMIT,
+        )?
+    } else {
+        Vec::new()
     };
+    // 匹配结果按相似度降序排列，第一条确认过的译文即是最佳候选
+    if let Some(best) = memory_matches
+        .iter()
+        .find(|candidate| candidate.entry.preferred)
+        .filter(|best| {
+            memory_config.auto_apply_score > 0 && best.score >= memory_config.auto_apply_score
+        })
+    {
+        let result = TranslationResult {
+            translated_text: best.entry.target_text.clone(),
+            from_lang: from_lang_value,
+            to_lang: to_lang_value,
+            service: "Memory".to_string(),
+            dictionary: None,
+            quality: None,
+            romanization: None,
+            memory_matches: memory_matches.clone(),
+        };
+        return Ok(with_romanization(result, &text, romanize));
+    }
 
-    let max_tokens = calculate_text_response_tokens(&text, Some(&token_config));
+    let max_tokens = calculate_text_response_tokens(&text, Some(&backend.token_limits));
     let request = TranslationRequest {
-        text,
-        from_lang: from_lang_value,
-        to_lang: to_lang_value,
+        text: text.clone(),
+        from_lang: from_lang_value.clone(),
+        to_lang: to_lang_value.clone(),
         max_tokens,
+        identifier_normalization: backend.prompt_templates.text.identifier_normalization,
     };
 
-    target_service
-        .translate(request, &api_key, &base_url, &model_id)
-        .await
+    let mut result = backend
+        .service
+        .translate(request, &backend.api_key, &backend.base_url, &backend.model_id)
+        .await?;
+    result.memory_matches = memory_matches;
+
+    if verify {
+        result.quality = Some(
+            quality::estimate(
+                &backend,
+                &text,
+                &result.translated_text,
+                &from_lang_value,
+                &to_lang_value,
+            )
+            .await,
+        );
+    }
+    Ok(with_romanization(result, &text, romanize))
+}
+
+/// 按需为翻译结果附带原文和译文的转写，两侧都不需要转写时保持为空
+fn with_romanization(
+    mut result: TranslationResult,
+    source_text: &str,
+    romanize: bool,
+) -> TranslationResult {
+    if romanize {
+        let annotation = romanization::romanize_pair(
+            source_text,
+            &result.from_lang,
+            &result.translated_text,
+            &result.to_lang,
+        );
+        result.romanization = (!annotation.is_empty()).then_some(annotation);
+    }
+    result
+}
+
+/// 对已有译文单独做回译校验和质量评估
+#[tauri::command]
+pub async fn verify_translation(
+    original_text: String,
+    translated_text: String,
+    from_language: Option<String>,
+    to_language: String,
+    service: String,
+    state: State<'_, AppState>,
+) -> Result<QualityReport, String> {
+    let backend = resolve_translation_backend(&state, &service)?;
+    Ok(quality::estimate(
+        &backend,
+        &original_text,
+        &translated_text,
+        &from_language.unwrap_or_default(),
+        &to_language,
+    )
+    .await)
+}
+
+/// 把文本转写为拉丁字母（拼音、罗马字等）；language 为空时根据文字自动判断
+#[tauri::command]
+pub fn romanize_text(text: String, language: Option<String>) -> Result<Romanization, String> {
+    romanization::romanize(&text, language.as_deref())
+        .ok_or_else(|| "该文本不需要转写或暂不支持该语言的转写".to_string())
+}
+
+/// 把同一段文本一次翻译成多个目标语言，成功的译文作为一组关联记录写入历史
+#[tauri::command]
+pub async fn translate_text_multi(
+    text: String,
+    from_language: Option<String>,
+    to_languages: Vec<String>,
+    service: String,
+    state: State<'_, AppState>,
+) -> Result<MultiTranslationResult, String> {
+    let from_lang = from_language.unwrap_or_default();
+    let backend = resolve_translation_backend(&state, &service)?;
+    let mut result = backend.translate_multi(&text, &from_lang, &to_languages).await?;
+
+    let now = chrono::Utc::now().to_rfc3339();
+    // 按请求中的语言顺序写入历史
+    let mut records: Vec<TranslationRecord> = Vec::new();
+    for lang in to_languages.iter().map(|lang| lang.trim()) {
+        let Some(translated_text) = result.translations.get(lang) else {
+            continue;
+        };
+        if records.iter().any(|record| record.to_language.as_deref() == Some(lang)) {
+            continue;
+        }
+        records.push(TranslationRecord {
+            id: None,
+            original_text: text.clone(),
+            translated_text: translated_text.clone(),
+            service: result.service.clone(),
+            from_language: Some(from_lang.clone()),
+            to_language: Some(lang.to_string()),
+            created_at: Some(now.clone()),
+            group_id: None,
+            operation: None,
+        });
+    }
+
+    let db = state
+        .db
+        .lock()
+        .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+    result.group_id = db
+        .save_translation_group(&records)
+        .map_err(|e| format!("保存翻译记录失败: {}", e))?;
+
+    Ok(result)
+}
+
+/// 命名模式：把一段描述转换为 camel/snake/kebab/Pascal/SCREAMING_SNAKE 多种写法的候选标识符
+#[tauri::command]
+pub async fn generate_identifier_names(
+    text: String,
+    service: String,
+    abbreviate: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<IdentifierNamingResult, String> {
+    let config = load_app_config(&state)?;
+    let mut naming_config = config.naming.clone();
+    if let Some(abbreviate) = abbreviate {
+        naming_config.abbreviate = abbreviate;
+    }
+
+    let backend = backend_from_config(&state, config, &service)?;
+    naming::generate_identifier_names(&backend, &text, &naming_config).await
+}
+
+/// 使用配置中的大模型执行写作操作，并以 operation 标记写入历史记录
+async fn run_writing_tool(
+    state: &AppState,
+    operation: WritingOperation,
+    text: String,
+    language: Option<String>,
+) -> Result<WritingResult, String> {
+    // 写作工具只支持大模型，忽略当前选择的翻译服务
+    let backend = resolve_translation_backend(state, "openai")?;
+    let result = writing::run(&backend, operation, &text, language.as_deref()).await?;
+
+    let record = TranslationRecord {
+        id: None,
+        original_text: result.original_text.clone(),
+        translated_text: result.output_text.clone(),
+        service: result.service.clone(),
+        from_language: None,
+        to_language: language,
+        created_at: Some(chrono::Utc::now().to_rfc3339()),
+        group_id: None,
+        operation: Some(operation.as_str().to_string()),
+    };
+    let db = state
+        .db
+        .lock()
+        .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+    db.save_translation(&record)
+        .map_err(|e| format!("保存历史记录失败: {}", e))?;
+
+    Ok(result)
+}
+
+/// 校对文本，返回修改后的文本和词级差异
+#[tauri::command]
+pub async fn proofread_text(
+    text: String,
+    state: State<'_, AppState>,
+) -> Result<WritingResult, String> {
+    run_writing_tool(&state, WritingOperation::Proofread, text, None).await
+}
+
+/// 以正式或随意的语气改写文本
+#[tauri::command]
+pub async fn rewrite_text(
+    text: String,
+    tone: WritingTone,
+    state: State<'_, AppState>,
+) -> Result<WritingResult, String> {
+    run_writing_tool(&state, tone.into(), text, None).await
+}
+
+/// 精简文本，保留要点
+#[tauri::command]
+pub async fn shorten_text(
+    text: String,
+    state: State<'_, AppState>,
+) -> Result<WritingResult, String> {
+    run_writing_tool(&state, WritingOperation::Shorten, text, None).await
+}
+
+/// 总结文本；language 为总结使用的语言，默认中文
+#[tauri::command]
+pub async fn summarize_text(
+    text: String,
+    language: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<WritingResult, String> {
+    run_writing_tool(&state, WritingOperation::Summarize, text, language).await
+}
+
+/// 解释术语、行话或代码；language 为解释使用的语言，默认中文
+#[tauri::command]
+pub async fn explain_text(
+    text: String,
+    language: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<WritingResult, String> {
+    run_writing_tool(&state, WritingOperation::Explain, text, language).await
 }
 
 #[tauri::command]
@@ -124,6 +483,8 @@ pub async fn save_translation(
         from_language: Some(from_language),
         to_language: Some(to_language),
         created_at: Some(chrono::Utc::now().to_rfc3339()),
+        group_id: None,
+        operation: None,
     };
 
     db.save_translation(&record)
@@ -133,80 +494,169 @@ pub async fn save_translation(
     Ok(())
 }
 
+/// 获取翻译历史，filter 可按收藏、标签、语言、服务和时间筛选
 #[tauri::command]
 pub async fn get_translation_history(
     limit: Option<i32>,
     offset: Option<i32>,
+    filter: Option<HistoryFilter>,
     state: State<'_, AppState>,
-) -> Result<Vec<TranslationRecord>, String> {
+) -> Result<Vec<HistoryEntry>, String> {
+    let filter = filter.unwrap_or_default();
+    filter.validate()?;
     let db = state
         .db
         .lock()
         .map_err(|e| format!("获取数据库连接失败: {}", e))?;
 
-    db.get_translation_history(limit, offset)
+    db.get_translation_history(limit, offset, &filter)
         .map_err(|e| format!("获取翻译历史失败: {}", e))
 }
 
+/// 获取多目标语言翻译写入的同组记录
+#[tauri::command]
+pub async fn get_translation_group(
+    group_id: i64,
+    state: State<'_, AppState>,
+) -> Result<Vec<TranslationRecord>, String> {
+    let db = state
+        .db
+        .lock()
+        .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+
+    db.get_translation_group(group_id)
+        .map_err(|e| format!("获取翻译分组失败: {}", e))
+}
+
+/// 单页搜索结果的默认条数和上限
+const SEARCH_PAGE_DEFAULT: usize = 50;
+const SEARCH_PAGE_MAX: usize = 200;
+
+/// 全文搜索翻译历史：支持 "短语"、前缀* 和多个词（同时满足），按语言对、服务和时间筛选；
+/// 翻页时把上一页返回的 next_cursor 原样传回
 #[tauri::command]
 pub async fn search_history(
     keyword: String,
-    limit: Option<i32>,
+    limit: Option<usize>,
+    cursor: Option<String>,
+    sort: Option<HistorySort>,
+    filter: Option<HistoryFilter>,
     state: State<'_, AppState>,
-) -> Result<Vec<TranslationRecord>, String> {
+) -> Result<HistorySearchPage, String> {
+    let filter = filter.unwrap_or_default();
+    filter.validate()?;
+    let cursor = cursor
+        .filter(|cursor| !cursor.is_empty())
+        .map(|cursor| HistoryCursor::decode(&cursor).ok_or("无效的分页游标".to_string()))
+        .transpose()?;
+    let limit = limit
+        .unwrap_or(SEARCH_PAGE_DEFAULT)
+        .clamp(1, SEARCH_PAGE_MAX);
+
     let db = state
         .db
         .lock()
         .map_err(|e| format!("获取数据库连接失败: {}", e))?;
 
-    db.search_history(&keyword, limit)
-        .map_err(|e| format!("搜索历史记录失败: {}", e))
+    db.search_history(
+        &keyword,
+        &filter,
+        sort.unwrap_or_default(),
+        cursor.as_ref(),
+        limit,
+    )
 }
 
+/// 清空历史记录，默认保留收藏；include_favorites 为 true 时连收藏一起删除。
+/// 返回的 deletion_id 可传给 undo_history_deletion 撤销
 #[tauri::command]
-pub async fn clear_history(state: State<'_, AppState>) -> Result<(), String> {
+pub async fn clear_history(
+    include_favorites: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<HistoryDeletion, String> {
     let db = state
         .db
         .lock()
         .map_err(|e| format!("获取数据库连接失败: {}", e))?;
 
-    db.clear_history()
+    db.clear_history(include_favorites.unwrap_or(false))
         .map_err(|e| format!("清空历史记录失败: {}", e))
 }
 
+/// 删除一条历史记录，返回的 deletion_id 可传给 undo_history_deletion 撤销
 #[tauri::command]
-pub async fn save_setting(
-    key: String,
-    value: String,
+pub async fn delete_translation(
+    id: i64,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<HistoryDeletion, String> {
     let db = state
         .db
         .lock()
         .map_err(|e| format!("获取数据库连接失败: {}", e))?;
 
-    db.save_setting(&key, &value)
-        .map_err(|e| format!("保存设置失败: {}", e))
+    db.delete_translation(id)
+        .map_err(|e| annotation_error("删除翻译记录", e))
 }
 
+/// 按服务、语言、时间范围等条件批量删除历史记录，可撤销；清空全部历史请使用 clear_history
 #[tauri::command]
-pub async fn get_setting(
-    key: String,
+pub async fn delete_history(
+    filter: HistoryFilter,
     state: State<'_, AppState>,
-) -> Result<Option<String>, String> {
+) -> Result<HistoryDeletion, String> {
+    filter.validate()?;
+    if filter.is_empty() {
+        return Err("请至少指定一个筛选条件".to_string());
+    }
+
     let db = state
         .db
         .lock()
         .map_err(|e| format!("获取数据库连接失败: {}", e))?;
 
-    db.get_setting(&key)
-        .map_err(|e| format!("获取设置失败: {}", e))
+    db.delete_history(&filter)
+        .map_err(|e| format!("删除历史记录失败: {}", e))
 }
 
+/// 撤销 delete_translation 或 delete_history，返回恢复的条数
 #[tauri::command]
-pub async fn save_api_key(
-    service: String,
-    api_key: String,
+pub async fn undo_history_deletion(
+    deletion_id: i64,
+    state: State<'_, AppState>,
+) -> Result<usize, String> {
+    let db = state
+        .db
+        .lock()
+        .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+
+    db.undo_history_deletion(deletion_id).map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => "删除记录不存在或已超过撤销时限".to_string(),
+        e => format!("撤销删除失败: {}", e),
+    })
+}
+
+/// 立即按保留策略清理历史记录，不等待定期清理任务
+#[tauri::command]
+pub async fn run_history_cleanup(state: State<'_, AppState>) -> Result<HousekeepingReport, String> {
+    let db = state
+        .db
+        .lock()
+        .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+
+    housekeeping::run(&db)
+}
+
+fn annotation_error(action: &str, error: rusqlite::Error) -> String {
+    match error {
+        rusqlite::Error::QueryReturnedNoRows => "翻译记录不存在".to_string(),
+        e => format!("{}失败: {}", action, e),
+    }
+}
+
+#[tauri::command]
+pub async fn set_history_favorite(
+    id: i64,
+    favorite: bool,
     state: State<'_, AppState>,
 ) -> Result<(), String> {
     let db = state
@@ -214,82 +664,566 @@ pub async fn save_api_key(
         .lock()
         .map_err(|e| format!("获取数据库连接失败: {}", e))?;
 
-    db.save_api_key(&service, &api_key)
-        .map_err(|e| format!("保存 API 密钥失败: {}", e))
+    db.set_history_favorite(id, favorite)
+        .map_err(|e| annotation_error("设置收藏", e))
 }
 
+/// 替换记录的全部标签，返回整理后的标签
 #[tauri::command]
-pub async fn get_api_key(
-    service: String,
+pub async fn set_history_tags(
+    id: i64,
+    tags: Vec<String>,
     state: State<'_, AppState>,
-) -> Result<Option<String>, String> {
+) -> Result<Vec<String>, String> {
+    let tags = history_search::normalize_tags(&tags)?;
     let db = state
         .db
         .lock()
         .map_err(|e| format!("获取数据库连接失败: {}", e))?;
 
-    db.get_api_key(&service)
-        .map_err(|e| format!("获取 API 密钥失败: {}", e))
+    db.set_history_tags(id, &tags)
+        .map_err(|e| annotation_error("设置标签", e))?;
+    Ok(tags)
 }
 
+/// 设置备注，传空值或空白删除备注
 #[tauri::command]
-pub async fn save_app_config(config: AppConfig, state: State<'_, AppState>) -> Result<(), String> {
-    validate_http_client(Some(&config.proxy)).map_err(|e| format!("验证代理配置失败: {}", e))?;
+pub async fn set_history_note(
+    id: i64,
+    note: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let db = state
+        .db
+        .lock()
+        .map_err(|e| format!("获取数据库连接失败: {}", e))?;
 
-    {
+    db.set_history_note(id, note.as_deref())
+        .map_err(|e| annotation_error("设置备注", e))
+}
+
+/// 列出所有标签及使用次数
+#[tauri::command]
+pub async fn list_history_tags(state: State<'_, AppState>) -> Result<Vec<TagCount>, String> {
+    let db = state
+        .db
+        .lock()
+        .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+
+    db.list_history_tags()
+        .map_err(|e| format!("获取标签失败: {}", e))
+}
+
+/// 把翻译历史导出为 JSON、CSV、TMX 或 XLSX 文件，可按时间、语言、收藏等条件筛选；
+/// 未指定格式时按扩展名判断，返回导出的条数
+#[tauri::command]
+pub async fn export_history(
+    path: String,
+    format: Option<String>,
+    filter: Option<HistoryFilter>,
+    state: State<'_, AppState>,
+) -> Result<usize, String> {
+    let format = ExchangeFormat::resolve(format.as_deref(), Path::new(&path))?;
+    let filter = filter.unwrap_or_default();
+    filter.validate()?;
+    let entries = {
         let db = state
             .db
             .lock()
             .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+        db.get_translation_history(Some(-1), None, &filter)
+            .map_err(|e| format!("获取翻译历史失败: {}", e))?
+    };
 
-        db.save_app_config(&config)
-            .map_err(|e| format!("保存应用配置失败: {}", e))?;
-    }
+    let bytes = history_exchange::export(&entries, format)?;
+    std::fs::write(&path, bytes).map_err(|e| format!("写入导出文件失败: {}", e))?;
+    Ok(entries.len())
+}
 
-    configure_http_client(Some(&config.proxy)).map_err(|e| format!("应用代理配置失败: {}", e))
+/// 从 export_history 支持的文件导入翻译历史，已存在的记录只合并收藏、标签和备注
+#[tauri::command]
+pub async fn import_history(
+    path: String,
+    format: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<HistoryImportSummary, String> {
+    let format = ExchangeFormat::resolve(format.as_deref(), Path::new(&path))?;
+    let bytes = std::fs::read(&path).map_err(|e| format!("读取导入文件失败: {}", e))?;
+    let parsed = history_exchange::import(&bytes, format)?;
+
+    let db = state
+        .db
+        .lock()
+        .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+
+    let mut summary = db
+        .import_history(&parsed.entries)
+        .map_err(|e| format!("导入翻译历史失败: {}", e))?;
+    summary.skipped = parsed.skipped;
+    Ok(summary)
 }
 
 #[tauri::command]
-pub async fn get_app_config(state: State<'_, AppState>) -> Result<AppConfig, String> {
+pub async fn get_database_status(state: State<'_, AppState>) -> Result<DatabaseStatus, String> {
     let db = state
         .db
         .lock()
         .map_err(|e| format!("获取数据库连接失败: {}", e))?;
 
-    db.get_app_config()
-        .map_err(|e| format!("获取应用配置失败: {}", e))
-        .and_then(|config_opt| config_opt.ok_or_else(|| "应用配置不存在".to_string()))
+    Ok(db.status())
 }
 
+/// 默认执行 quick_check，full 为 true 时执行较慢的 integrity_check
 #[tauri::command]
-pub async fn reload_shortcuts(app_handle: AppHandle) -> Result<(), String> {
-    register_shortcuts(&app_handle);
-    Ok(())
+pub async fn check_database_integrity(
+    full: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<IntegrityReport, String> {
+    let db = state
+        .db
+        .lock()
+        .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+
+    db.check_integrity(full.unwrap_or(false))
+        .map_err(|e| format!("检查数据库完整性失败: {}", e))
 }
 
-fn parse_model_value(value: &Value) -> Option<ModelInfo> {
-    match value {
-        Value::String(text) => Some(ModelInfo {
-            id: text.clone(),
-            label: text.clone(),
-        }),
-        Value::Object(map) => {
-            let identifier = map
-                .get("id")
-                .and_then(|v| v.as_str())
-                .or_else(|| map.get("model").and_then(|v| v.as_str()))
-                .or_else(|| map.get("name").and_then(|v| v.as_str()))
-                .or_else(|| map.get("slug").and_then(|v| v.as_str()))?;
+#[tauri::command]
+pub async fn create_database_backup(state: State<'_, AppState>) -> Result<BackupInfo, String> {
+    let db = state
+        .db
+        .lock()
+        .map_err(|e| format!("获取数据库连接失败: {}", e))?;
 
-            let owner = map
-                .get("owned_by")
-                .and_then(|v| v.as_str())
-                .or_else(|| map.get("organization").and_then(|v| v.as_str()))
-                .or_else(|| map.get("provider").and_then(|v| v.as_str()));
+    db.create_backup(BackupReason::Manual)
+}
 
-            let label = owner
-                .map(|o| format!("{} ({})", identifier, o))
-                .unwrap_or_else(|| identifier.to_string());
+#[tauri::command]
+pub async fn list_database_backups(state: State<'_, AppState>) -> Result<Vec<BackupInfo>, String> {
+    let db = state
+        .db
+        .lock()
+        .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+
+    db.list_backups()
+}
+
+/// 用备份替换当前数据库，替换前会自动备份当前数据库
+#[tauri::command]
+pub async fn restore_database_backup(
+    file_name: String,
+    state: State<'_, AppState>,
+) -> Result<DatabaseStatus, String> {
+    let db = state
+        .db
+        .lock()
+        .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+
+    db.restore_backup(&file_name)
+}
+
+#[tauri::command]
+pub async fn save_setting(
+    key: String,
+    value: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let db = state
+        .db
+        .lock()
+        .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+
+    db.save_setting(&key, &value)
+        .map_err(|e| format!("保存设置失败: {}", e))
+}
+
+#[tauri::command]
+pub async fn get_setting(
+    key: String,
+    state: State<'_, AppState>,
+) -> Result<Option<String>, String> {
+    let db = state
+        .db
+        .lock()
+        .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+
+    db.get_setting(&key)
+        .map_err(|e| format!("获取设置失败: {}", e))
+}
+
+/// 保存指定服务的 API 密钥到密钥存储，空白值表示删除
+#[tauri::command]
+pub async fn save_api_key(
+    service: String,
+    api_key: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    if secrets::is_redacted(&api_key) {
+        return Ok(());
+    }
+    state
+        .secrets
+        .set(&secrets::service_api_key(&service), &api_key)
+        .map_err(|e| format!("保存 API 密钥失败: {}", e))
+}
+
+/// 获取指定服务的 API 密钥，只返回脱敏后的值
+#[tauri::command]
+pub async fn get_api_key(
+    service: String,
+    state: State<'_, AppState>,
+) -> Result<Option<String>, String> {
+    state
+        .secrets
+        .get(&secrets::service_api_key(&service))
+        .map(|api_key| api_key.map(|api_key| secrets::redact(&api_key)))
+        .map_err(|e| format!("获取 API 密钥失败: {}", e))
+}
+
+/// 获取当前使用的密钥存储后端
+#[tauri::command]
+pub async fn get_secret_backend(state: State<'_, AppState>) -> Result<String, String> {
+    Ok(state.secrets.backend_name().to_string())
+}
+
+/// 切换密钥存储后端（keyring 或 encrypted-file），已保存的密钥会迁移到新后端；返回迁移的密钥数
+#[tauri::command]
+pub async fn set_secret_backend(
+    backend: String,
+    state: State<'_, AppState>,
+) -> Result<usize, String> {
+    let candidates = {
+        let db = state
+            .db
+            .lock()
+            .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+        secrets::known_secret_keys(&db)?
+    };
+    state
+        .secrets
+        .switch_backend(&backend, &candidates)
+        .map_err(|e| format!("切换密钥存储失败: {}", e))
+}
+
+/// 保存当前配置档案的配置；其中的 API 密钥存入密钥存储，配置里只保留空值，脱敏值表示沿用原密钥
+#[tauri::command]
+pub async fn save_app_config(
+    mut config: AppConfig,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    validate_http_client(Some(&config.proxy)).map_err(|e| format!("验证代理配置失败: {}", e))?;
+
+    {
+        let db = state
+            .db
+            .lock()
+            .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+
+        let profile_id = db
+            .active_profile_id()
+            .map_err(|e| format!("获取当前配置档案失败: {}", e))?;
+        secrets::extract_config_secrets(&state.secrets, profile_id, &mut config)
+            .map_err(|e| format!("保存 API 密钥失败: {}", e))?;
+        db.save_profile_config(profile_id, &config)
+            .map_err(|e| format!("保存应用配置失败: {}", e))?;
+    }
+
+    configure_http_client(Some(&config.proxy)).map_err(|e| format!("应用代理配置失败: {}", e))
+}
+
+/// 获取当前配置档案的配置；API 密钥默认脱敏，reveal_secrets 为 true 时返回原文
+#[tauri::command]
+pub async fn get_app_config(
+    reveal_secrets: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<AppConfig, String> {
+    let (profile_id, mut config) = {
+        let db = state
+            .db
+            .lock()
+            .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+
+        let profile_id = db
+            .active_profile_id()
+            .map_err(|e| format!("获取当前配置档案失败: {}", e))?;
+        let config = db
+            .get_profile_config(profile_id)
+            .map_err(|e| format!("获取应用配置失败: {}", e))?;
+        (profile_id, config)
+    };
+
+    if reveal_secrets.unwrap_or(false) {
+        secrets::reveal_config_secrets(&state.secrets, profile_id, &mut config)?;
+    } else {
+        secrets::redact_config_secrets(&state.secrets, profile_id, &mut config)?;
+    }
+    Ok(config)
+}
+
+fn profile_error(action: &str, error: rusqlite::Error) -> String {
+    match error {
+        rusqlite::Error::QueryReturnedNoRows => "配置档案不存在".to_string(),
+        rusqlite::Error::SqliteFailure(e, _)
+            if e.code == rusqlite::ErrorCode::ConstraintViolation =>
+        {
+            "已存在同名的配置档案".to_string()
+        }
+        e => format!("{}失败: {}", action, e),
+    }
+}
+
+#[tauri::command]
+pub async fn list_config_profiles(
+    state: State<'_, AppState>,
+) -> Result<Vec<ConfigProfile>, String> {
+    let db = state
+        .db
+        .lock()
+        .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+
+    db.list_config_profiles()
+        .map_err(|e| format!("获取配置档案失败: {}", e))
+}
+
+/// 新建配置档案；不传 config 时使用默认配置。新档案不会自动成为当前档案
+#[tauri::command]
+pub async fn create_config_profile(
+    name: String,
+    mut config: Option<AppConfig>,
+    state: State<'_, AppState>,
+) -> Result<ConfigProfile, String> {
+    let name = profiles::normalize_name(&name)?;
+    if let Some(config) = &config {
+        validate_http_client(Some(&config.proxy))
+            .map_err(|e| format!("验证代理配置失败: {}", e))?;
+    }
+
+    let db = state
+        .db
+        .lock()
+        .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+
+    // 先建档案拿到 id，密钥按档案 id 保存后再写入去掉密钥的配置
+    let profile = db
+        .create_config_profile(&name, None)
+        .map_err(|e| profile_error("新建配置档案", e))?;
+    if let Some(config) = config.as_mut() {
+        secrets::extract_config_secrets(&state.secrets, profile.id, config)
+            .map_err(|e| format!("保存 API 密钥失败: {}", e))?;
+        db.save_profile_config(profile.id, config)
+            .map_err(|e| profile_error("保存配置档案", e))?;
+    }
+    Ok(profile)
+}
+
+/// 复制配置档案及其 API 密钥
+#[tauri::command]
+pub async fn clone_config_profile(
+    source_id: i64,
+    name: String,
+    state: State<'_, AppState>,
+) -> Result<ConfigProfile, String> {
+    let name = profiles::normalize_name(&name)?;
+    let db = state
+        .db
+        .lock()
+        .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+
+    let profile = db
+        .clone_config_profile(source_id, &name)
+        .map_err(|e| profile_error("复制配置档案", e))?;
+    secrets::copy_profile_secrets(&state.secrets, source_id, profile.id)
+        .map_err(|e| format!("复制 API 密钥失败: {}", e))?;
+    Ok(profile)
+}
+
+/// 切换当前配置档案，并按新配置重新设置代理和全局快捷键；前端收到 config-profile-changed 后重新加载配置
+#[tauri::command]
+pub async fn switch_config_profile(
+    id: i64,
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<ConfigProfile, String> {
+    let (profile, config) = {
+        let db = state
+            .db
+            .lock()
+            .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+
+        let profile = db
+            .switch_config_profile(id)
+            .map_err(|e| profile_error("切换配置档案", e))?;
+        let config = db
+            .get_profile_config(id)
+            .map_err(|e| profile_error("读取配置档案", e))?;
+        (profile, config)
+    };
+
+    configure_http_client(Some(&config.proxy)).map_err(|e| format!("应用代理配置失败: {}", e))?;
+    // register_shortcuts 会自行读取配置，必须在释放数据库锁之后调用
+    register_shortcuts(&app_handle);
+    if let Err(e) = app_handle.emit("config-profile-changed", &profile) {
+        eprintln!("发送配置档案切换事件失败: {}", e);
+    }
+    Ok(profile)
+}
+
+/// 删除配置档案及其 API 密钥；不能删除当前档案
+#[tauri::command]
+pub async fn delete_config_profile(id: i64, state: State<'_, AppState>) -> Result<(), String> {
+    let db = state
+        .db
+        .lock()
+        .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+
+    let active = db
+        .active_profile_id()
+        .map_err(|e| format!("获取当前配置档案失败: {}", e))?;
+    if id == active {
+        return Err("不能删除当前使用的配置档案，请先切换到其他档案".to_string());
+    }
+    db.delete_config_profile(id)
+        .map_err(|e| profile_error("删除配置档案", e))?;
+    secrets::delete_profile_secrets(&state.secrets, id)
+        .map_err(|e| format!("删除 API 密钥失败: {}", e))
+}
+
+/// 把配置档案（默认为当前档案）导出为 JSON 或 TOML 文件，未指定格式时按扩展名判断；
+/// 提供口令时 API 密钥加密后一并导出，否则只记录设置过哪些密钥
+#[tauri::command]
+pub async fn export_config(
+    path: String,
+    format: Option<String>,
+    profile_id: Option<i64>,
+    passphrase: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let format = ConfigFileFormat::resolve(format.as_deref(), Path::new(&path))?;
+    let passphrase = passphrase.filter(|passphrase| !passphrase.is_empty());
+    let (profile, mut config) = {
+        let db = state
+            .db
+            .lock()
+            .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+
+        let profile_id = match profile_id {
+            Some(id) => id,
+            None => db
+                .active_profile_id()
+                .map_err(|e| format!("获取当前配置档案失败: {}", e))?,
+        };
+        let profile = db
+            .get_config_profile(profile_id)
+            .map_err(|e| profile_error("读取配置档案", e))?;
+        let config = db
+            .get_profile_config(profile_id)
+            .map_err(|e| profile_error("读取配置档案", e))?;
+        (profile, config)
+    };
+
+    if passphrase.is_some() {
+        secrets::reveal_config_secrets(&state.secrets, profile.id, &mut config)?;
+    } else {
+        secrets::redact_config_secrets(&state.secrets, profile.id, &mut config)?;
+    }
+    let bytes = config_exchange::export(
+        &config,
+        Some(profile.name.as_str()),
+        format,
+        passphrase.as_deref(),
+    )?;
+    std::fs::write(&path, bytes).map_err(|e| format!("写入配置文件失败: {}", e))
+}
+
+/// 导入 export_config 导出的配置文件，旧版本的文件先迁移到当前结构；
+/// new_profile 不为空时导入为新档案，否则覆盖当前档案并立即生效。文件中没有的密钥沿用档案原有的值
+#[tauri::command]
+pub async fn import_config(
+    path: String,
+    format: Option<String>,
+    passphrase: Option<String>,
+    new_profile: Option<String>,
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<ConfigImportReport, String> {
+    let format = ConfigFileFormat::resolve(format.as_deref(), Path::new(&path))?;
+    let new_profile = new_profile
+        .filter(|name| !name.trim().is_empty())
+        .map(|name| profiles::normalize_name(&name))
+        .transpose()?;
+    let bytes = std::fs::read(&path).map_err(|e| format!("读取配置文件失败: {}", e))?;
+    let parsed = config_exchange::import(&bytes, format, passphrase.as_deref())?;
+    let mut config = parsed.config;
+    let mut report = parsed.report;
+    validate_http_client(Some(&config.proxy)).map_err(|e| format!("验证代理配置失败: {}", e))?;
+
+    let profile = {
+        let db = state
+            .db
+            .lock()
+            .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+
+        let profile = match &new_profile {
+            Some(name) => db
+                .create_config_profile(name, None)
+                .map_err(|e| profile_error("新建配置档案", e))?,
+            None => {
+                let profile_id = db
+                    .active_profile_id()
+                    .map_err(|e| format!("获取当前配置档案失败: {}", e))?;
+                db.get_config_profile(profile_id)
+                    .map_err(|e| profile_error("读取配置档案", e))?
+            }
+        };
+        secrets::extract_config_secrets(&state.secrets, profile.id, &mut config)
+            .map_err(|e| format!("保存 API 密钥失败: {}", e))?;
+        db.save_profile_config(profile.id, &config)
+            .map_err(|e| profile_error("保存配置档案", e))?;
+        profile
+    };
+
+    if new_profile.is_none() {
+        configure_http_client(Some(&config.proxy))
+            .map_err(|e| format!("应用代理配置失败: {}", e))?;
+        // register_shortcuts 会自行读取配置，必须在释放数据库锁之后调用
+        register_shortcuts(&app_handle);
+        if let Err(e) = app_handle.emit("config-profile-changed", &profile) {
+            eprintln!("发送配置档案切换事件失败: {}", e);
+        }
+    }
+    report.profile = Some(profile);
+    Ok(report)
+}
+
+#[tauri::command]
+pub async fn reload_shortcuts(app_handle: AppHandle) -> Result<(), String> {
+    register_shortcuts(&app_handle);
+    Ok(())
+}
+
+fn parse_model_value(value: &Value) -> Option<ModelInfo> {
+    match value {
+        Value::String(text) => Some(ModelInfo {
+            id: text.clone(),
+            label: text.clone(),
+        }),
+        Value::Object(map) => {
+            let identifier = map
+                .get("id")
+                .and_then(|v| v.as_str())
+                .or_else(|| map.get("model").and_then(|v| v.as_str()))
+                .or_else(|| map.get("name").and_then(|v| v.as_str()))
+                .or_else(|| map.get("slug").and_then(|v| v.as_str()))?;
+
+            let owner = map
+                .get("owned_by")
+                .and_then(|v| v.as_str())
+                .or_else(|| map.get("organization").and_then(|v| v.as_str()))
+                .or_else(|| map.get("provider").and_then(|v| v.as_str()));
+
+            let label = owner
+                .map(|o| format!("{} ({})", identifier, o))
+                .unwrap_or_else(|| identifier.to_string());
 
             Some(ModelInfo {
                 id: identifier.to_string(),
@@ -340,7 +1274,19 @@ fn extract_model_list(payload: &Value) -> Vec<ModelInfo> {
 pub async fn fetch_available_models(
     base_url: String,
     api_key: String,
+    secret: Option<String>,
+    state: State<'_, AppState>,
 ) -> Result<Vec<ModelInfo>, String> {
+    // 设置页显示的是脱敏后的密钥，此时按 secret（translation 或 ocr）读取已保存的密钥
+    let api_key = if secrets::is_redacted(&api_key) {
+        let key = secret
+            .as_deref()
+            .and_then(secrets::config_secret_key)
+            .ok_or_else(|| "API Key不能为空".to_string())?;
+        resolve_profile_secret(&state, key)?
+    } else {
+        api_key
+    };
     let trimmed_base = base_url.trim();
     let trimmed_key = api_key.trim();
 
@@ -577,6 +1523,606 @@ pub async fn capture_and_ocr(state: State<'_, AppState>) -> Result<String, Strin
     run_ocr_on_image_data(buffer, state).await
 }
 
+// ============================================================================
+// File Translation Commands
+// ============================================================================
+
+/// 翻译本地化资源文件，只翻译值、保留键与注释，返回写出的目标文件信息
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+pub async fn translate_resource_file(
+    app_handle: AppHandle,
+    path: String,
+    from_language: Option<String>,
+    to_language: String,
+    service: String,
+    output_path: Option<String>,
+    force: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<ResourceTranslationSummary, String> {
+    let backend = resolve_translation_backend(&state, &service)?;
+    let input_path = PathBuf::from(&path);
+    let output_path = output_path.map(PathBuf::from);
+
+    file_translation::resource::translate_resource_file(
+        &backend,
+        &input_path,
+        output_path.as_deref(),
+        &from_language.unwrap_or_default(),
+        &to_language,
+        force.unwrap_or(false),
+        |completed, total| file_translation::emit_progress(&app_handle, &path, completed, total),
+    )
+    .await
+}
+
+/// 读取字幕文件，返回字幕条目供前端预览
+#[tauri::command]
+pub async fn load_subtitle_file(path: String) -> Result<Vec<SubtitleCue>, String> {
+    file_translation::subtitle::load_subtitle_file(&PathBuf::from(path))
+        .map(|document| document.cues)
+}
+
+/// 翻译字幕文件（SRT/WebVTT/ASS），保留时间轴与样式标签，进度通过事件推送
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+pub async fn translate_subtitle_file(
+    app_handle: AppHandle,
+    path: String,
+    from_language: Option<String>,
+    to_language: String,
+    service: String,
+    mode: Option<SubtitleOutputMode>,
+    output_path: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<SubtitleTranslationSummary, String> {
+    let backend = resolve_translation_backend(&state, &service)?;
+    let input_path = PathBuf::from(&path);
+    let output_path = output_path.map(PathBuf::from);
+
+    file_translation::subtitle::translate_subtitle_file(
+        &backend,
+        &input_path,
+        output_path.as_deref(),
+        &from_language.unwrap_or_default(),
+        &to_language,
+        mode.unwrap_or_default(),
+        |completed, total| file_translation::emit_progress(&app_handle, &path, completed, total),
+    )
+    .await
+}
+
+/// 翻译整篇文档（Markdown/HTML/纯文本/DOCX/EPUB），保留原有结构，可输出双语对照
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+pub async fn translate_document(
+    app_handle: AppHandle,
+    path: String,
+    from_language: Option<String>,
+    to_language: String,
+    service: String,
+    mode: Option<DocumentOutputMode>,
+    output_path: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<DocumentTranslationSummary, String> {
+    let backend = resolve_translation_backend(&state, &service)?;
+    let input_path = PathBuf::from(&path);
+    let output_path = output_path.map(PathBuf::from);
+
+    file_translation::document::translate_document(
+        &backend,
+        &input_path,
+        output_path.as_deref(),
+        &from_language.unwrap_or_default(),
+        &to_language,
+        mode.unwrap_or_default(),
+        |completed, total| file_translation::emit_progress(&app_handle, &path, completed, total),
+    )
+    .await
+}
+
+// ============================================================================
+// Offline Dictionary Commands
+// ============================================================================
+
+/// 导入离线词典（StarDict/MDict/DSL），作为后台任务运行
+/// source_language/target_language 为词条和释义的语言，为空时使用词典文件头中的语言（仅 DSL 提供）
+#[tauri::command]
+pub async fn import_offline_dictionary(
+    app_handle: AppHandle,
+    path: String,
+    name: Option<String>,
+    source_language: Option<String>,
+    target_language: Option<String>,
+) -> Result<JobRecord, String> {
+    jobs::submit(
+        &app_handle,
+        JobKind::ImportDictionary {
+            path,
+            name,
+            source_lang: source_language,
+            target_lang: target_language,
+        },
+    )
+}
+
+#[tauri::command]
+pub async fn list_offline_dictionaries(
+    state: State<'_, AppState>,
+) -> Result<Vec<OfflineDictionary>, String> {
+    state
+        .offline_dictionaries
+        .list()
+        .map_err(|e| format!("获取离线词典列表失败: {}", e))
+}
+
+/// 按传入的 id 顺序设置词典优先级
+#[tauri::command]
+pub async fn reorder_offline_dictionaries(
+    ids: Vec<i64>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    state
+        .offline_dictionaries
+        .reorder(&ids)
+        .map_err(|e| format!("调整词典顺序失败: {}", e))
+}
+
+#[tauri::command]
+pub async fn set_offline_dictionary_enabled(
+    id: i64,
+    enabled: bool,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    state
+        .offline_dictionaries
+        .set_enabled(id, enabled)
+        .map_err(|e| format!("更新词典状态失败: {}", e))
+}
+
+/// 设置词典的语言方向，翻译时只自动查询方向一致的词典
+#[tauri::command]
+pub async fn set_offline_dictionary_languages(
+    id: i64,
+    source_language: Option<String>,
+    target_language: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    state
+        .offline_dictionaries
+        .set_languages(id, source_language.as_deref(), target_language.as_deref())
+        .map_err(|e| format!("更新词典语言失败: {}", e))
+}
+
+#[tauri::command]
+pub async fn remove_offline_dictionary(id: i64, state: State<'_, AppState>) -> Result<(), String> {
+    state
+        .offline_dictionaries
+        .remove(id)
+        .map_err(|e| format!("删除离线词典失败: {}", e))
+}
+
+/// 查询离线词典，支持精确、前缀和模糊匹配
+#[tauri::command]
+pub async fn lookup_offline_dictionary(
+    query: String,
+    limit: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<Vec<OfflineDictionaryMatch>, String> {
+    state
+        .offline_dictionaries
+        .lookup(&query, limit.unwrap_or(20))
+        .map_err(|e| format!("查询离线词典失败: {}", e))
+}
+
+// ============================================================================
+// Translation Memory Commands
+// ============================================================================
+
+/// 翻译结果中附带的记忆库匹配条数
+const MEMORY_MATCH_LIMIT: usize = 5;
+
+/// 在记忆库中查找与原文相似的译文
+fn find_memory_matches(
+    state: &AppState,
+    text: &str,
+    from_language: &str,
+    to_language: &str,
+    min_score: u8,
+    limit: usize,
+) -> Result<Vec<MemoryMatch>, String> {
+    let (min_length, max_length) = translation_memory::length_window(text, min_score);
+    let candidates = {
+        let db = state
+            .db
+            .lock()
+            .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+        db.memory_candidates(
+            &translation_memory::normalize_lang(from_language),
+            &translation_memory::normalize_lang(to_language),
+            min_length,
+            max_length,
+        )
+        .map_err(|e| format!("查询翻译记忆库失败: {}", e))?
+    };
+    Ok(translation_memory::rank_matches(text, candidates, min_score, limit))
+}
+
+/// 查询记忆库中的精确和模糊匹配，min_score 默认取配置 translation_memory.min_score
+#[tauri::command]
+pub async fn search_translation_memory(
+    text: String,
+    from_language: Option<String>,
+    to_language: String,
+    min_score: Option<u8>,
+    limit: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<Vec<MemoryMatch>, String> {
+    let min_score = match min_score {
+        Some(score) => score,
+        None => load_app_config(&state)?.translation_memory.min_score,
+    };
+    find_memory_matches(
+        &state,
+        &text,
+        &from_language.unwrap_or_default(),
+        &to_language,
+        min_score,
+        limit.unwrap_or(MEMORY_MATCH_LIMIT),
+    )
+}
+
+/// 保存用户修改后的译文，作为该原文的首选记忆库条目
+#[tauri::command]
+pub async fn save_translation_correction(
+    original_text: String,
+    corrected_text: String,
+    from_language: Option<String>,
+    to_language: String,
+    state: State<'_, AppState>,
+) -> Result<i64, String> {
+    if original_text.trim().is_empty() || corrected_text.trim().is_empty() {
+        return Err("原文和译文不能为空".to_string());
+    }
+    let entry = MemoryEntry::new(
+        &original_text,
+        &corrected_text,
+        &from_language.unwrap_or_default(),
+        &to_language,
+        MemoryOrigin::User,
+    );
+    let db = state
+        .db
+        .lock()
+        .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+
+    db.save_memory_entry(&entry)
+        .map_err(|e| format!("保存记忆库条目失败: {}", e))
+}
+
+#[tauri::command]
+pub async fn delete_memory_entry(id: i64, state: State<'_, AppState>) -> Result<(), String> {
+    let db = state
+        .db
+        .lock()
+        .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+
+    db.delete_memory_entry(id)
+        .map_err(|e| format!("删除记忆库条目失败: {}", e))
+}
+
+/// 从 TMX 文件导入记忆库，返回导入的条目数
+#[tauri::command]
+pub async fn import_tmx(path: String, state: State<'_, AppState>) -> Result<usize, String> {
+    let bytes = std::fs::read(&path).map_err(|e| format!("读取 TMX 文件失败: {}", e))?;
+    let content = offline_dictionary::decode_text(&bytes, None);
+    let entries = translation_memory::tmx::parse(&content)?;
+
+    let db = state
+        .db
+        .lock()
+        .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+
+    db.import_memory_entries(&entries)
+        .map_err(|e| format!("导入记忆库失败: {}", e))
+}
+
+/// 把记忆库导出为 TMX 文件，可按源语言、目标语言筛选，返回导出的条目数
+#[tauri::command]
+pub async fn export_tmx(
+    path: String,
+    source_language: Option<String>,
+    target_language: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<usize, String> {
+    let entries = {
+        let db = state
+            .db
+            .lock()
+            .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+        db.list_memory_entries(
+            &translation_memory::normalize_lang(&source_language.unwrap_or_default()),
+            &translation_memory::normalize_lang(&target_language.unwrap_or_default()),
+        )
+        .map_err(|e| format!("读取记忆库失败: {}", e))?
+    };
+
+    std::fs::write(&path, translation_memory::tmx::write(&entries))
+        .map_err(|e| format!("写入 TMX 文件失败: {}", e))?;
+    Ok(entries.len())
+}
+
+// ============================================================================
+// Vocabulary Commands
+// ============================================================================
+
+/// 复习队列单次返回的默认条数
+const DUE_VOCABULARY_DEFAULT: i64 = 50;
+
+/// 补全单词的读音：依次使用词典查询结果、离线词典和拼音/罗马字转写
+fn complete_vocabulary_draft(
+    state: &AppState,
+    draft: VocabularyDraft,
+    entry: Option<&DictionaryEntry>,
+) -> Result<VocabularyDraft, String> {
+    let mut draft = draft.normalized()?;
+    if let Some(entry) = entry {
+        draft.fill_from_dictionary(entry);
+    }
+    if draft.phonetic.is_none() && draft.kind == Some(VocabularyKind::Word) {
+        if let Ok(Some(entry)) = state.offline_dictionaries.lookup_entry(
+            &draft.term,
+            &draft.source_lang,
+            &draft.target_lang,
+        ) {
+            draft.fill_from_dictionary(&entry);
+        }
+    }
+    if draft.phonetic.is_none() {
+        let language = Some(draft.source_lang.as_str()).filter(|lang| !lang.is_empty());
+        draft.phonetic = romanization::romanize(&draft.term, language).map(|result| result.text);
+    }
+    Ok(draft)
+}
+
+fn vocabulary_error(action: &str, error: rusqlite::Error) -> String {
+    match error {
+        rusqlite::Error::QueryReturnedNoRows => "生词本条目不存在".to_string(),
+        rusqlite::Error::SqliteFailure(e, _)
+            if e.code == rusqlite::ErrorCode::ConstraintViolation =>
+        {
+            "生词本中已有相同的单词".to_string()
+        }
+        e => format!("{}失败: {}", action, e),
+    }
+}
+
+/// 加入生词本；dictionary 为翻译结果中的词典词条，用于补全读音和例句
+#[tauri::command]
+pub async fn add_vocabulary(
+    item: VocabularyDraft,
+    dictionary: Option<DictionaryEntry>,
+    state: State<'_, AppState>,
+) -> Result<VocabularyItem, String> {
+    let draft = complete_vocabulary_draft(&state, item, dictionary.as_ref())?;
+    let db = state
+        .db
+        .lock()
+        .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+
+    db.save_vocabulary(&draft)
+        .map_err(|e| vocabulary_error("加入生词本", e))
+}
+
+/// 把一条翻译历史加入生词本，原文作为单词或句子，译文作为释义
+#[tauri::command]
+pub async fn add_vocabulary_from_history(
+    history_id: i64,
+    kind: Option<VocabularyKind>,
+    state: State<'_, AppState>,
+) -> Result<VocabularyItem, String> {
+    let record = {
+        let db = state
+            .db
+            .lock()
+            .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+        db.get_translation(history_id)
+            .map_err(|e| format!("获取翻译记录失败: {}", e))?
+            .ok_or("翻译记录不存在".to_string())?
+    };
+    let draft = VocabularyDraft {
+        term: record.original_text,
+        translation: record.translated_text,
+        source_lang: record.from_language.unwrap_or_default(),
+        target_lang: record.to_language.unwrap_or_default(),
+        kind,
+        history_id: record.id,
+        ..Default::default()
+    };
+    let draft = complete_vocabulary_draft(&state, draft, None)?;
+    let db = state
+        .db
+        .lock()
+        .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+
+    db.save_vocabulary(&draft)
+        .map_err(|e| vocabulary_error("加入生词本", e))
+}
+
+/// 修改生词本条目，复习进度保持不变
+#[tauri::command]
+pub async fn update_vocabulary(
+    id: i64,
+    item: VocabularyDraft,
+    state: State<'_, AppState>,
+) -> Result<VocabularyItem, String> {
+    let draft = item.normalized()?;
+    let db = state
+        .db
+        .lock()
+        .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+
+    db.update_vocabulary(id, &draft)
+        .map_err(|e| vocabulary_error("修改生词本条目", e))
+}
+
+#[tauri::command]
+pub async fn delete_vocabulary(id: i64, state: State<'_, AppState>) -> Result<(), String> {
+    let db = state
+        .db
+        .lock()
+        .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+
+    db.delete_vocabulary(id)
+        .map_err(|e| vocabulary_error("删除生词本条目", e))
+}
+
+#[tauri::command]
+pub async fn list_vocabulary(
+    keyword: Option<String>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+    state: State<'_, AppState>,
+) -> Result<Vec<VocabularyItem>, String> {
+    let db = state
+        .db
+        .lock()
+        .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+
+    db.list_vocabulary(keyword.as_deref(), Some(limit.unwrap_or(50)), offset)
+        .map_err(|e| vocabulary_error("获取生词本", e))
+}
+
+/// 当前到期待复习的条目，最早到期的在前
+#[tauri::command]
+pub async fn get_due_vocabulary(
+    limit: Option<i64>,
+    state: State<'_, AppState>,
+) -> Result<Vec<VocabularyItem>, String> {
+    let db = state
+        .db
+        .lock()
+        .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+
+    db.due_vocabulary(
+        &vocabulary::timestamp(chrono::Utc::now()),
+        limit.unwrap_or(DUE_VOCABULARY_DEFAULT),
+    )
+    .map_err(|e| vocabulary_error("获取复习队列", e))
+}
+
+/// 提交一次复习结果，返回更新后的条目（含下次复习时间）
+#[tauri::command]
+pub async fn review_vocabulary(
+    id: i64,
+    grade: ReviewGrade,
+    state: State<'_, AppState>,
+) -> Result<VocabularyItem, String> {
+    let db = state
+        .db
+        .lock()
+        .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+
+    db.review_vocabulary(id, grade, chrono::Utc::now())
+        .map_err(|e| vocabulary_error("记录复习结果", e))
+}
+
+#[tauri::command]
+pub async fn get_vocabulary_stats(state: State<'_, AppState>) -> Result<VocabularyStats, String> {
+    let db = state
+        .db
+        .lock()
+        .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+
+    db.vocabulary_stats(chrono::Utc::now())
+        .map_err(|e| vocabulary_error("获取生词本统计", e))
+}
+
+/// 把生词本导出为 Anki 牌组（apkg）或 CSV；format 为空时按文件扩展名判断，返回导出的条目数
+#[tauri::command]
+pub async fn export_vocabulary(
+    path: String,
+    format: Option<String>,
+    deck_name: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<usize, String> {
+    let format = format
+        .or_else(|| {
+            std::path::Path::new(&path)
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_string())
+        })
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let items = {
+        let db = state
+            .db
+            .lock()
+            .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+        db.list_vocabulary(None, None, None)
+            .map_err(|e| vocabulary_error("读取生词本", e))?
+    };
+
+    let content = match format.as_str() {
+        "apkg" => {
+            let deck_name = deck_name
+                .map(|name| name.trim().to_string())
+                .filter(|name| !name.is_empty())
+                .unwrap_or_else(|| "AI Translate 生词本".to_string());
+            vocabulary::anki::write_apkg(&items, &deck_name)?
+        }
+        "csv" => vocabulary::anki::write_csv(&items).into_bytes(),
+        other => return Err(format!("不支持的导出格式: {}", other)),
+    };
+    std::fs::write(&path, content).map_err(|e| format!("写入导出文件失败: {}", e))?;
+    Ok(items.len())
+}
+
+// ============================================================================
+// Background Job Commands
+// ============================================================================
+
+/// 提交后台任务（文档/字幕/资源文件翻译、模型下载），立即返回任务记录
+#[tauri::command]
+pub async fn submit_job(app_handle: AppHandle, job: JobKind) -> Result<JobRecord, String> {
+    jobs::submit(&app_handle, job)
+}
+
+/// 获取任务列表，按创建时间倒序
+#[tauri::command]
+pub async fn list_jobs(
+    limit: Option<i32>,
+    state: State<'_, AppState>,
+) -> Result<Vec<JobRecord>, String> {
+    let db = state
+        .db
+        .lock()
+        .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+
+    db.list_jobs(limit)
+        .map_err(|e| format!("获取任务列表失败: {}", e))
+}
+
+#[tauri::command]
+pub async fn pause_job(app_handle: AppHandle, id: i64) -> Result<JobRecord, String> {
+    jobs::pause(&app_handle, id)
+}
+
+#[tauri::command]
+pub async fn resume_job(app_handle: AppHandle, id: i64) -> Result<JobRecord, String> {
+    jobs::resume(&app_handle, id)
+}
+
+#[tauri::command]
+pub async fn cancel_job(app_handle: AppHandle, id: i64) -> Result<JobRecord, String> {
+    jobs::cancel(&app_handle, id)
+}
+
+#[tauri::command]
+pub async fn retry_job(app_handle: AppHandle, id: i64) -> Result<JobRecord, String> {
+    jobs::retry(&app_handle, id)
+}
+
 // ============================================================================
 // Speech-to-Text Commands
 // ============================================================================
@@ -599,7 +2145,7 @@ fn get_whisper_engine() -> &'static std::sync::Mutex<WhisperEngine> {
     WHISPER_ENGINE.get_or_init(|| std::sync::Mutex::new(WhisperEngine::new()))
 }
 
-fn get_model_manager() -> &'static ModelManager {
+pub(crate) fn get_model_manager() -> &'static ModelManager {
     MODEL_MANAGER.get_or_init(|| ModelManager::new().expect("Failed to create model manager"))
 }
 
@@ -612,70 +2158,42 @@ pub fn get_speech_models() -> Vec<ModelStatus> {
 /// Check if a specific model is downloaded
 #[tauri::command]
 pub fn is_model_downloaded(model: String) -> bool {
-    let model = match model.as_str() {
-        "tiny" => WhisperModel::Tiny,
-        "base" => WhisperModel::Base,
-        "small" => WhisperModel::Small,
-        "medium" => WhisperModel::Medium,
-        "turbo" => WhisperModel::Turbo,
-        "large" => WhisperModel::Large,
-        _ => return false,
-    };
-    get_model_manager().is_model_available(model)
+    match WhisperModel::from_name(&model) {
+        Some(model) => get_model_manager().is_model_available(model),
+        None => false,
+    }
 }
 
 /// Download a Whisper model
+///
+/// The download runs as a background job; this command waits for it to finish
+/// so existing callers keep their behavior.
 #[tauri::command]
 pub async fn download_speech_model(
     model: String,
     app: AppHandle,
 ) -> Result<(), String> {
-    let model_enum = match model.as_str() {
-        "tiny" => WhisperModel::Tiny,
-        "base" => WhisperModel::Base,
-        "small" => WhisperModel::Small,
-        "medium" => WhisperModel::Medium,
-        "turbo" => WhisperModel::Turbo,
-        "large" => WhisperModel::Large,
-        _ => return Err("Invalid model name".to_string()),
-    };
+    if WhisperModel::from_name(&model).is_none() {
+        return Err("Invalid model name".to_string());
+    }
 
-    let manager = get_model_manager();
-    let model_name = model.clone();
-    
-    // Run download in blocking task
-    tokio::task::spawn_blocking(move || {
-        manager.download_model(model_enum, |downloaded, total| {
-            let progress = if total > 0 {
-                (downloaded as f64 / total as f64 * 100.0) as u32
-            } else {
-                0
-            };
-            let _ = app.emit("speech-model-download-progress", serde_json::json!({
-                "model": model_name.clone(),
-                "downloaded": downloaded,
-                "total": total,
-                "progress": progress
-            }));
-        })
-    })
-    .await
-    .map_err(|e| format!("Download task failed: {}", e))?
-    .map_err(|e| format!("Model download failed: {}", e))
+    let job = jobs::submit(&app, JobKind::DownloadSpeechModel { model })?;
+    let job = jobs::wait_for(&app, job.id).await?;
+    match job.status {
+        JobStatus::Done => Ok(()),
+        JobStatus::Paused => Err("Model download paused".to_string()),
+        JobStatus::Cancelled => Err("Model download cancelled".to_string()),
+        _ => Err(job
+            .error
+            .unwrap_or_else(|| "Model download failed".to_string())),
+    }
 }
 
 /// Load a Whisper model for transcription
 #[tauri::command]
 pub fn load_speech_model(model: String) -> Result<(), String> {
-    let model_enum = match model.as_str() {
-        "tiny" => WhisperModel::Tiny,
-        "base" => WhisperModel::Base,
-        "small" => WhisperModel::Small,
-        "medium" => WhisperModel::Medium,
-        "turbo" => WhisperModel::Turbo,
-        "large" => WhisperModel::Large,
-        _ => return Err("Invalid model name".to_string()),
-    };
+    let model_enum =
+        WhisperModel::from_name(&model).ok_or_else(|| "Invalid model name".to_string())?;
 
     let manager = get_model_manager();
     let model_path = manager.get_model_path(model_enum);
@@ -0,0 +1,542 @@
+// Config export and import
+// Writes a configuration profile to a versioned JSON or TOML file that can be moved between
+// machines. API keys are left out or sealed with a passphrase; importing migrates files written
+// for older AppConfig schemas and reports which settings fell back to their defaults
+
+use crate::database::AppConfig;
+use crate::profiles::ConfigProfile;
+use crate::secrets::{self, CONFIG_SECRET_KEYS};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::Sha256;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// 导出文件的格式标识
+const FILE_FORMAT: &str = "ai-translate-config";
+/// 导出文件中 AppConfig 的结构版本；结构变化需要改写旧文件时加一，并在 SCHEMA_MIGRATIONS 末尾追加迁移
+pub const SCHEMA_VERSION: u32 = 1;
+/// 依次把结构版本 0 -> 1、1 -> 2 ... 的文件升级到下一版本
+const SCHEMA_MIGRATIONS: &[fn(&mut ConfigDocument)] = &[migrate_inline_secrets];
+
+const KDF: &str = "pbkdf2-sha256";
+const KDF_ITERATIONS: u32 = 310_000;
+/// 导入时接受的迭代次数，避免被改过的文件用极大的次数拖住导入，或用极小的次数削弱口令
+const MIN_KDF_ITERATIONS: u32 = KDF_ITERATIONS / 2;
+const MAX_KDF_ITERATIONS: u32 = KDF_ITERATIONS * 4;
+const CIPHER: &str = "chacha20poly1305";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const MIN_PASSPHRASE_CHARS: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigFileFormat {
+    Json,
+    Toml,
+}
+
+impl ConfigFileFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value
+            .trim()
+            .trim_start_matches('.')
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "json" => Some(ConfigFileFormat::Json),
+            "toml" => Some(ConfigFileFormat::Toml),
+            _ => None,
+        }
+    }
+
+    /// 优先使用显式指定的格式，否则按扩展名判断
+    pub fn resolve(format: Option<&str>, path: &Path) -> Result<Self, String> {
+        let value = format
+            .map(str::to_string)
+            .or_else(|| {
+                path.extension()
+                    .map(|ext| ext.to_string_lossy().to_string())
+            })
+            .unwrap_or_default();
+        Self::parse(&value).ok_or_else(|| format!("不支持的配置文件格式: {}", value))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ConfigFile {
+    format: String,
+    schema_version: u32,
+    exported_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    profile_name: Option<String>,
+    secrets: SecretsSection,
+    /// 去掉密钥字段的 AppConfig
+    config: Value,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+enum SecretsSection {
+    /// 未导出密钥；keys 为原档案中已设置的密钥
+    Redacted {
+        keys: Vec<String>,
+    },
+    Encrypted(SealedSecrets),
+}
+
+/// 键名到密钥的 JSON，用口令派生的密钥加密，格式标识作为附加认证数据
+#[derive(Debug, Serialize, Deserialize)]
+struct SealedSecrets {
+    kdf: String,
+    iterations: u32,
+    salt: String,
+    cipher: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// 迁移过程中的文件内容
+struct ConfigDocument {
+    config: Value,
+    /// 旧版本文件中以明文保存的密钥
+    plaintext_secrets: BTreeMap<String, String>,
+}
+
+/// 导入结果
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ConfigImportReport {
+    /// 导入到的配置档案，由调用方填写
+    pub profile: Option<ConfigProfile>,
+    /// 文件的结构版本，低于 SCHEMA_VERSION 时已迁移到当前版本
+    pub schema_version: u32,
+    pub migrated: bool,
+    /// 文件中缺少、使用默认值的设置，如 hotkeys.writing_tools；整组缺少时只列出组名
+    pub defaulted_fields: Vec<String>,
+    /// 文件中有、但当前版本不认识而忽略的设置
+    pub ignored_fields: Vec<String>,
+    /// 从文件中导入的密钥
+    pub imported_secrets: Vec<String>,
+    /// 导出时未包含、沿用目标档案原有值的密钥
+    pub omitted_secrets: Vec<String>,
+}
+
+pub struct ParsedConfig {
+    /// 密钥字段为导入的密钥；未导入的为脱敏值，保存时沿用目标档案原有的密钥
+    pub config: AppConfig,
+    /// 导出时的档案名
+    pub profile_name: Option<String>,
+    pub report: ConfigImportReport,
+}
+
+/// 导出配置；config 中的密钥应为实际值，未提供口令时不导出密钥
+pub fn export(
+    config: &AppConfig,
+    profile_name: Option<&str>,
+    format: ConfigFileFormat,
+    passphrase: Option<&str>,
+) -> Result<Vec<u8>, String> {
+    let mut config = serde_json::to_value(config).map_err(|e| format!("序列化配置失败: {}", e))?;
+    let mut secrets = BTreeMap::new();
+    for key in CONFIG_SECRET_KEYS {
+        if let Some(Value::String(value)) = take_path(&mut config, key) {
+            if !value.trim().is_empty() {
+                secrets.insert(key.to_string(), value);
+            }
+        }
+    }
+
+    let secrets = match passphrase {
+        Some(passphrase) => seal(&secrets, passphrase)?,
+        None => SecretsSection::Redacted {
+            keys: secrets.into_keys().collect(),
+        },
+    };
+    let file = ConfigFile {
+        format: FILE_FORMAT.to_string(),
+        schema_version: SCHEMA_VERSION,
+        exported_at: Utc::now().to_rfc3339(),
+        profile_name: profile_name.map(str::to_string),
+        secrets,
+        config,
+    };
+
+    let text = match format {
+        ConfigFileFormat::Json => {
+            serde_json::to_string_pretty(&file).map_err(|e| format!("生成 JSON 失败: {}", e))?
+        }
+        ConfigFileFormat::Toml => {
+            toml::to_string_pretty(&file).map_err(|e| format!("生成 TOML 失败: {}", e))?
+        }
+    };
+    Ok(text.into_bytes())
+}
+
+/// 解析导出文件并迁移到当前结构版本；也接受旧版本直接保存的 AppConfig（结构版本 0）
+pub fn import(
+    bytes: &[u8],
+    format: ConfigFileFormat,
+    passphrase: Option<&str>,
+) -> Result<ParsedConfig, String> {
+    let text = std::str::from_utf8(bytes).map_err(|_| "配置文件不是 UTF-8 编码".to_string())?;
+    let value: Value = match format {
+        ConfigFileFormat::Json => {
+            serde_json::from_str(text).map_err(|e| format!("解析 JSON 失败: {}", e))?
+        }
+        ConfigFileFormat::Toml => {
+            toml::from_str(text).map_err(|e| format!("解析 TOML 失败: {}", e))?
+        }
+    };
+
+    let (schema_version, profile_name, secret_section, config) = match value.get("format") {
+        Some(Value::String(marker)) if marker == FILE_FORMAT => {
+            let schema_version = value
+                .get("schema_version")
+                .and_then(Value::as_u64)
+                .ok_or_else(|| "配置文件缺少结构版本".to_string())?;
+            if schema_version > u64::from(SCHEMA_VERSION) {
+                return Err(format!(
+                    "配置文件的结构版本 {} 高于当前支持的版本 {}，请先升级应用",
+                    schema_version, SCHEMA_VERSION
+                ));
+            }
+            let file: ConfigFile =
+                serde_json::from_value(value).map_err(|e| format!("配置文件无效: {}", e))?;
+            (
+                file.schema_version,
+                file.profile_name,
+                Some(file.secrets),
+                file.config,
+            )
+        }
+        Some(_) => return Err("不是本应用导出的配置文件".to_string()),
+        None if value.get("translation").is_some() => (0, None, None, value),
+        None => return Err("不是有效的配置文件".to_string()),
+    };
+    if !config.is_object() {
+        return Err("配置文件中的 config 不是对象".to_string());
+    }
+
+    let mut document = ConfigDocument {
+        config,
+        plaintext_secrets: BTreeMap::new(),
+    };
+    for migrate in &SCHEMA_MIGRATIONS[schema_version as usize..] {
+        migrate(&mut document);
+    }
+    // 当前版本的 config 不应包含密钥，手工加入的也不导入
+    for key in CONFIG_SECRET_KEYS {
+        take_path(&mut document.config, key);
+    }
+
+    let mut report = ConfigImportReport {
+        schema_version,
+        migrated: schema_version < SCHEMA_VERSION,
+        ..Default::default()
+    };
+    // complete 表示文件包含原档案的全部密钥，文件中没有的即原档案未设置
+    let (imported, omitted, complete) = match secret_section {
+        Some(SecretsSection::Encrypted(sealed)) => {
+            let passphrase = passphrase
+                .filter(|passphrase| !passphrase.is_empty())
+                .ok_or_else(|| "配置文件中的 API 密钥已加密，请提供导出时设置的口令".to_string())?;
+            let secrets = open(&sealed, passphrase)?;
+            (secrets, Vec::new(), true)
+        }
+        Some(SecretsSection::Redacted { keys }) => (BTreeMap::new(), keys, false),
+        None => (document.plaintext_secrets, Vec::new(), false),
+    };
+
+    let mut parsed: AppConfig = serde_json::from_value(document.config.clone())
+        .map_err(|e| format!("配置文件中的设置无效: {}", e))?;
+    let mut normalized =
+        serde_json::to_value(&parsed).map_err(|e| format!("序列化配置失败: {}", e))?;
+    for key in CONFIG_SECRET_KEYS {
+        take_path(&mut normalized, key);
+    }
+    diff_fields(
+        &normalized,
+        &document.config,
+        "",
+        &mut report.defaulted_fields,
+    );
+    diff_fields(
+        &document.config,
+        &normalized,
+        "",
+        &mut report.ignored_fields,
+    );
+
+    for (key, value) in [
+        (
+            secrets::TRANSLATION_API_KEY,
+            &mut parsed.translation.api_key,
+        ),
+        (secrets::OCR_API_KEY, &mut parsed.ocr.api_key),
+    ] {
+        match imported.get(key) {
+            Some(secret) => {
+                *value = secret.clone();
+                report.imported_secrets.push(key.to_string());
+            }
+            None if complete => value.clear(),
+            // 保存时脱敏值表示沿用目标档案原有的密钥
+            None => *value = secrets::REDACTED_PREFIX.to_string(),
+        }
+    }
+    report.omitted_secrets = omitted;
+
+    Ok(ParsedConfig {
+        config: parsed,
+        profile_name,
+        report,
+    })
+}
+
+/// 结构版本 0：没有外层信息的 AppConfig，密钥以明文保存在 api_key 字段中
+fn migrate_inline_secrets(document: &mut ConfigDocument) {
+    for key in CONFIG_SECRET_KEYS {
+        if let Some(Value::String(value)) = take_path(&mut document.config, key) {
+            if !value.trim().is_empty() && !secrets::is_redacted(&value) {
+                document.plaintext_secrets.insert(key.to_string(), value);
+            }
+        }
+    }
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], iterations: u32) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, iterations, &mut key);
+    key
+}
+
+fn seal(secrets: &BTreeMap<String, String>, passphrase: &str) -> Result<SecretsSection, String> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_CHARS {
+        return Err(format!("口令至少需要 {} 个字符", MIN_PASSPHRASE_CHARS));
+    }
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let key = derive_key(passphrase, &salt, KDF_ITERATIONS);
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(&key));
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let plaintext = serde_json::to_vec(secrets).map_err(|e| format!("序列化密钥失败: {}", e))?;
+    let ciphertext = cipher
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: &plaintext,
+                aad: FILE_FORMAT.as_bytes(),
+            },
+        )
+        .map_err(|e| format!("加密密钥失败: {}", e))?;
+
+    Ok(SecretsSection::Encrypted(SealedSecrets {
+        kdf: KDF.to_string(),
+        iterations: KDF_ITERATIONS,
+        salt: STANDARD.encode(salt),
+        cipher: CIPHER.to_string(),
+        nonce: STANDARD.encode(nonce),
+        ciphertext: STANDARD.encode(ciphertext),
+    }))
+}
+
+fn open(sealed: &SealedSecrets, passphrase: &str) -> Result<BTreeMap<String, String>, String> {
+    if sealed.kdf != KDF || sealed.cipher != CIPHER {
+        return Err(format!(
+            "不支持的加密方式: {} / {}",
+            sealed.kdf, sealed.cipher
+        ));
+    }
+    let invalid = |_| "配置文件中的加密密钥已损坏".to_string();
+    let salt = STANDARD.decode(&sealed.salt).map_err(invalid)?;
+    let nonce = STANDARD.decode(&sealed.nonce).map_err(invalid)?;
+    let ciphertext = STANDARD.decode(&sealed.ciphertext).map_err(invalid)?;
+    if nonce.len() != NONCE_LEN {
+        return Err("配置文件中的加密密钥已损坏".to_string());
+    }
+    if !(MIN_KDF_ITERATIONS..=MAX_KDF_ITERATIONS).contains(&sealed.iterations) {
+        return Err(format!(
+            "配置文件中的密钥派生迭代次数 {} 超出允许范围",
+            sealed.iterations
+        ));
+    }
+
+    let key = derive_key(passphrase, &salt, sealed.iterations);
+    let plaintext = ChaCha20Poly1305::new(Key::from_slice(&key))
+        .decrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: &ciphertext,
+                aad: FILE_FORMAT.as_bytes(),
+            },
+        )
+        .map_err(|_| "口令错误或配置文件已损坏".to_string())?;
+    serde_json::from_slice(&plaintext).map_err(|_| "配置文件中的加密密钥已损坏".to_string())
+}
+
+/// 取出并删除点号分隔路径上的值
+fn take_path(value: &mut Value, path: &str) -> Option<Value> {
+    let mut segments: Vec<&str> = path.split('.').collect();
+    let last = segments.pop()?;
+    let mut current = value;
+    for segment in segments {
+        current = current.get_mut(segment)?;
+    }
+    current.as_object_mut()?.remove(last)
+}
+
+/// 收集 left 中有而 right 中没有的字段路径；两边都是对象的字段继续比较下一层
+fn diff_fields(left: &Value, right: &Value, prefix: &str, out: &mut Vec<String>) {
+    let (Some(left), Some(right)) = (left.as_object(), right.as_object()) else {
+        return;
+    };
+    for (key, left_value) in left {
+        let path = join_path(prefix, key);
+        match right.get(key) {
+            Some(right_value) => diff_fields(left_value, right_value, &path, out),
+            None => out.push(path),
+        }
+    }
+}
+
+fn join_path(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", prefix, key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PASSPHRASE: &str = "correct horse battery";
+
+    /// 结构版本 0 的文件：直接保存的 AppConfig，密钥以明文保存，缺少后来加入的设置
+    const LEGACY_CONFIG: &str = r#"{
+        "translation": {
+            "service": "openai",
+            "base_url": "https://api.openai.com/v1",
+            "api_key": "sk-legacy-translation",
+            "model_id": "gpt-4o-mini"
+        },
+        "ocr": {
+            "base_url": "https://api.openai.com/v1",
+            "api_key": "sk-legacy-ocr",
+            "model_id": "gpt-4o-mini",
+            "reuse_translation": false
+        },
+        "hotkeys": {
+            "popup_window": "Alt+A",
+            "slide_translation": "Alt+D",
+            "screenshot_translation": "Alt+S"
+        },
+        "retired_setting": true
+    }"#;
+
+    fn sample_config() -> AppConfig {
+        import(LEGACY_CONFIG.as_bytes(), ConfigFileFormat::Json, None)
+            .unwrap()
+            .config
+    }
+
+    #[test]
+    fn migrates_schema_zero_file() {
+        let parsed = import(LEGACY_CONFIG.as_bytes(), ConfigFileFormat::Json, None).unwrap();
+
+        assert_eq!(parsed.report.schema_version, 0);
+        assert!(parsed.report.migrated);
+        assert_eq!(parsed.config.translation.api_key, "sk-legacy-translation");
+        assert_eq!(parsed.config.ocr.api_key, "sk-legacy-ocr");
+        assert_eq!(
+            parsed.report.imported_secrets,
+            vec![
+                secrets::TRANSLATION_API_KEY.to_string(),
+                secrets::OCR_API_KEY.to_string()
+            ]
+        );
+        assert_eq!(parsed.report.ignored_fields, vec!["retired_setting"]);
+    }
+
+    #[test]
+    fn reports_defaulted_fields() {
+        let report = import(LEGACY_CONFIG.as_bytes(), ConfigFileFormat::Json, None)
+            .unwrap()
+            .report;
+
+        assert!(report
+            .defaulted_fields
+            .contains(&"hotkeys.writing_tools".to_string()));
+        // 整组缺少时只列出组名
+        assert!(report.defaulted_fields.contains(&"proxy".to_string()));
+        assert!(!report
+            .defaulted_fields
+            .iter()
+            .any(|field| field.starts_with("proxy.")));
+        // 密钥不算作使用默认值的设置
+        assert!(!report
+            .defaulted_fields
+            .iter()
+            .any(|field| field.ends_with("api_key")));
+    }
+
+    #[test]
+    fn round_trips_sealed_secrets() {
+        let config = sample_config();
+        for format in [ConfigFileFormat::Json, ConfigFileFormat::Toml] {
+            let bytes = export(&config, Some("工作"), format, Some(PASSPHRASE)).unwrap();
+            let text = String::from_utf8(bytes.clone()).unwrap();
+            assert!(!text.contains("sk-legacy"));
+
+            let parsed = import(&bytes, format, Some(PASSPHRASE)).unwrap();
+            assert_eq!(parsed.profile_name.as_deref(), Some("工作"));
+            assert_eq!(parsed.report.schema_version, SCHEMA_VERSION);
+            assert!(!parsed.report.migrated);
+            assert!(parsed.report.defaulted_fields.is_empty());
+            assert!(parsed.report.ignored_fields.is_empty());
+            assert_eq!(parsed.config.translation.api_key, "sk-legacy-translation");
+            assert_eq!(parsed.config.ocr.api_key, "sk-legacy-ocr");
+
+            assert!(import(&bytes, format, Some("wrong passphrase")).is_err());
+            assert!(import(&bytes, format, None).is_err());
+        }
+    }
+
+    #[test]
+    fn round_trip_without_passphrase_omits_secrets() {
+        let bytes = export(&sample_config(), None, ConfigFileFormat::Json, None).unwrap();
+        let text = String::from_utf8(bytes.clone()).unwrap();
+        assert!(!text.contains("sk-legacy"));
+
+        let parsed = import(&bytes, ConfigFileFormat::Json, None).unwrap();
+        assert!(parsed.report.imported_secrets.is_empty());
+        assert_eq!(parsed.report.omitted_secrets.len(), 2);
+        assert!(secrets::is_redacted(&parsed.config.translation.api_key));
+    }
+
+    #[test]
+    fn rejects_out_of_range_iterations() {
+        let bytes = export(
+            &sample_config(),
+            None,
+            ConfigFileFormat::Json,
+            Some(PASSPHRASE),
+        )
+        .unwrap();
+        for iterations in [0, 1_000, u32::MAX] {
+            let mut file: Value = serde_json::from_slice(&bytes).unwrap();
+            file["secrets"]["iterations"] = iterations.into();
+            let tampered = serde_json::to_vec(&file).unwrap();
+            let err = import(&tampered, ConfigFileFormat::Json, Some(PASSPHRASE))
+                .err()
+                .unwrap();
+            assert!(err.contains("迭代次数"), "{}", err);
+        }
+    }
+}
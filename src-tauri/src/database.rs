@@ -1,9 +1,32 @@
-use chrono::Utc;
-use rusqlite::{params, Connection, Error as RusqliteError, ErrorCode, Result};
+use crate::backup::{
+    self, BackupInfo, BackupReason, DatabaseMode, DatabaseStatus, IntegrityReport,
+};
+use crate::dictionary::DictionaryExample;
+use crate::history_exchange::HistoryImportSummary;
+use crate::history_search::{
+    self, HistoryCursor, HistoryFilter, HistorySearchHit, HistorySearchPage, HistorySort,
+};
+use crate::housekeeping::{self, HistoryDeletion};
+use crate::languages;
+use crate::migrations;
+use crate::profiles::{self, ConfigProfile};
+use crate::translation_memory::{self, MemoryEntry, MemoryOrigin};
+use crate::vocabulary::{
+    self, ReviewGrade, ReviewState, VocabularyDraft, VocabularyItem, VocabularyKind,
+    VocabularyStats,
+};
+use chrono::{DateTime, Utc};
+use rusqlite::types::Value;
+use rusqlite::{
+    params, params_from_iter, Connection, Error as RusqliteError, ErrorCode, OptionalExtension,
+    Result,
+};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tauri::{AppHandle, Manager};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -15,6 +38,30 @@ pub struct TranslationRecord {
     pub from_language: Option<String>,
     pub to_language: Option<String>,
     pub created_at: Option<String>,
+    /// 多目标语言翻译的分组编号（组内首条记录的 id），单条翻译为空
+    #[serde(default)]
+    pub group_id: Option<i64>,
+    /// 写作工具的操作类型（proofread、summarize 等），普通翻译为空
+    #[serde(default)]
+    pub operation: Option<String>,
+}
+
+/// 带收藏、标签和备注的翻译历史，批注保存在单独的表中
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HistoryEntry {
+    #[serde(flatten)]
+    pub record: TranslationRecord,
+    #[serde(default)]
+    pub favorite: bool,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TagCount {
+    pub tag: String,
+    pub count: i64,
 }
 
 fn default_service() -> String {
@@ -26,6 +73,8 @@ pub struct TranslationConfig {
     #[serde(default = "default_service")]
     pub service: String,
     pub base_url: String,
+    /// 只用于和前端交换（脱敏值或新输入的密钥），保存时移入密钥存储
+    #[serde(default)]
     pub api_key: String,
     pub model_id: String,
 }
@@ -33,6 +82,8 @@ pub struct TranslationConfig {
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct OcrConfig {
     pub base_url: String,
+    /// 同 TranslationConfig::api_key
+    #[serde(default)]
     pub api_key: String,
     pub model_id: String,
     pub reuse_translation: bool,
@@ -43,6 +94,26 @@ pub struct HotkeyConfig {
     pub popup_window: String,
     pub slide_translation: String,
     pub screenshot_translation: String,
+    #[serde(default = "default_identifier_naming_hotkey")]
+    pub identifier_naming: String,
+    #[serde(default = "default_writing_tools_hotkey")]
+    pub writing_tools: String,
+}
+
+fn default_identifier_naming_hotkey() -> String {
+    if cfg!(target_os = "macos") {
+        "Option+N".to_string()
+    } else {
+        "Alt+N".to_string()
+    }
+}
+
+fn default_writing_tools_hotkey() -> String {
+    if cfg!(target_os = "macos") {
+        "Option+W".to_string()
+    } else {
+        "Alt+W".to_string()
+    }
 }
 
 impl HotkeyConfig {
@@ -57,6 +128,8 @@ impl HotkeyConfig {
             popup_window: popup.to_string(),
             slide_translation: slide.to_string(),
             screenshot_translation: screenshot.to_string(),
+            identifier_naming: default_identifier_naming_hotkey(),
+            writing_tools: default_writing_tools_hotkey(),
         }
     }
 }
@@ -126,6 +199,192 @@ impl Default for AutostartConfig {
     }
 }
 
+fn default_auto_lookup() -> bool {
+    true
+}
+
+/// 词典模式配置
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DictionaryConfig {
+    /// 输入为单词或短语时自动使用词典模式
+    #[serde(default = "default_auto_lookup")]
+    pub auto_lookup: bool,
+}
+
+impl Default for DictionaryConfig {
+    fn default() -> Self {
+        DictionaryConfig {
+            auto_lookup: default_auto_lookup(),
+        }
+    }
+}
+
+/// 译文质量校验配置
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct QualityConfig {
+    /// 每次翻译后自动回译并评估质量（会额外消耗一次请求）
+    #[serde(default)]
+    pub auto_verify: bool,
+}
+
+fn default_memory_enabled() -> bool {
+    true
+}
+
+fn default_memory_min_score() -> u8 {
+    70
+}
+
+fn default_memory_auto_apply_score() -> u8 {
+    0
+}
+
+/// 翻译记忆库配置
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TranslationMemoryConfig {
+    /// 翻译时查询记忆库并在结果中附带匹配
+    #[serde(default = "default_memory_enabled")]
+    pub enabled: bool,
+    /// 返回的模糊匹配的最低相似度（百分比）
+    #[serde(default = "default_memory_min_score")]
+    pub min_score: u8,
+    /// 用户确认过的最佳匹配达到该相似度时直接使用记忆库译文，不再请求翻译服务；0 表示关闭（默认）
+    #[serde(default = "default_memory_auto_apply_score")]
+    pub auto_apply_score: u8,
+}
+
+impl Default for TranslationMemoryConfig {
+    fn default() -> Self {
+        TranslationMemoryConfig {
+            enabled: default_memory_enabled(),
+            min_score: default_memory_min_score(),
+            auto_apply_score: default_memory_auto_apply_score(),
+        }
+    }
+}
+
+/// 翻译历史保留策略，由定期清理任务执行；默认不清理
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HistoryRetentionConfig {
+    /// 只保留最近多少天的记录，0 表示不按时间清理
+    #[serde(default)]
+    pub max_age_days: u32,
+    /// 最多保留的记录条数，0 表示不限
+    #[serde(default)]
+    pub max_entries: u32,
+    /// 清理时跳过收藏的记录，收藏也不计入条数上限
+    #[serde(default = "default_retention_keep_favorites")]
+    pub keep_favorites: bool,
+}
+
+fn default_retention_keep_favorites() -> bool {
+    true
+}
+
+impl Default for HistoryRetentionConfig {
+    fn default() -> Self {
+        HistoryRetentionConfig {
+            max_age_days: 0,
+            max_entries: 0,
+            keep_favorites: default_retention_keep_favorites(),
+        }
+    }
+}
+
+/// 罗马字注音配置
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct RomanizationConfig {
+    /// 翻译结果中自动附带原文和译文的拉丁字母转写（拼音、罗马字等）
+    #[serde(default)]
+    pub annotate_translations: bool,
+}
+
+/// 翻译前对代码标识符的处理方式
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum IdentifierNormalization {
+    /// 原样发送
+    Off,
+    /// 仅当整段输入是一个标识符时拆分
+    SingleIdentifier,
+    /// 拆分文本中识别出的标识符，保留网址、路径、连字符单词和专有名词
+    #[default]
+    Auto,
+}
+
+/// 单个提示词模板的设置
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PromptTemplateConfig {
+    #[serde(default)]
+    pub identifier_normalization: IdentifierNormalization,
+}
+
+/// 各提示词模板的设置：text 用于划词/输入框翻译，batch 用于文件批量翻译
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PromptTemplatesConfig {
+    #[serde(default = "PromptTemplatesConfig::default_text")]
+    pub text: PromptTemplateConfig,
+    #[serde(default = "PromptTemplatesConfig::default_batch")]
+    pub batch: PromptTemplateConfig,
+}
+
+impl PromptTemplatesConfig {
+    fn default_text() -> PromptTemplateConfig {
+        PromptTemplateConfig {
+            identifier_normalization: IdentifierNormalization::Auto,
+        }
+    }
+
+    fn default_batch() -> PromptTemplateConfig {
+        // 文件中的键名、占位符大多需要原样保留，默认不改写
+        PromptTemplateConfig {
+            identifier_normalization: IdentifierNormalization::Off,
+        }
+    }
+}
+
+impl Default for PromptTemplatesConfig {
+    fn default() -> Self {
+        PromptTemplatesConfig {
+            text: Self::default_text(),
+            batch: Self::default_batch(),
+        }
+    }
+}
+
+/// 自定义缩写规则，例如 configuration -> config
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AbbreviationRule {
+    pub word: String,
+    pub abbreviation: String,
+}
+
+fn default_naming_candidate_count() -> u32 {
+    3
+}
+
+/// 命名模式配置
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NamingConfig {
+    /// 是否使用缩写（内置常用缩写 + 自定义规则）
+    #[serde(default)]
+    pub abbreviate: bool,
+    #[serde(default)]
+    pub abbreviations: Vec<AbbreviationRule>,
+    #[serde(default = "default_naming_candidate_count")]
+    pub candidate_count: u32,
+}
+
+impl Default for NamingConfig {
+    fn default() -> Self {
+        NamingConfig {
+            abbreviate: false,
+            abbreviations: Vec::new(),
+            candidate_count: default_naming_candidate_count(),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct AppConfig {
     pub translation: TranslationConfig,
@@ -137,79 +396,320 @@ pub struct AppConfig {
     pub token_limits: TokenLimitConfig,
     #[serde(default)]
     pub autostart: AutostartConfig,
+    #[serde(default)]
+    pub naming: NamingConfig,
+    #[serde(default)]
+    pub prompt_templates: PromptTemplatesConfig,
+    #[serde(default)]
+    pub dictionary: DictionaryConfig,
+    #[serde(default)]
+    pub quality: QualityConfig,
+    #[serde(default)]
+    pub romanization: RomanizationConfig,
+    #[serde(default)]
+    pub translation_memory: TranslationMemoryConfig,
+    #[serde(default)]
+    pub history_retention: HistoryRetentionConfig,
+}
+
+/// 后台任务状态
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Paused,
+    Failed,
+    Cancelled,
+    Done,
+}
+
+impl JobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobStatus::Queued => "queued",
+            JobStatus::Running => "running",
+            JobStatus::Paused => "paused",
+            JobStatus::Failed => "failed",
+            JobStatus::Cancelled => "cancelled",
+            JobStatus::Done => "done",
+        }
+    }
+
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "running" => JobStatus::Running,
+            "paused" => JobStatus::Paused,
+            "failed" => JobStatus::Failed,
+            "cancelled" => JobStatus::Cancelled,
+            "done" => JobStatus::Done,
+            _ => JobStatus::Queued,
+        }
+    }
+
+    /// 是否为终止状态（不会再自动运行）
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            JobStatus::Failed | JobStatus::Cancelled | JobStatus::Done
+        )
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct JobRecord {
+    pub id: i64,
+    pub kind: String,
+    pub params: serde_json::Value,
+    pub status: JobStatus,
+    pub progress: f64,
+    pub message: Option<String>,
+    pub result_path: Option<String>,
+    pub error: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
 }
 
 #[derive(Clone)]
 pub struct Database {
     conn: Arc<Mutex<Connection>>,
+    /// 数据库文件路径；降级模式下连接的是内存数据库，仍保留原路径以便从备份恢复
+    path: PathBuf,
+    status: Arc<Mutex<DatabaseStatus>>,
 }
 
 impl Database {
     pub fn new(app_handle: &AppHandle) -> Result<Self> {
-        let app_dir = resolve_app_data_dir(app_handle).map_err(io_to_rusqlite_error)?;
+        let db_path = prepare_db_path(app_handle)?;
+        let conn = open_connection(&db_path)?;
+        Ok(Database::from_parts(
+            conn,
+            db_path.clone(),
+            DatabaseStatus::normal(&db_path),
+        ))
+    }
+
+    /// 启动时使用：数据库损坏时隔离损坏文件并从最近的可用备份恢复，
+    /// 被锁定或版本过新等无法打开的情况降级为内存数据库，保证应用总能启动
+    pub fn open_or_recover(app_handle: &AppHandle) -> Self {
+        let db_path = match prepare_db_path(app_handle) {
+            Ok(db_path) => db_path,
+            Err(e) => {
+                return Database::degraded(
+                    PathBuf::from("trans.db"),
+                    format!("无法创建数据目录: {}", e),
+                )
+            }
+        };
+
+        match open_connection(&db_path) {
+            Ok(conn) => {
+                Database::from_parts(conn, db_path.clone(), DatabaseStatus::normal(&db_path))
+            }
+            Err(e) if is_corruption(&e) => Database::recover(db_path, e),
+            Err(e) => Database::degraded(db_path, format!("无法打开数据库: {}", e)),
+        }
+    }
+
+    fn from_parts(conn: Connection, path: PathBuf, status: DatabaseStatus) -> Self {
+        Database {
+            conn: Arc::new(Mutex::new(conn)),
+            path,
+            status: Arc::new(Mutex::new(status)),
+        }
+    }
+
+    /// 隔离损坏的数据库，依次尝试从新到旧的备份；都不可用时新建空数据库
+    fn recover(db_path: PathBuf, cause: RusqliteError) -> Self {
+        eprintln!("数据库已损坏: {}", cause);
+        let quarantined = match backup::quarantine(&db_path) {
+            Ok(moved) => moved
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect(),
+            Err(e) => return Database::degraded(db_path, format!("数据库已损坏且无法隔离: {}", e)),
+        };
+        let mut status = DatabaseStatus {
+            mode: DatabaseMode::Recovered,
+            quarantined,
+            ..DatabaseStatus::normal(&db_path)
+        };
+
+        let candidates = backup::list(&db_path).unwrap_or_else(|e| {
+            eprintln!("读取备份列表失败: {}", e);
+            Vec::new()
+        });
+        for candidate in candidates {
+            let restored = backup::verify(&candidate.path)
+                .and_then(|_| backup::restore_file(&candidate.path, &db_path))
+                .and_then(|_| {
+                    open_connection(&db_path).map_err(|e| format!("打开恢复后的数据库失败: {}", e))
+                });
+            match restored {
+                Ok(conn) => {
+                    println!("已从备份 {} 恢复数据库", candidate.file_name);
+                    status.message = Some(format!(
+                        "数据库已损坏，已隔离并从备份 {}（{}）恢复",
+                        candidate.file_name, candidate.created_at
+                    ));
+                    status.restored_from = Some(candidate.file_name);
+                    return Database::from_parts(conn, db_path, status);
+                }
+                Err(e) => {
+                    eprintln!("无法使用备份 {}: {}", candidate.file_name, e);
+                    if let Err(e) = backup::remove_database_files(&db_path) {
+                        return Database::degraded(db_path, e);
+                    }
+                }
+            }
+        }
+
+        match open_connection(&db_path) {
+            Ok(conn) => {
+                status.message = Some("数据库已损坏且没有可用的备份，已新建空数据库".to_string());
+                Database::from_parts(conn, db_path, status)
+            }
+            Err(e) => Database::degraded(db_path, format!("新建数据库失败: {}", e)),
+        }
+    }
+
+    /// 测试用的内存数据库，状态为正常
+    #[cfg(test)]
+    pub(crate) fn in_memory() -> Self {
+        let path = PathBuf::from("trans.db");
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrations::run(&mut conn, &path).unwrap();
+        Database::from_parts(conn, path.clone(), DatabaseStatus::normal(&path))
+    }
+
+    /// 使用内存数据库运行，不改动磁盘上的文件
+    fn degraded(db_path: PathBuf, message: String) -> Self {
+        eprintln!("{}，将使用内存数据库运行", message);
+        let mut conn = Connection::open_in_memory().expect("创建内存数据库失败");
+        migrations::run(&mut conn, &db_path).expect("初始化内存数据库失败");
+        let status = DatabaseStatus {
+            mode: DatabaseMode::Degraded,
+            message: Some(format!("{}。本次运行的数据不会被保存", message)),
+            ..DatabaseStatus::normal(&db_path)
+        };
+        Database::from_parts(conn, db_path, status)
+    }
 
-        // 确保应用数据目录存在
-        fs::create_dir_all(&app_dir).map_err(io_to_rusqlite_error)?;
+    pub fn status(&self) -> DatabaseStatus {
+        self.status.lock().unwrap().clone()
+    }
 
-        println!("配置目录: {}", app_dir.display());
-        let db_path = app_dir.join("trans.db");
-        let conn = Connection::open(db_path)?;
-        let conn = Arc::new(Mutex::new(conn));
+    /// 记录启动时其他存储（离线词典、密钥）出现的问题，随数据库状态一起提示用户
+    pub fn add_warning(&self, warning: String) {
+        self.status.lock().unwrap().warnings.push(warning);
+    }
 
-        let db = Database { conn };
-        db.init_tables()?;
-        Ok(db)
+    pub fn is_degraded(&self) -> bool {
+        self.status.lock().unwrap().mode == DatabaseMode::Degraded
     }
 
-    fn init_tables(&self) -> Result<()> {
+    pub fn check_integrity(&self, full: bool) -> Result<IntegrityReport> {
         let conn = self.conn.lock().unwrap();
+        backup::check_integrity(&conn, full)
+    }
 
-        // 创建翻译历史表
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS translation_history (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                original_text TEXT NOT NULL,
-                translated_text TEXT NOT NULL,
-                service TEXT NOT NULL,
-                from_language TEXT,
-                to_language TEXT,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP
-            )",
-            [],
-        )?;
+    pub fn create_backup(&self, reason: BackupReason) -> std::result::Result<BackupInfo, String> {
+        if self.is_degraded() {
+            return Err("当前以降级模式运行，没有可备份的数据库".to_string());
+        }
+        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        backup::create(&conn, &self.path, reason)
+    }
 
-        // 创建用户设置表
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS user_settings (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                key TEXT UNIQUE NOT NULL,
-                value TEXT NOT NULL,
-                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
-            )",
-            [],
-        )?;
+    /// 距离上次自动备份超过一天时备份，返回新建的备份
+    pub fn backup_if_due(&self) -> std::result::Result<Option<BackupInfo>, String> {
+        if self.is_degraded() || !backup::is_auto_backup_due(&self.path, Utc::now())? {
+            return Ok(None);
+        }
+        self.create_backup(BackupReason::Auto).map(Some)
+    }
 
-        // 创建 API 密钥表
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS api_keys (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                service TEXT UNIQUE NOT NULL,
-                api_key TEXT NOT NULL,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP
-            )",
-            [],
-        )?;
+    pub fn list_backups(&self) -> std::result::Result<Vec<BackupInfo>, String> {
+        backup::list(&self.path)
+    }
 
-        // 创建索引
-        conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_translation_history_created_at 
-             ON translation_history(created_at DESC)",
-            [],
-        )?;
+    /// 清除所有备份中旧版本明文保存的密钥，返回清除的备份数；
+    /// 无法清除的备份（包括不会自动删除的迁移前备份）提示用户手动删除
+    pub fn scrub_backup_secrets(&self) -> std::result::Result<usize, String> {
+        let mut scrubbed = 0;
+        for backup in backup::list(&self.path)? {
+            match backup::scrub_secrets(&backup.path) {
+                Ok(true) => scrubbed += 1,
+                Ok(false) => {}
+                Err(err) => self.add_warning(format!(
+                    "备份 {} 中可能仍有明文保存的 API 密钥，请手动删除该备份: {}",
+                    backup.file_name, err
+                )),
+            }
+        }
+        Ok(scrubbed)
+    }
 
+    // 清除敏感数据后调用：开启 secure_delete 并 VACUUM，已删除的内容不再残留在空闲页中
+    pub fn erase_free_pages(&self) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.pragma_update(None, "secure_delete", true)?;
+        conn.execute("VACUUM", [])?;
         Ok(())
     }
 
+    /// 用指定备份替换当前数据库，替换前先备份当前数据库；
+    /// 所有 Database 克隆共享同一个连接，替换后立即生效
+    pub fn restore_backup(&self, file_name: &str) -> std::result::Result<DatabaseStatus, String> {
+        let source = backup::find(&self.path, file_name)?;
+        backup::verify(&source.path)?;
+
+        let mut conn = self.conn.lock().map_err(|e| e.to_string())?;
+        let mut status = self.status.lock().map_err(|e| e.to_string())?;
+        if status.mode != DatabaseMode::Degraded {
+            if let Err(e) = backup::create(&conn, &self.path, BackupReason::PreRestore) {
+                eprintln!("恢复前备份当前数据库失败: {}", e);
+            }
+        }
+        if !source.path.exists() {
+            return Err(format!("备份不存在: {}", file_name));
+        }
+
+        // 先关闭原连接才能替换文件；降级模式下保留内存数据库，恢复失败时换回
+        let placeholder = Connection::open_in_memory().map_err(|e| e.to_string())?;
+        let previous = std::mem::replace(&mut *conn, placeholder);
+        let previous = if status.mode == DatabaseMode::Degraded {
+            Some(previous)
+        } else {
+            drop(previous);
+            None
+        };
+        let restored = backup::restore_file(&source.path, &self.path).and_then(|_| {
+            open_connection(&self.path).map_err(|e| format!("打开恢复后的数据库失败: {}", e))
+        });
+        match restored {
+            Ok(restored) => {
+                *conn = restored;
+                *status = DatabaseStatus {
+                    message: Some(format!("已从备份 {} 恢复", file_name)),
+                    restored_from: Some(file_name.to_string()),
+                    ..DatabaseStatus::normal(&self.path)
+                };
+                Ok(status.clone())
+            }
+            Err(e) => {
+                match previous {
+                    Some(previous) => *conn = previous,
+                    None => match open_connection(&self.path) {
+                        Ok(original) => *conn = original,
+                        Err(reopen) => eprintln!("重新打开数据库失败: {}", reopen),
+                    },
+                }
+                Err(e)
+            }
+        }
+    }
+
     // 保存翻译记录
     pub fn save_translation(&self, record: &TranslationRecord) -> Result<i64> {
         let conn = self.conn.lock().unwrap();
@@ -218,48 +718,47 @@ impl Database {
             .clone()
             .unwrap_or_else(|| Utc::now().to_rfc3339());
 
-        conn.execute(
-            "INSERT INTO translation_history 
-             (original_text, translated_text, service, from_language, to_language, created_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-            params![
-                record.original_text,
-                record.translated_text,
-                record.service,
-                record.from_language,
-                record.to_language,
-                created_at
-            ],
-        )?;
-        Ok(conn.last_insert_rowid())
+        let id = insert_translation(&conn, record, &created_at, record.group_id)?;
+        index_history_memory(&conn, id, record)?;
+        Ok(id)
     }
 
-    // 获取翻译历史
-    pub fn get_translation_history(
-        &self,
-        limit: Option<i32>,
-        offset: Option<i32>,
-    ) -> Result<Vec<TranslationRecord>> {
+    // 在一个事务中保存同一原文的多条译文，组内记录共用首条记录的 id 作为 group_id
+    pub fn save_translation_group(&self, records: &[TranslationRecord]) -> Result<Option<i64>> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let now = Utc::now().to_rfc3339();
+        let mut group_id: Option<i64> = None;
+
+        for record in records {
+            let created_at = record.created_at.clone().unwrap_or_else(|| now.clone());
+            let id = insert_translation(&tx, record, &created_at, group_id)?;
+            index_history_memory(&tx, id, record)?;
+            if group_id.is_none() {
+                tx.execute(
+                    "UPDATE translation_history SET group_id = ?1 WHERE id = ?1",
+                    params![id],
+                )?;
+                group_id = Some(id);
+            }
+        }
+
+        tx.commit()?;
+        Ok(group_id)
+    }
+
+    // 获取同一分组的全部译文
+    pub fn get_translation_group(&self, group_id: i64) -> Result<Vec<TranslationRecord>> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
-            "SELECT id, original_text, translated_text, service, from_language, to_language, 
-                    created_at
-             FROM translation_history 
-             ORDER BY datetime(created_at) DESC
-             LIMIT ?1 OFFSET ?2",
+            "SELECT id, original_text, translated_text, service, from_language, to_language,
+                    created_at, group_id, operation
+             FROM translation_history
+             WHERE group_id = ?1
+             ORDER BY id",
         )?;
 
-        let rows = stmt.query_map(params![limit.unwrap_or(50), offset.unwrap_or(0)], |row| {
-            Ok(TranslationRecord {
-                id: Some(row.get(0)?),
-                original_text: row.get(1)?,
-                translated_text: row.get(2)?,
-                service: row.get(3)?,
-                from_language: row.get(4)?,
-                to_language: row.get(5)?,
-                created_at: Some(row.get(6)?),
-            })
-        })?;
+        let rows = stmt.query_map(params![group_id], row_to_translation_record)?;
 
         let mut records = Vec::new();
         for row in rows {
@@ -268,112 +767,969 @@ impl Database {
         Ok(records)
     }
 
-    // 搜索翻译历史
-    pub fn search_history(
+    // 获取翻译历史（按时间倒序），可按收藏、标签等条件筛选
+    pub fn get_translation_history(
         &self,
-        keyword: &str,
         limit: Option<i32>,
-    ) -> Result<Vec<TranslationRecord>> {
+        offset: Option<i32>,
+        filter: &HistoryFilter,
+    ) -> Result<Vec<HistoryEntry>> {
+        let mut conditions = Vec::new();
+        let mut values = Vec::new();
+        push_history_filter(filter, &mut conditions, &mut values);
+        let where_clause = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+        values.push(Value::Integer(limit.unwrap_or(50).into()));
+        values.push(Value::Integer(offset.unwrap_or(0).into()));
+
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT id, original_text, translated_text, service, from_language, to_language, 
-                    created_at
-             FROM translation_history 
-             WHERE original_text LIKE ?1 OR translated_text LIKE ?1
-             ORDER BY datetime(created_at) DESC
-             LIMIT ?2",
-        )?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT h.id, h.original_text, h.translated_text, h.service, h.from_language,
+                    h.to_language, h.created_at, h.group_id, h.operation
+             FROM translation_history h
+             {where_clause}
+             ORDER BY datetime(h.created_at) DESC
+             LIMIT ? OFFSET ?"
+        ))?;
 
-        let search_pattern = format!("%{}%", keyword);
-        let rows = stmt.query_map(params![search_pattern, limit.unwrap_or(50)], |row| {
-            Ok(TranslationRecord {
-                id: Some(row.get(0)?),
-                original_text: row.get(1)?,
-                translated_text: row.get(2)?,
-                service: row.get(3)?,
-                from_language: row.get(4)?,
-                to_language: row.get(5)?,
-                created_at: Some(row.get(6)?),
-            })
-        })?;
+        let rows = stmt.query_map(params_from_iter(values.iter()), row_to_translation_record)?;
 
         let mut records = Vec::new();
         for row in rows {
             records.push(row?);
         }
-        Ok(records)
-    }
-
-    // 保存用户设置
-    pub fn save_setting(&self, key: &str, value: &str) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
-        conn.execute(
-            "INSERT OR REPLACE INTO user_settings (key, value) VALUES (?1, ?2)",
-            params![key, value],
-        )?;
-        Ok(())
+        attach_annotations(&conn, records)
     }
 
-    // 获取用户设置
-    pub fn get_setting(&self, key: &str) -> Result<Option<String>> {
-        let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare("SELECT value FROM user_settings WHERE key = ?1")?;
-
-        let mut rows = stmt.query_map(params![key], |row| row.get::<_, String>(0))?;
-
-        if let Some(row) = rows.next() {
-            Ok(Some(row?))
+    // 全文搜索翻译历史：有可索引的关键词时默认按 BM25 相关度排序，否则按时间倒序；
+    // 游标与实际排序方式不一致时返回错误
+    pub fn search_history(
+        &self,
+        keyword: &str,
+        filter: &HistoryFilter,
+        sort: HistorySort,
+        cursor: Option<&HistoryCursor>,
+        limit: usize,
+    ) -> std::result::Result<HistorySearchPage, String> {
+        let terms = history_search::parse_query(keyword);
+        let fts = history_search::fts_expression(&terms);
+        let sort = if fts.is_some() {
+            sort
         } else {
-            Ok(None)
+            HistorySort::Newest
+        };
+        if let Some(cursor) = cursor {
+            cursor.validate(sort)?;
         }
+        self.query_history_page(&terms, fts, filter, sort, cursor, limit)
+            .map_err(|e| format!("搜索历史记录失败: {}", e))
     }
 
-    // 保存 API 密钥
-    pub fn save_api_key(&self, service: &str, api_key: &str) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
-        conn.execute(
-            "INSERT OR REPLACE INTO api_keys (service, api_key) VALUES (?1, ?2)",
-            params![service, api_key],
-        )?;
-        Ok(())
-    }
-
-    // 获取 API 密钥
-    pub fn get_api_key(&self, service: &str) -> Result<Option<String>> {
-        let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare("SELECT api_key FROM api_keys WHERE service = ?1")?;
+    // 查询 limit + 1 条来判断是否还有下一页；游标已由调用方按排序方式校验
+    fn query_history_page(
+        &self,
+        terms: &[history_search::SearchTerm],
+        fts: Option<String>,
+        filter: &HistoryFilter,
+        sort: HistorySort,
+        cursor: Option<&HistoryCursor>,
+        limit: usize,
+    ) -> Result<HistorySearchPage> {
+        let by_relevance = sort == HistorySort::Relevance;
 
-        let mut rows = stmt.query_map(params![service], |row| row.get::<_, String>(0))?;
+        let mut conditions: Vec<String> = Vec::new();
+        let mut values: Vec<Value> = Vec::new();
+        let (source, score) = match fts {
+            Some(expression) => {
+                conditions.push("translation_history_fts MATCH ?".to_string());
+                values.push(Value::Text(expression));
+                (
+                    "translation_history_fts JOIN translation_history h ON h.id = translation_history_fts.rowid",
+                    "bm25(translation_history_fts)",
+                )
+            }
+            None => ("translation_history h", "NULL"),
+        };
 
-        if let Some(row) = rows.next() {
-            Ok(Some(row?))
-        } else {
-            Ok(None)
+        // 三元组索引只能做子串匹配：前缀词另外要求出现在开头或空白之后，过短的词直接用 LIKE
+        for term in terms {
+            let text = history_search::escape_like(term.text());
+            if term.needs_word_boundary() {
+                conditions.push(
+                    "(h.original_text LIKE ? ESCAPE '\\' OR h.original_text LIKE ? ESCAPE '\\'
+                      OR h.original_text LIKE ? ESCAPE '\\' OR h.translated_text LIKE ? ESCAPE '\\'
+                      OR h.translated_text LIKE ? ESCAPE '\\' OR h.translated_text LIKE ? ESCAPE '\\')"
+                        .to_string(),
+                );
+                for _ in 0..2 {
+                    values.push(Value::Text(format!("{}%", text)));
+                    values.push(Value::Text(format!("% {}%", text)));
+                    values.push(Value::Text(format!("%\n{}%", text)));
+                }
+            } else if !term.is_indexed() {
+                conditions.push(
+                    "(h.original_text LIKE ? ESCAPE '\\' OR h.translated_text LIKE ? ESCAPE '\\')"
+                        .to_string(),
+                );
+                values.push(Value::Text(format!("%{}%", text)));
+                values.push(Value::Text(format!("%{}%", text)));
+            }
         }
-    }
 
-    // 删除翻译记录
-    #[allow(dead_code)]
-    pub fn delete_translation(&self, id: i64) -> Result<()> {
+        push_history_filter(filter, &mut conditions, &mut values);
+
+        let order = if by_relevance {
+            "score ASC, id DESC"
+        } else {
+            "sort_time DESC, id DESC"
+        };
+        // 游标条件放在外层查询，使 score 和 sort_time 可以直接比较
+        let outer_where = match cursor {
+            None => "",
+            Some(HistoryCursor::Relevance { score, id }) => {
+                values.extend([
+                    Value::Real(*score),
+                    Value::Real(*score),
+                    Value::Integer(*id),
+                ]);
+                "WHERE score > ? OR (score = ? AND id < ?)"
+            }
+            Some(HistoryCursor::Newest { created_at, id }) => {
+                values.extend([
+                    Value::Text(created_at.clone()),
+                    Value::Text(created_at.clone()),
+                    Value::Integer(*id),
+                ]);
+                "WHERE sort_time < ? OR (sort_time = ? AND id < ?)"
+            }
+        };
+        values.push(Value::Integer(limit as i64 + 1));
+
+        let inner_where = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+        let sql = format!(
+            "SELECT * FROM (
+                 SELECT h.id AS id, h.original_text, h.translated_text, h.service, h.from_language,
+                        h.to_language, h.created_at, h.group_id, h.operation,
+                        {score} AS score, datetime(h.created_at) AS sort_time
+                 FROM {source}
+                 {inner_where}
+             )
+             {outer_where}
+             ORDER BY {order}
+             LIMIT ?"
+        );
+
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(params_from_iter(values.iter()), |row| {
+            Ok((
+                row_to_translation_record(row)?,
+                row.get::<_, Option<f64>>(9)?,
+                row.get::<_, Option<String>>(10)?,
+            ))
+        })?;
+
+        let mut records = Vec::new();
+        let mut keys = Vec::new();
+        let mut has_more = false;
+        for row in rows {
+            let (record, score, sort_time) = row?;
+            if records.len() == limit {
+                has_more = true;
+                break;
+            }
+            keys.push((score, sort_time, record.id.unwrap_or_default()));
+            records.push(record);
+        }
+        let last_key = keys.last().cloned();
+
+        let items: Vec<HistorySearchHit> = attach_annotations(&conn, records)?
+            .into_iter()
+            .zip(keys)
+            .map(|(entry, (score, _, _))| HistorySearchHit {
+                original_snippet: history_search::snippet(&entry.record.original_text, terms),
+                translated_snippet: history_search::snippet(&entry.record.translated_text, terms),
+                entry,
+                score,
+            })
+            .collect();
+
+        let next_cursor = match last_key {
+            Some((score, sort_time, id)) if has_more => Some(if by_relevance {
+                HistoryCursor::Relevance {
+                    score: score.unwrap_or_default(),
+                    id,
+                }
+            } else {
+                HistoryCursor::Newest {
+                    created_at: sort_time.unwrap_or_default(),
+                    id,
+                }
+            }),
+            _ => None,
+        };
+        Ok(HistorySearchPage {
+            items,
+            next_cursor: next_cursor.map(|cursor| cursor.encode()),
+        })
+    }
+
+    // 保存记忆库条目；同一原文和译文已存在时累加使用次数，标记为首选时取消同一原文其他译文的首选
+    pub fn save_memory_entry(&self, entry: &MemoryEntry) -> Result<i64> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let id = upsert_memory_entry(&tx, entry)?;
+        tx.commit()?;
+        Ok(id)
+    }
+
+    // 批量导入记忆库条目，返回处理的条目数
+    pub fn import_memory_entries(&self, entries: &[MemoryEntry]) -> Result<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        for entry in entries {
+            upsert_memory_entry(&tx, entry)?;
+        }
+        tx.commit()?;
+        Ok(entries.len())
+    }
+
+    // 取出可能匹配的记忆库条目：目标语言相同、原文长度在范围内，源语言相同或未知
+    pub fn memory_candidates(
+        &self,
+        source_lang: &str,
+        target_lang: &str,
+        min_length: usize,
+        max_length: usize,
+    ) -> Result<Vec<MemoryEntry>> {
         let conn = self.conn.lock().unwrap();
-        conn.execute("DELETE FROM translation_history WHERE id = ?1", params![id])?;
+        let mut stmt = conn.prepare(
+            "SELECT id, source_text, target_text, source_lang, target_lang, origin, preferred,
+                    use_count, updated_at
+             FROM translation_memory
+             WHERE target_lang = ?1
+               AND (?2 = '' OR source_lang = '' OR source_lang = ?2)
+               AND source_length BETWEEN ?3 AND ?4",
+        )?;
+
+        let rows = stmt.query_map(
+            params![target_lang, source_lang, min_length as i64, max_length as i64],
+            row_to_memory_entry,
+        )?;
+
+        let mut entries = Vec::new();
+        for row in rows {
+            entries.push(row?);
+        }
+        Ok(entries)
+    }
+
+    // 列出记忆库条目，语言为空时不过滤
+    pub fn list_memory_entries(
+        &self,
+        source_lang: &str,
+        target_lang: &str,
+    ) -> Result<Vec<MemoryEntry>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, source_text, target_text, source_lang, target_lang, origin, preferred,
+                    use_count, updated_at
+             FROM translation_memory
+             WHERE (?1 = '' OR source_lang = ?1) AND (?2 = '' OR target_lang = ?2)
+             ORDER BY id",
+        )?;
+
+        let rows = stmt.query_map(params![source_lang, target_lang], row_to_memory_entry)?;
+
+        let mut entries = Vec::new();
+        for row in rows {
+            entries.push(row?);
+        }
+        Ok(entries)
+    }
+
+    // 删除记忆库条目
+    pub fn delete_memory_entry(&self, id: i64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM translation_memory WHERE id = ?1", params![id])?;
+        conn.execute(
+            "DELETE FROM translation_memory_history WHERE memory_id = ?1",
+            params![id],
+        )?;
         Ok(())
     }
 
-    // 清空翻译历史
-    pub fn clear_history(&self) -> Result<()> {
+    // 加入生词本；同一单词已存在时更新释义，合并例句，保留复习进度
+    pub fn save_vocabulary(&self, draft: &VocabularyDraft) -> Result<VocabularyItem> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let now = vocabulary::timestamp(Utc::now());
+        let kind = draft.kind.unwrap_or_default();
+
+        let existing = tx
+            .query_row(
+                &format!(
+                    "SELECT {VOCABULARY_COLUMNS} FROM vocabulary
+                     WHERE term = ?1 AND source_lang = ?2 AND target_lang = ?3"
+                ),
+                params![draft.term, draft.source_lang, draft.target_lang],
+                row_to_vocabulary_item,
+            )
+            .optional()?;
+
+        let id = match existing {
+            Some(item) => {
+                let examples = vocabulary::merge_examples(item.examples, draft.examples.clone());
+                tx.execute(
+                    "UPDATE vocabulary SET
+                        translation = ?2, kind = ?3, phonetic = COALESCE(?4, phonetic),
+                        examples = ?5, note = COALESCE(?6, note),
+                        history_id = COALESCE(?7, history_id), updated_at = ?8
+                     WHERE id = ?1",
+                    params![
+                        item.id,
+                        draft.translation,
+                        kind.as_str(),
+                        draft.phonetic,
+                        examples_json(&examples),
+                        draft.note,
+                        draft.history_id,
+                        now
+                    ],
+                )?;
+                item.id
+            }
+            None => {
+                let review = ReviewState::new(Utc::now());
+                tx.execute(
+                    "INSERT INTO vocabulary
+                        (term, translation, source_lang, target_lang, kind, phonetic, examples,
+                         note, history_id, ease, due_at, created_at, updated_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?12)",
+                    params![
+                        draft.term,
+                        draft.translation,
+                        draft.source_lang,
+                        draft.target_lang,
+                        kind.as_str(),
+                        draft.phonetic,
+                        examples_json(&draft.examples),
+                        draft.note,
+                        draft.history_id,
+                        review.ease,
+                        review.due_at,
+                        now
+                    ],
+                )?;
+                tx.last_insert_rowid()
+            }
+        };
+
+        let item = get_vocabulary_item(&tx, id)?;
+        tx.commit()?;
+        Ok(item)
+    }
+
+    // 修改生词本条目的内容，复习进度不变
+    pub fn update_vocabulary(&self, id: i64, draft: &VocabularyDraft) -> Result<VocabularyItem> {
         let conn = self.conn.lock().unwrap();
-        conn.execute("DELETE FROM translation_history", [])?;
+        let updated = conn.execute(
+            "UPDATE vocabulary SET
+                term = ?2, translation = ?3, source_lang = ?4, target_lang = ?5, kind = ?6,
+                phonetic = ?7, examples = ?8, note = ?9, updated_at = ?10
+             WHERE id = ?1",
+            params![
+                id,
+                draft.term,
+                draft.translation,
+                draft.source_lang,
+                draft.target_lang,
+                draft.kind.unwrap_or_default().as_str(),
+                draft.phonetic,
+                examples_json(&draft.examples),
+                draft.note,
+                vocabulary::timestamp(Utc::now())
+            ],
+        )?;
+        if updated == 0 {
+            return Err(RusqliteError::QueryReturnedNoRows);
+        }
+        get_vocabulary_item(&conn, id)
+    }
+
+    pub fn delete_vocabulary(&self, id: i64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM vocabulary WHERE id = ?1", params![id])?;
         Ok(())
     }
 
-    // 保存应用配置
+    // 按加入时间倒序列出生词本，keyword 匹配单词或释义；limit 为空时返回全部
+    pub fn list_vocabulary(
+        &self,
+        keyword: Option<&str>,
+        limit: Option<i64>,
+        offset: Option<i64>,
+    ) -> Result<Vec<VocabularyItem>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {VOCABULARY_COLUMNS} FROM vocabulary
+             WHERE ?1 IS NULL OR term LIKE ?1 ESCAPE '\\' OR translation LIKE ?1 ESCAPE '\\'
+             ORDER BY id DESC
+             LIMIT ?2 OFFSET ?3"
+        ))?;
+
+        let pattern = keyword
+            .map(str::trim)
+            .filter(|keyword| !keyword.is_empty())
+            .map(|keyword| format!("%{}%", history_search::escape_like(keyword)));
+        let rows = stmt.query_map(
+            params![pattern, limit.unwrap_or(-1), offset.unwrap_or(0)],
+            row_to_vocabulary_item,
+        )?;
+
+        let mut items = Vec::new();
+        for row in rows {
+            items.push(row?);
+        }
+        Ok(items)
+    }
+
+    // 到期待复习的条目，最早到期的在前
+    pub fn due_vocabulary(&self, now: &str, limit: i64) -> Result<Vec<VocabularyItem>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {VOCABULARY_COLUMNS} FROM vocabulary
+             WHERE due_at <= ?1
+             ORDER BY due_at, id
+             LIMIT ?2"
+        ))?;
+
+        let rows = stmt.query_map(params![now, limit], row_to_vocabulary_item)?;
+
+        let mut items = Vec::new();
+        for row in rows {
+            items.push(row?);
+        }
+        Ok(items)
+    }
+
+    // 记录一次复习：按 SM-2 更新调度状态并写入复习记录
+    pub fn review_vocabulary(
+        &self,
+        id: i64,
+        grade: ReviewGrade,
+        now: DateTime<Utc>,
+    ) -> Result<VocabularyItem> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let item = get_vocabulary_item(&tx, id)?;
+        let review = vocabulary::schedule(&item.review, grade, now);
+
+        tx.execute(
+            "UPDATE vocabulary SET
+                ease = ?2, interval_days = ?3, repetitions = ?4, lapses = ?5,
+                due_at = ?6, last_reviewed_at = ?7
+             WHERE id = ?1",
+            params![
+                id,
+                review.ease,
+                review.interval_days,
+                review.repetitions,
+                review.lapses,
+                review.due_at,
+                review.last_reviewed_at
+            ],
+        )?;
+        tx.execute(
+            "INSERT INTO vocabulary_reviews (vocabulary_id, grade, interval_days, ease, reviewed_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                id,
+                grade.as_str(),
+                review.interval_days,
+                review.ease,
+                vocabulary::timestamp(now)
+            ],
+        )?;
+
+        let item = get_vocabulary_item(&tx, id)?;
+        tx.commit()?;
+        Ok(item)
+    }
+
+    pub fn vocabulary_stats(&self, now: DateTime<Utc>) -> Result<VocabularyStats> {
+        let conn = self.conn.lock().unwrap();
+        let day_start = vocabulary::timestamp(
+            now.date_naive()
+                .and_hms_opt(0, 0, 0)
+                .unwrap_or_default()
+                .and_utc(),
+        );
+        conn.query_row(
+            "SELECT
+                (SELECT COUNT(*) FROM vocabulary),
+                (SELECT COUNT(*) FROM vocabulary WHERE due_at <= ?1),
+                (SELECT COUNT(*) FROM vocabulary WHERE last_reviewed_at IS NULL),
+                (SELECT COUNT(*) FROM vocabulary_reviews WHERE reviewed_at >= ?2)",
+            params![vocabulary::timestamp(now), day_start],
+            |row| {
+                Ok(VocabularyStats {
+                    total: row.get(0)?,
+                    due: row.get(1)?,
+                    new_items: row.get(2)?,
+                    reviewed_today: row.get(3)?,
+                })
+            },
+        )
+    }
+
+    // 按 id 获取单条翻译历史
+    pub fn get_translation(&self, id: i64) -> Result<Option<TranslationRecord>> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT id, original_text, translated_text, service, from_language, to_language,
+                    created_at, group_id, operation
+             FROM translation_history
+             WHERE id = ?1",
+            params![id],
+            row_to_translation_record,
+        )
+        .optional()
+    }
+
+    // 保存用户设置
+    pub fn save_setting(&self, key: &str, value: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO user_settings (key, value) VALUES (?1, ?2)",
+            params![key, value],
+        )?;
+        Ok(())
+    }
+
+    // 获取用户设置
+    pub fn get_setting(&self, key: &str) -> Result<Option<String>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT value FROM user_settings WHERE key = ?1")?;
+
+        let mut rows = stmt.query_map(params![key], |row| row.get::<_, String>(0))?;
+
+        if let Some(row) = rows.next() {
+            Ok(Some(row?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    // 旧版本明文保存的 API 密钥，迁移到密钥存储后删除
+    pub fn list_api_keys(&self) -> Result<Vec<(String, String)>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT service, api_key FROM api_keys ORDER BY service")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+        let mut keys = Vec::new();
+        for row in rows {
+            keys.push(row?);
+        }
+        Ok(keys)
+    }
+
+    pub fn delete_api_key(&self, service: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM api_keys WHERE service = ?1", params![service])?;
+        Ok(())
+    }
+
+    // 删除一条翻译记录，移入回收站以便撤销；记录不存在时返回 QueryReturnedNoRows
+    pub fn delete_translation(&self, id: i64) -> Result<HistoryDeletion> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        ensure_history_exists(&tx, id)?;
+        let deletion = housekeeping::move_to_trash(&tx, "h.id = ?", vec![Value::Integer(id)])?;
+        tx.commit()?;
+        Ok(deletion)
+    }
+
+    // 按筛选条件批量删除，移入回收站以便撤销
+    pub fn delete_history(&self, filter: &HistoryFilter) -> Result<HistoryDeletion> {
+        let mut conditions = Vec::new();
+        let mut values = Vec::new();
+        push_history_filter(filter, &mut conditions, &mut values);
+        if conditions.is_empty() {
+            conditions.push("1 = 1".to_string());
+        }
+
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let deletion = housekeeping::move_to_trash(&tx, &conditions.join(" AND "), values)?;
+        tx.commit()?;
+        Ok(deletion)
+    }
+
+    // 撤销 delete_translation 或 delete_history，返回恢复的条数；已过期或已撤销时返回 QueryReturnedNoRows
+    pub fn undo_history_deletion(&self, deletion_id: i64) -> Result<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let restored = housekeeping::restore_from_trash(&tx, deletion_id)?;
+        tx.commit()?;
+        Ok(restored)
+    }
+
+    // 执行保留策略并清空过期的回收站，返回（过期删除的条数，回收站永久删除的条数）
+    pub fn expire_history(&self, policy: &HistoryRetentionConfig) -> Result<(usize, usize)> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let expired = housekeeping::apply_retention(&tx, policy)?;
+        let purged = housekeeping::purge_trash(&tx)?;
+        tx.commit()?;
+        Ok((expired, purged))
+    }
+
+    // 大量删除后空闲页较多时整理数据库，返回是否执行了 VACUUM
+    pub fn vacuum_if_fragmented(&self) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        housekeeping::vacuum_if_fragmented(&conn)
+    }
+
+    // 清空翻译历史，include_favorites 为 false 时保留收藏的记录；与其他删除一样移入回收站以便撤销，
+    // 记忆库条目和数据库整理在回收站过期清理时处理
+    pub fn clear_history(&self, include_favorites: bool) -> Result<HistoryDeletion> {
+        let condition = if include_favorites {
+            "1 = 1"
+        } else {
+            housekeeping::NOT_FAVORITE
+        };
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let deletion = housekeeping::move_to_trash(&tx, condition, Vec::new())?;
+        tx.commit()?;
+        Ok(deletion)
+    }
+
+    // 设置或取消收藏
+    pub fn set_history_favorite(&self, id: i64, favorite: bool) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        ensure_history_exists(&tx, id)?;
+        tx.execute(
+            "INSERT INTO history_annotations (history_id, favorite, updated_at)
+             VALUES (?1, ?2, ?3)
+             ON CONFLICT(history_id) DO UPDATE SET
+                 favorite = excluded.favorite,
+                 updated_at = excluded.updated_at",
+            params![id, favorite, Utc::now().to_rfc3339()],
+        )?;
+        remove_empty_annotation(&tx, id)?;
+        tx.commit()
+    }
+
+    // 设置备注，空白备注视为删除
+    pub fn set_history_note(&self, id: i64, note: Option<&str>) -> Result<()> {
+        let note = note.map(str::trim).filter(|note| !note.is_empty());
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        ensure_history_exists(&tx, id)?;
+        tx.execute(
+            "INSERT INTO history_annotations (history_id, note, updated_at)
+             VALUES (?1, ?2, ?3)
+             ON CONFLICT(history_id) DO UPDATE SET
+                 note = excluded.note,
+                 updated_at = excluded.updated_at",
+            params![id, note, Utc::now().to_rfc3339()],
+        )?;
+        remove_empty_annotation(&tx, id)?;
+        tx.commit()
+    }
+
+    // 用给定标签替换记录原有的全部标签，标签应已由 history_search::normalize_tags 整理
+    pub fn set_history_tags(&self, id: i64, tags: &[String]) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        ensure_history_exists(&tx, id)?;
+        tx.execute(
+            "DELETE FROM history_tags WHERE history_id = ?1",
+            params![id],
+        )?;
+        for tag in tags {
+            tx.execute(
+                "INSERT OR IGNORE INTO history_tags (history_id, tag) VALUES (?1, ?2)",
+                params![id, tag],
+            )?;
+        }
+        tx.commit()
+    }
+
+    // 所有标签及使用次数，按次数和名称排序
+    pub fn list_history_tags(&self) -> Result<Vec<TagCount>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT MIN(tag), COUNT(*) AS count
+             FROM history_tags
+             GROUP BY tag
+             ORDER BY count DESC, MIN(tag) COLLATE NOCASE",
+        )?;
+
+        let rows = stmt.query_map([], |row| {
+            Ok(TagCount {
+                tag: row.get(0)?,
+                count: row.get(1)?,
+            })
+        })?;
+
+        let mut tags = Vec::new();
+        for row in rows {
+            tags.push(row?);
+        }
+        Ok(tags)
+    }
+
+    // 在一个事务中导入历史记录。原文、译文和语言都相同的记录视为重复，不再新增，
+    // 只合并批注：收藏取并集、补充缺少的标签、原来没有备注时使用导入的备注
+    pub fn import_history(&self, entries: &[HistoryEntry]) -> Result<HistoryImportSummary> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let now = Utc::now().to_rfc3339();
+        let mut summary = HistoryImportSummary::default();
+
+        // 原文、译文和语言对（按目录代码归一、忽略大小写）都相同的记录视为重复；一次读出已有记录，避免逐条查询
+        let history_key = |original: &str, translated: &str, from: &str, to: &str| {
+            (
+                original.to_string(),
+                translated.to_string(),
+                languages::normalize(from).to_ascii_lowercase(),
+                languages::normalize(to).to_ascii_lowercase(),
+            )
+        };
+        let mut known: HashMap<(String, String, String, String), i64> = HashMap::new();
+        {
+            let mut stmt = tx.prepare(
+                "SELECT id, original_text, translated_text,
+                        IFNULL(from_language, ''), IFNULL(to_language, '')
+                 FROM translation_history
+                 ORDER BY id",
+            )?;
+            let rows = stmt.query_map([], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, String>(4)?,
+                ))
+            })?;
+            for row in rows {
+                let (id, original, translated, from, to) = row?;
+                known
+                    .entry(history_key(&original, &translated, &from, &to))
+                    .or_insert(id);
+            }
+        }
+
+        for entry in entries {
+            let record = &entry.record;
+            let key = history_key(
+                &record.original_text,
+                &record.translated_text,
+                record.from_language.as_deref().unwrap_or_default(),
+                record.to_language.as_deref().unwrap_or_default(),
+            );
+            let existing = known.get(&key).copied();
+
+            let id = match existing {
+                Some(id) => {
+                    summary.duplicates += 1;
+                    id
+                }
+                None => {
+                    let created_at = record.created_at.clone().unwrap_or_else(|| now.clone());
+                    let id = insert_translation(&tx, record, &created_at, None)?;
+                    index_history_memory(&tx, id, record)?;
+                    known.insert(key, id);
+                    summary.imported += 1;
+                    id
+                }
+            };
+
+            if entry.favorite || entry.note.is_some() {
+                tx.execute(
+                    "INSERT INTO history_annotations (history_id, favorite, note, updated_at)
+                     VALUES (?1, ?2, ?3, ?4)
+                     ON CONFLICT(history_id) DO UPDATE SET
+                         favorite = MAX(favorite, excluded.favorite),
+                         note = IFNULL(note, excluded.note),
+                         updated_at = excluded.updated_at",
+                    params![id, entry.favorite, entry.note, now],
+                )?;
+            }
+            for tag in &entry.tags {
+                tx.execute(
+                    "INSERT OR IGNORE INTO history_tags (history_id, tag) VALUES (?1, ?2)",
+                    params![id, tag],
+                )?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(summary)
+    }
+
+    // 新建后台任务
+    pub fn create_job(&self, kind: &str, params: &serde_json::Value) -> Result<JobRecord> {
+        let id = {
+            let conn = self.conn.lock().unwrap();
+            let now = Utc::now().to_rfc3339();
+            conn.execute(
+                "INSERT INTO jobs (kind, params, status, progress, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, 0, ?4, ?4)",
+                params![kind, params.to_string(), JobStatus::Queued.as_str(), now],
+            )?;
+            conn.last_insert_rowid()
+        };
+        self.get_job(id)?.ok_or(RusqliteError::QueryReturnedNoRows)
+    }
+
+    // 获取单个后台任务
+    pub fn get_job(&self, id: i64) -> Result<Option<JobRecord>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, kind, params, status, progress, message, result_path, error,
+                    created_at, updated_at
+             FROM jobs WHERE id = ?1",
+        )?;
+        let mut rows = stmt.query_map(params![id], job_from_row)?;
+        rows.next().transpose()
+    }
+
+    // 获取后台任务列表（最新的在前）
+    pub fn list_jobs(&self, limit: Option<i32>) -> Result<Vec<JobRecord>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, kind, params, status, progress, message, result_path, error,
+                    created_at, updated_at
+             FROM jobs ORDER BY id DESC LIMIT ?1",
+        )?;
+        let rows = stmt.query_map(params![limit.unwrap_or(100)], job_from_row)?;
+        rows.collect()
+    }
+
+    // 取出最早排队的任务并标记为运行中
+    pub fn claim_next_job(&self) -> Result<Option<JobRecord>> {
+        let id: Option<i64> = {
+            let conn = self.conn.lock().unwrap();
+            let id = conn
+                .query_row(
+                    "SELECT id FROM jobs WHERE status = ?1 ORDER BY id ASC LIMIT 1",
+                    params![JobStatus::Queued.as_str()],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            if let Some(id) = id {
+                conn.execute(
+                    "UPDATE jobs SET status = ?2, error = NULL, updated_at = ?3 WHERE id = ?1",
+                    params![id, JobStatus::Running.as_str(), Utc::now().to_rfc3339()],
+                )?;
+            }
+            id
+        };
+        match id {
+            Some(id) => self.get_job(id),
+            None => Ok(None),
+        }
+    }
+
+    // 更新任务状态；进入排队状态时清空上一次的错误信息，进度保留给继续执行的任务
+    pub fn update_job_status(
+        &self,
+        id: i64,
+        status: JobStatus,
+        error: Option<&str>,
+        result_path: Option<&str>,
+    ) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE jobs SET status = ?2, error = ?3,
+                    result_path = COALESCE(?4, result_path),
+                    progress = CASE WHEN ?2 = 'done' THEN 100 ELSE progress END,
+                    updated_at = ?5
+             WHERE id = ?1",
+            params![id, status.as_str(), error, result_path, Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    // 重试的任务从头计算进度
+    pub fn reset_job_progress(&self, id: i64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE jobs SET progress = 0, message = NULL, updated_at = ?2 WHERE id = ?1",
+            params![id, Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    // 更新任务进度
+    pub fn update_job_progress(&self, id: i64, progress: f64, message: Option<&str>) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE jobs SET progress = ?2, message = COALESCE(?3, message), updated_at = ?4
+             WHERE id = ?1",
+            params![id, progress, message, Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    // 应用上次退出时仍在运行的任务重新排队，启动后自动续跑
+    pub fn requeue_interrupted_jobs(&self) -> Result<usize> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE jobs SET status = ?1, progress = 0 WHERE status = ?2",
+            params![JobStatus::Queued.as_str(), JobStatus::Running.as_str()],
+        )
+    }
+
+    // 保存当前配置档案的配置
     pub fn save_app_config(&self, config: &AppConfig) -> Result<()> {
-        println!("正在保存应用配置到数据库...");
+        let profile_id = self.active_profile_id()?;
+        self.save_profile_config(profile_id, config)
+    }
+
+    // 获取当前配置档案的配置
+    pub fn get_app_config(&self) -> Result<Option<AppConfig>> {
+        let profile_id = self.active_profile_id()?;
+        self.get_profile_config(profile_id).map(Some)
+    }
+
+    pub fn active_profile_id(&self) -> Result<i64> {
+        let conn = self.conn.lock().unwrap();
+        profiles::active_id(&conn)
+    }
+
+    pub fn list_config_profiles(&self) -> Result<Vec<ConfigProfile>> {
+        let conn = self.conn.lock().unwrap();
+        profiles::list(&conn)
+    }
+
+    // 档案不存在时返回 QueryReturnedNoRows
+    pub fn get_config_profile(&self, id: i64) -> Result<ConfigProfile> {
+        let conn = self.conn.lock().unwrap();
+        profiles::get(&conn, id)
+    }
+
+    // 保存指定档案的配置；档案不存在时返回 QueryReturnedNoRows
+    pub fn save_profile_config(&self, profile_id: i64, config: &AppConfig) -> Result<()> {
+        println!("正在保存配置档案 {} 的配置...", profile_id);
         let config_json = serde_json::to_string(config)
             .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
 
-        match self.save_setting("app_config", &config_json) {
+        let conn = self.conn.lock().unwrap();
+        match profiles::store_config(&conn, profile_id, Some(&config_json)) {
             Ok(_) => {
                 println!("配置已保存到数据库");
                 Ok(())
@@ -385,33 +1741,92 @@ impl Database {
         }
     }
 
-    // 获取应用配置
-    pub fn get_app_config(&self) -> Result<Option<AppConfig>> {
-        println!("尝试从数据库加载配置...");
-        if let Some(config_json) = self.get_setting("app_config")? {
-            match serde_json::from_str::<AppConfig>(&config_json) {
-                Ok(config) => {
-                    println!("从数据库加载配置成功");
-                    Ok(Some(config))
-                }
-                Err(e) => {
-                    eprintln!("解析数据库中的配置失败: {}", e);
-                    // 解析失败时返回错误，以便前端知道发生了什么
-                    Err(rusqlite::Error::FromSqlConversionFailure(
-                        0,
-                        rusqlite::types::Type::Text,
-                        Box::new(e),
-                    ))
+    // 获取指定档案的配置，未保存过配置时使用默认配置；档案不存在时返回 QueryReturnedNoRows
+    pub fn get_profile_config(&self, profile_id: i64) -> Result<AppConfig> {
+        println!("尝试从数据库加载配置档案 {} 的配置...", profile_id);
+        let stored = {
+            let conn = self.conn.lock().unwrap();
+            profiles::load_config(&conn, profile_id)?
+        };
+        let Some(config_json) = stored else {
+            println!("未找到配置，使用默认配置");
+            return Ok(self.default_config());
+        };
+
+        match serde_json::from_str::<AppConfig>(&config_json) {
+            Ok(config) => {
+                println!("从数据库加载配置成功");
+                Ok(config)
+            }
+            Err(e) => {
+                // 解析失败时把原配置另存后清空，改用默认配置，避免每次读取都失败
+                let quarantine_key = format!(
+                    "config_profile.{}.corrupt-{}",
+                    profile_id,
+                    Utc::now().format("%Y%m%dT%H%M%SZ")
+                );
+                eprintln!(
+                    "解析数据库中的配置失败: {}，原配置已另存为 {}",
+                    e, quarantine_key
+                );
+                {
+                    let mut conn = self.conn.lock().unwrap();
+                    let tx = conn.transaction()?;
+                    tx.execute(
+                        "INSERT OR REPLACE INTO user_settings (key, value) VALUES (?1, ?2)",
+                        params![quarantine_key, config_json],
+                    )?;
+                    profiles::store_config(&tx, profile_id, None)?;
+                    tx.commit()?;
                 }
+                self.status.lock().unwrap().warnings.push(format!(
+                    "配置无法解析（{}），已另存为 {} 并恢复默认配置",
+                    e, quarantine_key
+                ));
+                Ok(self.default_config())
             }
-        } else {
-            println!("未找到配置，使用默认配置");
-            Ok(self.get_default_config())
         }
     }
 
-    fn get_default_config(&self) -> Option<AppConfig> {
-        Some(AppConfig {
+    // 新建配置档案，config 为空时使用默认配置；名称重复时返回唯一约束错误
+    pub fn create_config_profile(
+        &self,
+        name: &str,
+        config: Option<&AppConfig>,
+    ) -> Result<ConfigProfile> {
+        let config_json = config
+            .map(serde_json::to_string)
+            .transpose()
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+
+        let conn = self.conn.lock().unwrap();
+        let id = profiles::create(&conn, name, config_json.as_deref())?;
+        profiles::get(&conn, id)
+    }
+
+    // 复制配置档案（不含密钥，密钥由调用方在密钥存储中复制）
+    pub fn clone_config_profile(&self, source_id: i64, name: &str) -> Result<ConfigProfile> {
+        let conn = self.conn.lock().unwrap();
+        let id = profiles::duplicate(&conn, source_id, name)?;
+        profiles::get(&conn, id)
+    }
+
+    // 切换当前配置档案；档案不存在时返回 QueryReturnedNoRows
+    pub fn switch_config_profile(&self, id: i64) -> Result<ConfigProfile> {
+        let conn = self.conn.lock().unwrap();
+        profiles::get(&conn, id)?;
+        profiles::activate(&conn, id)?;
+        profiles::get(&conn, id)
+    }
+
+    // 删除配置档案，调用方需确保删除的不是当前档案
+    pub fn delete_config_profile(&self, id: i64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        profiles::delete(&conn, id)
+    }
+
+    fn default_config(&self) -> AppConfig {
+        AppConfig {
             translation: TranslationConfig {
                 service: "openai".to_string(),
                 base_url: "https://api.openai.com/v1".to_string(),
@@ -428,8 +1843,423 @@ impl Database {
             proxy: ProxyConfig::default(),
             token_limits: TokenLimitConfig::default(),
             autostart: AutostartConfig::default(),
+            naming: NamingConfig::default(),
+            prompt_templates: PromptTemplatesConfig::default(),
+            dictionary: DictionaryConfig::default(),
+            quality: QualityConfig::default(),
+            romanization: RomanizationConfig::default(),
+            translation_memory: TranslationMemoryConfig::default(),
+            history_retention: HistoryRetentionConfig::default(),
+        }
+    }
+}
+
+fn ensure_history_exists(conn: &Connection, id: i64) -> Result<()> {
+    conn.query_row(
+        "SELECT id FROM translation_history WHERE id = ?1",
+        params![id],
+        |_| Ok(()),
+    )
+}
+
+fn insert_translation(
+    conn: &Connection,
+    record: &TranslationRecord,
+    created_at: &str,
+    group_id: Option<i64>,
+) -> Result<i64> {
+    conn.execute(
+        "INSERT INTO translation_history
+         (original_text, translated_text, service, from_language, to_language, created_at,
+          group_id, operation)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            record.original_text,
+            record.translated_text,
+            record.service,
+            record.from_language,
+            record.to_language,
+            created_at,
+            group_id,
+            record.operation
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+// 既未收藏也没有备注的批注行没有意义，直接删除
+fn remove_empty_annotation(conn: &Connection, id: i64) -> Result<()> {
+    conn.execute(
+        "DELETE FROM history_annotations WHERE history_id = ?1 AND favorite = 0 AND note IS NULL",
+        params![id],
+    )?;
+    Ok(())
+}
+
+// 为历史记录附上收藏、备注和标签
+fn attach_annotations(
+    conn: &Connection,
+    records: Vec<TranslationRecord>,
+) -> Result<Vec<HistoryEntry>> {
+    let ids: Vec<i64> = records.iter().filter_map(|record| record.id).collect();
+    let mut annotations: HashMap<i64, (bool, Option<String>)> = HashMap::new();
+    let mut tags: HashMap<i64, Vec<String>> = HashMap::new();
+
+    if !ids.is_empty() {
+        let placeholders = vec!["?"; ids.len()].join(", ");
+        let mut stmt = conn.prepare(&format!(
+            "SELECT history_id, favorite, note FROM history_annotations
+             WHERE history_id IN ({placeholders})"
+        ))?;
+        let rows = stmt.query_map(params_from_iter(ids.iter()), |row| {
+            Ok((row.get::<_, i64>(0)?, row.get(1)?, row.get(2)?))
+        })?;
+        for row in rows {
+            let (id, favorite, note) = row?;
+            annotations.insert(id, (favorite, note));
+        }
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT history_id, tag FROM history_tags
+             WHERE history_id IN ({placeholders})
+             ORDER BY created_at, rowid"
+        ))?;
+        let rows = stmt.query_map(params_from_iter(ids.iter()), |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?;
+        for row in rows {
+            let (id, tag) = row?;
+            tags.entry(id).or_default().push(tag);
+        }
+    }
+
+    Ok(records
+        .into_iter()
+        .map(|record| {
+            let id = record.id.unwrap_or_default();
+            let (favorite, note) = annotations.remove(&id).unwrap_or_default();
+            HistoryEntry {
+                favorite,
+                note,
+                tags: tags.remove(&id).unwrap_or_default(),
+                record,
+            }
         })
+        .collect())
+}
+
+// 把筛选条件追加为 translation_history h 上的 WHERE 条件
+fn push_history_filter(
+    filter: &HistoryFilter,
+    conditions: &mut Vec<String>,
+    values: &mut Vec<Value>,
+) {
+    for (column, code) in [
+        ("h.from_language", &filter.from_language),
+        ("h.to_language", &filter.to_language),
+    ] {
+        let Some(code) = code.as_deref().filter(|code| !code.trim().is_empty()) else {
+            continue;
+        };
+        let codes = languages::equivalent_codes(code);
+        conditions.push(format!(
+            "{} COLLATE NOCASE IN ({})",
+            column,
+            vec!["?"; codes.len()].join(", ")
+        ));
+        values.extend(codes.into_iter().map(Value::Text));
+    }
+    if let Some(service) = filter.service.as_deref().filter(|s| !s.trim().is_empty()) {
+        conditions.push("h.service = ? COLLATE NOCASE".to_string());
+        values.push(Value::Text(service.trim().to_string()));
+    }
+    if let Some(since) = filter.since.as_deref() {
+        conditions.push("datetime(h.created_at) >= datetime(?)".to_string());
+        values.push(Value::Text(since.to_string()));
+    }
+    if let Some(until) = filter.until.as_deref() {
+        // 只给日期时包含当天全天
+        if history_search::is_date_only(until) {
+            conditions.push("datetime(h.created_at) < datetime(?, '+1 day')".to_string());
+        } else {
+            conditions.push("datetime(h.created_at) <= datetime(?)".to_string());
+        }
+        values.push(Value::Text(until.to_string()));
+    }
+    if let Some(favorite) = filter.favorite {
+        conditions.push(format!(
+            "{}EXISTS (SELECT 1 FROM history_annotations a WHERE a.history_id = h.id AND a.favorite = 1)",
+            if favorite { "" } else { "NOT " }
+        ));
+    }
+    for tag in &filter.tags {
+        let tag = tag.trim();
+        if tag.is_empty() {
+            continue;
+        }
+        conditions.push(
+            "EXISTS (SELECT 1 FROM history_tags t WHERE t.history_id = h.id AND t.tag = ?)"
+                .to_string(),
+        );
+        values.push(Value::Text(tag.to_string()));
+    }
+}
+
+fn row_to_translation_record(row: &rusqlite::Row<'_>) -> Result<TranslationRecord> {
+    Ok(TranslationRecord {
+        id: Some(row.get(0)?),
+        original_text: row.get(1)?,
+        translated_text: row.get(2)?,
+        service: row.get(3)?,
+        from_language: row.get(4)?,
+        to_language: row.get(5)?,
+        created_at: Some(row.get(6)?),
+        group_id: row.get(7)?,
+        operation: row.get(8)?,
+    })
+}
+
+const VOCABULARY_COLUMNS: &str = "id, term, translation, source_lang, target_lang, kind, phonetic,
+    examples, note, history_id, ease, interval_days, repetitions, lapses, due_at, last_reviewed_at,
+    created_at, updated_at";
+
+fn get_vocabulary_item(conn: &Connection, id: i64) -> Result<VocabularyItem> {
+    conn.query_row(
+        &format!("SELECT {VOCABULARY_COLUMNS} FROM vocabulary WHERE id = ?1"),
+        params![id],
+        row_to_vocabulary_item,
+    )
+}
+
+fn row_to_vocabulary_item(row: &rusqlite::Row<'_>) -> Result<VocabularyItem> {
+    let kind: String = row.get(5)?;
+    let examples: String = row.get(7)?;
+    Ok(VocabularyItem {
+        id: row.get(0)?,
+        term: row.get(1)?,
+        translation: row.get(2)?,
+        source_lang: row.get(3)?,
+        target_lang: row.get(4)?,
+        kind: VocabularyKind::parse(&kind),
+        phonetic: row.get(6)?,
+        examples: serde_json::from_str(&examples).unwrap_or_default(),
+        note: row.get(8)?,
+        history_id: row.get(9)?,
+        review: ReviewState {
+            ease: row.get(10)?,
+            interval_days: row.get(11)?,
+            repetitions: row.get(12)?,
+            lapses: row.get(13)?,
+            due_at: row.get(14)?,
+            last_reviewed_at: row.get(15)?,
+        },
+        created_at: row.get(16)?,
+        updated_at: row.get(17)?,
+    })
+}
+
+fn examples_json(examples: &[DictionaryExample]) -> String {
+    serde_json::to_string(examples).unwrap_or_else(|_| "[]".to_string())
+}
+
+fn row_to_memory_entry(row: &rusqlite::Row<'_>) -> Result<MemoryEntry> {
+    let origin: String = row.get(5)?;
+    Ok(MemoryEntry {
+        id: Some(row.get(0)?),
+        source_text: row.get(1)?,
+        target_text: row.get(2)?,
+        source_lang: row.get(3)?,
+        target_lang: row.get(4)?,
+        origin: MemoryOrigin::parse(&origin),
+        preferred: row.get(6)?,
+        use_count: row.get(7)?,
+        updated_at: row.get(8)?,
+    })
+}
+
+fn upsert_memory_entry(conn: &Connection, entry: &MemoryEntry) -> Result<i64> {
+    let normalized = translation_memory::normalize_segment(&entry.source_text);
+    let updated_at = entry
+        .updated_at
+        .clone()
+        .unwrap_or_else(|| Utc::now().to_rfc3339());
+
+    if entry.preferred {
+        conn.execute(
+            "UPDATE translation_memory SET preferred = 0
+             WHERE normalized_source = ?1 AND source_lang = ?2 AND target_lang = ?3",
+            params![normalized, entry.source_lang, entry.target_lang],
+        )?;
+    }
+    conn.query_row(
+        "INSERT INTO translation_memory
+         (source_text, target_text, normalized_source, source_length, source_lang, target_lang,
+          origin, preferred, use_count, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?10)
+         ON CONFLICT(normalized_source, source_lang, target_lang, target_text) DO UPDATE SET
+             use_count = use_count + excluded.use_count,
+             preferred = MAX(preferred, excluded.preferred),
+             origin = CASE WHEN excluded.preferred = 1 THEN excluded.origin ELSE origin END,
+             updated_at = MAX(updated_at, excluded.updated_at)
+         RETURNING id",
+        params![
+            entry.source_text,
+            entry.target_text,
+            normalized,
+            normalized.chars().count() as i64,
+            entry.source_lang,
+            entry.target_lang,
+            entry.origin.as_str(),
+            entry.preferred,
+            entry.use_count.max(1),
+            updated_at
+        ],
+        |row| row.get(0),
+    )
+}
+
+/// 普通翻译的历史记录同时写入记忆库，写作工具等其他操作不写入；返回记忆库条目的 id
+fn index_translation_memory(conn: &Connection, record: &TranslationRecord) -> Result<Option<i64>> {
+    let Some(entry) = history_memory_entry(record) else {
+        return Ok(None);
+    };
+    upsert_memory_entry(conn, &entry).map(Some)
+}
+
+fn history_memory_entry(record: &TranslationRecord) -> Option<MemoryEntry> {
+    if record.operation.is_some()
+        || record.original_text.trim().is_empty()
+        || record.translated_text.trim().is_empty()
+    {
+        return None;
+    }
+    let mut entry = MemoryEntry::new(
+        &record.original_text,
+        &record.translated_text,
+        record.from_language.as_deref().unwrap_or_default(),
+        record.to_language.as_deref().unwrap_or_default(),
+        MemoryOrigin::History,
+    );
+    entry.updated_at = record.created_at.clone();
+    Some(entry)
+}
+
+/// 写入记忆库并记录来源历史，历史被永久删除后由 prune_history_memory 清理
+fn index_history_memory(
+    conn: &Connection,
+    history_id: i64,
+    record: &TranslationRecord,
+) -> Result<()> {
+    if let Some(memory_id) = index_translation_memory(conn, record)? {
+        conn.execute(
+            "INSERT OR IGNORE INTO translation_memory_history (memory_id, history_id)
+             VALUES (?1, ?2)",
+            params![memory_id, history_id],
+        )?;
+    }
+    Ok(())
+}
+
+/// 删除来源历史都已永久删除（不在历史表也不在回收站）的记忆库条目；
+/// 用户确认的译文和导入的条目不受影响，返回删除的条目数
+pub(crate) fn prune_history_memory(conn: &Connection) -> Result<usize> {
+    conn.execute(
+        "DELETE FROM translation_memory_history
+         WHERE history_id NOT IN (SELECT id FROM translation_history)
+           AND history_id NOT IN (SELECT history_id FROM history_trash)",
+        [],
+    )?;
+    conn.execute(
+        "DELETE FROM translation_memory
+         WHERE origin = 'history' AND preferred = 0
+           AND id NOT IN (SELECT memory_id FROM translation_memory_history)",
+        [],
+    )
+}
+
+/// 用已有的翻译历史建立记忆库索引，由迁移在事务中调用
+pub(crate) fn backfill_translation_memory(conn: &Connection) -> Result<()> {
+    let mut stmt = conn.prepare(
+        "SELECT id, original_text, translated_text, service, from_language, to_language,
+                created_at, group_id, operation
+         FROM translation_history
+         ORDER BY id",
+    )?;
+    let records = stmt
+        .query_map([], row_to_translation_record)?
+        .collect::<Result<Vec<_>>>()?;
+    for record in &records {
+        if let Some(history_id) = record.id {
+            index_history_memory(conn, history_id, record)?;
+        }
+    }
+    Ok(())
+}
+
+fn job_from_row(row: &rusqlite::Row<'_>) -> Result<JobRecord> {
+    let params: String = row.get(2)?;
+    let status: String = row.get(3)?;
+    Ok(JobRecord {
+        id: row.get(0)?,
+        kind: row.get(1)?,
+        params: serde_json::from_str(&params).unwrap_or(serde_json::Value::Null),
+        status: JobStatus::parse(&status),
+        progress: row.get(4)?,
+        message: row.get(5)?,
+        result_path: row.get(6)?,
+        error: row.get(7)?,
+        created_at: row.get(8)?,
+        updated_at: row.get(9)?,
+    })
+}
+
+fn prepare_db_path(app_handle: &AppHandle) -> Result<PathBuf> {
+    let app_dir = resolve_app_data_dir(app_handle).map_err(io_to_rusqlite_error)?;
+
+    // 确保应用数据目录存在
+    fs::create_dir_all(&app_dir).map_err(io_to_rusqlite_error)?;
+
+    println!("配置目录: {}", app_dir.display());
+    Ok(app_dir.join("trans.db"))
+}
+
+/// 打开数据库并在迁移前做快速完整性检查，检查不通过时返回 DatabaseCorrupt 错误
+fn open_connection(db_path: &Path) -> Result<Connection> {
+    let mut conn = Connection::open(db_path)?;
+    // 其他进程短暂持有锁时等待，而不是立即失败
+    conn.busy_timeout(Duration::from_secs(5))?;
+
+    let integrity = backup::check_integrity(&conn, false)?;
+    if !integrity.ok {
+        return Err(RusqliteError::SqliteFailure(
+            rusqlite::ffi::Error {
+                code: ErrorCode::DatabaseCorrupt,
+                extended_code: rusqlite::ffi::SQLITE_CORRUPT,
+            },
+            Some(integrity.errors.join("; ")),
+        ));
+    }
+
+    let report = migrations::run(&mut conn, db_path)?;
+    if report.from_version != report.to_version {
+        println!(
+            "数据库已从版本 {} 升级到 {}，备份: {}",
+            report.from_version,
+            report.to_version,
+            report
+                .backup_path
+                .as_deref()
+                .map_or("无".to_string(), |path| path.display().to_string())
+        );
     }
+    Ok(conn)
+}
+
+pub(crate) fn is_corruption(err: &RusqliteError) -> bool {
+    matches!(
+        err.sqlite_error_code(),
+        Some(ErrorCode::DatabaseCorrupt | ErrorCode::NotADatabase)
+    )
 }
 
 fn io_to_rusqlite_error(err: std::io::Error) -> RusqliteError {
@@ -442,7 +2272,7 @@ fn io_to_rusqlite_error(err: std::io::Error) -> RusqliteError {
     )
 }
 
-fn resolve_app_data_dir(app_handle: &AppHandle) -> std::io::Result<PathBuf> {
+pub(crate) fn resolve_app_data_dir(app_handle: &AppHandle) -> std::io::Result<PathBuf> {
     if let Ok(dir) = app_handle.path().app_data_dir() {
         return Ok(dir);
     }
@@ -477,3 +2307,186 @@ fn resolve_app_data_dir(app_handle: &AppHandle) -> std::io::Result<PathBuf> {
     dir.push(&identifier);
     Ok(dir)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn memory_database() -> Database {
+        Database::in_memory()
+    }
+
+    fn record(text: &str, created_at: &str) -> TranslationRecord {
+        TranslationRecord {
+            id: None,
+            original_text: text.to_string(),
+            translated_text: format!("{} 的译文", text),
+            service: "OpenAI".to_string(),
+            from_language: Some("en".to_string()),
+            to_language: Some("zh-CN".to_string()),
+            created_at: Some(created_at.to_string()),
+            group_id: None,
+            operation: None,
+        }
+    }
+
+    fn history(db: &Database) -> Vec<HistoryEntry> {
+        db.get_translation_history(Some(100), None, &HistoryFilter::default())
+            .unwrap()
+    }
+
+    fn count(db: &Database, sql: &str) -> i64 {
+        let conn = db.conn.lock().unwrap();
+        conn.query_row(sql, [], |row| row.get(0)).unwrap()
+    }
+
+    fn history_memory(db: &Database) -> i64 {
+        count(
+            db,
+            "SELECT COUNT(*) FROM translation_memory WHERE origin = 'history'",
+        )
+    }
+
+    #[test]
+    fn requeue_keeps_progress_until_reset() {
+        let db = memory_database();
+        let job = db
+            .create_job("translate_document", &serde_json::json!({}))
+            .unwrap();
+        db.update_job_progress(job.id, 40.0, Some("已完成 4 段"))
+            .unwrap();
+        db.update_job_status(job.id, JobStatus::Paused, None, None)
+            .unwrap();
+        db.update_job_status(job.id, JobStatus::Queued, None, None)
+            .unwrap();
+        assert_eq!(db.get_job(job.id).unwrap().unwrap().progress, 40.0);
+
+        db.reset_job_progress(job.id).unwrap();
+        let job = db.get_job(job.id).unwrap().unwrap();
+        assert_eq!(job.progress, 0.0);
+        assert_eq!(job.message, None);
+    }
+
+    #[test]
+    fn import_treats_language_aliases_as_duplicates() {
+        let db = memory_database();
+        let id = db
+            .save_translation(&record("Good morning", "2024-05-01T08:00:00+00:00"))
+            .unwrap();
+
+        // zh-CN 与 zh-Hans、zh 是同一种语言
+        let mut imported = record("Good morning", "2024-06-01T08:00:00+00:00");
+        imported.from_language = Some("EN".to_string());
+        imported.to_language = Some("zh-Hans".to_string());
+        let entry = HistoryEntry {
+            record: imported,
+            favorite: true,
+            tags: Vec::new(),
+            note: None,
+        };
+        let summary = db.import_history(&[entry]).unwrap();
+        assert_eq!(summary.imported, 0);
+        assert_eq!(summary.duplicates, 1);
+
+        let entries = history(&db);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].record.id, Some(id));
+        assert!(entries[0].favorite);
+    }
+
+    #[test]
+    fn undo_restores_id_and_annotations() {
+        let db = memory_database();
+        let id = db
+            .save_translation(&record("Good morning", "2024-05-01T08:00:00+00:00"))
+            .unwrap();
+        db.set_history_favorite(id, true).unwrap();
+        db.set_history_tags(id, &["greeting".to_string(), "work".to_string()])
+            .unwrap();
+
+        let deletion = db.delete_translation(id).unwrap();
+        assert_eq!(deletion.deleted, 1);
+        assert!(history(&db).is_empty());
+
+        assert_eq!(
+            db.undo_history_deletion(deletion.deletion_id.unwrap())
+                .unwrap(),
+            1
+        );
+        let entries = history(&db);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].record.id, Some(id));
+        assert!(entries[0].favorite);
+        let mut tags = entries[0].tags.clone();
+        tags.sort();
+        assert_eq!(tags, vec!["greeting".to_string(), "work".to_string()]);
+        // 已撤销的删除不能再次撤销
+        assert!(db
+            .undo_history_deletion(deletion.deletion_id.unwrap())
+            .is_err());
+    }
+
+    #[test]
+    fn retention_keeps_favorites() {
+        let db = memory_database();
+        let old = db
+            .save_translation(&record("old", "2020-01-01T00:00:00+00:00"))
+            .unwrap();
+        let old_favorite = db
+            .save_translation(&record("old favorite", "2020-01-02T00:00:00+00:00"))
+            .unwrap();
+        db.set_history_favorite(old_favorite, true).unwrap();
+        let recent = db
+            .save_translation(&record("recent", &Utc::now().to_rfc3339()))
+            .unwrap();
+
+        let policy = HistoryRetentionConfig {
+            max_age_days: 30,
+            max_entries: 0,
+            keep_favorites: true,
+        };
+        assert_eq!(db.expire_history(&policy).unwrap(), (1, 0));
+        let ids: Vec<Option<i64>> = history(&db).iter().map(|entry| entry.record.id).collect();
+        assert_eq!(ids, vec![Some(recent), Some(old_favorite)]);
+        assert!(!ids.contains(&Some(old)));
+
+        let policy = HistoryRetentionConfig {
+            keep_favorites: false,
+            ..policy
+        };
+        assert_eq!(db.expire_history(&policy).unwrap(), (1, 0));
+        assert_eq!(history(&db).len(), 1);
+    }
+
+    #[test]
+    fn purges_trash_after_undo_window() {
+        let db = memory_database();
+        db.save_translation(&record("Good morning", "2024-05-01T08:00:00+00:00"))
+            .unwrap();
+        db.save_translation(&record("Good night", "2024-05-01T22:00:00+00:00"))
+            .unwrap();
+        assert_eq!(history_memory(&db), 2);
+
+        let no_retention = HistoryRetentionConfig::default();
+        let deletion = db.clear_history(true).unwrap();
+        assert_eq!(deletion.deleted, 2);
+        // 撤销时限内回收站和记忆库条目都保留
+        assert_eq!(db.expire_history(&no_retention).unwrap(), (0, 0));
+        assert_eq!(history_memory(&db), 2);
+
+        db.conn
+            .lock()
+            .unwrap()
+            .execute(
+                "UPDATE history_deletions SET deleted_at = datetime('now', '-25 hours')",
+                [],
+            )
+            .unwrap();
+        assert_eq!(db.expire_history(&no_retention).unwrap(), (0, 2));
+        assert_eq!(count(&db, "SELECT COUNT(*) FROM history_trash"), 0);
+        assert_eq!(history_memory(&db), 0);
+        assert!(db
+            .undo_history_deletion(deletion.deletion_id.unwrap())
+            .is_err());
+    }
+}
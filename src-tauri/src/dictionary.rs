@@ -0,0 +1,400 @@
+// Dictionary lookup
+// Returns structured entries (phonetics, senses, examples, inflections, synonyms) for single words
+// and short phrases instead of a bare translation
+
+use crate::http_client::http_client;
+use crate::identifiers;
+use crate::token_limits::calculate_text_response_tokens;
+use crate::translation::{TranslationBackend, TranslationService};
+use serde::{Deserialize, Serialize};
+
+const DICTIONARY_SYSTEM_PROMPT: &str = "You are a bilingual lexicographer. Given a word or short phrase, write a concise learner's dictionary entry.\n\nRules:\n1. Fill every field of the JSON schema; use empty strings or empty arrays when something does not apply\n2. Use IPA for phonetics of alphabetic languages, pinyin with tone marks for Chinese and romaji for Japanese\n3. Definitions are written in the target language; examples are written in the source language with a translation in the target language\n4. List the most common senses first and keep at most two examples per sense";
+
+/// 自动进入词典模式的最大字符数
+const MAX_LOOKUP_CHARS: usize = 32;
+/// 拉丁字母等空格分词语言最多几个单词
+const MAX_LOOKUP_WORDS: usize = 3;
+/// 中日韩文字最多几个字
+const MAX_LOOKUP_CJK_CHARS: usize = 6;
+
+/// 读音
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Phonetic {
+    /// ipa / pinyin / romaji / other
+    pub notation: String,
+    pub text: String,
+    /// 口音或地区，如 UK、US，可为空
+    pub region: String,
+}
+
+/// 例句
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DictionaryExample {
+    pub source: String,
+    pub translation: String,
+}
+
+/// 按词性划分的义项
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DictionarySense {
+    pub part_of_speech: String,
+    pub definition: String,
+    pub translations: Vec<String>,
+    pub examples: Vec<DictionaryExample>,
+    /// 来源词典名称，离线词典查询时填写
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub source: String,
+}
+
+/// 词形变化，如 past tense -> ran
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Inflection {
+    pub label: String,
+    pub form: String,
+}
+
+/// 词典查询结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DictionaryEntry {
+    pub headword: String,
+    pub phonetics: Vec<Phonetic>,
+    pub senses: Vec<DictionarySense>,
+    pub inflections: Vec<Inflection>,
+    pub synonyms: Vec<String>,
+}
+
+impl DictionaryEntry {
+    /// 首个义项的首个译文，作为普通翻译结果展示
+    pub fn primary_translation(&self) -> Option<&str> {
+        self.senses
+            .iter()
+            .flat_map(|sense| sense.translations.iter())
+            .map(|translation| translation.trim())
+            .find(|translation| !translation.is_empty())
+    }
+
+    fn is_empty(&self) -> bool {
+        self.senses.is_empty() && self.phonetics.is_empty()
+    }
+}
+
+/// 判断输入是否适合走词典模式：单个词或很短的词组，不含句子标点和代码标识符
+pub fn is_dictionary_candidate(text: &str) -> bool {
+    let text = text.trim();
+    if text.is_empty() || text.contains('\n') || text.chars().count() > MAX_LOOKUP_CHARS {
+        return false;
+    }
+
+    if text.chars().any(is_cjk) {
+        let count = text.chars().filter(|c| !c.is_whitespace()).count();
+        return count <= MAX_LOOKUP_CJK_CHARS && text.chars().all(|c| is_cjk(c) || c.is_whitespace());
+    }
+
+    let words: Vec<&str> = text.split_whitespace().collect();
+    words.len() <= MAX_LOOKUP_WORDS
+        && text
+            .chars()
+            .all(|c| c.is_alphabetic() || c.is_whitespace() || matches!(c, '\'' | '-' | '’'))
+        && words
+            .iter()
+            .all(|word| identifiers::split_identifier(word).is_none())
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{4e00}'..='\u{9fff}'
+        | '\u{3400}'..='\u{4dbf}'
+        | '\u{3040}'..='\u{30ff}'
+        | '\u{ac00}'..='\u{d7af}')
+}
+
+/// 查询词条：大模型通过 JSON Schema 约束返回结构化结果，谷歌翻译使用其词典接口
+pub async fn lookup(
+    backend: &TranslationBackend,
+    text: &str,
+    from_lang: &str,
+    to_lang: &str,
+) -> Result<DictionaryEntry, String> {
+    let text = text.trim();
+    let entry = match backend.service {
+        TranslationService::OpenAI => lookup_openai(backend, text, from_lang, to_lang).await?,
+        TranslationService::Google => lookup_google(text, from_lang, to_lang).await?,
+        TranslationService::Baidu => return Err("百度翻译暂不支持词典模式".to_string()),
+    };
+
+    if entry.is_empty() {
+        return Err(format!("没有找到词条: {}", text));
+    }
+    Ok(entry)
+}
+
+async fn lookup_openai(
+    backend: &TranslationBackend,
+    text: &str,
+    from_lang: &str,
+    to_lang: &str,
+) -> Result<DictionaryEntry, String> {
+    let source = if from_lang.is_empty() || from_lang == "auto" {
+        "the detected source language"
+    } else {
+        from_lang
+    };
+    let prompt = format!(
+        "Write a dictionary entry for the following {} word or phrase, with definitions and translations in {}:\n\n{}",
+        source, to_lang, text
+    );
+    let max_tokens = calculate_text_response_tokens(text, Some(&backend.token_limits));
+
+    let content = backend
+        .translator()
+        .chat_completion_with_format(
+            DICTIONARY_SYSTEM_PROMPT,
+            &prompt,
+            max_tokens,
+            0.2,
+            Some(&response_format()),
+        )
+        .await?;
+
+    let start = content.find('{').ok_or("词典结果不是有效的 JSON")?;
+    let end = content.rfind('}').ok_or("词典结果不是有效的 JSON")?;
+    let mut entry: DictionaryEntry = serde_json::from_str(&content[start..=end])
+        .map_err(|e| format!("解析词典结果失败: {}", e))?;
+    if entry.headword.trim().is_empty() {
+        entry.headword = text.to_string();
+    }
+    Ok(entry)
+}
+
+fn response_format() -> serde_json::Value {
+    let string_array = serde_json::json!({ "type": "array", "items": { "type": "string" } });
+    serde_json::json!({
+        "type": "json_schema",
+        "json_schema": {
+            "name": "dictionary_entry",
+            "strict": true,
+            "schema": {
+                "type": "object",
+                "additionalProperties": false,
+                "required": ["headword", "phonetics", "senses", "inflections", "synonyms"],
+                "properties": {
+                    "headword": { "type": "string" },
+                    "phonetics": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "additionalProperties": false,
+                            "required": ["notation", "text", "region"],
+                            "properties": {
+                                "notation": { "type": "string", "enum": ["ipa", "pinyin", "romaji", "other"] },
+                                "text": { "type": "string" },
+                                "region": { "type": "string" }
+                            }
+                        }
+                    },
+                    "senses": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "additionalProperties": false,
+                            "required": ["part_of_speech", "definition", "translations", "examples"],
+                            "properties": {
+                                "part_of_speech": { "type": "string" },
+                                "definition": { "type": "string" },
+                                "translations": string_array,
+                                "examples": {
+                                    "type": "array",
+                                    "items": {
+                                        "type": "object",
+                                        "additionalProperties": false,
+                                        "required": ["source", "translation"],
+                                        "properties": {
+                                            "source": { "type": "string" },
+                                            "translation": { "type": "string" }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    },
+                    "inflections": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "additionalProperties": false,
+                            "required": ["label", "form"],
+                            "properties": {
+                                "label": { "type": "string" },
+                                "form": { "type": "string" }
+                            }
+                        }
+                    },
+                    "synonyms": string_array
+                }
+            }
+        }
+    })
+}
+
+/// 谷歌翻译词典接口：dt=bd 词性与译文，md 释义，ex 例句，ss 同义词，rm 读音
+async fn lookup_google(text: &str, from_lang: &str, to_lang: &str) -> Result<DictionaryEntry, String> {
+    let from_lang = if from_lang.is_empty() { "auto" } else { from_lang };
+    let params = [
+        ("client", "gtx"),
+        ("sl", from_lang),
+        ("tl", to_lang),
+        ("hl", to_lang),
+        ("dt", "t"),
+        ("dt", "bd"),
+        ("dt", "md"),
+        ("dt", "ex"),
+        ("dt", "ss"),
+        ("dt", "rm"),
+        ("q", text),
+    ];
+
+    let response = http_client()
+        .get("https://translate.googleapis.com/translate_a/single")
+        .query(&params)
+        .send()
+        .await
+        .map_err(|e| format!("请求谷歌词典失败: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("谷歌词典接口返回错误: {}", response.status()));
+    }
+    let body: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| format!("解析谷歌词典响应失败: {}", e))?;
+
+    Ok(parse_google_entry(text, &body))
+}
+
+fn parse_google_entry(text: &str, body: &serde_json::Value) -> DictionaryEntry {
+    let as_str = |value: &serde_json::Value| value.as_str().map(str::to_string);
+    let items = |value: Option<&serde_json::Value>| -> Vec<serde_json::Value> {
+        value.and_then(|v| v.as_array()).cloned().unwrap_or_default()
+    };
+
+    let mut entry = DictionaryEntry {
+        headword: text.to_string(),
+        ..Default::default()
+    };
+
+    // [0] 的最后一项为 [null, null, 译文读音, 原文读音]
+    let sentences = items(body.get(0));
+    let mut translation = String::new();
+    for sentence in &sentences {
+        if let Some(part) = sentence.get(0).and_then(as_str) {
+            translation.push_str(&part);
+        }
+        if let Some(reading) = sentence.get(3).and_then(as_str).filter(|r| !r.is_empty()) {
+            let notation = if text.chars().any(|c| ('\u{4e00}'..='\u{9fff}').contains(&c)) {
+                "pinyin"
+            } else if text.chars().any(|c| ('\u{3040}'..='\u{30ff}').contains(&c)) {
+                "romaji"
+            } else {
+                "other"
+            };
+            entry.phonetics.push(Phonetic {
+                notation: notation.to_string(),
+                text: reading,
+                region: String::new(),
+            });
+        }
+    }
+
+    // [1]: [[词性, [译文...], [[译文, [回译...]], ...], 原形], ...]
+    for group in items(body.get(1)) {
+        let part_of_speech = group.get(0).and_then(as_str).unwrap_or_default();
+        let translations: Vec<String> = items(group.get(1)).iter().filter_map(as_str).collect();
+        entry.senses.push(DictionarySense {
+            part_of_speech,
+            translations,
+            ..Default::default()
+        });
+    }
+
+    // [12]: [[词性, [[释义, id, 例句], ...], 原形], ...]
+    for group in items(body.get(12)) {
+        let part_of_speech = group.get(0).and_then(as_str).unwrap_or_default();
+        for definition in items(group.get(1)) {
+            let Some(meaning) = definition.get(0).and_then(as_str) else {
+                continue;
+            };
+            let examples = definition
+                .get(2)
+                .and_then(as_str)
+                .map(|example| DictionaryExample {
+                    source: example,
+                    translation: String::new(),
+                })
+                .into_iter()
+                .collect();
+            match entry
+                .senses
+                .iter_mut()
+                .find(|sense| sense.part_of_speech == part_of_speech && sense.definition.is_empty())
+            {
+                Some(sense) => {
+                    sense.definition = meaning;
+                    sense.examples = examples;
+                }
+                None => entry.senses.push(DictionarySense {
+                    part_of_speech: part_of_speech.clone(),
+                    definition: meaning,
+                    examples,
+                    ..Default::default()
+                }),
+            }
+        }
+    }
+
+    // [11]: [[词性, [[[同义词...], id], ...], 原形], ...]
+    for group in items(body.get(11)) {
+        for set in items(group.get(1)) {
+            for synonym in items(set.get(0)).iter().filter_map(as_str) {
+                if !entry.synonyms.contains(&synonym) {
+                    entry.synonyms.push(synonym);
+                }
+            }
+        }
+    }
+
+    // [13]: [[["<b>word</b> in a sentence", ...], ...]]，没有义项可挂时放到第一个义项
+    let examples: Vec<String> = items(body.get(13).and_then(|v| v.get(0)))
+        .iter()
+        .filter_map(|example| example.get(0).and_then(as_str))
+        .map(|example| example.replace("<b>", "").replace("</b>", ""))
+        .collect();
+    if !examples.is_empty() {
+        if entry.senses.is_empty() {
+            entry.senses.push(DictionarySense::default());
+        }
+        let sense = &mut entry.senses[0];
+        for example in examples.into_iter().take(2) {
+            sense.examples.push(DictionaryExample {
+                source: example,
+                translation: String::new(),
+            });
+        }
+    }
+
+    // 没有词典数据时至少保留整体译文
+    if entry.senses.iter().all(|sense| sense.translations.is_empty()) && !translation.is_empty() {
+        match entry.senses.first_mut() {
+            Some(sense) => sense.translations.push(translation),
+            None => entry.senses.push(DictionarySense {
+                translations: vec![translation],
+                ..Default::default()
+            }),
+        }
+    }
+
+    entry
+}
@@ -0,0 +1,1040 @@
+// Document translation
+// Splits Markdown, HTML, plain text, DOCX and EPUB files into translatable segments
+// while keeping headings, lists, tables and inline styles in place
+
+use super::{lines_with_offsets, placeholders};
+use crate::translation::TranslationBackend;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+/// 支持的文档格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DocumentFormat {
+    Markdown,
+    Html,
+    PlainText,
+    Docx,
+    Epub,
+}
+
+impl DocumentFormat {
+    /// 根据扩展名识别格式
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "md" | "markdown" => Some(DocumentFormat::Markdown),
+            "html" | "htm" | "xhtml" => Some(DocumentFormat::Html),
+            "txt" | "text" => Some(DocumentFormat::PlainText),
+            "docx" => Some(DocumentFormat::Docx),
+            "epub" => Some(DocumentFormat::Epub),
+            _ => None,
+        }
+    }
+}
+
+/// 输出模式：仅译文，或原文与译文逐段对照
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DocumentOutputMode {
+    #[default]
+    Translated,
+    Bilingual,
+}
+
+/// 文档翻译结果摘要
+#[derive(Debug, Clone, Serialize)]
+pub struct DocumentTranslationSummary {
+    pub output_path: String,
+    pub format: DocumentFormat,
+    pub mode: DocumentOutputMode,
+    pub segments: usize,
+}
+
+#[derive(Debug, Clone)]
+enum SegmentKind {
+    /// 直接替换区间文本；双语模式下以 joiner 连接原文与译文
+    Text { joiner: String },
+    /// 整个 <w:p> 段落，runs 为段落内各 <w:t> 元素的相对区间（不含嵌套段落中的 <w:t>）；
+    /// 嵌套的段落（文本框等）是紧随其后、区间落在其中的片段
+    WordParagraph { runs: Vec<(usize, usize)> },
+}
+
+#[derive(Debug, Clone)]
+struct Segment {
+    start: usize,
+    end: usize,
+    source: String,
+    kind: SegmentKind,
+}
+
+/// 文档中的一个文本部件（普通文件本身，或压缩包内的某个 XML/XHTML 文件）
+#[derive(Debug, Clone)]
+struct DocumentPart {
+    name: String,
+    content: String,
+    segments: Vec<Segment>,
+    markdown: bool,
+}
+
+impl DocumentPart {
+    fn new(name: &str, content: String, format: DocumentFormat) -> Self {
+        let segments = match format {
+            DocumentFormat::Markdown => segment_markdown(&content),
+            DocumentFormat::Html | DocumentFormat::Epub => segment_html(&content),
+            DocumentFormat::PlainText => segment_plain_text(&content),
+            DocumentFormat::Docx => segment_docx(&content),
+        };
+        DocumentPart {
+            name: name.to_string(),
+            content,
+            segments,
+            markdown: format == DocumentFormat::Markdown,
+        }
+    }
+
+    fn protect(&self, segment: &Segment) -> placeholders::ProtectedText {
+        if self.markdown {
+            placeholders::protect_markdown(&segment.source)
+        } else {
+            placeholders::protect(&segment.source)
+        }
+    }
+
+    fn render(&self, translations: &[String], mode: DocumentOutputMode) -> String {
+        let mut output = String::with_capacity(self.content.len() * 2);
+        let mut cursor = 0;
+        let mut index = 0;
+        while index < self.segments.len().min(translations.len()) {
+            let segment = &self.segments[index];
+            let translated = &translations[index];
+            let nested = self.segments[index + 1..]
+                .iter()
+                .take_while(|child| child.start < segment.end)
+                .count();
+            output.push_str(&self.content[cursor..segment.start]);
+            let original = &self.content[segment.start..segment.end];
+            match &segment.kind {
+                SegmentKind::Text { joiner } => {
+                    if mode == DocumentOutputMode::Bilingual {
+                        output.push_str(original);
+                        output.push_str(joiner);
+                    }
+                    output.push_str(translated);
+                }
+                SegmentKind::WordParagraph { .. } => {
+                    if mode == DocumentOutputMode::Bilingual {
+                        output.push_str(original);
+                    }
+                    let children: Vec<(&Segment, &String)> = self.segments
+                        [index + 1..=index + nested]
+                        .iter()
+                        .zip(&translations[index + 1..])
+                        .collect();
+                    output.push_str(&render_word_paragraph(
+                        &self.content,
+                        segment,
+                        translated,
+                        &children,
+                    ));
+                }
+            }
+            cursor = segment.end;
+            index += 1 + nested;
+        }
+        output.push_str(&self.content[cursor..]);
+        output
+    }
+}
+
+/// 翻译文档并写出译文或双语对照文档
+pub async fn translate_document(
+    backend: &TranslationBackend,
+    input_path: &Path,
+    output_path: Option<&Path>,
+    from_lang: &str,
+    to_lang: &str,
+    mode: DocumentOutputMode,
+    on_progress: impl FnMut(usize, usize),
+) -> Result<DocumentTranslationSummary, String> {
+    let format = DocumentFormat::from_path(input_path)
+        .ok_or_else(|| format!("不支持的文档格式: {}", input_path.display()))?;
+    let bytes = fs::read(input_path).map_err(|e| format!("读取文档失败: {}", e))?;
+
+    let parts = match format {
+        DocumentFormat::Docx | DocumentFormat::Epub => read_archive_parts(&bytes, format)?,
+        _ => {
+            let content = String::from_utf8_lossy(&bytes);
+            let content = content.strip_prefix('\u{feff}').unwrap_or(&content);
+            vec![DocumentPart::new("", content.to_string(), format)]
+        }
+    };
+
+    // 所有部件的片段合并后统一分批翻译，再按顺序分发回各部件
+    let protected: Vec<placeholders::ProtectedText> = parts
+        .iter()
+        .flat_map(|part| part.segments.iter().map(move |segment| part.protect(segment)))
+        .collect();
+    if protected.is_empty() {
+        return Err("文档中没有可翻译的内容".to_string());
+    }
+    let texts: Vec<String> = protected.iter().map(|p| p.text.clone()).collect();
+    let translated = backend
+        .translate_segments(&texts, from_lang, to_lang, on_progress)
+        .await?;
+    let mut translations = translated
+        .iter()
+        .zip(&protected)
+        .map(|(text, protected)| placeholders::restore(text, &protected.placeholders));
+
+    let mut rendered = Vec::with_capacity(parts.len());
+    for part in &parts {
+        let part_translations: Vec<String> = translations
+            .by_ref()
+            .take(part.segments.len())
+            .map(|text| escape_for_part(format, &text))
+            .collect();
+        rendered.push((part.name.clone(), part.render(&part_translations, mode)));
+    }
+
+    let output_path = output_path
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| default_output_path(input_path, to_lang, mode));
+    let output = match format {
+        DocumentFormat::Docx | DocumentFormat::Epub => write_archive(&bytes, &rendered)?,
+        _ => rendered
+            .pop()
+            .map(|(_, content)| content.into_bytes())
+            .unwrap_or_default(),
+    };
+    fs::write(&output_path, output).map_err(|e| format!("写入文档失败: {}", e))?;
+
+    Ok(DocumentTranslationSummary {
+        output_path: output_path.to_string_lossy().to_string(),
+        format,
+        mode,
+        segments: protected.len(),
+    })
+}
+
+fn default_output_path(input_path: &Path, to_lang: &str, mode: DocumentOutputMode) -> PathBuf {
+    let stem = input_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let extension = input_path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let suffix = match mode {
+        DocumentOutputMode::Translated => to_lang.to_string(),
+        DocumentOutputMode::Bilingual => format!("{}.bilingual", to_lang),
+    };
+    input_path.with_file_name(format!("{}.{}.{}", stem, suffix, extension))
+}
+
+/// DOCX 的 <w:t> 在写回段落时单独转义；HTML 只需补齐模型输出的裸 `&`
+fn escape_for_part(format: DocumentFormat, text: &str) -> String {
+    match format {
+        DocumentFormat::Docx => text.to_string(),
+        DocumentFormat::Html | DocumentFormat::Epub => escape_bare_ampersands(text),
+        DocumentFormat::Markdown | DocumentFormat::PlainText => text.to_string(),
+    }
+}
+
+fn escape_bare_ampersands(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    for (i, ch) in text.char_indices() {
+        let rest = &text[i..];
+        let is_entity = ch == '&'
+            && rest.find(';').is_some_and(|end| {
+                end > 1
+                    && end <= 10
+                    && rest[1..end]
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '#')
+            });
+        if ch == '&' && !is_entity {
+            output.push_str("&amp;");
+        } else {
+            output.push(ch);
+        }
+    }
+    output
+}
+
+// ---------------------------------------------------------------------------
+// 压缩包（DOCX / EPUB）
+// ---------------------------------------------------------------------------
+
+fn is_translatable_entry(format: DocumentFormat, name: &str) -> bool {
+    let lower = name.to_lowercase();
+    match format {
+        DocumentFormat::Docx => {
+            lower == "word/document.xml"
+                || lower == "word/footnotes.xml"
+                || lower == "word/endnotes.xml"
+                || (lower.starts_with("word/header") && lower.ends_with(".xml"))
+                || (lower.starts_with("word/footer") && lower.ends_with(".xml"))
+        }
+        DocumentFormat::Epub => {
+            lower.ends_with(".xhtml") || lower.ends_with(".html") || lower.ends_with(".htm")
+        }
+        _ => false,
+    }
+}
+
+fn read_archive_parts(bytes: &[u8], format: DocumentFormat) -> Result<Vec<DocumentPart>, String> {
+    let mut archive =
+        ZipArchive::new(Cursor::new(bytes)).map_err(|e| format!("打开文档压缩包失败: {}", e))?;
+    let mut parts = Vec::new();
+
+    for index in 0..archive.len() {
+        let mut file = archive
+            .by_index(index)
+            .map_err(|e| format!("读取压缩包条目失败: {}", e))?;
+        let name = file.name().to_string();
+        if !is_translatable_entry(format, &name) {
+            continue;
+        }
+        let mut content = String::new();
+        file.read_to_string(&mut content)
+            .map_err(|e| format!("读取 {} 失败: {}", name, e))?;
+        parts.push(DocumentPart::new(&name, content, format));
+    }
+
+    Ok(parts)
+}
+
+/// 按原顺序重建压缩包：未改动的条目原样拷贝（保证 EPUB 的 mimetype 仍是第一个且不压缩）
+fn write_archive(original: &[u8], rendered: &[(String, String)]) -> Result<Vec<u8>, String> {
+    let mut archive =
+        ZipArchive::new(Cursor::new(original)).map_err(|e| format!("打开文档压缩包失败: {}", e))?;
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    for index in 0..archive.len() {
+        let name = archive
+            .by_index_raw(index)
+            .map_err(|e| format!("读取压缩包条目失败: {}", e))?
+            .name()
+            .to_string();
+
+        match rendered.iter().find(|(part, _)| *part == name) {
+            Some((_, content)) => {
+                writer
+                    .start_file(name.as_str(), options)
+                    .map_err(|e| format!("写入 {} 失败: {}", name, e))?;
+                writer
+                    .write_all(content.as_bytes())
+                    .map_err(|e| format!("写入 {} 失败: {}", name, e))?;
+            }
+            None => {
+                let file = archive
+                    .by_index_raw(index)
+                    .map_err(|e| format!("读取压缩包条目失败: {}", e))?;
+                writer
+                    .raw_copy_file(file)
+                    .map_err(|e| format!("复制 {} 失败: {}", name, e))?;
+            }
+        }
+    }
+
+    writer
+        .finish()
+        .map(Cursor::into_inner)
+        .map_err(|e| format!("生成文档压缩包失败: {}", e))
+}
+
+// ---------------------------------------------------------------------------
+// 纯文本与 Markdown
+// ---------------------------------------------------------------------------
+
+fn text_segment(start: usize, end: usize, source: &str, joiner: &str) -> Segment {
+    Segment {
+        start,
+        end,
+        source: source.to_string(),
+        kind: SegmentKind::Text {
+            joiner: joiner.to_string(),
+        },
+    }
+}
+
+fn segment_plain_text(content: &str) -> Vec<Segment> {
+    let lines = lines_with_offsets(content);
+    let mut segments = Vec::new();
+    let mut paragraph: Option<(usize, usize)> = None;
+
+    for (offset, line) in lines.iter().copied().chain(std::iter::once((content.len(), ""))) {
+        if line.trim().is_empty() {
+            if let Some((start, end)) = paragraph.take() {
+                segments.push(text_segment(start, end, &content[start..end], "\n\n"));
+            }
+            continue;
+        }
+        let start = offset + (line.len() - line.trim_start().len());
+        let end = offset + line.trim_end().len();
+        paragraph = Some(match paragraph {
+            Some((first, _)) => (first, end),
+            None => (start, end),
+        });
+    }
+
+    segments
+}
+
+/// 列表项前缀长度：`- `、`* `、`+ `、`1. `、`1) `，以及可选的任务框 `[ ] `
+fn list_marker_len(text: &str) -> Option<usize> {
+    let bytes = text.as_bytes();
+    let mut len = match bytes.first()? {
+        b'-' | b'*' | b'+' => 1,
+        b'0'..=b'9' => {
+            let digits = bytes.iter().take_while(|b| b.is_ascii_digit()).count();
+            if !matches!(bytes.get(digits), Some(b'.') | Some(b')')) {
+                return None;
+            }
+            digits + 1
+        }
+        _ => return None,
+    };
+    if bytes.get(len) != Some(&b' ') {
+        return None;
+    }
+    len += 1;
+    let rest = &text[len..];
+    if rest.starts_with("[ ] ") || rest.starts_with("[x] ") || rest.starts_with("[X] ") {
+        len += 4;
+    }
+    Some(len)
+}
+
+fn is_thematic_break(text: &str) -> bool {
+    let compact: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+    compact.len() >= 3
+        && (compact.chars().all(|c| c == '-')
+            || compact.chars().all(|c| c == '*')
+            || compact.chars().all(|c| c == '_')
+            || compact.chars().all(|c| c == '='))
+}
+
+/// 拆分表格行（不含开头的 `|`），返回各单元格的 (起点, 终点)；
+/// 转义的 `\|` 和行内代码中的 `|` 不是分隔符
+fn table_cells(row: &str) -> Vec<(usize, usize)> {
+    let bytes = row.as_bytes();
+    let mut cells = Vec::new();
+    let mut cell_start = 0;
+    let mut pos = 0;
+
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'\\' => pos += 2,
+            b'`' => {
+                let run = bytes[pos..].iter().take_while(|b| **b == b'`').count();
+                let ticks = &row[pos..pos + run];
+                pos += run;
+                // 只有等长的反引号串才能闭合行内代码，找不到时反引号按普通字符处理
+                let mut search = pos;
+                while let Some(found) = row[search..].find(ticks) {
+                    let close = search + found;
+                    let close_run = bytes[close..].iter().take_while(|b| **b == b'`').count();
+                    if close_run == run {
+                        pos = close + run;
+                        break;
+                    }
+                    search = close + close_run;
+                }
+            }
+            b'|' => {
+                cells.push((cell_start, pos));
+                pos += 1;
+                cell_start = pos;
+            }
+            _ => pos += 1,
+        }
+    }
+    cells.push((cell_start, bytes.len()));
+
+    cells
+}
+
+fn segment_markdown(content: &str) -> Vec<Segment> {
+    let lines = lines_with_offsets(content);
+    let mut segments = Vec::new();
+    let mut paragraph: Option<(usize, usize)> = None;
+    let mut fence: Option<&str> = None;
+    // 最近一个列表项正文的起始列；列表内缩进到该列之后 4 格才是代码块
+    let mut list_indent: Option<usize> = None;
+    let mut index = 0;
+
+    let flush = |paragraph: &mut Option<(usize, usize)>, segments: &mut Vec<Segment>| {
+        if let Some((start, end)) = paragraph.take() {
+            segments.push(text_segment(start, end, &content[start..end], "\n\n"));
+        }
+    };
+
+    // YAML front matter
+    if lines.first().is_some_and(|(_, line)| line.trim() == "---") {
+        index = lines[1..]
+            .iter()
+            .position(|(_, line)| line.trim() == "---" || line.trim() == "...")
+            .map(|i| i + 2)
+            .unwrap_or(0);
+    }
+
+    while index < lines.len() {
+        let (offset, line) = lines[index];
+        index += 1;
+        let trimmed = line.trim_start();
+        let indent = line.len() - trimmed.len();
+
+        if let Some(marker) = fence {
+            if trimmed.starts_with(marker) {
+                fence = None;
+            }
+            continue;
+        }
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            flush(&mut paragraph, &mut segments);
+            fence = Some(&trimmed[..3]);
+            continue;
+        }
+        if trimmed.is_empty() {
+            flush(&mut paragraph, &mut segments);
+            continue;
+        }
+        if list_indent.is_some_and(|column| indent < column) && list_marker_len(trimmed).is_none() {
+            list_indent = None;
+        }
+        let code_indent = list_indent.map_or(4, |column| column + 4);
+        if paragraph.is_none()
+            && (indent >= code_indent || (list_indent.is_none() && line.starts_with('\t')))
+        {
+            // 缩进代码块
+            continue;
+        }
+        if is_thematic_break(trimmed)
+            || trimmed.starts_with("<!--")
+            || (trimmed.starts_with('[') && trimmed.contains("]:"))
+        {
+            flush(&mut paragraph, &mut segments);
+            continue;
+        }
+
+        let content_start = offset + indent;
+        let content_end = offset + line.trim_end().len();
+
+        // 标题
+        let hashes = trimmed.chars().take_while(|c| *c == '#').count();
+        if (1..=6).contains(&hashes) && trimmed[hashes..].starts_with(' ') {
+            flush(&mut paragraph, &mut segments);
+            let text = trimmed[hashes..].trim().trim_end_matches('#').trim_end();
+            if !text.is_empty() {
+                let start = content_start + hashes + (trimmed[hashes..].len() - trimmed[hashes..].trim_start().len());
+                segments.push(text_segment(start, start + text.len(), text, " / "));
+            }
+            continue;
+        }
+
+        // 表格行：逐个单元格翻译，分隔行跳过
+        if let Some(row) = trimmed.strip_prefix('|') {
+            flush(&mut paragraph, &mut segments);
+            let is_separator = trimmed
+                .chars()
+                .all(|c| matches!(c, '|' | '-' | ':' | ' ' | '\t'));
+            if is_separator {
+                continue;
+            }
+            for (cell_start, cell_end) in table_cells(row) {
+                let cell = &row[cell_start..cell_end];
+                let cell_text = cell.trim();
+                if !cell_text.is_empty() {
+                    let start =
+                        content_start + 1 + cell_start + (cell.len() - cell.trim_start().len());
+                    segments.push(text_segment(start, start + cell_text.len(), cell_text, " / "));
+                }
+            }
+            continue;
+        }
+
+        // 引用与列表项：保留前缀，只翻译正文
+        let mut prefix_len = 0;
+        while trimmed[prefix_len..].starts_with('>') {
+            prefix_len += 1;
+            if trimmed[prefix_len..].starts_with(' ') {
+                prefix_len += 1;
+            }
+        }
+        let quote_len = prefix_len;
+        if let Some(marker) = list_marker_len(&trimmed[prefix_len..]) {
+            prefix_len += marker;
+            list_indent = Some(indent + prefix_len);
+        }
+        if prefix_len > 0 {
+            flush(&mut paragraph, &mut segments);
+            let text = trimmed[prefix_len..].trim_end();
+            if !text.is_empty() {
+                let start = content_start + prefix_len;
+                let joiner = format!(
+                    "\n{}{}{}",
+                    " ".repeat(indent),
+                    &trimmed[..quote_len],
+                    " ".repeat(prefix_len - quote_len)
+                );
+                segments.push(text_segment(start, start + text.len(), text, &joiner));
+            }
+            continue;
+        }
+
+        if trimmed.starts_with('<') {
+            // HTML 块原样保留
+            flush(&mut paragraph, &mut segments);
+            continue;
+        }
+
+        paragraph = Some(match paragraph {
+            Some((start, _)) => (start, content_end),
+            None => (content_start, content_end),
+        });
+    }
+    flush(&mut paragraph, &mut segments);
+
+    segments
+}
+
+// ---------------------------------------------------------------------------
+// HTML / XHTML
+// ---------------------------------------------------------------------------
+
+const INLINE_TAGS: &[&str] = &[
+    "a", "abbr", "b", "bdi", "bdo", "br", "cite", "code", "data", "dfn", "em", "font", "i",
+    "kbd", "mark", "q", "rp", "rt", "ruby", "s", "samp", "small", "span", "strong", "sub",
+    "sup", "time", "u", "var", "wbr",
+];
+
+const SKIPPED_TAGS: &[&str] = &["script", "style", "pre", "svg", "math"];
+
+fn tag_name(tag: &str) -> String {
+    tag.trim_start_matches('<')
+        .trim_start_matches('/')
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == ':' || *c == '-')
+        .collect::<String>()
+        .to_lowercase()
+}
+
+fn segment_html(content: &str) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut pos = 0;
+    // (起点, 终点, 是否包含文字)
+    let mut current: Option<(usize, usize, bool)> = None;
+    let mut in_title = false;
+
+    let close = |current: &mut Option<(usize, usize, bool)>, in_title: bool, segments: &mut Vec<Segment>| {
+        if let Some((start, end, true)) = current.take() {
+            let joiner = if in_title { " / " } else { "<br/>" };
+            segments.push(text_segment(start, end, &content[start..end], joiner));
+        }
+    };
+
+    while pos < content.len() {
+        let Some(found) = content[pos..].find('<') else {
+            break;
+        };
+        let text_start = pos;
+        let tag_start = pos + found;
+
+        let text = &content[text_start..tag_start];
+        if !text.trim().is_empty() {
+            let start = text_start + (text.len() - text.trim_start().len());
+            let end = text_start + text.trim_end().len();
+            current = Some(match current {
+                Some((first, _, _)) => (first, end, true),
+                None => (start, end, true),
+            });
+        }
+
+        let rest = &content[tag_start..];
+        if rest.starts_with("<!--") {
+            close(&mut current, in_title, &mut segments);
+            pos = tag_start + rest.find("-->").map(|i| i + 3).unwrap_or(rest.len());
+            continue;
+        }
+        if rest.starts_with("<!") || rest.starts_with("<?") {
+            close(&mut current, in_title, &mut segments);
+            pos = tag_start + rest.find('>').map(|i| i + 1).unwrap_or(rest.len());
+            continue;
+        }
+
+        let tag_end = tag_start + rest.find('>').map(|i| i + 1).unwrap_or(rest.len());
+        let tag = &content[tag_start..tag_end];
+        let name = tag_name(tag);
+        let closing = tag.starts_with("</");
+        pos = tag_end;
+
+        if INLINE_TAGS.contains(&name.as_str()) {
+            current = Some(match current {
+                Some((first, _, has_text)) => (first, tag_end, has_text),
+                None => (tag_start, tag_end, false),
+            });
+            continue;
+        }
+
+        close(&mut current, in_title, &mut segments);
+        if name == "title" {
+            in_title = !closing;
+        }
+
+        if !closing && !tag.ends_with("/>") && SKIPPED_TAGS.contains(&name.as_str()) {
+            let end_tag = format!("</{}", name);
+            pos = content[pos..]
+                .to_ascii_lowercase()
+                .find(&end_tag)
+                .map(|i| pos + i)
+                .unwrap_or(content.len());
+        }
+    }
+    close(&mut current, in_title, &mut segments);
+
+    segments
+}
+
+// ---------------------------------------------------------------------------
+// DOCX (word/document.xml 等)
+// ---------------------------------------------------------------------------
+
+fn xml_unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// 判断 `rest` 是否以指定元素的开始标签开头（排除 <w:pPr> 这类同前缀元素）
+fn starts_element(rest: &str, name: &str) -> bool {
+    rest.strip_prefix(name)
+        .and_then(|after| after.chars().next())
+        .is_some_and(|c| c == '>' || c == ' ' || c == '/')
+}
+
+fn segment_docx(content: &str) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut pos = 0;
+    // 尚未闭合的段落：开始位置和其中已闭合的嵌套段落区间
+    let mut open: Vec<(usize, Vec<(usize, usize)>)> = Vec::new();
+
+    while let Some(found) = content[pos..].find('<') {
+        let tag_start = pos + found;
+        let rest = &content[tag_start..];
+        let tag_end = tag_start + rest.find('>').map(|i| i + 1).unwrap_or(rest.len());
+        pos = tag_end;
+
+        if starts_element(rest, "<w:p") {
+            if !content[tag_start..tag_end].ends_with("/>") {
+                open.push((tag_start, Vec::new()));
+            }
+        } else if rest.starts_with("</w:p>") {
+            if let Some((start, nested)) = open.pop() {
+                if let Some(segment) = word_paragraph(content, start, tag_end, &nested) {
+                    segments.push(segment);
+                }
+                // 文本框、内容控件中的段落嵌套在外层段落里，外层段落收集文本时跳过它们
+                if let Some((_, parent_nested)) = open.last_mut() {
+                    parent_nested.push((start, tag_end));
+                }
+            }
+        }
+    }
+
+    // 内层段落先闭合，按开始位置排序后外层段落排在其嵌套段落之前
+    segments.sort_by_key(|segment| segment.start);
+    segments
+}
+
+/// 收集段落中的 <w:t> 文本，跳过 nested（绝对区间）中的嵌套段落；
+/// 多个文本节点时用 <gN>…</gN> 标出各自的 run，以便保留行内样式
+fn word_paragraph(
+    content: &str,
+    start: usize,
+    end: usize,
+    nested: &[(usize, usize)],
+) -> Option<Segment> {
+    let paragraph = &content[start..end];
+    let mut runs = Vec::new();
+    let mut texts = Vec::new();
+    let mut pos = 0;
+
+    while let Some(found) = paragraph[pos..].find("<w:t") {
+        let element_start = pos + found;
+        if let Some((_, nested_end)) = nested.iter().find(|(nested_start, nested_end)| {
+            (*nested_start..*nested_end).contains(&(start + element_start))
+        }) {
+            pos = nested_end - start;
+            continue;
+        }
+        let rest = &paragraph[element_start..];
+        pos = element_start + 4;
+        if !starts_element(rest, "<w:t") {
+            continue;
+        }
+        let open_end = element_start + rest.find('>')? + 1;
+        if paragraph[element_start..open_end].ends_with("/>") {
+            pos = open_end;
+            continue;
+        }
+        let close = open_end + paragraph[open_end..].find("</w:t>")?;
+        let element_end = close + "</w:t>".len();
+        runs.push((element_start, element_end));
+        texts.push(xml_unescape(&paragraph[open_end..close]));
+        pos = element_end;
+    }
+
+    if texts.iter().all(|text| text.trim().is_empty()) {
+        return None;
+    }
+
+    let source = if texts.len() == 1 {
+        texts[0].clone()
+    } else {
+        texts
+            .iter()
+            .enumerate()
+            .filter(|(_, text)| !text.is_empty())
+            .map(|(i, text)| format!("<g{}>{}</g{}>", i, text, i))
+            .collect::<String>()
+    };
+
+    Some(Segment {
+        start,
+        end,
+        source,
+        kind: SegmentKind::WordParagraph { runs },
+    })
+}
+
+/// 把译文分配回各个 run：<gN> 内的文字写入第 N 个 <w:t>，标记外的文字并入前一个 run
+fn distribute_runs(translated: &str, run_count: usize) -> Vec<String> {
+    let mut texts = vec![String::new(); run_count];
+    if run_count == 1 {
+        texts[0] = translated.to_string();
+        return texts;
+    }
+
+    let mut current = 0;
+    let mut rest = translated;
+    while !rest.is_empty() {
+        if let Some(open) = rest.strip_prefix("<g") {
+            let digits: String = open.chars().take_while(|c| c.is_ascii_digit()).collect();
+            if let (Ok(index), Some(after)) = (
+                digits.parse::<usize>(),
+                open[digits.len()..].strip_prefix('>'),
+            ) {
+                if index < run_count {
+                    current = index;
+                    rest = after;
+                    continue;
+                }
+            }
+        }
+        if let Some(close) = rest.strip_prefix("</g") {
+            if let Some(end) = close.find('>') {
+                if close[..end].chars().all(|c| c.is_ascii_digit()) {
+                    rest = &close[end + 1..];
+                    continue;
+                }
+            }
+        }
+        let ch = rest.chars().next().unwrap_or_default();
+        texts[current].push(ch);
+        rest = &rest[ch.len_utf8()..];
+    }
+
+    texts
+}
+
+/// 生成段落的译文版本：各 run 写入分配到的译文，嵌套段落替换为各自的译文版本
+fn render_word_paragraph(
+    content: &str,
+    segment: &Segment,
+    translated: &str,
+    nested: &[(&Segment, &String)],
+) -> String {
+    let SegmentKind::WordParagraph { runs } = &segment.kind else {
+        return translated.to_string();
+    };
+    let texts = distribute_runs(translated, runs.len());
+    // 段落自身的 run 不在嵌套段落中，两者的区间互不重叠
+    let mut replacements: Vec<(usize, usize, String)> = runs
+        .iter()
+        .zip(texts)
+        .map(|((start, end), text)| {
+            (
+                segment.start + start,
+                segment.start + end,
+                format!("<w:t xml:space=\"preserve\">{}</w:t>", xml_escape(&text)),
+            )
+        })
+        .collect();
+    let mut index = 0;
+    while index < nested.len() {
+        let (child, child_translated) = nested[index];
+        let descendants = nested[index + 1..]
+            .iter()
+            .take_while(|(descendant, _)| descendant.start < child.end)
+            .count();
+        replacements.push((
+            child.start,
+            child.end,
+            render_word_paragraph(
+                content,
+                child,
+                child_translated,
+                &nested[index + 1..index + 1 + descendants],
+            ),
+        ));
+        index += 1 + descendants;
+    }
+    replacements.sort_by_key(|(start, _, _)| *start);
+
+    let mut output = String::with_capacity(segment.end - segment.start + translated.len());
+    let mut cursor = segment.start;
+    for (start, end, text) in replacements {
+        output.push_str(&content[cursor..start]);
+        output.push_str(&text);
+        cursor = end;
+    }
+    output.push_str(&content[cursor..segment.end]);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sources(segments: &[Segment]) -> Vec<&str> {
+        segments.iter().map(|s| s.source.as_str()).collect()
+    }
+
+    #[test]
+    fn markdown_segments_keep_structure() {
+        let content = "---\ntitle: Notes\n---\n# Title #\n\nFirst line\nsecond line\n\n```rust\nlet x = 1;\n```\n\n    indented code\n\n> quoted text\n\n- [ ] task item\n- item\n    - nested item\n\n  continued item\n";
+        let segments = segment_markdown(content);
+        assert_eq!(
+            sources(&segments),
+            [
+                "Title",
+                "First line\nsecond line",
+                "quoted text",
+                "task item",
+                "item",
+                "nested item",
+                "continued item",
+            ]
+        );
+        for segment in &segments {
+            assert_eq!(&content[segment.start..segment.end], segment.source);
+        }
+    }
+
+    #[test]
+    fn markdown_list_code_needs_extra_indent() {
+        let segments = segment_markdown("- item\n\n        code in item\n\ntext\n\n    code\n");
+        assert_eq!(sources(&segments), ["item", "text"]);
+    }
+
+    #[test]
+    fn markdown_table_cells_respect_escapes_and_code() {
+        let content = "| Name | Note |\n| --- | :---: |\n| a \\| b | use `x | y` here |\n";
+        let segments = segment_markdown(content);
+        assert_eq!(
+            sources(&segments),
+            ["Name", "Note", "a \\| b", "use `x | y` here"]
+        );
+        for segment in &segments {
+            assert_eq!(&content[segment.start..segment.end], segment.source);
+        }
+        assert_eq!(table_cells("``a|b`` | c"), [(0, 8), (9, 11)]);
+        assert_eq!(table_cells("`a | b"), [(0, 3), (4, 6)]);
+    }
+
+    #[test]
+    fn markdown_bilingual_render() {
+        let part = DocumentPart::new("", "# Hi\n\n- one\n".to_string(), DocumentFormat::Markdown);
+        let translations = ["你好".to_string(), "一".to_string()];
+        assert_eq!(
+            part.render(&translations, DocumentOutputMode::Translated),
+            "# 你好\n\n- 一\n"
+        );
+        assert_eq!(
+            part.render(&translations, DocumentOutputMode::Bilingual),
+            "# Hi / 你好\n\n- one\n  一\n"
+        );
+    }
+
+    #[test]
+    fn html_segments_group_inline_tags() {
+        let content = "<html><head><title>Page</title><style>p { color: red; }</style></head><body>\n<p>Hello <b>bold</b> world</p>\n<!-- note -->\n<script>var s = \"text\";</script>\n<div>Block<br>next</div>\n</body></html>";
+        let segments = segment_html(content);
+        assert_eq!(
+            sources(&segments),
+            ["Page", "Hello <b>bold</b> world", "Block<br>next"]
+        );
+
+        let part = DocumentPart::new("", content.to_string(), DocumentFormat::Html);
+        let translations = ["页面", "你好 <b>粗体</b> 世界", "块<br>下一行"].map(String::from);
+        let rendered = part.render(&translations, DocumentOutputMode::Bilingual);
+        assert!(rendered.contains("<title>Page / 页面</title>"));
+        assert!(rendered.contains("<p>Hello <b>bold</b> world<br/>你好 <b>粗体</b> 世界</p>"));
+        assert!(rendered.contains("<script>var s = \"text\";</script>"));
+    }
+
+    #[test]
+    fn docx_segments_keep_runs_and_nested_paragraphs() {
+        let content = concat!(
+            "<w:body>",
+            "<w:p><w:pPr/><w:r><w:t>Hello </w:t></w:r><w:r><w:rPr><w:b/></w:rPr><w:t>world</w:t></w:r>",
+            "<w:r><w:txbxContent><w:p><w:r><w:t>Box &amp; text</w:t></w:r></w:p></w:txbxContent></w:r></w:p>",
+            "<w:p><w:r><w:t xml:space=\"preserve\"> </w:t></w:r></w:p>",
+            "</w:body>"
+        );
+        let segments = segment_docx(content);
+        assert_eq!(
+            sources(&segments),
+            ["<g0>Hello </g0><g1>world</g1>", "Box & text"]
+        );
+
+        let part = DocumentPart::new(
+            "word/document.xml",
+            content.to_string(),
+            DocumentFormat::Docx,
+        );
+        let translations = ["<g0>你好</g0><g1>世界</g1>", "框 & 文字"].map(String::from);
+        let rendered = part.render(&translations, DocumentOutputMode::Translated);
+        assert!(rendered.contains(
+            "<w:t xml:space=\"preserve\">你好</w:t></w:r><w:r><w:rPr><w:b/></w:rPr><w:t xml:space=\"preserve\">世界</w:t>"
+        ));
+        assert!(rendered.contains("<w:t xml:space=\"preserve\">框 &amp; 文字</w:t>"));
+        assert!(!rendered.contains("Hello"));
+        assert!(rendered.contains("<w:p><w:r><w:t xml:space=\"preserve\"> </w:t></w:r></w:p>"));
+    }
+
+    #[test]
+    fn distributes_text_outside_markers_to_previous_run() {
+        assert_eq!(
+            distribute_runs("<g1>B</g1> and <g0>A</g0>", 2),
+            ["A", "B and "]
+        );
+        assert_eq!(distribute_runs("plain", 1), ["plain"]);
+    }
+}
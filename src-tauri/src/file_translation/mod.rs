@@ -0,0 +1,51 @@
+// File translation module
+// Parses translatable files into segments and translates them in batches
+
+pub mod document;
+pub mod placeholders;
+pub mod resource;
+pub mod subtitle;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+/// 文件翻译进度事件名
+pub const FILE_TRANSLATION_PROGRESS_EVENT: &str = "file-translation-progress";
+
+/// 文件翻译进度负载
+#[derive(Debug, Clone, Serialize)]
+pub struct FileTranslationProgress {
+    pub file: String,
+    pub completed: usize,
+    pub total: usize,
+    pub progress: u32,
+}
+
+/// 按行切分并记录每行的起始偏移（行内容不含换行符）
+pub(super) fn lines_with_offsets(content: &str) -> Vec<(usize, &str)> {
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    for line in content.split('\n') {
+        lines.push((offset, line.strip_suffix('\r').unwrap_or(line)));
+        offset += line.len() + 1;
+    }
+    lines
+}
+
+/// 向前端广播文件翻译进度
+pub fn emit_progress(app: &AppHandle, file: &str, completed: usize, total: usize) {
+    let progress = if total > 0 {
+        (completed as f64 / total as f64 * 100.0) as u32
+    } else {
+        100
+    };
+    let _ = app.emit(
+        FILE_TRANSLATION_PROGRESS_EVENT,
+        FileTranslationProgress {
+            file: file.to_string(),
+            completed,
+            total,
+            progress,
+        },
+    );
+}
@@ -0,0 +1,304 @@
+// Placeholder protection
+// Replaces format specifiers and markup with opaque tokens before sending text to a provider
+
+/// 受保护的文本：占位符被替换为 ⟦n⟧ 形式的记号
+#[derive(Debug, Clone)]
+pub struct ProtectedText {
+    pub text: String,
+    pub placeholders: Vec<String>,
+}
+
+/// 将 printf/ICU/模板占位符和标签替换为不可翻译的记号；ICU plural/select 的分支内容保留为可翻译文本
+pub fn protect(text: &str) -> ProtectedText {
+    protect_with(text, false)
+}
+
+/// 在 protect 的基础上额外保护 Markdown 行内代码与链接地址
+pub fn protect_markdown(text: &str) -> ProtectedText {
+    protect_with(text, true)
+}
+
+fn protect_with(text: &str, markdown: bool) -> ProtectedText {
+    let mut protected = ProtectedText {
+        text: String::with_capacity(text.len()),
+        placeholders: Vec::new(),
+    };
+    protect_into(text, markdown, false, &mut protected);
+    protected
+}
+
+/// count_sign 为 true 时（ICU plural 分支内）把代表数量的 # 也当作占位符
+fn protect_into(text: &str, markdown: bool, count_sign: bool, protected: &mut ProtectedText) {
+    let mut index = 0;
+
+    while index < text.len() {
+        let rest = &text[index..];
+        // ICU plural/select 只保护参数名、关键字和分支选择器，分支内容仍需翻译
+        if let Some((parts, plural)) = icu_parts(rest) {
+            for part in &parts {
+                match part {
+                    IcuPart::Syntax(syntax) => push_placeholder(syntax, protected),
+                    IcuPart::Branch(branch) => protect_into(branch, markdown, plural, protected),
+                }
+            }
+            index += parts.iter().map(IcuPart::len).sum::<usize>();
+            continue;
+        }
+
+        let matched = if count_sign && rest.starts_with('#') {
+            Some(1)
+        } else if markdown {
+            markdown_len(rest).or_else(|| placeholder_len(rest))
+        } else {
+            placeholder_len(rest)
+        };
+        if let Some(len) = matched {
+            push_placeholder(&rest[..len], protected);
+            index += len;
+        } else {
+            let ch = rest.chars().next().unwrap_or_default();
+            protected.text.push(ch);
+            index += ch.len_utf8();
+        }
+    }
+}
+
+fn push_placeholder(placeholder: &str, protected: &mut ProtectedText) {
+    protected
+        .text
+        .push_str(&format!("⟦{}⟧", protected.placeholders.len()));
+    protected.placeholders.push(placeholder.to_string());
+}
+
+/// 把记号还原为原始占位符，模型丢失的记号会追加在末尾以免信息丢失
+pub fn restore(text: &str, placeholders: &[String]) -> String {
+    let mut result = text.to_string();
+    for (i, placeholder) in placeholders.iter().enumerate() {
+        let token = format!("⟦{}⟧", i);
+        if result.contains(&token) {
+            result = result.replacen(&token, placeholder, 1);
+        } else {
+            result.push_str(placeholder);
+        }
+    }
+    result
+}
+
+fn placeholder_len(rest: &str) -> Option<usize> {
+    let bytes = rest.as_bytes();
+    match bytes.first()? {
+        b'%' => printf_len(bytes),
+        b'{' => brace_len(rest),
+        b'$' if bytes.get(1) == Some(&b'{') => brace_len(&rest[1..]).map(|len| len + 1),
+        b'<' => tag_len(rest),
+        _ => None,
+    }
+}
+
+/// 匹配 `code` 行内代码和 ](url) 链接地址
+fn markdown_len(rest: &str) -> Option<usize> {
+    if rest.starts_with('`') {
+        let fence = rest.len() - rest.trim_start_matches('`').len();
+        let close = rest[fence..].find(&rest[..fence])?;
+        return Some(fence + close + fence);
+    }
+    if rest.starts_with("](") {
+        let mut depth = 0usize;
+        for (i, ch) in rest.char_indices().skip(1) {
+            match ch {
+                '(' => depth += 1,
+                ')' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(i + 1);
+                    }
+                }
+                '\n' => return None,
+                _ => {}
+            }
+        }
+    }
+    None
+}
+
+/// 匹配 %s、%1$d、%.2f、%@、%(name)s、%% 等格式说明符
+fn printf_len(bytes: &[u8]) -> Option<usize> {
+    let mut i = 1;
+    match bytes.get(i)? {
+        b'%' => return Some(2),
+        b'(' => {
+            let close = bytes[i..].iter().position(|b| *b == b')')? + i;
+            i = close + 1;
+        }
+        _ => {
+            let digits = bytes[i..].iter().take_while(|b| b.is_ascii_digit()).count();
+            if digits > 0 && bytes.get(i + digits) == Some(&b'$') {
+                i += digits + 1;
+            }
+        }
+    }
+
+    i += bytes[i..]
+        .iter()
+        .take_while(|b| matches!(b, b'-' | b'+' | b' ' | b'#' | b'0'))
+        .count();
+    i += bytes[i..].iter().take_while(|b| b.is_ascii_digit()).count();
+    if bytes.get(i) == Some(&b'.') {
+        i += 1;
+        i += bytes[i..].iter().take_while(|b| b.is_ascii_digit()).count();
+    }
+    i += bytes[i..]
+        .iter()
+        .take(2)
+        .take_while(|b| matches!(b, b'h' | b'l' | b'L' | b'z' | b'j' | b't' | b'q'))
+        .count();
+
+    match bytes.get(i)? {
+        b'd' | b'i' | b'o' | b'u' | b'x' | b'X' | b'e' | b'E' | b'f' | b'F' | b'g' | b'G'
+        | b'a' | b'A' | b'c' | b's' | b'S' | b'p' | b'n' | b'@' => Some(i + 1),
+        _ => None,
+    }
+}
+
+/// ICU 复合参数的组成部分，依次拼接即为原文
+enum IcuPart<'a> {
+    /// 参数名、关键字、分支选择器和括号，如 `{count, plural, one {`、`} other {`、`}}`
+    Syntax(&'a str),
+    /// 分支内容，如 `# item`
+    Branch(&'a str),
+}
+
+impl IcuPart<'_> {
+    fn len(&self) -> usize {
+        match self {
+            IcuPart::Syntax(text) | IcuPart::Branch(text) => text.len(),
+        }
+    }
+}
+
+/// 解析 {count, plural, one {# item} other {# items}} 和 {gender, select, ...} 这类 ICU 复合参数；
+/// 第二个返回值表示分支中的 # 代表数量（plural、selectordinal）
+fn icu_parts(rest: &str) -> Option<(Vec<IcuPart<'_>>, bool)> {
+    let bytes = rest.as_bytes();
+    if bytes.first() != Some(&b'{') {
+        return None;
+    }
+    let skip_whitespace = |i: &mut usize| {
+        while bytes.get(*i).is_some_and(|b| b.is_ascii_whitespace()) {
+            *i += 1;
+        }
+    };
+
+    let mut i = 1;
+    skip_whitespace(&mut i);
+    let name_len = bytes[i..]
+        .iter()
+        .take_while(|b| b.is_ascii_alphanumeric() || **b == b'_')
+        .count();
+    if name_len == 0 {
+        return None;
+    }
+    i += name_len;
+    skip_whitespace(&mut i);
+    if bytes.get(i) != Some(&b',') {
+        return None;
+    }
+    i += 1;
+    skip_whitespace(&mut i);
+    let keyword_len = bytes[i..]
+        .iter()
+        .take_while(|b| b.is_ascii_alphabetic())
+        .count();
+    let plural = match &rest[i..i + keyword_len] {
+        "plural" | "selectordinal" => true,
+        "select" => false,
+        _ => return None,
+    };
+    i += keyword_len;
+    skip_whitespace(&mut i);
+    if bytes.get(i) != Some(&b',') {
+        return None;
+    }
+    i += 1;
+
+    let mut parts = Vec::new();
+    let mut syntax_start = 0;
+    loop {
+        skip_whitespace(&mut i);
+        if *bytes.get(i)? == b'}' {
+            if parts.is_empty() {
+                return None;
+            }
+            parts.push(IcuPart::Syntax(&rest[syntax_start..=i]));
+            return Some((parts, plural));
+        }
+
+        let selector_len = bytes[i..]
+            .iter()
+            .take_while(|b| !b.is_ascii_whitespace() && !matches!(b, b'{' | b'}'))
+            .count();
+        if selector_len == 0 {
+            return None;
+        }
+        let offset = rest[i..].starts_with("offset:");
+        i += selector_len;
+        skip_whitespace(&mut i);
+        if offset {
+            continue;
+        }
+        if bytes.get(i) != Some(&b'{') {
+            return None;
+        }
+        i += 1;
+        parts.push(IcuPart::Syntax(&rest[syntax_start..i]));
+
+        let mut depth = 1usize;
+        let close = rest[i..].char_indices().find_map(|(offset, ch)| {
+            match ch {
+                '{' => depth += 1,
+                '}' => depth -= 1,
+                _ => {}
+            }
+            (depth == 0).then_some(i + offset)
+        })?;
+        parts.push(IcuPart::Branch(&rest[i..close]));
+        syntax_start = close;
+        i = close + 1;
+    }
+}
+
+/// 匹配 {name}、{0}、{{name}}、{ $count } 等花括号占位符（支持嵌套）
+fn brace_len(rest: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    for (i, ch) in rest.char_indices() {
+        match ch {
+            '{' => depth += 1,
+            '}' => {
+                depth = depth.checked_sub(1)?;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            '\n' => return None,
+            _ => {}
+        }
+        if i > 200 {
+            return None;
+        }
+    }
+    None
+}
+
+/// 匹配 <b>、</b>、<br/>、<xliff:g id="x"> 等内联标签
+fn tag_len(rest: &str) -> Option<usize> {
+    let bytes = rest.as_bytes();
+    let first = if bytes.get(1) == Some(&b'/') { 2 } else { 1 };
+    if !bytes.get(first)?.is_ascii_alphabetic() {
+        return None;
+    }
+    let close = rest.find('>')?;
+    if rest[1..close].contains('<') || rest[..close].contains('\n') {
+        return None;
+    }
+    Some(close + 1)
+}
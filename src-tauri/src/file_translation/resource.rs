@@ -0,0 +1,1603 @@
+// Resource file translation
+// Translates i18n resource files (JSON, YAML, gettext PO, Android strings.xml,
+// Apple .strings, Fluent) while leaving keys, comments and layout untouched
+
+use super::{lines_with_offsets, placeholders};
+use crate::languages;
+use crate::translation::TranslationBackend;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 支持的资源文件格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResourceFormat {
+    Json,
+    Yaml,
+    Po,
+    AndroidXml,
+    AppleStrings,
+    Fluent,
+}
+
+impl ResourceFormat {
+    /// 根据扩展名识别格式
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "json" => Some(ResourceFormat::Json),
+            "yml" | "yaml" => Some(ResourceFormat::Yaml),
+            "po" | "pot" => Some(ResourceFormat::Po),
+            "xml" => Some(ResourceFormat::AndroidXml),
+            "strings" => Some(ResourceFormat::AppleStrings),
+            "ftl" => Some(ResourceFormat::Fluent),
+            _ => None,
+        }
+    }
+}
+
+/// 值在原文件中的书写方式，决定写回时如何转义
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Escape {
+    Json,
+    YamlPlain,
+    YamlDouble,
+    YamlSingle,
+    YamlBlock {
+        indent: usize,
+    },
+    Po {
+        plural: Option<usize>,
+    },
+    /// 按目标语言补出的复数形式，写回时连同 `msgstr[n]` 整行一起生成
+    PoInserted {
+        plural: usize,
+        crlf: bool,
+    },
+    AndroidXml,
+    AppleStrings,
+    Fluent,
+}
+
+/// 资源文件中的一条可翻译条目
+#[derive(Debug, Clone)]
+pub struct ResourceEntry {
+    pub key: String,
+    pub source: String,
+    /// 文件内已有的译文（仅 PO 这类源/译同文件的格式）
+    pub existing: Option<String>,
+    start: usize,
+    end: usize,
+    escape: Escape,
+}
+
+/// 解析后的资源文件：原文 + 可替换的值区间
+#[derive(Debug, Clone)]
+pub struct ResourceDocument {
+    pub format: ResourceFormat,
+    pub entries: Vec<ResourceEntry>,
+    content: String,
+    /// Rails 风格 YAML 中作为唯一根键的语言代码（`en:`）所在区间
+    locale_root: Option<(usize, usize)>,
+    /// 与条目无关的额外改写（区间起点, 区间终点, 替换内容）
+    patches: Vec<(usize, usize, String)>,
+}
+
+impl ResourceDocument {
+    pub fn parse(format: ResourceFormat, content: &str) -> Result<Self, String> {
+        let mut entries = match format {
+            ResourceFormat::Json => parse_json(content)?,
+            ResourceFormat::Yaml => parse_yaml(content),
+            ResourceFormat::Po => parse_po(content),
+            ResourceFormat::AndroidXml => parse_android(content)?,
+            ResourceFormat::AppleStrings => parse_apple_strings(content)?,
+            ResourceFormat::Fluent => parse_fluent(content),
+        };
+        entries.sort_by_key(|entry| entry.start);
+
+        // 键不含语言根键，源文件（en.*）与目标文件（de.*）的条目才能按键对应
+        let locale_root = match format {
+            ResourceFormat::Yaml => yaml_locale_root(content),
+            _ => None,
+        };
+        if let Some((start, end)) = locale_root {
+            let prefix = format!("{}.", &content[start..end]);
+            for entry in &mut entries {
+                if let Some(key) = entry.key.strip_prefix(&prefix) {
+                    entry.key = key.to_string();
+                }
+            }
+        }
+
+        Ok(ResourceDocument {
+            format,
+            entries,
+            content: content.to_string(),
+            locale_root,
+            patches: Vec::new(),
+        })
+    }
+
+    /// 按目标语言调整与值无关的结构：YAML 语言根键改为目标语言，PO 复数形式按目标语言的数量增删
+    pub fn retarget(&mut self, to_lang: &str) {
+        match self.format {
+            ResourceFormat::Yaml => {
+                if let Some((start, end)) = self.locale_root {
+                    self.patches.push((start, end, to_lang.to_string()));
+                }
+            }
+            ResourceFormat::Po => self.retarget_po_plurals(to_lang),
+            _ => {}
+        }
+    }
+
+    fn retarget_po_plurals(&mut self, to_lang: &str) {
+        let Some((forms, rule)) = po_plural_forms(to_lang) else {
+            return;
+        };
+        let crlf = self.content.contains("\r\n");
+        let header_end = self
+            .entries
+            .first()
+            .map(|entry| entry.start)
+            .unwrap_or(self.content.len());
+        let mut has_plural = false;
+        let mut entries = Vec::with_capacity(self.entries.len());
+        let mut remaining = std::mem::take(&mut self.entries).into_iter().peekable();
+
+        while let Some(entry) = remaining.next() {
+            let Escape::Po {
+                plural: Some(index),
+            } = entry.escape
+            else {
+                entries.push(entry);
+                continue;
+            };
+            has_plural = true;
+
+            if index >= forms {
+                // 目标语言用不到的复数形式连同所在行一起删除
+                let mut line_start = self.content[..entry.start].rfind('\n').unwrap_or(0);
+                if self.content[..line_start].ends_with('\r') {
+                    line_start -= 1;
+                }
+                self.patches.push((line_start, entry.end, String::new()));
+                continue;
+            }
+
+            let last_of_message = !remaining.peek().is_some_and(
+                |next| matches!(next.escape, Escape::Po { plural: Some(n) } if n > index),
+            );
+            if last_of_message {
+                let base = entry
+                    .key
+                    .rsplit_once('[')
+                    .map(|(base, _)| base.to_string())
+                    .unwrap_or_else(|| entry.key.clone());
+                for plural in index + 1..forms {
+                    entries.push(ResourceEntry {
+                        key: format!("{}[{}]", base, plural),
+                        source: entry.source.clone(),
+                        existing: None,
+                        start: entry.end,
+                        end: entry.end,
+                        escape: Escape::PoInserted { plural, crlf },
+                    });
+                }
+            }
+            entries.push(entry);
+        }
+        entries.sort_by_key(|entry| entry.start);
+        self.entries = entries;
+
+        if has_plural {
+            if let Some(patch) = po_plural_header_patch(&self.content[..header_end], rule, crlf) {
+                self.patches.push(patch);
+            }
+        }
+    }
+
+    /// 以给定的值（与 entries 一一对应）重新生成文件内容
+    pub fn render(&self, values: &[String]) -> String {
+        let mut edits: Vec<(usize, usize, String)> = self
+            .entries
+            .iter()
+            .zip(values)
+            .map(|(entry, value)| (entry.start, entry.end, escape_value(entry.escape, value)))
+            .collect();
+        edits.extend(self.patches.iter().cloned());
+        edits.sort_by_key(|(start, _, _)| *start);
+
+        let mut output = String::with_capacity(self.content.len());
+        let mut cursor = 0;
+        for (start, end, replacement) in edits {
+            output.push_str(&self.content[cursor..start]);
+            output.push_str(&replacement);
+            cursor = end;
+        }
+        output.push_str(&self.content[cursor..]);
+        output
+    }
+
+    /// 键到值的映射，用于读取已有的目标语言文件
+    pub fn values_by_key(&self) -> HashMap<String, String> {
+        self.entries
+            .iter()
+            .map(|entry| (entry.key.clone(), entry.source.clone()))
+            .collect()
+    }
+}
+
+/// 资源文件翻译结果摘要
+#[derive(Debug, Clone, Serialize)]
+pub struct ResourceTranslationSummary {
+    pub output_path: String,
+    pub format: ResourceFormat,
+    pub total: usize,
+    pub translated: usize,
+    pub skipped: usize,
+}
+
+/// 翻译资源文件并写出目标语言文件
+pub async fn translate_resource_file(
+    backend: &TranslationBackend,
+    input_path: &Path,
+    output_path: Option<&Path>,
+    from_lang: &str,
+    to_lang: &str,
+    force: bool,
+    on_progress: impl FnMut(usize, usize),
+) -> Result<ResourceTranslationSummary, String> {
+    let format = ResourceFormat::from_path(input_path)
+        .ok_or_else(|| format!("不支持的资源文件格式: {}", input_path.display()))?;
+    let content =
+        fs::read_to_string(input_path).map_err(|e| format!("读取资源文件失败: {}", e))?;
+    let mut document = ResourceDocument::parse(format, &content)?;
+    document.retarget(to_lang);
+
+    let output_path = output_path
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| default_output_path(input_path, format, from_lang, to_lang));
+
+    // PO 文件自带译文；其他格式从已存在的目标文件中读取旧译文
+    let previous = if format == ResourceFormat::Po || !output_path.exists() {
+        HashMap::new()
+    } else {
+        let target = fs::read_to_string(&output_path)
+            .map_err(|e| format!("读取已有译文文件失败: {}", e))?;
+        ResourceDocument::parse(format, &target)?.values_by_key()
+    };
+
+    let mut values = Vec::with_capacity(document.entries.len());
+    let mut pending = Vec::new();
+    for (index, entry) in document.entries.iter().enumerate() {
+        let existing = entry
+            .existing
+            .clone()
+            .or_else(|| previous.get(&entry.key).cloned())
+            .filter(|value| !value.trim().is_empty() && *value != entry.source);
+
+        match existing {
+            Some(value) if !force => values.push(value),
+            _ if entry.source.trim().is_empty() => values.push(entry.source.clone()),
+            _ => {
+                values.push(String::new());
+                pending.push(index);
+            }
+        }
+    }
+
+    let protected: Vec<placeholders::ProtectedText> = pending
+        .iter()
+        .map(|index| placeholders::protect(&document.entries[*index].source))
+        .collect();
+    let texts: Vec<String> = protected.iter().map(|p| p.text.clone()).collect();
+
+    let translated = backend
+        .translate_segments(&texts, from_lang, to_lang, on_progress)
+        .await?;
+
+    for ((index, protected), text) in pending.iter().zip(&protected).zip(translated) {
+        values[*index] = placeholders::restore(&text, &protected.placeholders);
+    }
+
+    if let Some(parent) = output_path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("创建输出目录失败: {}", e))?;
+    }
+    fs::write(&output_path, document.render(&values))
+        .map_err(|e| format!("写入译文文件失败: {}", e))?;
+
+    Ok(ResourceTranslationSummary {
+        output_path: output_path.to_string_lossy().to_string(),
+        format,
+        total: document.entries.len(),
+        translated: pending.len(),
+        skipped: document.entries.len() - pending.len(),
+    })
+}
+
+/// 按各平台的本地化目录约定推导输出路径
+pub fn default_output_path(
+    input_path: &Path,
+    format: ResourceFormat,
+    from_lang: &str,
+    to_lang: &str,
+) -> PathBuf {
+    let file_name = input_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let parent = input_path.parent().unwrap_or_else(|| Path::new(""));
+    let parent_name = parent
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    if format == ResourceFormat::AndroidXml
+        && (parent_name == "values" || parent_name.starts_with("values-"))
+    {
+        let android_lang = to_lang.replace('-', "-r");
+        let dir = parent.with_file_name(format!("values-{}", android_lang));
+        return dir.join(file_name);
+    }
+
+    if format == ResourceFormat::AppleStrings && parent_name.ends_with(".lproj") {
+        let dir = parent.with_file_name(format!("{}.lproj", to_lang));
+        return dir.join(file_name);
+    }
+
+    let stem = input_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let extension = input_path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let extension = if extension == "pot" {
+        "po".to_string()
+    } else {
+        extension
+    };
+
+    if !from_lang.is_empty() && stem.eq_ignore_ascii_case(from_lang) {
+        return parent.join(format!("{}.{}", to_lang, extension));
+    }
+    if parent_name.eq_ignore_ascii_case(from_lang) && !from_lang.is_empty() {
+        return parent
+            .with_file_name(to_lang)
+            .join(format!("{}.{}", stem, extension));
+    }
+    parent.join(format!("{}.{}.{}", stem, to_lang, extension))
+}
+
+fn escape_value(escape: Escape, value: &str) -> String {
+    match escape {
+        Escape::Json => {
+            let quoted = serde_json::to_string(value).unwrap_or_default();
+            quoted[1..quoted.len() - 1].to_string()
+        }
+        Escape::YamlDouble => escape_double_quoted(value),
+        Escape::YamlSingle => value.replace('\'', "''").replace('\n', " "),
+        Escape::YamlPlain => {
+            if yaml_plain_is_safe(value) {
+                value.to_string()
+            } else {
+                format!("\"{}\"", escape_double_quoted(value))
+            }
+        }
+        Escape::YamlBlock { indent } => {
+            let padding = " ".repeat(indent);
+            value
+                .lines()
+                .map(|line| {
+                    if line.is_empty() {
+                        String::new()
+                    } else {
+                        format!("{}{}", padding, line)
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        }
+        Escape::Po { .. } => format!("\"{}\"", escape_double_quoted(value)),
+        Escape::PoInserted { plural, crlf } => format!(
+            "{}msgstr[{}] \"{}\"",
+            if crlf { "\r\n" } else { "\n" },
+            plural,
+            escape_double_quoted(value)
+        ),
+        Escape::AndroidXml => escape_android(value),
+        Escape::AppleStrings => escape_double_quoted(value),
+        Escape::Fluent => value.replace('\n', " "),
+    }
+}
+
+fn escape_double_quoted(value: &str) -> String {
+    let mut output = String::with_capacity(value.len());
+    for ch in value.chars() {
+        match ch {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\t' => output.push_str("\\t"),
+            '\r' => output.push_str("\\r"),
+            _ => output.push(ch),
+        }
+    }
+    output
+}
+
+fn unescape_double_quoted(value: &str) -> String {
+    let mut output = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            output.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => output.push('\n'),
+            Some('t') => output.push('\t'),
+            Some('r') => output.push('\r'),
+            Some('u') | Some('U') => {
+                let hex: String = chars.clone().take(4).collect();
+                match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                    Some(decoded) if hex.len() == 4 => {
+                        output.push(decoded);
+                        for _ in 0..4 {
+                            chars.next();
+                        }
+                    }
+                    _ => output.push_str("\\u"),
+                }
+            }
+            Some(other) => output.push(other),
+            None => output.push('\\'),
+        }
+    }
+    output
+}
+
+fn yaml_plain_is_safe(value: &str) -> bool {
+    let Some(first) = value.chars().next() else {
+        return false;
+    };
+    let reserved_start = "-?:,[]{}#&*!|>'\"%@`".contains(first);
+    let lowered = value.to_lowercase();
+    let looks_like_scalar = matches!(
+        lowered.as_str(),
+        "true" | "false" | "yes" | "no" | "on" | "off" | "null" | "~"
+    ) || value.parse::<f64>().is_ok();
+
+    !reserved_start
+        && !looks_like_scalar
+        && !value.contains(": ")
+        && !value.contains(" #")
+        && !value.contains('\n')
+        && value.trim() == value
+        && !value.ends_with(':')
+}
+
+// ---------------------------------------------------------------------------
+// JSON
+// ---------------------------------------------------------------------------
+
+struct JsonScanner<'a> {
+    src: &'a str,
+    bytes: &'a [u8],
+    pos: usize,
+    entries: Vec<ResourceEntry>,
+}
+
+fn parse_json(content: &str) -> Result<Vec<ResourceEntry>, String> {
+    let mut scanner = JsonScanner {
+        src: content,
+        bytes: content.as_bytes(),
+        pos: 0,
+        entries: Vec::new(),
+    };
+    scanner.skip_whitespace();
+    scanner.value("")?;
+    Ok(scanner.entries)
+}
+
+impl JsonScanner<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.pos < self.bytes.len() && self.bytes[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("解析JSON失败: {} (位置 {})", message, self.pos)
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), String> {
+        self.skip_whitespace();
+        if self.bytes.get(self.pos) == Some(&byte) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("缺少 '{}'", byte as char)))
+        }
+    }
+
+    /// 读取一个字符串，返回 (内容起始, 内容结束, 反转义后的值)
+    fn string(&mut self) -> Result<(usize, usize, String), String> {
+        self.expect(b'"')?;
+        let start = self.pos;
+        while self.pos < self.bytes.len() {
+            match self.bytes[self.pos] {
+                b'\\' => self.pos += 2,
+                b'"' => {
+                    let end = self.pos;
+                    self.pos += 1;
+                    let value = serde_json::from_str::<String>(&self.src[start - 1..=end])
+                        .map_err(|e| self.error(&e.to_string()))?;
+                    return Ok((start, end, value));
+                }
+                _ => self.pos += 1,
+            }
+        }
+        Err(self.error("字符串未结束"))
+    }
+
+    fn value(&mut self, path: &str) -> Result<(), String> {
+        self.skip_whitespace();
+        match self.bytes.get(self.pos) {
+            Some(b'{') => {
+                self.pos += 1;
+                self.skip_whitespace();
+                if self.bytes.get(self.pos) == Some(&b'}') {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                loop {
+                    let (_, _, key) = self.string()?;
+                    self.expect(b':')?;
+                    let child = join_key(path, &key);
+                    self.value(&child)?;
+                    self.skip_whitespace();
+                    match self.bytes.get(self.pos) {
+                        Some(b',') => self.pos += 1,
+                        Some(b'}') => {
+                            self.pos += 1;
+                            return Ok(());
+                        }
+                        _ => return Err(self.error("对象未正确结束")),
+                    }
+                    self.skip_whitespace();
+                }
+            }
+            Some(b'[') => {
+                self.pos += 1;
+                self.skip_whitespace();
+                if self.bytes.get(self.pos) == Some(&b']') {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                let mut index = 0;
+                loop {
+                    self.value(&format!("{}[{}]", path, index))?;
+                    index += 1;
+                    self.skip_whitespace();
+                    match self.bytes.get(self.pos) {
+                        Some(b',') => self.pos += 1,
+                        Some(b']') => {
+                            self.pos += 1;
+                            return Ok(());
+                        }
+                        _ => return Err(self.error("数组未正确结束")),
+                    }
+                }
+            }
+            Some(b'"') => {
+                let (start, end, value) = self.string()?;
+                self.entries.push(ResourceEntry {
+                    key: path.to_string(),
+                    source: value,
+                    existing: None,
+                    start,
+                    end,
+                    escape: Escape::Json,
+                });
+                Ok(())
+            }
+            Some(_) => {
+                while self.pos < self.bytes.len()
+                    && !matches!(self.bytes[self.pos], b',' | b'}' | b']')
+                    && !self.bytes[self.pos].is_ascii_whitespace()
+                {
+                    self.pos += 1;
+                }
+                Ok(())
+            }
+            None => Err(self.error("意外的文件结尾")),
+        }
+    }
+}
+
+fn join_key(parent: &str, key: &str) -> String {
+    if parent.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", parent, key)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// YAML（覆盖本地化文件常用的子集：映射、序列、引号与块标量）
+// ---------------------------------------------------------------------------
+
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+fn parse_yaml(content: &str) -> Vec<ResourceEntry> {
+    let lines = lines_with_offsets(content);
+    let mut entries = Vec::new();
+    let mut stack: Vec<(usize, String)> = Vec::new();
+    let mut sequence_counters: HashMap<String, usize> = HashMap::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let (offset, line) = lines[i];
+        i += 1;
+        let trimmed = line.trim_start();
+        if trimmed.is_empty()
+            || trimmed.starts_with('#')
+            || trimmed.starts_with("---")
+            || trimmed.starts_with("...")
+        {
+            continue;
+        }
+
+        let indent = indent_of(line);
+        while stack.last().is_some_and(|(level, _)| *level >= indent) {
+            stack.pop();
+        }
+
+        let mut content_indent = indent;
+        let mut rest = trimmed;
+        let mut rest_offset = offset + indent;
+
+        if rest == "-" || rest.starts_with("- ") {
+            let parent = current_path(&stack);
+            let counter = sequence_counters
+                .entry(format!("{}@{}", parent, indent))
+                .or_insert(0);
+            let item_key = format!("[{}]", counter);
+            *counter += 1;
+            stack.push((indent, item_key));
+            let skipped = rest.len() - rest[1..].trim_start().len();
+            rest_offset += skipped;
+            rest = rest[1..].trim_start();
+            content_indent = indent + skipped;
+            if rest.is_empty() {
+                continue;
+            }
+            if split_yaml_key(rest).is_none() {
+                let key = current_path(&stack);
+                if let Some(entry) = yaml_scalar(&key, rest, rest_offset) {
+                    entries.push(entry);
+                }
+                continue;
+            }
+        }
+
+        let Some((key, value_start)) = split_yaml_key(rest) else {
+            continue;
+        };
+        let full_key = join_key(&current_path(&stack), &key);
+        let value = rest[value_start..].trim_start();
+        let value_offset = rest_offset + (rest.len() - value.len());
+
+        if value.is_empty() || value.starts_with('#') {
+            stack.push((content_indent, key));
+            continue;
+        }
+
+        if value.starts_with('|') || value.starts_with('>') {
+            // 块标量：收集缩进更深的后续行
+            let first = i;
+            let mut last = i;
+            let mut block_indent = None;
+            while i < lines.len() {
+                let (_, block_line) = lines[i];
+                if block_line.trim().is_empty() {
+                    i += 1;
+                    continue;
+                }
+                let level = indent_of(block_line);
+                if level <= content_indent {
+                    break;
+                }
+                block_indent.get_or_insert(level);
+                i += 1;
+                last = i;
+            }
+            if let Some(block_indent) = block_indent {
+                let start = lines[first].0;
+                let (last_offset, last_line) = lines[last - 1];
+                let end = last_offset + last_line.len();
+                let source = lines[first..last]
+                    .iter()
+                    .map(|(_, l)| l.get(block_indent..).unwrap_or("").to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                entries.push(ResourceEntry {
+                    key: full_key,
+                    source,
+                    existing: None,
+                    start,
+                    end,
+                    escape: Escape::YamlBlock {
+                        indent: block_indent,
+                    },
+                });
+            }
+            continue;
+        }
+
+        if let Some(entry) = yaml_scalar(&full_key, value, value_offset) {
+            entries.push(entry);
+        }
+    }
+
+    entries
+}
+
+/// 整个文件只有一个以语言代码命名的根键（Rails 的 `en:`）时，返回该键在原文中的区间
+fn yaml_locale_root(content: &str) -> Option<(usize, usize)> {
+    let mut root = None;
+    for (offset, line) in lines_with_offsets(content) {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty()
+            || trimmed.starts_with('#')
+            || trimmed.starts_with("---")
+            || trimmed.starts_with("...")
+            || indent_of(line) > 0
+        {
+            continue;
+        }
+        if root.is_some() {
+            return None;
+        }
+        let (key, value_start) = split_yaml_key(trimmed)?;
+        let value = trimmed[value_start..].trim_start();
+        if !(value.is_empty() || value.starts_with('#')) || languages::find(&key).is_none() {
+            return None;
+        }
+        let start = offset + trimmed.find(key.as_str())?;
+        root = Some((start, start + key.len()));
+    }
+    root
+}
+
+fn current_path(stack: &[(usize, String)]) -> String {
+    stack.iter().fold(String::new(), |path, (_, key)| {
+        if key.starts_with('[') {
+            format!("{}{}", path, key)
+        } else {
+            join_key(&path, key)
+        }
+    })
+}
+
+/// 拆分 `key: value`，返回 (键, 值在 rest 中的起始位置)
+fn split_yaml_key(rest: &str) -> Option<(String, usize)> {
+    let (key, after_key) = if rest.starts_with('"') || rest.starts_with('\'') {
+        let quote = rest.chars().next()?;
+        let close = rest[1..].find(quote)? + 1;
+        (rest[1..close].to_string(), close + 1)
+    } else {
+        let colon = rest.find(": ").or_else(|| rest.strip_suffix(':').map(|s| s.len()))?;
+        (rest[..colon].trim_end().to_string(), colon)
+    };
+    if key.is_empty() || key.contains(" #") {
+        return None;
+    }
+    let remainder = &rest[after_key..];
+    let remainder_trimmed = remainder.trim_start();
+    if !remainder_trimmed.starts_with(':') {
+        return None;
+    }
+    let value_start = after_key + (remainder.len() - remainder_trimmed.len()) + 1;
+    Some((key, value_start))
+}
+
+fn yaml_scalar(key: &str, value: &str, offset: usize) -> Option<ResourceEntry> {
+    let first = value.chars().next()?;
+    let (start, end, source, escape) = match first {
+        '"' => {
+            let bytes = value.as_bytes();
+            let mut pos = 1;
+            while pos < bytes.len() && bytes[pos] != b'"' {
+                pos += if bytes[pos] == b'\\' { 2 } else { 1 };
+            }
+            if pos >= bytes.len() {
+                return None;
+            }
+            (
+                1,
+                pos,
+                unescape_double_quoted(&value[1..pos]),
+                Escape::YamlDouble,
+            )
+        }
+        '\'' => {
+            let mut pos = 1;
+            loop {
+                let found = value[pos..].find('\'')? + pos;
+                if value[found + 1..].starts_with('\'') {
+                    pos = found + 2;
+                } else {
+                    pos = found;
+                    break;
+                }
+            }
+            (
+                1,
+                pos,
+                value[1..pos].replace("''", "'"),
+                Escape::YamlSingle,
+            )
+        }
+        '&' | '*' | '!' | '[' | '{' | '|' | '>' | '%' | '@' | '`' => return None,
+        _ => {
+            let end = value.find(" #").unwrap_or(value.len());
+            let text = value[..end].trim_end();
+            let lowered = text.to_lowercase();
+            if matches!(
+                lowered.as_str(),
+                "true" | "false" | "yes" | "no" | "on" | "off" | "null" | "~"
+            ) || text.parse::<f64>().is_ok()
+            {
+                return None;
+            }
+            (0, text.len(), text.to_string(), Escape::YamlPlain)
+        }
+    };
+
+    Some(ResourceEntry {
+        key: key.to_string(),
+        source,
+        existing: None,
+        start: offset + start,
+        end: offset + end,
+        escape,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// gettext PO
+// ---------------------------------------------------------------------------
+
+#[derive(Default)]
+struct PoMessage {
+    context: String,
+    msgid: String,
+    msgid_plural: Option<String>,
+    fuzzy: bool,
+    /// (复数序号, 区间起点, 区间终点, 现有译文)
+    msgstr: Vec<(usize, usize, usize, String)>,
+}
+
+/// 提取 PO 行中的引号字符串
+fn po_quoted(text: &str) -> Option<String> {
+    let trimmed = text.trim();
+    let inner = trimmed.strip_prefix('"')?.strip_suffix('"')?;
+    Some(unescape_double_quoted(inner))
+}
+
+/// 目标语言的复数形式数量及 gettext 的 Plural-Forms 规则，未收录的语言返回 None（保持原文件的形式数）
+fn po_plural_forms(lang: &str) -> Option<(usize, &'static str)> {
+    let code = languages::normalize(lang);
+    let primary = code
+        .split('-')
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let forms = match primary.as_str() {
+        "zh" | "yue" | "ja" | "ko" | "vi" | "th" | "id" | "ms" | "lo" | "km" | "my" => {
+            (1, "nplurals=1; plural=0;")
+        }
+        "pt" if code.eq_ignore_ascii_case("pt-BR") => (2, "nplurals=2; plural=(n > 1);"),
+        "fr" | "fa" | "hy" => (2, "nplurals=2; plural=(n > 1);"),
+        "en" | "de" | "nl" | "sv" | "da" | "nb" | "nn" | "no" | "it" | "es" | "pt" | "el" | "fi"
+        | "hu" | "bg" | "et" | "he" | "ca" | "eu" | "gl" | "hi" | "bn" | "ta" | "te" | "ur"
+        | "tr" | "kk" | "mn" | "af" | "sw" => (2, "nplurals=2; plural=(n != 1);"),
+        "ru" | "uk" | "be" | "sr" | "hr" | "bs" => (
+            3,
+            "nplurals=3; plural=(n%10==1 && n%100!=11 ? 0 : n%10>=2 && n%10<=4 && (n%100<10 || n%100>=20) ? 1 : 2);",
+        ),
+        "pl" => (
+            3,
+            "nplurals=3; plural=(n==1 ? 0 : n%10>=2 && n%10<=4 && (n%100<10 || n%100>=20) ? 1 : 2);",
+        ),
+        "cs" | "sk" => (3, "nplurals=3; plural=(n==1 ? 0 : (n>=2 && n<=4) ? 1 : 2);"),
+        "ro" => (
+            3,
+            "nplurals=3; plural=(n==1 ? 0 : (n==0 || (n%100 > 0 && n%100 < 20)) ? 1 : 2);",
+        ),
+        "lt" => (
+            3,
+            "nplurals=3; plural=(n%10==1 && n%100!=11 ? 0 : n%10>=2 && (n%100<10 || n%100>=20) ? 1 : 2);",
+        ),
+        "lv" => (3, "nplurals=3; plural=(n%10==1 && n%100!=11 ? 0 : n != 0 ? 1 : 2);"),
+        "sl" => (
+            4,
+            "nplurals=4; plural=(n%100==1 ? 0 : n%100==2 ? 1 : n%100==3 || n%100==4 ? 2 : 3);",
+        ),
+        "ga" => (5, "nplurals=5; plural=(n==1 ? 0 : n==2 ? 1 : n<7 ? 2 : n<11 ? 3 : 4);"),
+        "ar" => (
+            6,
+            "nplurals=6; plural=(n==0 ? 0 : n==1 ? 1 : n==2 ? 2 : n%100>=3 && n%100<=10 ? 3 : n%100>=11 ? 4 : 5);",
+        ),
+        _ => return None,
+    };
+    Some(forms)
+}
+
+/// 改写文件头中的 Plural-Forms；文件头没有这一项时追加到 `msgstr ""` 之后
+fn po_plural_header_patch(header: &str, rule: &str, crlf: bool) -> Option<(usize, usize, String)> {
+    if let Some(start) = header.find("Plural-Forms:") {
+        let rest = &header[start..];
+        let end = start + rest.find("\\n").or_else(|| rest.find('"'))?;
+        return Some((start, end, format!("Plural-Forms: {}", rule)));
+    }
+
+    let lines = lines_with_offsets(header);
+    let position = lines
+        .windows(2)
+        .position(|pair| pair[0].1.trim() == "msgid \"\"" && pair[1].1.trim() == "msgstr \"\"")?;
+    let (offset, line) = lines[position + 1];
+    let end = offset + line.len();
+    let newline = if crlf { "\r\n" } else { "\n" };
+    Some((
+        end,
+        end,
+        format!("{}\"Plural-Forms: {}\\n\"", newline, rule),
+    ))
+}
+
+fn parse_po(content: &str) -> Vec<ResourceEntry> {
+    #[derive(PartialEq)]
+    enum Field {
+        None,
+        Context,
+        Id,
+        Plural,
+        Str,
+    }
+
+    let mut entries = Vec::new();
+    let mut message = PoMessage::default();
+    let mut field = Field::None;
+
+    let mut flush = |message: &mut PoMessage| {
+        if !message.msgid.is_empty() {
+            for (n, start, end, existing) in message.msgstr.drain(..) {
+                let source = match (&message.msgid_plural, n) {
+                    (Some(plural), n) if n > 0 => plural.clone(),
+                    _ => message.msgid.clone(),
+                };
+                let mut key = if message.context.is_empty() {
+                    message.msgid.clone()
+                } else {
+                    format!("{}\u{4}{}", message.context, message.msgid)
+                };
+                if message.msgid_plural.is_some() {
+                    key.push_str(&format!("[{}]", n));
+                }
+                entries.push(ResourceEntry {
+                    key,
+                    source,
+                    existing: Some(existing).filter(|_| !message.fuzzy),
+                    start,
+                    end,
+                    escape: Escape::Po {
+                        plural: message.msgid_plural.as_ref().map(|_| n),
+                    },
+                });
+            }
+        }
+        *message = PoMessage::default();
+    };
+
+    for (offset, line) in lines_with_offsets(content) {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            flush(&mut message);
+            field = Field::None;
+            continue;
+        }
+        if trimmed.starts_with('#') {
+            if field == Field::Str {
+                flush(&mut message);
+                field = Field::None;
+            }
+            if trimmed.starts_with("#,") && trimmed.contains("fuzzy") {
+                message.fuzzy = true;
+            }
+            continue;
+        }
+
+        let line_start = offset + (line.len() - line.trim_start().len());
+        if let Some(rest) = trimmed.strip_prefix("msgctxt ") {
+            if field == Field::Str {
+                flush(&mut message);
+            }
+            message.context = po_quoted(rest).unwrap_or_default();
+            field = Field::Context;
+        } else if let Some(rest) = trimmed.strip_prefix("msgid_plural ") {
+            message.msgid_plural = po_quoted(rest);
+            field = Field::Plural;
+        } else if let Some(rest) = trimmed.strip_prefix("msgid ") {
+            if field == Field::Str {
+                flush(&mut message);
+            }
+            message.msgid = po_quoted(rest).unwrap_or_default();
+            field = Field::Id;
+        } else if trimmed.starts_with("msgstr") {
+            let Some(quote) = trimmed.find('"') else {
+                continue;
+            };
+            let index = trimmed[..quote]
+                .trim()
+                .strip_prefix("msgstr[")
+                .and_then(|s| s.strip_suffix(']'))
+                .and_then(|s| s.parse::<usize>().ok())
+                .unwrap_or(0);
+            let start = line_start + quote;
+            let end = line_start + trimmed.len();
+            let value = po_quoted(&trimmed[quote..]).unwrap_or_default();
+            message.msgstr.push((index, start, end, value));
+            field = Field::Str;
+        } else if trimmed.starts_with('"') {
+            let value = po_quoted(trimmed).unwrap_or_default();
+            match field {
+                Field::Context => message.context.push_str(&value),
+                Field::Id => message.msgid.push_str(&value),
+                Field::Plural => {
+                    if let Some(plural) = message.msgid_plural.as_mut() {
+                        plural.push_str(&value);
+                    }
+                }
+                Field::Str => {
+                    if let Some(last) = message.msgstr.last_mut() {
+                        last.2 = line_start + trimmed.len();
+                        last.3.push_str(&value);
+                    }
+                }
+                Field::None => {}
+            }
+        }
+    }
+    flush(&mut message);
+
+    entries
+}
+
+// ---------------------------------------------------------------------------
+// Android strings.xml
+// ---------------------------------------------------------------------------
+
+fn xml_attribute(tag: &str, name: &str) -> Option<String> {
+    let needle = format!("{}=", name);
+    let mut search = 0;
+    while let Some(found) = tag[search..].find(&needle) {
+        let position = search + found;
+        let boundary = tag[..position]
+            .chars()
+            .last()
+            .is_some_and(|c| c.is_whitespace());
+        let rest = &tag[position + needle.len()..];
+        let quote = rest.chars().next()?;
+        if boundary && (quote == '"' || quote == '\'') {
+            let close = rest[1..].find(quote)?;
+            return Some(rest[1..=close].to_string());
+        }
+        search = position + needle.len();
+    }
+    None
+}
+
+fn parse_android(content: &str) -> Result<Vec<ResourceEntry>, String> {
+    let mut entries = Vec::new();
+    let mut pos = 0;
+    // 当前所在的 <string-array>/<plurals> 名称及条目计数
+    let mut container: Option<(String, usize)> = None;
+
+    while let Some(found) = content[pos..].find('<') {
+        let tag_start = pos + found;
+        let rest = &content[tag_start..];
+
+        if rest.starts_with("<!--") {
+            pos = tag_start + rest.find("-->").map(|i| i + 3).unwrap_or(rest.len());
+            continue;
+        }
+        let tag_end = tag_start
+            + rest
+                .find('>')
+                .ok_or_else(|| "解析strings.xml失败: 标签未闭合".to_string())?
+            + 1;
+        let tag = &content[tag_start..tag_end];
+        pos = tag_end;
+
+        let tag_name: String = tag[1..]
+            .chars()
+            .take_while(|c| !c.is_whitespace() && *c != '>' && *c != '/')
+            .collect();
+        let self_closing = tag.ends_with("/>");
+
+        match tag_name.as_str() {
+            "string-array" | "plurals" if !self_closing => {
+                let translatable = xml_attribute(tag, "translatable").as_deref() != Some("false");
+                let name = xml_attribute(tag, "name").unwrap_or_default();
+                if translatable {
+                    container = Some((name, 0));
+                } else {
+                    let closing = format!("</{}>", tag_name);
+                    pos = content[pos..]
+                        .find(&closing)
+                        .map(|i| pos + i + closing.len())
+                        .unwrap_or(content.len());
+                }
+            }
+            "/string-array" | "/plurals" => container = None,
+            "string" | "item" if !self_closing => {
+                let closing = format!("</{}>", tag_name);
+                let inner_end = content[pos..]
+                    .find(&closing)
+                    .map(|i| pos + i)
+                    .ok_or_else(|| format!("解析strings.xml失败: 缺少 {}", closing))?;
+                let inner = &content[pos..inner_end];
+                let inner_start = pos;
+                pos = inner_end + closing.len();
+
+                let key = if tag_name == "string" {
+                    if xml_attribute(tag, "translatable").as_deref() == Some("false") {
+                        continue;
+                    }
+                    match xml_attribute(tag, "name") {
+                        Some(name) => name,
+                        None => continue,
+                    }
+                } else if let Some((name, counter)) = container.as_mut() {
+                    let suffix = xml_attribute(tag, "quantity").unwrap_or_else(|| counter.to_string());
+                    *counter += 1;
+                    format!("{}[{}]", name, suffix)
+                } else {
+                    continue;
+                };
+
+                if inner.trim_start().starts_with("<![CDATA[") || inner.trim().starts_with('@') {
+                    continue;
+                }
+
+                entries.push(ResourceEntry {
+                    key,
+                    source: unescape_android(inner),
+                    existing: None,
+                    start: inner_start,
+                    end: inner_end,
+                    escape: Escape::AndroidXml,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    Ok(entries)
+}
+
+/// 去除 Android 的反斜杠转义和外层双引号，XML 实体与内联标签原样保留
+fn unescape_android(inner: &str) -> String {
+    let trimmed = inner.trim();
+    let unquoted = trimmed
+        .strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .unwrap_or(trimmed);
+
+    let mut output = String::with_capacity(unquoted.len());
+    let mut chars = unquoted.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            output.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => output.push('\n'),
+            Some('t') => output.push('\t'),
+            Some(other) => output.push(other),
+            None => {}
+        }
+    }
+    output
+}
+
+fn escape_android(value: &str) -> String {
+    let mut output = String::with_capacity(value.len());
+    let mut in_tag = false;
+    for (i, ch) in value.char_indices() {
+        match ch {
+            '<' => {
+                in_tag = true;
+                output.push(ch);
+            }
+            '>' => {
+                in_tag = false;
+                output.push(ch);
+            }
+            '\'' | '"' if !in_tag => {
+                output.push('\\');
+                output.push(ch);
+            }
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\t' => output.push_str("\\t"),
+            '@' | '?' if i == 0 => {
+                output.push('\\');
+                output.push(ch);
+            }
+            '&' if !is_xml_entity(&value[i..]) => output.push_str("&amp;"),
+            _ => output.push(ch),
+        }
+    }
+    output
+}
+
+fn is_xml_entity(text: &str) -> bool {
+    text.find(';').is_some_and(|end| {
+        end > 1
+            && end <= 10
+            && text[1..end]
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '#')
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Apple .strings
+// ---------------------------------------------------------------------------
+
+fn parse_apple_strings(content: &str) -> Result<Vec<ResourceEntry>, String> {
+    let bytes = content.as_bytes();
+    let mut entries = Vec::new();
+    let mut pos = 0;
+
+    let skip_trivia = |pos: &mut usize| loop {
+        while *pos < bytes.len() && bytes[*pos].is_ascii_whitespace() {
+            *pos += 1;
+        }
+        if content[*pos..].starts_with("/*") {
+            *pos = content[*pos..]
+                .find("*/")
+                .map(|i| *pos + i + 2)
+                .unwrap_or(bytes.len());
+        } else if content[*pos..].starts_with("//") {
+            *pos = content[*pos..]
+                .find('\n')
+                .map(|i| *pos + i + 1)
+                .unwrap_or(bytes.len());
+        } else {
+            break;
+        }
+    };
+
+    let read_token = |pos: &mut usize| -> Result<(usize, usize, String), String> {
+        if bytes.get(*pos) == Some(&b'"') {
+            let start = *pos + 1;
+            let mut cursor = start;
+            while cursor < bytes.len() && bytes[cursor] != b'"' {
+                cursor += if bytes[cursor] == b'\\' { 2 } else { 1 };
+            }
+            if cursor >= bytes.len() {
+                return Err("解析.strings失败: 字符串未结束".to_string());
+            }
+            *pos = cursor + 1;
+            Ok((start, cursor, unescape_double_quoted(&content[start..cursor])))
+        } else {
+            let start = *pos;
+            while *pos < bytes.len()
+                && (bytes[*pos].is_ascii_alphanumeric() || matches!(bytes[*pos], b'_' | b'.'))
+            {
+                *pos += 1;
+            }
+            if *pos == start {
+                return Err(format!("解析.strings失败: 非法字符 (位置 {})", start));
+            }
+            Ok((start, *pos, content[start..*pos].to_string()))
+        }
+    };
+
+    loop {
+        skip_trivia(&mut pos);
+        if pos >= bytes.len() {
+            break;
+        }
+        let (_, _, key) = read_token(&mut pos)?;
+        skip_trivia(&mut pos);
+        if bytes.get(pos) != Some(&b'=') {
+            return Err(format!("解析.strings失败: 缺少 '=' (位置 {})", pos));
+        }
+        pos += 1;
+        skip_trivia(&mut pos);
+        let (start, end, value) = read_token(&mut pos)?;
+        skip_trivia(&mut pos);
+        if bytes.get(pos) == Some(&b';') {
+            pos += 1;
+        }
+        entries.push(ResourceEntry {
+            key,
+            source: value,
+            existing: None,
+            start,
+            end,
+            escape: Escape::AppleStrings,
+        });
+    }
+
+    Ok(entries)
+}
+
+// ---------------------------------------------------------------------------
+// Fluent (.ftl)
+// ---------------------------------------------------------------------------
+
+fn is_fluent_identifier(text: &str) -> bool {
+    let mut chars = text.chars();
+    let first = match chars.next() {
+        Some('-') => chars.next(),
+        other => other,
+    };
+    first.is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+fn parse_fluent(content: &str) -> Vec<ResourceEntry> {
+    let mut entries = Vec::new();
+    let mut message_id = String::new();
+    let mut continuation = 0;
+
+    let mut push = |key: String, text: &str, start: usize| {
+        let text = text.trim_end();
+        // 选择表达式的开头（`{ $n ->`）不是可翻译文本
+        if text.is_empty() || text.ends_with("->") {
+            return;
+        }
+        entries.push(ResourceEntry {
+            key,
+            source: text.to_string(),
+            existing: None,
+            start,
+            end: start + text.len(),
+            escape: Escape::Fluent,
+        });
+    };
+
+    for (offset, line) in lines_with_offsets(content) {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let indent = line.len() - trimmed.len();
+
+        if indent == 0 {
+            let Some(eq) = line.find('=') else {
+                continue;
+            };
+            let id = line[..eq].trim();
+            if !is_fluent_identifier(id) {
+                continue;
+            }
+            message_id = id.to_string();
+            continuation = 0;
+            let value = &line[eq + 1..];
+            let value_trimmed = value.trim_start();
+            let start = offset + eq + 1 + (value.len() - value_trimmed.len());
+            push(message_id.clone(), value_trimmed, start);
+            continue;
+        }
+
+        if message_id.is_empty() || trimmed.starts_with('}') {
+            continue;
+        }
+
+        if let Some(attribute) = trimmed.strip_prefix('.') {
+            if let Some(eq) = attribute.find('=') {
+                let name = attribute[..eq].trim();
+                let value = &attribute[eq + 1..];
+                let value_trimmed = value.trim_start();
+                let start = offset + indent + 1 + eq + 1 + (value.len() - value_trimmed.len());
+                push(format!("{}.{}", message_id, name), value_trimmed, start);
+            }
+            continue;
+        }
+
+        let variant = trimmed.strip_prefix('*').unwrap_or(trimmed);
+        if variant.starts_with('[') {
+            if let Some(close) = variant.find(']') {
+                let name = &variant[1..close];
+                let value = &variant[close + 1..];
+                let value_trimmed = value.trim_start();
+                let start = offset + line.len() - value.len() + (value.len() - value_trimmed.len());
+                push(format!("{}[{}]", message_id, name), value_trimmed, start);
+            }
+            continue;
+        }
+
+        continuation += 1;
+        push(
+            format!("{}+{}", message_id, continuation),
+            trimmed,
+            offset + indent,
+        );
+    }
+
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 以原文写回应得到原文件，以大写后的原文写回只改动值
+    fn round_trip(format: ResourceFormat, content: &str, expected: &str) -> ResourceDocument {
+        let document = ResourceDocument::parse(format, content).unwrap();
+        let sources: Vec<String> = document.entries.iter().map(|e| e.source.clone()).collect();
+        assert_eq!(document.render(&sources), content);
+        let upper: Vec<String> = sources.iter().map(|s| s.to_uppercase()).collect();
+        assert_eq!(document.render(&upper), expected);
+        document
+    }
+
+    fn keys(document: &ResourceDocument) -> Vec<&str> {
+        document.entries.iter().map(|e| e.key.as_str()).collect()
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let document = round_trip(
+            ResourceFormat::Json,
+            "{\n  \"title\": \"Hello \\\"world\\\"\",\n  \"menu\": {\"items\": [\"open\", \"close\"]},\n  \"count\": 3\n}\n",
+            "{\n  \"title\": \"HELLO \\\"WORLD\\\"\",\n  \"menu\": {\"items\": [\"OPEN\", \"CLOSE\"]},\n  \"count\": 3\n}\n",
+        );
+        assert_eq!(keys(&document), ["title", "menu.items[0]", "menu.items[1]"]);
+        assert_eq!(document.entries[0].source, "Hello \"world\"");
+    }
+
+    #[test]
+    fn yaml_round_trip() {
+        let document = round_trip(
+            ResourceFormat::Yaml,
+            "# comment\nen:\n  greeting: Hello\n  quoted: \"Say \\\"hi\\\"\"\n  single: 'it''s'\n  enabled: true\n  body: |\n    first line\n    second line\n  list:\n    - apple\n    - pear\n",
+            "# comment\nen:\n  greeting: HELLO\n  quoted: \"SAY \\\"HI\\\"\"\n  single: 'IT''S'\n  enabled: true\n  body: |\n    FIRST LINE\n    SECOND LINE\n  list:\n    - APPLE\n    - PEAR\n",
+        );
+        assert_eq!(
+            keys(&document),
+            ["greeting", "quoted", "single", "body", "list[0]", "list[1]"]
+        );
+        assert_eq!(document.entries[3].source, "first line\nsecond line");
+    }
+
+    #[test]
+    fn yaml_locale_root_follows_target() {
+        let content = "en:\n  greeting: Hello\n";
+        let mut document = ResourceDocument::parse(ResourceFormat::Yaml, content).unwrap();
+        document.retarget("de");
+        assert_eq!(
+            document.render(&["Hallo".to_string()]),
+            "de:\n  greeting: Hallo\n"
+        );
+
+        // 已有的目标文件按去掉根键后的键对应
+        let target = ResourceDocument::parse(ResourceFormat::Yaml, "de:\n  greeting: Hallo\n");
+        assert_eq!(
+            target
+                .unwrap()
+                .values_by_key()
+                .get("greeting")
+                .map(String::as_str),
+            Some("Hallo")
+        );
+
+        // 根键不是语言代码或不止一个根键时不改写
+        for content in ["app:\n  greeting: Hello\n", "en:\n  a: b\nfr:\n  a: c\n"] {
+            let mut document = ResourceDocument::parse(ResourceFormat::Yaml, content).unwrap();
+            document.retarget("de");
+            let sources: Vec<String> = document.entries.iter().map(|e| e.source.clone()).collect();
+            assert_eq!(document.render(&sources), content);
+        }
+    }
+
+    const PO: &str = "msgid \"\"\nmsgstr \"\"\n\"Content-Type: text/plain; charset=UTF-8\\n\"\n\"Plural-Forms: nplurals=2; plural=(n != 1);\\n\"\n\n#, fuzzy\nmsgid \"Open\"\nmsgstr \"Ouvrir\"\n\nmsgctxt \"menu\"\nmsgid \"Save\"\nmsgstr \"\"\n\nmsgid \"%d file\"\nmsgid_plural \"%d files\"\nmsgstr[0] \"\"\nmsgstr[1] \"\"\n";
+
+    #[test]
+    fn po_round_trip() {
+        let document = ResourceDocument::parse(ResourceFormat::Po, PO).unwrap();
+        assert_eq!(
+            keys(&document),
+            ["Open", "menu\u{4}Save", "%d file[0]", "%d file[1]"]
+        );
+        // fuzzy 条目的旧译文不可信
+        assert_eq!(document.entries[0].existing, None);
+        assert_eq!(document.entries[3].source, "%d files");
+
+        let values = ["O", "S", "F", "FS"].map(String::from);
+        let rendered = document.render(&values);
+        assert!(rendered.contains("msgid \"Open\"\nmsgstr \"O\"\n"));
+        assert!(rendered.contains("msgstr[0] \"F\"\nmsgstr[1] \"FS\"\n"));
+        assert!(rendered.contains("nplurals=2; plural=(n != 1);"));
+    }
+
+    #[test]
+    fn po_plural_forms_follow_target() {
+        let mut document = ResourceDocument::parse(ResourceFormat::Po, PO).unwrap();
+        document.retarget("ru");
+        assert_eq!(
+            keys(&document)[2..],
+            ["%d file[0]", "%d file[1]", "%d file[2]"]
+        );
+        let values = ["O", "S", "F0", "F1", "F2"].map(String::from);
+        let rendered = document.render(&values);
+        assert!(rendered.contains("msgstr[0] \"F0\"\nmsgstr[1] \"F1\"\nmsgstr[2] \"F2\"\n"));
+        assert!(rendered.contains("\"Plural-Forms: nplurals=3; plural=(n%10==1"));
+        assert!(!rendered.contains("nplurals=2"));
+        let reparsed = ResourceDocument::parse(ResourceFormat::Po, &rendered).unwrap();
+        assert_eq!(reparsed.entries.len(), 5);
+
+        let mut document = ResourceDocument::parse(ResourceFormat::Po, PO).unwrap();
+        document.retarget("ja");
+        assert_eq!(keys(&document)[2..], ["%d file[0]"]);
+        let values = ["O", "S", "F"].map(String::from);
+        let rendered = document.render(&values);
+        assert!(rendered.ends_with("msgid_plural \"%d files\"\nmsgstr[0] \"F\"\n"));
+        assert!(rendered.contains("\"Plural-Forms: nplurals=1; plural=0;\\n\""));
+
+        // 文件头没有 Plural-Forms 时补上
+        let without_header = PO.replace("\"Plural-Forms: nplurals=2; plural=(n != 1);\\n\"\n", "");
+        let mut document = ResourceDocument::parse(ResourceFormat::Po, &without_header).unwrap();
+        document.retarget("pl");
+        let values = ["O", "S", "F0", "F1", "F2"].map(String::from);
+        let rendered = document.render(&values);
+        assert!(rendered.starts_with("msgid \"\"\nmsgstr \"\"\n\"Plural-Forms: nplurals=3;"));
+        assert!(rendered.contains("msgstr[2] \"F2\""));
+    }
+
+    #[test]
+    fn android_round_trip() {
+        let document = round_trip(
+            ResourceFormat::AndroidXml,
+            "<resources>\n    <!-- <string name=\"x\">no</string> -->\n    <string name=\"app\">Notes</string>\n    <string name=\"id\" translatable=\"false\">abc</string>\n    <string-array name=\"days\">\n        <item>mon</item>\n    </string-array>\n    <plurals name=\"files\">\n        <item quantity=\"one\">%d file</item>\n    </plurals>\n</resources>\n",
+            "<resources>\n    <!-- <string name=\"x\">no</string> -->\n    <string name=\"app\">NOTES</string>\n    <string name=\"id\" translatable=\"false\">abc</string>\n    <string-array name=\"days\">\n        <item>MON</item>\n    </string-array>\n    <plurals name=\"files\">\n        <item quantity=\"one\">%D FILE</item>\n    </plurals>\n</resources>\n",
+        );
+        assert_eq!(document.entries.len(), 3);
+        assert_eq!(document.entries[0].key, "app");
+    }
+
+    #[test]
+    fn apple_strings_round_trip() {
+        let document = round_trip(
+            ResourceFormat::AppleStrings,
+            "/* Title */\n\"title\" = \"Hello \\\"you\\\"\";\n// note\nkey_2 = \"Bye\";\n",
+            "/* Title */\n\"title\" = \"HELLO \\\"YOU\\\"\";\n// note\nkey_2 = \"BYE\";\n",
+        );
+        assert_eq!(keys(&document), ["title", "key_2"]);
+    }
+
+    #[test]
+    fn fluent_round_trip() {
+        let document = round_trip(
+            ResourceFormat::Fluent,
+            "# comment\nhello = Hello\n    .title = Greeting\nfiles = { $n ->\n    [one] One file\n   *[other] Many files\n}\n",
+            "# comment\nhello = HELLO\n    .title = GREETING\nfiles = { $n ->\n    [one] ONE FILE\n   *[other] MANY FILES\n}\n",
+        );
+        assert_eq!(document.entries[0].key, "hello");
+        assert_eq!(document.entries.len(), 4);
+    }
+}
@@ -0,0 +1,308 @@
+// Subtitle file translation
+// Translates SRT, WebVTT and ASS/SSA cues while keeping timestamps and styling tags intact
+
+use super::{lines_with_offsets, placeholders};
+use crate::translation::TranslationBackend;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 支持的字幕格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubtitleFormat {
+    Srt,
+    WebVtt,
+    Ass,
+}
+
+impl SubtitleFormat {
+    /// 根据扩展名识别格式
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "srt" => Some(SubtitleFormat::Srt),
+            "vtt" => Some(SubtitleFormat::WebVtt),
+            "ass" | "ssa" => Some(SubtitleFormat::Ass),
+            _ => None,
+        }
+    }
+}
+
+/// 输出模式：仅译文，或原文 + 译文双语字幕
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SubtitleOutputMode {
+    #[default]
+    Translated,
+    Bilingual,
+}
+
+/// 一条字幕
+#[derive(Debug, Clone, Serialize)]
+pub struct SubtitleCue {
+    pub index: usize,
+    pub start: String,
+    pub end: String,
+    pub text: String,
+    #[serde(skip)]
+    span_start: usize,
+    #[serde(skip)]
+    span_end: usize,
+}
+
+/// 解析后的字幕文件
+#[derive(Debug, Clone)]
+pub struct SubtitleDocument {
+    pub format: SubtitleFormat,
+    pub cues: Vec<SubtitleCue>,
+    content: String,
+    line_ending: &'static str,
+}
+
+impl SubtitleDocument {
+    pub fn parse(format: SubtitleFormat, content: &str) -> Result<Self, String> {
+        // 去掉 UTF-8 BOM，避免第一条字幕的序号解析失败
+        let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+        let cues = match format {
+            SubtitleFormat::Srt | SubtitleFormat::WebVtt => parse_timed_blocks(content),
+            SubtitleFormat::Ass => parse_ass(content)?,
+        };
+        if cues.is_empty() {
+            return Err("字幕文件中没有可翻译的内容".to_string());
+        }
+
+        let line_ending = if content.contains("\r\n") { "\r\n" } else { "\n" };
+        Ok(SubtitleDocument {
+            format,
+            cues,
+            content: content.to_string(),
+            line_ending,
+        })
+    }
+
+    /// 用译文（与 cues 一一对应）生成新的字幕内容
+    pub fn render(&self, translations: &[String], mode: SubtitleOutputMode) -> String {
+        let mut output = String::with_capacity(self.content.len() * 2);
+        let mut cursor = 0;
+        for (cue, translated) in self.cues.iter().zip(translations) {
+            output.push_str(&self.content[cursor..cue.span_start]);
+            let text = match mode {
+                SubtitleOutputMode::Translated => translated.clone(),
+                SubtitleOutputMode::Bilingual => format!("{}\n{}", cue.text, translated),
+            };
+            output.push_str(&self.encode_text(&text));
+            cursor = cue.span_end;
+        }
+        output.push_str(&self.content[cursor..]);
+        output
+    }
+
+    fn encode_text(&self, text: &str) -> String {
+        match self.format {
+            SubtitleFormat::Ass => text.replace('\n', "\\N"),
+            SubtitleFormat::Srt | SubtitleFormat::WebVtt => text
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .collect::<Vec<_>>()
+                .join(self.line_ending),
+        }
+    }
+}
+
+/// 字幕翻译结果摘要
+#[derive(Debug, Clone, Serialize)]
+pub struct SubtitleTranslationSummary {
+    pub output_path: String,
+    pub format: SubtitleFormat,
+    pub mode: SubtitleOutputMode,
+    pub cues: usize,
+}
+
+/// 读取并解析字幕文件
+pub fn load_subtitle_file(path: &Path) -> Result<SubtitleDocument, String> {
+    let format = SubtitleFormat::from_path(path)
+        .ok_or_else(|| format!("不支持的字幕格式: {}", path.display()))?;
+    let content = fs::read_to_string(path).map_err(|e| format!("读取字幕文件失败: {}", e))?;
+    SubtitleDocument::parse(format, &content)
+}
+
+/// 翻译字幕文件并写出译文或双语字幕
+pub async fn translate_subtitle_file(
+    backend: &TranslationBackend,
+    input_path: &Path,
+    output_path: Option<&Path>,
+    from_lang: &str,
+    to_lang: &str,
+    mode: SubtitleOutputMode,
+    on_progress: impl FnMut(usize, usize),
+) -> Result<SubtitleTranslationSummary, String> {
+    let document = load_subtitle_file(input_path)?;
+
+    let protected: Vec<placeholders::ProtectedText> = document
+        .cues
+        .iter()
+        .map(|cue| placeholders::protect(&cue.text))
+        .collect();
+    let texts: Vec<String> = protected.iter().map(|p| p.text.clone()).collect();
+
+    // 相邻字幕按批发送，模型能看到上下文，翻译更连贯
+    let translated = backend
+        .translate_segments(&texts, from_lang, to_lang, on_progress)
+        .await?;
+    let translations: Vec<String> = translated
+        .iter()
+        .zip(&protected)
+        .map(|(text, protected)| placeholders::restore(text, &protected.placeholders))
+        .collect();
+
+    let output_path = output_path
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| default_output_path(input_path, to_lang, mode));
+    fs::write(&output_path, document.render(&translations, mode))
+        .map_err(|e| format!("写入字幕文件失败: {}", e))?;
+
+    Ok(SubtitleTranslationSummary {
+        output_path: output_path.to_string_lossy().to_string(),
+        format: document.format,
+        mode,
+        cues: document.cues.len(),
+    })
+}
+
+fn default_output_path(input_path: &Path, to_lang: &str, mode: SubtitleOutputMode) -> PathBuf {
+    let stem = input_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let extension = input_path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let suffix = match mode {
+        SubtitleOutputMode::Translated => to_lang.to_string(),
+        SubtitleOutputMode::Bilingual => format!("{}.bilingual", to_lang),
+    };
+    input_path.with_file_name(format!("{}.{}.{}", stem, suffix, extension))
+}
+
+/// 解析 SRT / WebVTT：以时间轴行定位字幕，随后直到空行为止都是字幕文本
+fn parse_timed_blocks(content: &str) -> Vec<SubtitleCue> {
+    let lines = lines_with_offsets(content);
+    let mut cues = Vec::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let (_, line) = lines[i];
+        i += 1;
+        let Some((start, end)) = parse_timing_line(line) else {
+            continue;
+        };
+
+        let first = i;
+        while i < lines.len() && !lines[i].1.trim().is_empty() {
+            i += 1;
+        }
+        if i == first {
+            continue;
+        }
+
+        let span_start = lines[first].0;
+        let (last_offset, last_line) = lines[i - 1];
+        let text = lines[first..i]
+            .iter()
+            .map(|(_, l)| *l)
+            .collect::<Vec<_>>()
+            .join("\n");
+        cues.push(SubtitleCue {
+            index: cues.len() + 1,
+            start,
+            end,
+            text,
+            span_start,
+            span_end: last_offset + last_line.len(),
+        });
+    }
+
+    cues
+}
+
+/// 解析 `00:00:01,000 --> 00:00:02,000 [cue settings]`
+fn parse_timing_line(line: &str) -> Option<(String, String)> {
+    let (start, rest) = line.split_once("-->")?;
+    let end = rest.split_whitespace().next()?;
+    let start = start.trim();
+    let is_timestamp = |value: &str| {
+        !value.is_empty()
+            && value
+                .chars()
+                .all(|c| c.is_ascii_digit() || matches!(c, ':' | ',' | '.'))
+    };
+    if is_timestamp(start) && is_timestamp(end) {
+        Some((start.to_string(), end.to_string()))
+    } else {
+        None
+    }
+}
+
+/// 解析 ASS/SSA 的 [Events] 段，仅翻译 Dialogue 的 Text 字段
+fn parse_ass(content: &str) -> Result<Vec<SubtitleCue>, String> {
+    let mut cues = Vec::new();
+    let mut in_events = false;
+    let mut fields: Vec<String> = Vec::new();
+
+    for (offset, line) in lines_with_offsets(content) {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') {
+            in_events = trimmed.eq_ignore_ascii_case("[events]");
+            continue;
+        }
+        if !in_events {
+            continue;
+        }
+
+        if let Some(format) = trimmed.strip_prefix("Format:") {
+            fields = format
+                .split(',')
+                .map(|field| field.trim().to_lowercase())
+                .collect();
+            continue;
+        }
+
+        let Some(body) = line.strip_prefix("Dialogue:") else {
+            continue;
+        };
+        if fields.is_empty() {
+            return Err("ASS字幕缺少 Format 行".to_string());
+        }
+
+        let parts: Vec<&str> = body.splitn(fields.len(), ',').collect();
+        if parts.len() != fields.len() {
+            continue;
+        }
+        let field = |name: &str| {
+            fields
+                .iter()
+                .position(|f| f == name)
+                .and_then(|i| parts.get(i))
+                .map(|value| value.trim().to_string())
+                .unwrap_or_default()
+        };
+
+        let text = parts[parts.len() - 1];
+        if text.trim().is_empty() {
+            continue;
+        }
+        let span_end = offset + line.len();
+        cues.push(SubtitleCue {
+            index: cues.len() + 1,
+            start: field("start"),
+            end: field("end"),
+            text: text.replace("\\N", "\n").replace("\\n", "\n"),
+            span_start: span_end - text.len(),
+            span_end,
+        });
+    }
+
+    Ok(cues)
+}
@@ -0,0 +1,134 @@
+// CSV (RFC 4180) reading and writing for history export
+
+/// 写入 UTF-8 BOM，Excel 打开时才能正确识别中文等非 ASCII 文字
+const BOM: &str = "\u{feff}";
+
+pub fn write(rows: &[Vec<String>]) -> String {
+    let mut output = String::from(BOM);
+    for row in rows {
+        let cells: Vec<String> = row.iter().map(|cell| quote(cell)).collect();
+        output.push_str(&cells.join(","));
+        output.push_str("\r\n");
+    }
+    output
+}
+
+/// 解析 CSV；分隔符按首行判断，支持逗号、制表符和分号
+pub fn parse(content: &str) -> Result<Vec<Vec<String>>, String> {
+    let content = content.trim_start_matches('\u{feff}');
+    let delimiter = detect_delimiter(content.lines().next().unwrap_or_default());
+
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut cell = String::new();
+    let mut in_quotes = false;
+    let mut chars = content.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        if in_quotes {
+            match ch {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    cell.push('"');
+                }
+                '"' => in_quotes = false,
+                _ => cell.push(ch),
+            }
+            continue;
+        }
+        match ch {
+            '"' if cell.is_empty() => in_quotes = true,
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\r' | '\n' => {
+                row.push(std::mem::take(&mut cell));
+                rows.push(std::mem::take(&mut row));
+            }
+            _ if ch == delimiter => row.push(std::mem::take(&mut cell)),
+            _ => cell.push(ch),
+        }
+    }
+    if in_quotes {
+        return Err("CSV 格式错误：引号未闭合".to_string());
+    }
+    if !cell.is_empty() || !row.is_empty() {
+        row.push(cell);
+        rows.push(row);
+    }
+
+    // 去掉空行
+    rows.retain(|row| row.iter().any(|cell| !cell.trim().is_empty()));
+    Ok(rows)
+}
+
+fn quote(cell: &str) -> String {
+    if cell.contains([',', '"', '\r', '\n']) || cell.starts_with(char::is_whitespace) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
+
+fn detect_delimiter(header: &str) -> char {
+    [',', '\t', ';']
+        .into_iter()
+        .max_by_key(|delimiter| header.matches(*delimiter).count())
+        .filter(|delimiter| header.contains(*delimiter))
+        .unwrap_or(',')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cells(rows: &[&[&str]]) -> Vec<Vec<String>> {
+        rows.iter()
+            .map(|row| row.iter().map(|cell| cell.to_string()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn parses_quoted_cells_and_crlf_rows() {
+        let content = "\u{feff}original_text,translated_text\r\n\
+                       \"Hello, world\",\"你好，\"\"世界\"\"\"\r\n\
+                       \"two\r\nlines\",两行\r\n";
+        assert_eq!(
+            parse(content).unwrap(),
+            cells(&[
+                &["original_text", "translated_text"],
+                &["Hello, world", "你好，\"世界\""],
+                &["two\r\nlines", "两行"],
+            ])
+        );
+    }
+
+    #[test]
+    fn detects_delimiter_from_header_and_skips_blank_lines() {
+        assert_eq!(
+            parse("a;b\n1;2,5\n\n;\n3;4").unwrap(),
+            cells(&[&["a", "b"], &["1", "2,5"], &["3", "4"]])
+        );
+        assert_eq!(
+            parse("a\tb\nx y\tz").unwrap(),
+            cells(&[&["a", "b"], &["x y", "z"]])
+        );
+        // 最后一行没有换行符，末尾的空单元格也要保留
+        assert_eq!(parse("a,b\n1,").unwrap(), cells(&[&["a", "b"], &["1", ""]]));
+    }
+
+    #[test]
+    fn rejects_unclosed_quotes() {
+        assert!(parse("a,b\n\"open,2").is_err());
+    }
+
+    #[test]
+    fn written_rows_parse_back() {
+        let rows = cells(&[
+            &["original_text", "note"],
+            &[" leading space", "comma, \"quote\"\nnewline"],
+            &["plain", ""],
+        ]);
+        let output = write(&rows);
+        assert!(output.starts_with(BOM));
+        assert_eq!(parse(&output).unwrap(), rows);
+    }
+}
@@ -0,0 +1,344 @@
+// History export and import
+// Moves translation history (with favorites, tags and notes) in and out of trans.db as JSON,
+// CSV, TMX or XLSX so it can be shared between machines and teams
+
+mod csv;
+mod xlsx;
+
+use crate::database::{HistoryEntry, TranslationRecord};
+use crate::history_search;
+use crate::translation_memory::tmx::{self, TmxUnit};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// JSON 导出文件的格式标识和版本
+const ARCHIVE_FORMAT: &str = "ai-translate-history";
+const ARCHIVE_VERSION: u32 = 1;
+/// 导入时没有服务名称的记录使用的名称
+const IMPORTED_SERVICE: &str = "Import";
+
+/// CSV 和 XLSX 的列，第一行为列名，导入时按列名识别（不区分顺序和大小写）
+const COLUMNS: &[&str] = &[
+    "original_text",
+    "translated_text",
+    "from_language",
+    "to_language",
+    "service",
+    "created_at",
+    "operation",
+    "favorite",
+    "tags",
+    "note",
+];
+/// 表格中多个标签之间的分隔符
+const TAG_SEPARATOR: &str = "; ";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExchangeFormat {
+    Json,
+    Csv,
+    Tmx,
+    Xlsx,
+}
+
+impl ExchangeFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value
+            .trim()
+            .trim_start_matches('.')
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "json" => Some(ExchangeFormat::Json),
+            "csv" => Some(ExchangeFormat::Csv),
+            "tmx" => Some(ExchangeFormat::Tmx),
+            "xlsx" => Some(ExchangeFormat::Xlsx),
+            _ => None,
+        }
+    }
+
+    /// 未指定格式时按文件扩展名判断
+    pub fn resolve(format: Option<&str>, path: &Path) -> Result<Self, String> {
+        let value = format
+            .map(str::to_string)
+            .or_else(|| {
+                path.extension()
+                    .map(|ext| ext.to_string_lossy().to_string())
+            })
+            .unwrap_or_default();
+        Self::parse(&value).ok_or_else(|| format!("不支持的文件格式: {}", value))
+    }
+}
+
+/// 导入结果：新增条数、已存在而合并批注的条数、缺少原文或译文而跳过的条数
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HistoryImportSummary {
+    pub imported: usize,
+    pub duplicates: usize,
+    pub skipped: usize,
+}
+
+/// 从文件中读出的历史记录
+#[derive(Debug, Clone, Default)]
+pub struct ParsedHistory {
+    pub entries: Vec<HistoryEntry>,
+    pub skipped: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct HistoryArchive {
+    format: String,
+    version: u32,
+    exported_at: String,
+    entries: Vec<HistoryEntry>,
+}
+
+/// JSON 导入同时接受导出文件和只包含记录的数组
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ArchiveContent {
+    Archive(HistoryArchive),
+    Entries(Vec<HistoryEntry>),
+}
+
+pub fn export(entries: &[HistoryEntry], format: ExchangeFormat) -> Result<Vec<u8>, String> {
+    match format {
+        ExchangeFormat::Json => {
+            let archive = HistoryArchive {
+                format: ARCHIVE_FORMAT.to_string(),
+                version: ARCHIVE_VERSION,
+                exported_at: Utc::now().to_rfc3339(),
+                entries: entries.to_vec(),
+            };
+            serde_json::to_vec_pretty(&archive).map_err(|e| format!("生成 JSON 失败: {}", e))
+        }
+        ExchangeFormat::Csv => Ok(csv::write(&table_rows(entries)).into_bytes()),
+        ExchangeFormat::Tmx => {
+            let units: Vec<TmxUnit> = entries.iter().map(entry_to_unit).collect();
+            Ok(tmx::write_units(&units).into_bytes())
+        }
+        ExchangeFormat::Xlsx => xlsx::write("History", &table_rows(entries)),
+    }
+}
+
+pub fn import(bytes: &[u8], format: ExchangeFormat) -> Result<ParsedHistory, String> {
+    let entries = match format {
+        ExchangeFormat::Json => {
+            let content: ArchiveContent =
+                serde_json::from_slice(bytes).map_err(|e| format!("解析 JSON 失败: {}", e))?;
+            match content {
+                ArchiveContent::Archive(archive) => {
+                    if archive.format != ARCHIVE_FORMAT || archive.version > ARCHIVE_VERSION {
+                        return Err(format!(
+                            "不支持的历史记录文件: {} 版本 {}",
+                            archive.format, archive.version
+                        ));
+                    }
+                    archive.entries
+                }
+                ArchiveContent::Entries(entries) => entries,
+            }
+        }
+        ExchangeFormat::Csv => {
+            let content = crate::offline_dictionary::decode_text(bytes, None);
+            table_entries(csv::parse(&content)?)?
+        }
+        ExchangeFormat::Tmx => {
+            let content = crate::offline_dictionary::decode_text(bytes, None);
+            tmx::parse_units(&content)?
+                .into_iter()
+                .map(unit_to_entry)
+                .collect()
+        }
+        ExchangeFormat::Xlsx => table_entries(xlsx::read(bytes)?)?,
+    };
+
+    let total = entries.len();
+    let entries: Vec<HistoryEntry> = entries.into_iter().filter_map(normalize_entry).collect();
+    Ok(ParsedHistory {
+        skipped: total - entries.len(),
+        entries,
+    })
+}
+
+/// 整理导入的记录：去掉首尾空白，缺少原文或译文时丢弃，无法识别的时间改为导入时间
+fn normalize_entry(mut entry: HistoryEntry) -> Option<HistoryEntry> {
+    let record = &mut entry.record;
+    if record.original_text.trim().is_empty() || record.translated_text.trim().is_empty() {
+        return None;
+    }
+    record.id = None;
+    record.group_id = None;
+    record.service = Some(record.service.trim())
+        .filter(|service| !service.is_empty())
+        .unwrap_or(IMPORTED_SERVICE)
+        .to_string();
+    record.from_language = non_empty(record.from_language.take());
+    record.to_language = non_empty(record.to_language.take());
+    record.operation = non_empty(record.operation.take());
+    record.created_at = record.created_at.as_deref().and_then(normalize_time);
+    entry.note = non_empty(entry.note.take());
+    // 过长的标签单独丢弃，不影响其他标签
+    let tags: Vec<String> = entry
+        .tags
+        .iter()
+        .filter(|tag| history_search::normalize_tags(std::slice::from_ref(tag)).is_ok())
+        .cloned()
+        .collect();
+    entry.tags = history_search::normalize_tags(&tags).unwrap_or_default();
+    Some(entry)
+}
+
+/// 接受 RFC 3339 和 SQLite CURRENT_TIMESTAMP 格式（UTC），统一为 RFC 3339
+fn normalize_time(value: &str) -> Option<String> {
+    let value = value.trim();
+    DateTime::parse_from_rfc3339(value)
+        .map(|time| time.with_timezone(&Utc))
+        .or_else(|_| {
+            NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").map(|time| time.and_utc())
+        })
+        .ok()
+        .map(|time| time.to_rfc3339())
+}
+
+fn table_rows(entries: &[HistoryEntry]) -> Vec<Vec<String>> {
+    let mut rows = vec![COLUMNS.iter().map(|column| column.to_string()).collect()];
+    for entry in entries {
+        let record = &entry.record;
+        rows.push(vec![
+            record.original_text.clone(),
+            record.translated_text.clone(),
+            record.from_language.clone().unwrap_or_default(),
+            record.to_language.clone().unwrap_or_default(),
+            record.service.clone(),
+            record.created_at.clone().unwrap_or_default(),
+            record.operation.clone().unwrap_or_default(),
+            entry.favorite.to_string(),
+            entry.tags.join(TAG_SEPARATOR),
+            entry.note.clone().unwrap_or_default(),
+        ]);
+    }
+    rows
+}
+
+fn table_entries(rows: Vec<Vec<String>>) -> Result<Vec<HistoryEntry>, String> {
+    let mut rows = rows.into_iter();
+    let Some(header) = rows.next() else {
+        return Ok(Vec::new());
+    };
+    let index: HashMap<String, usize> = header
+        .iter()
+        .enumerate()
+        .map(|(i, name)| (name.trim().to_ascii_lowercase(), i))
+        .collect();
+    for required in ["original_text", "translated_text"] {
+        if !index.contains_key(required) {
+            return Err(format!("缺少 {} 列", required));
+        }
+    }
+
+    Ok(rows
+        .map(|row| {
+            let cell = |name: &str| {
+                index
+                    .get(name)
+                    .and_then(|&i| row.get(i))
+                    .map(|value| value.trim().to_string())
+                    .unwrap_or_default()
+            };
+            let optional = |name: &str| Some(cell(name)).filter(|value| !value.is_empty());
+            HistoryEntry {
+                record: TranslationRecord {
+                    id: None,
+                    original_text: cell("original_text"),
+                    translated_text: cell("translated_text"),
+                    service: cell("service"),
+                    from_language: optional("from_language"),
+                    to_language: optional("to_language"),
+                    created_at: optional("created_at"),
+                    group_id: None,
+                    operation: optional("operation"),
+                },
+                favorite: is_truthy(&cell("favorite")),
+                tags: split_tags(&cell("tags")),
+                note: optional("note"),
+            }
+        })
+        .collect())
+}
+
+fn entry_to_unit(entry: &HistoryEntry) -> TmxUnit {
+    let record = &entry.record;
+    let mut props = vec![("x-service".to_string(), record.service.clone())];
+    if let Some(operation) = &record.operation {
+        props.push(("x-operation".to_string(), operation.clone()));
+    }
+    if entry.favorite {
+        props.push(("x-favorite".to_string(), "true".to_string()));
+    }
+    for tag in &entry.tags {
+        props.push(("x-tag".to_string(), tag.clone()));
+    }
+    if let Some(note) = &entry.note {
+        props.push(("x-note".to_string(), note.clone()));
+    }
+    TmxUnit {
+        source_lang: record.from_language.clone().unwrap_or_default(),
+        source_text: record.original_text.clone(),
+        target_lang: record.to_language.clone().unwrap_or_default(),
+        target_text: record.translated_text.clone(),
+        props,
+        change_date: record.created_at.clone(),
+        usage_count: 1,
+    }
+}
+
+fn unit_to_entry(unit: TmxUnit) -> HistoryEntry {
+    let prop = |name: &str| unit.prop(name).map(str::to_string);
+    HistoryEntry {
+        favorite: unit.prop("x-favorite") == Some("true"),
+        tags: unit
+            .props
+            .iter()
+            .filter(|(name, _)| name == "x-tag")
+            .map(|(_, tag)| tag.clone())
+            .collect(),
+        note: prop("x-note"),
+        record: TranslationRecord {
+            id: None,
+            service: prop("x-service").unwrap_or_else(|| "TMX".to_string()),
+            operation: prop("x-operation"),
+            original_text: unit.source_text,
+            translated_text: unit.target_text,
+            from_language: Some(unit.source_lang),
+            to_language: Some(unit.target_lang),
+            created_at: unit.change_date,
+            group_id: None,
+        },
+    }
+}
+
+fn split_tags(value: &str) -> Vec<String> {
+    value
+        .split([';', '；'])
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn is_truthy(value: &str) -> bool {
+    matches!(
+        value.trim().to_ascii_lowercase().as_str(),
+        "true" | "1" | "yes" | "y" | "是" | "★"
+    )
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
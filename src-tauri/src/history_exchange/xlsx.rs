@@ -0,0 +1,449 @@
+// Minimal XLSX (Office Open XML spreadsheet) reading and writing for history export
+// Writes a single sheet with inline strings; reads the first sheet, resolving shared strings
+
+use std::io::{Cursor, Read, Write};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+const CONTENT_TYPES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/><Override PartName="/xl/worksheets/sheet1.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/><Override PartName="/xl/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.styles+xml"/></Types>"#;
+
+const ROOT_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/></Relationships>"#;
+
+const WORKBOOK_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet1.xml"/><Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/></Relationships>"#;
+
+/// 最小样式表；第二个样式为加粗，用于表头
+const STYLES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<styleSheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><fonts count="2"><font><sz val="11"/><name val="Calibri"/></font><font><b/><sz val="11"/><name val="Calibri"/></font></fonts><fills count="2"><fill><patternFill patternType="none"/></fill><fill><patternFill patternType="gray125"/></fill></fills><borders count="1"><border><left/><right/><top/><bottom/><diagonal/></border></borders><cellStyleXfs count="1"><xf numFmtId="0" fontId="0" fillId="0" borderId="0"/></cellStyleXfs><cellXfs count="2"><xf numFmtId="0" fontId="0" fillId="0" borderId="0" xfId="0"/><xf numFmtId="0" fontId="1" fillId="0" borderId="0" xfId="0" applyFont="1"/></cellXfs></styleSheet>"#;
+
+const DEFAULT_SHEET: &str = "xl/worksheets/sheet1.xml";
+
+/// Excel 的最大列数（A 到 XFD）
+const MAX_COLUMNS: usize = 16_384;
+
+/// 生成只有一个工作表的 xlsx，第一行按表头加粗
+pub fn write(sheet_name: &str, rows: &[Vec<String>]) -> Result<Vec<u8>, String> {
+    let workbook = format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets><sheet name="{}" sheetId="1" r:id="rId1"/></sheets></workbook>"#,
+        escape(sheet_name)
+    );
+
+    let mut sheet = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><sheetViews><sheetView workbookViewId="0"><pane ySplit="1" topLeftCell="A2" activePane="bottomLeft" state="frozen"/></sheetView></sheetViews><sheetData>"#,
+    );
+    for (row_index, row) in rows.iter().enumerate() {
+        let number = row_index + 1;
+        let style = if row_index == 0 { r#" s="1""# } else { "" };
+        sheet.push_str(&format!(r#"<row r="{}">"#, number));
+        for (column, value) in row.iter().enumerate() {
+            if value.is_empty() {
+                continue;
+            }
+            sheet.push_str(&format!(
+                r#"<c r="{}{}" t="inlineStr"{}><is><t xml:space="preserve">{}</t></is></c>"#,
+                column_name(column),
+                number,
+                style,
+                escape(value)
+            ));
+        }
+        sheet.push_str("</row>");
+    }
+    sheet.push_str("</sheetData></worksheet>");
+
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for (name, content) in [
+        ("[Content_Types].xml", CONTENT_TYPES),
+        ("_rels/.rels", ROOT_RELS),
+        ("xl/workbook.xml", workbook.as_str()),
+        ("xl/_rels/workbook.xml.rels", WORKBOOK_RELS),
+        ("xl/styles.xml", STYLES),
+        (DEFAULT_SHEET, sheet.as_str()),
+    ] {
+        writer
+            .start_file(name, options)
+            .and_then(|_| writer.write_all(content.as_bytes()).map_err(Into::into))
+            .map_err(|e| format!("写入 xlsx 文件失败: {}", e))?;
+    }
+    writer
+        .finish()
+        .map(|cursor| cursor.into_inner())
+        .map_err(|e| format!("写入 xlsx 文件失败: {}", e))
+}
+
+/// 读取第一个工作表的所有行，空单元格补为空字符串
+pub fn read(bytes: &[u8]) -> Result<Vec<Vec<String>>, String> {
+    let mut archive =
+        ZipArchive::new(Cursor::new(bytes)).map_err(|e| format!("打开 xlsx 文件失败: {}", e))?;
+
+    let shared = read_entry(&mut archive, "xl/sharedStrings.xml")?
+        .map(|content| shared_strings(&content))
+        .unwrap_or_default();
+    let sheet_path = first_sheet_path(&mut archive)?.unwrap_or_else(|| DEFAULT_SHEET.to_string());
+    let sheet = read_entry(&mut archive, &sheet_path)?
+        .ok_or_else(|| format!("xlsx 文件中缺少工作表: {}", sheet_path))?;
+
+    let mut rows = Vec::new();
+    let mut pos = 0;
+    while let Some((start, end)) = find_element(&sheet, "row", pos) {
+        let (body, next) = element_body(&sheet, "row", start, end);
+        pos = next;
+        rows.push(row_cells(body, &shared));
+    }
+    rows.retain(|row| row.iter().any(|cell| !cell.trim().is_empty()));
+    Ok(rows)
+}
+
+fn read_entry(
+    archive: &mut ZipArchive<Cursor<&[u8]>>,
+    name: &str,
+) -> Result<Option<String>, String> {
+    let mut file = match archive.by_name(name) {
+        Ok(file) => file,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(format!("读取 {} 失败: {}", name, e)),
+    };
+    let mut content = String::new();
+    file.read_to_string(&mut content)
+        .map_err(|e| format!("读取 {} 失败: {}", name, e))?;
+    Ok(Some(content))
+}
+
+/// 按 workbook.xml 中第一个 sheet 的关系 id 找到工作表文件
+fn first_sheet_path(archive: &mut ZipArchive<Cursor<&[u8]>>) -> Result<Option<String>, String> {
+    let Some(workbook) = read_entry(archive, "xl/workbook.xml")? else {
+        return Ok(None);
+    };
+    let Some(rels) = read_entry(archive, "xl/_rels/workbook.xml.rels")? else {
+        return Ok(None);
+    };
+    let Some(id) = find_element(&workbook, "sheet", 0)
+        .and_then(|(start, end)| attribute(&workbook[start..end], "r:id"))
+    else {
+        return Ok(None);
+    };
+
+    let mut pos = 0;
+    while let Some((start, end)) = find_element(&rels, "Relationship", pos) {
+        pos = end;
+        let tag = &rels[start..end];
+        if attribute(tag, "Id").as_deref() != Some(id.as_str()) {
+            continue;
+        }
+        return Ok(
+            attribute(tag, "Target").map(|target| match target.strip_prefix('/') {
+                Some(absolute) => absolute.to_string(),
+                None => format!("xl/{}", target),
+            }),
+        );
+    }
+    Ok(None)
+}
+
+fn shared_strings(content: &str) -> Vec<String> {
+    let mut strings = Vec::new();
+    let mut pos = 0;
+    while let Some((start, end)) = find_element(content, "si", pos) {
+        let (body, next) = element_body(content, "si", start, end);
+        pos = next;
+        strings.push(text_runs(body));
+    }
+    strings
+}
+
+fn row_cells(row: &str, shared: &[String]) -> Vec<String> {
+    let mut cells: Vec<String> = Vec::new();
+    let mut pos = 0;
+    while let Some((start, end)) = find_element(row, "c", pos) {
+        let tag = &row[start..end];
+        let (body, next) = element_body(row, "c", start, end);
+        pos = next;
+
+        // 没有 r 属性时紧接上一个单元格；引用无效或超出 XFD 的单元格忽略
+        let column = match attribute(tag, "r") {
+            Some(reference) => match column_index(&reference) {
+                Some(column) => column,
+                None => continue,
+            },
+            None => cells.len(),
+        };
+        if column >= MAX_COLUMNS {
+            continue;
+        }
+        let value = match attribute(tag, "t").as_deref() {
+            Some("inlineStr") => text_runs(body),
+            Some("s") => element_text(body, "v")
+                .and_then(|index| index.trim().parse::<usize>().ok())
+                .and_then(|index| shared.get(index).cloned())
+                .unwrap_or_default(),
+            Some("e") => String::new(),
+            _ => element_text(body, "v").map(unescape).unwrap_or_default(),
+        };
+        if cells.len() <= column {
+            cells.resize(column + 1, String::new());
+        }
+        cells[column] = value;
+    }
+    cells
+}
+
+/// 拼接富文本中的所有 <t>，忽略注音（<rPh>）
+fn text_runs(body: &str) -> String {
+    let mut text = String::new();
+    let mut pos = 0;
+    while let Some((start, end)) = find_element(body, "t", pos) {
+        let (content, next) = element_body(body, "t", start, end);
+        pos = next;
+        // 注音中的 <t> 不属于单元格文本
+        let before = &body[..start];
+        let in_phonetic = before
+            .rfind("<rPh")
+            .is_some_and(|open| !before[open..].contains("</rPh>"));
+        if !in_phonetic {
+            text.push_str(&unescape(content));
+        }
+    }
+    text
+}
+
+fn element_text<'a>(body: &'a str, name: &str) -> Option<&'a str> {
+    let (start, end) = find_element(body, name, 0)?;
+    Some(element_body(body, name, start, end).0)
+}
+
+/// 元素内容和元素结束后的位置；自闭合元素内容为空
+fn element_body<'a>(content: &'a str, name: &str, start: usize, end: usize) -> (&'a str, usize) {
+    if content[start..end].ends_with("/>") {
+        return ("", end);
+    }
+    let close = format!("</{}>", name);
+    match content[end..].find(&close) {
+        Some(offset) => (&content[end..end + offset], end + offset + close.len()),
+        None => (&content[end..], content.len()),
+    }
+}
+
+/// 查找开始标签，返回标签的起止位置
+fn find_element(content: &str, name: &str, from: usize) -> Option<(usize, usize)> {
+    let open = format!("<{}", name);
+    let mut pos = from;
+    while let Some(offset) = content[pos..].find(&open) {
+        let start = pos + offset;
+        let after = start + open.len();
+        if content[after..].starts_with(['>', ' ', '/', '\t', '\r', '\n']) {
+            let end = content[after..].find('>')? + after + 1;
+            return Some((start, end));
+        }
+        pos = after;
+    }
+    None
+}
+
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let mut pos = 0;
+    while let Some(offset) = tag[pos..].find(name) {
+        let start = pos + offset;
+        pos = start + name.len();
+        if !tag[..start].ends_with(char::is_whitespace) {
+            continue;
+        }
+        let Some(rest) = tag[pos..].trim_start().strip_prefix('=') else {
+            continue;
+        };
+        let rest = rest.trim_start();
+        let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+        let value = &rest[1..];
+        let end = value.find(quote)?;
+        return Some(unescape(&value[..end]));
+    }
+    None
+}
+
+/// 列号（从 0 开始）转为 A、B … Z、AA
+fn column_name(mut index: usize) -> String {
+    let mut name = Vec::new();
+    loop {
+        name.push(b'A' + (index % 26) as u8);
+        if index < 26 {
+            break;
+        }
+        index = index / 26 - 1;
+    }
+    name.reverse();
+    String::from_utf8(name).unwrap_or_default()
+}
+
+/// 单元格引用（如 C12）转为列号
+fn column_index(reference: &str) -> Option<usize> {
+    let letters: Vec<u8> = reference
+        .bytes()
+        .take_while(u8::is_ascii_alphabetic)
+        .map(|b| b.to_ascii_uppercase())
+        .collect();
+    if letters.is_empty() || letters.len() > 3 {
+        return None;
+    }
+    let number = letters.iter().try_fold(0usize, |acc, b| {
+        acc.checked_mul(26)?.checked_add((b - b'A' + 1) as usize)
+    })?;
+    (number <= MAX_COLUMNS).then(|| number - 1)
+}
+
+/// XML 转义，并去掉 XML 1.0 不允许的控制字符（Excel 遇到会拒绝打开）
+fn escape(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '"' => output.push_str("&quot;"),
+            '\t' | '\n' | '\r' => output.push(ch),
+            '\u{0}'..='\u{1f}' | '\u{fffe}' | '\u{ffff}' => {}
+            _ => output.push(ch),
+        }
+    }
+    output
+}
+
+/// 反转义 XML 实体，同时还原 Excel 的 _xHHHH_ 转义（如 _x000D_ 表示回车）
+fn unescape(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(index) = rest.find(['&', '_']) {
+        output.push_str(&rest[..index]);
+        rest = &rest[index..];
+        let (decoded, length) = if rest.starts_with('&') {
+            let entity = rest
+                .find(';')
+                .filter(|end| *end <= 10)
+                .map(|end| (&rest[1..end], end + 1));
+            let ch = entity.and_then(|(entity, _)| match entity {
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "amp" => Some('&'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                _ => entity
+                    .strip_prefix("#x")
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                    .and_then(char::from_u32),
+            });
+            (ch, entity.map_or(1, |(_, length)| length))
+        } else {
+            let ch = rest
+                .get(1..7)
+                .filter(|code| code.starts_with('x') && code.ends_with('_'))
+                .and_then(|code| code.get(1..5))
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .and_then(char::from_u32);
+            (ch, 7)
+        };
+        match decoded {
+            Some(ch) => {
+                output.push(ch);
+                rest = &rest[length..];
+            }
+            None => {
+                output.push_str(&rest[..1]);
+                rest = &rest[1..];
+            }
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 按 Excel 的方式组装工作簿：共享字符串、工作表放在非默认路径
+    fn workbook(sheet: &str, shared: &str) -> Vec<u8> {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default();
+        for (name, content) in [
+            (
+                "xl/workbook.xml",
+                r#"<workbook><sheets><sheet name="Data" sheetId="3" r:id="rId7"/></sheets></workbook>"#,
+            ),
+            (
+                "xl/_rels/workbook.xml.rels",
+                r#"<Relationships><Relationship Id="rId1" Target="styles.xml"/><Relationship Id="rId7" Target="worksheets/data.xml"/></Relationships>"#,
+            ),
+            ("xl/sharedStrings.xml", shared),
+            ("xl/worksheets/data.xml", sheet),
+        ] {
+            writer.start_file(name, options).unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn reads_shared_strings_rich_text_and_sparse_cells() {
+        let shared = r#"<sst><si><t>original_text</t></si><si><t>translated_text</t></si><si><r><t>Hello </t></r><r><rPr><b/></rPr><t xml:space="preserve">world</t></r></si><si><t>漢字</t><rPh sb="0" eb="2"><t>かんじ</t></rPh></si></sst>"#;
+        let sheet = r#"<worksheet><sheetData>
+            <row r="1"><c r="A1" t="s"><v>0</v></c><c r="B1" t="s"><v>1</v></c></row>
+            <row r="2"><c r="A2" t="s"><v>2</v></c><c r="C2"><v>42</v></c></row>
+            <row r="3"><c r="B3" t="s"><v>3</v></c><c r="D3" t="e"><v>#N/A</v></c></row>
+            <row r="4"/>
+            <row r="5"><c r="A5" t="inlineStr"><is><t>a &amp; b_x000D_</t></is></c><c t="str"><v>next</v></c></row>
+        </sheetData></worksheet>"#;
+        assert_eq!(
+            read(&workbook(sheet, shared)).unwrap(),
+            vec![
+                vec!["original_text".to_string(), "translated_text".to_string()],
+                vec!["Hello world".to_string(), String::new(), "42".to_string()],
+                vec![
+                    String::new(),
+                    "漢字".to_string(),
+                    String::new(),
+                    String::new()
+                ],
+                vec!["a & b\r".to_string(), "next".to_string()],
+            ]
+        );
+    }
+
+    #[test]
+    fn written_sheet_reads_back() {
+        let rows = vec![
+            vec!["original_text".to_string(), "translated_text".to_string()],
+            vec![
+                "<tag> & \"quote\"".to_string(),
+                "第一行\n第二行".to_string(),
+            ],
+            vec![String::new(), "only second column".to_string()],
+        ];
+        let bytes = write("History", &rows).unwrap();
+        assert_eq!(read(&bytes).unwrap(), rows);
+    }
+
+    #[test]
+    fn converts_column_references() {
+        for (index, name) in [
+            (0, "A"),
+            (25, "Z"),
+            (26, "AA"),
+            (701, "ZZ"),
+            (16_383, "XFD"),
+        ] {
+            assert_eq!(column_name(index), name);
+            assert_eq!(column_index(&format!("{}12", name)), Some(index));
+        }
+        assert_eq!(column_index("XFE1"), None);
+        assert_eq!(column_index("12"), None);
+    }
+
+    #[test]
+    fn rejects_files_that_are_not_zip_archives() {
+        assert!(read(b"original_text,translated_text").is_err());
+    }
+}
@@ -0,0 +1,326 @@
+// History search
+// Parses search input into phrase, prefix and plain terms for the trigram FTS5 index over
+// translation history, encodes paging cursors and builds highlighted snippets
+
+use crate::database::HistoryEntry;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+/// 三元组分词的最短可索引长度，更短的词改用 LIKE 匹配
+const MIN_INDEXED_CHARS: usize = 3;
+/// 单个标签的最大长度
+const MAX_TAG_CHARS: usize = 50;
+/// 摘要中匹配位置前后保留的字符数
+const SNIPPET_CONTEXT_CHARS: usize = 30;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SearchTerm {
+    /// 普通词，在原文或译文中任意位置出现即可
+    Word(String),
+    /// 以 * 结尾的词，需出现在词首
+    Prefix(String),
+    /// 双引号括起的短语，需完整连续出现
+    Phrase(String),
+}
+
+impl SearchTerm {
+    pub fn text(&self) -> &str {
+        match self {
+            SearchTerm::Word(text) | SearchTerm::Prefix(text) | SearchTerm::Phrase(text) => text,
+        }
+    }
+
+    /// 能否走 FTS5 三元组索引
+    pub fn is_indexed(&self) -> bool {
+        self.text().chars().count() >= MIN_INDEXED_CHARS
+    }
+
+    /// 前缀词需要出现在词首；中日文等不以空格分词的文字没有词首概念，按普通词处理
+    pub fn needs_word_boundary(&self) -> bool {
+        matches!(self, SearchTerm::Prefix(text) if !text.chars().any(is_unspaced_script))
+    }
+}
+
+/// 搜索筛选条件，字段为空表示不限
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HistoryFilter {
+    pub from_language: Option<String>,
+    pub to_language: Option<String>,
+    /// 翻译服务名称，不区分大小写
+    pub service: Option<String>,
+    /// 起止时间（含），RFC 3339 时间或 YYYY-MM-DD 日期；只给日期的 until 包含当天全天
+    pub since: Option<String>,
+    pub until: Option<String>,
+    /// true 只要收藏，false 只要未收藏
+    pub favorite: Option<bool>,
+    /// 需同时带有的标签，不区分大小写
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl HistoryFilter {
+    /// 没有任何有效条件（空白字符串视为未设置）
+    pub fn is_empty(&self) -> bool {
+        let blank = |value: &Option<String>| value.as_deref().is_none_or(|v| v.trim().is_empty());
+        blank(&self.from_language)
+            && blank(&self.to_language)
+            && blank(&self.service)
+            && self.since.is_none()
+            && self.until.is_none()
+            && self.favorite.is_none()
+            && self.tags.iter().all(|tag| tag.trim().is_empty())
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        for date in [&self.since, &self.until].into_iter().flatten() {
+            if !is_date_only(date) && DateTime::parse_from_rfc3339(date).is_err() {
+                return Err(format!("无效的日期: {}", date));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 排序方式：有关键词时默认按相关度，否则按时间
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HistorySort {
+    #[default]
+    Relevance,
+    Newest,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HistorySearchHit {
+    #[serde(flatten)]
+    pub entry: HistoryEntry,
+    /// 原文中匹配位置附近的片段，匹配文字用 <mark> 标出，其余内容已做 HTML 转义
+    pub original_snippet: Option<String>,
+    pub translated_snippet: Option<String>,
+    /// BM25 相关度，越小越相关；未使用全文索引时为空
+    pub score: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HistorySearchPage {
+    pub items: Vec<HistorySearchHit>,
+    /// 下一页的游标，没有更多结果时为空
+    pub next_cursor: Option<String>,
+}
+
+/// 分页游标：上一页最后一条的排序键和 id
+#[derive(Debug, Clone, PartialEq)]
+pub enum HistoryCursor {
+    Relevance { score: f64, id: i64 },
+    Newest { created_at: String, id: i64 },
+}
+
+impl HistoryCursor {
+    pub fn encode(&self) -> String {
+        let raw = match self {
+            HistoryCursor::Relevance { score, id } => format!("r|{:x}|{}", score.to_bits(), id),
+            HistoryCursor::Newest { created_at, id } => format!("n|{}|{}", created_at, id),
+        };
+        URL_SAFE_NO_PAD.encode(raw)
+    }
+
+    /// 游标只能用于产生它的排序方式，sort 为实际使用的排序（没有可索引的关键词时总是按时间）
+    pub fn validate(&self, sort: HistorySort) -> Result<(), String> {
+        match (self, sort) {
+            (HistoryCursor::Relevance { .. }, HistorySort::Relevance)
+            | (HistoryCursor::Newest { .. }, HistorySort::Newest) => Ok(()),
+            _ => Err("分页游标与排序方式不匹配".to_string()),
+        }
+    }
+
+    pub fn decode(value: &str) -> Option<Self> {
+        let raw = String::from_utf8(URL_SAFE_NO_PAD.decode(value).ok()?).ok()?;
+        let mut parts = raw.splitn(3, '|');
+        let kind = parts.next()?;
+        let key = parts.next()?;
+        let id = parts.next()?.parse().ok()?;
+        match kind {
+            "r" => Some(HistoryCursor::Relevance {
+                score: f64::from_bits(u64::from_str_radix(key, 16).ok()?),
+                id,
+            }),
+            "n" => Some(HistoryCursor::Newest {
+                created_at: key.to_string(),
+                id,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// 解析搜索输入：双引号内为短语，以 * 结尾为前缀，其余按空白拆分为普通词
+pub fn parse_query(query: &str) -> Vec<SearchTerm> {
+    let mut terms = Vec::new();
+    let mut rest = query.trim();
+
+    while !rest.is_empty() {
+        if let Some(after_quote) = rest.strip_prefix('"') {
+            let (phrase, remaining) = match after_quote.find('"') {
+                Some(end) => (&after_quote[..end], &after_quote[end + 1..]),
+                None => (after_quote, ""),
+            };
+            let phrase = phrase.split_whitespace().collect::<Vec<_>>().join(" ");
+            if !phrase.is_empty() {
+                terms.push(SearchTerm::Phrase(phrase));
+            }
+            rest = remaining.trim_start();
+            continue;
+        }
+
+        let end = rest
+            .find(|c: char| c.is_whitespace() || c == '"')
+            .unwrap_or(rest.len());
+        let word = &rest[..end];
+        rest = rest[end..].trim_start();
+
+        match word.strip_suffix('*') {
+            Some(prefix) if !prefix.trim_end_matches('*').is_empty() => {
+                terms.push(SearchTerm::Prefix(prefix.trim_end_matches('*').to_string()))
+            }
+            Some(_) => {}
+            None => terms.push(SearchTerm::Word(word.to_string())),
+        }
+    }
+    terms
+}
+
+/// 由可索引的词组成 FTS5 查询表达式，各词之间为 AND；没有可索引的词时返回 None
+pub fn fts_expression(terms: &[SearchTerm]) -> Option<String> {
+    let parts: Vec<String> = terms
+        .iter()
+        .filter(|term| term.is_indexed())
+        .map(|term| format!("\"{}\"", term.text().replace('"', "\"\"")))
+        .collect();
+    (!parts.is_empty()).then(|| parts.join(" AND "))
+}
+
+/// LIKE 模式中转义通配符，配合 ESCAPE '\' 使用
+pub fn escape_like(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        if matches!(ch, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(ch);
+    }
+    escaped
+}
+
+/// 生成带高亮的摘要；文本中没有任何匹配时返回 None
+pub fn snippet(text: &str, terms: &[SearchTerm]) -> Option<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let lower: Vec<char> = chars.iter().map(|ch| fold_char(*ch)).collect();
+
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for term in terms {
+        let needle: Vec<char> = term.text().chars().map(fold_char).collect();
+        if needle.is_empty() || needle.len() > lower.len() {
+            continue;
+        }
+        for start in 0..=lower.len() - needle.len() {
+            if lower[start..start + needle.len()] != needle[..] {
+                continue;
+            }
+            if term.needs_word_boundary() && !is_word_start(&chars, start) {
+                continue;
+            }
+            ranges.push((start, start + needle.len()));
+        }
+    }
+    if ranges.is_empty() {
+        return None;
+    }
+    ranges.sort();
+
+    let first = ranges[0].0;
+    let window_start = first.saturating_sub(SNIPPET_CONTEXT_CHARS);
+    let window_end = (ranges[0].1 + SNIPPET_CONTEXT_CHARS * 2).min(chars.len());
+
+    let mut output = String::new();
+    if window_start > 0 {
+        output.push('…');
+    }
+    let mut pos = window_start;
+    for (start, end) in ranges {
+        // 跳过与上一个高亮重叠或超出窗口的匹配
+        if start < pos || start >= window_end {
+            continue;
+        }
+        let end = end.min(window_end);
+        push_escaped(&mut output, &chars[pos..start]);
+        output.push_str("<mark>");
+        push_escaped(&mut output, &chars[start..end]);
+        output.push_str("</mark>");
+        pos = end;
+    }
+    push_escaped(&mut output, &chars[pos..window_end]);
+    if window_end < chars.len() {
+        output.push('…');
+    }
+    Some(output)
+}
+
+/// 整理用户输入的标签：合并空白、去掉空标签和重复标签（不区分大小写），保留首次出现的写法
+pub fn normalize_tags(tags: &[String]) -> Result<Vec<String>, String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.split_whitespace().collect::<Vec<_>>().join(" ");
+        if tag.is_empty() {
+            continue;
+        }
+        if tag.chars().count() > MAX_TAG_CHARS {
+            return Err(format!(
+                "标签过长（最多 {} 个字符）: {}",
+                MAX_TAG_CHARS, tag
+            ));
+        }
+        if !normalized
+            .iter()
+            .any(|existing| existing.to_lowercase() == tag.to_lowercase())
+        {
+            normalized.push(tag);
+        }
+    }
+    Ok(normalized)
+}
+
+/// 是否为 YYYY-MM-DD 形式的日期
+pub fn is_date_only(value: &str) -> bool {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok()
+}
+
+fn is_unspaced_script(ch: char) -> bool {
+    matches!(ch,
+        '\u{3040}'..='\u{30ff}' // 平假名、片假名
+        | '\u{3400}'..='\u{4dbf}'
+        | '\u{4e00}'..='\u{9fff}'
+        | '\u{f900}'..='\u{faff}'
+        | '\u{0e00}'..='\u{0e7f}' // 泰文
+    )
+}
+
+fn fold_char(ch: char) -> char {
+    ch.to_lowercase().next().unwrap_or(ch)
+}
+
+fn is_word_start(chars: &[char], index: usize) -> bool {
+    index == 0 || !chars[index - 1].is_alphanumeric()
+}
+
+fn push_escaped(output: &mut String, chars: &[char]) {
+    for ch in chars {
+        match ch {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '"' => output.push_str("&quot;"),
+            _ => output.push(*ch),
+        }
+    }
+}
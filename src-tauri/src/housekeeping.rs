@@ -0,0 +1,284 @@
+// History housekeeping
+// Retention policy enforcement, the undo trash for user deletions and VACUUM after large
+// deletions, plus the background task that runs them periodically
+
+use crate::database::{prune_history_memory, Database, HistoryRetentionConfig};
+use chrono::{Duration, Utc};
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, Result, Transaction};
+use serde::Serialize;
+
+/// 用户删除的记录在回收站中保留的时间，超过后无法撤销
+const UNDO_WINDOW_HOURS: i64 = 24;
+/// 定期清理的间隔
+const HOUSEKEEPING_INTERVAL_HOURS: u64 = 6;
+/// 启动后延迟执行第一次清理，避免拖慢启动
+const HOUSEKEEPING_STARTUP_DELAY_SECS: u64 = 60;
+/// 空闲页至少达到这么多且占总页数的比例达到 VACUUM_MIN_FREE_PERCENT 时才整理数据库
+const VACUUM_MIN_FREE_PAGES: i64 = 256;
+const VACUUM_MIN_FREE_PERCENT: i64 = 20;
+
+/// 收藏的记录，保留策略设置了保留收藏时不会被清理
+pub(crate) const NOT_FAVORITE: &str =
+    "NOT EXISTS (SELECT 1 FROM history_annotations a WHERE a.history_id = h.id AND a.favorite = 1)";
+
+/// 一次删除的结果；deletion_id 用于撤销，没有删除任何记录时为空
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryDeletion {
+    pub deletion_id: Option<i64>,
+    pub deleted: usize,
+    /// 撤销的截止时间，之后随时可能被定期清理永久删除
+    pub undo_expires_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct HousekeepingReport {
+    /// 按保留策略删除的记录数
+    pub expired: usize,
+    /// 从回收站中永久删除的记录数
+    pub trash_purged: usize,
+    pub vacuumed: bool,
+}
+
+/// 把满足条件（translation_history h 上的 WHERE 条件）的记录连同收藏、备注和标签移入回收站
+pub fn move_to_trash(
+    tx: &Transaction<'_>,
+    condition: &str,
+    values: Vec<Value>,
+) -> Result<HistoryDeletion> {
+    let deleted_at = Utc::now();
+    tx.execute(
+        "INSERT INTO history_deletions (deleted_count, deleted_at) VALUES (0, ?1)",
+        params![deleted_at.to_rfc3339()],
+    )?;
+    let deletion_id = tx.last_insert_rowid();
+
+    let mut all_values = vec![Value::Integer(deletion_id)];
+    all_values.extend(values);
+    let moved = tx.execute(
+        &format!(
+            "INSERT INTO history_trash (
+                 deletion_id, history_id, original_text, translated_text, service,
+                 from_language, to_language, created_at, group_id, operation,
+                 favorite, note, tags
+             )
+             SELECT ?, h.id, h.original_text, h.translated_text, h.service,
+                    h.from_language, h.to_language, h.created_at, h.group_id, h.operation,
+                    IFNULL(a.favorite, 0), a.note,
+                    (SELECT json_group_array(t.tag) FROM history_tags t WHERE t.history_id = h.id)
+             FROM translation_history h
+             LEFT JOIN history_annotations a ON a.history_id = h.id
+             WHERE {condition}"
+        ),
+        params_from_iter(all_values.iter()),
+    )?;
+
+    if moved == 0 {
+        tx.execute(
+            "DELETE FROM history_deletions WHERE id = ?1",
+            params![deletion_id],
+        )?;
+        return Ok(HistoryDeletion {
+            deletion_id: None,
+            deleted: 0,
+            undo_expires_at: None,
+        });
+    }
+
+    // 收藏、标签和全文索引由 translation_history 上的删除触发器清理
+    tx.execute(
+        "DELETE FROM translation_history
+         WHERE id IN (SELECT history_id FROM history_trash WHERE deletion_id = ?1)",
+        params![deletion_id],
+    )?;
+    tx.execute(
+        "UPDATE history_deletions SET deleted_count = ?2 WHERE id = ?1",
+        params![deletion_id, moved as i64],
+    )?;
+
+    Ok(HistoryDeletion {
+        deletion_id: Some(deletion_id),
+        deleted: moved,
+        undo_expires_at: Some((deleted_at + Duration::hours(UNDO_WINDOW_HOURS)).to_rfc3339()),
+    })
+}
+
+/// 撤销一次删除，按原 id 恢复记录及其收藏、备注和标签；删除记录不存在时返回 QueryReturnedNoRows
+pub fn restore_from_trash(tx: &Transaction<'_>, deletion_id: i64) -> Result<usize> {
+    tx.query_row(
+        "SELECT id FROM history_deletions WHERE id = ?1",
+        params![deletion_id],
+        |_| Ok(()),
+    )?;
+
+    let restored = tx.execute(
+        "INSERT INTO translation_history (
+             id, original_text, translated_text, service, from_language, to_language,
+             created_at, group_id, operation
+         )
+         SELECT history_id, original_text, translated_text, service, from_language, to_language,
+                created_at, group_id, operation
+         FROM history_trash
+         WHERE deletion_id = ?1
+           AND history_id NOT IN (SELECT id FROM translation_history)",
+        params![deletion_id],
+    )?;
+    tx.execute(
+        "INSERT OR IGNORE INTO history_annotations (history_id, favorite, note)
+         SELECT history_id, favorite, note FROM history_trash
+         WHERE deletion_id = ?1 AND (favorite = 1 OR note IS NOT NULL)",
+        params![deletion_id],
+    )?;
+    tx.execute(
+        "INSERT OR IGNORE INTO history_tags (history_id, tag)
+         SELECT trash.history_id, tag.value
+         FROM history_trash trash, json_each(trash.tags) tag
+         WHERE trash.deletion_id = ?1",
+        params![deletion_id],
+    )?;
+    discard_deletions(tx, "id = ?1", params![deletion_id])?;
+    Ok(restored)
+}
+
+/// 按保留策略直接删除过期和超出条数的记录，不进入回收站
+pub fn apply_retention(tx: &Transaction<'_>, policy: &HistoryRetentionConfig) -> Result<usize> {
+    let guard = if policy.keep_favorites {
+        format!("AND {}", NOT_FAVORITE)
+    } else {
+        String::new()
+    };
+    let mut expired = 0;
+
+    if policy.max_age_days > 0 {
+        expired += tx.execute(
+            &format!(
+                "DELETE FROM translation_history AS h
+                 WHERE datetime(h.created_at) < datetime('now', ?1) {guard}"
+            ),
+            params![format!("-{} days", policy.max_age_days)],
+        )?;
+    }
+
+    if policy.max_entries > 0 {
+        expired += tx.execute(
+            &format!(
+                "DELETE FROM translation_history
+                 WHERE id IN (
+                     SELECT h.id FROM translation_history h
+                     WHERE 1 = 1 {guard}
+                     ORDER BY datetime(h.created_at) DESC, h.id DESC
+                     LIMIT -1 OFFSET ?1
+                 )"
+            ),
+            params![policy.max_entries],
+        )?;
+    }
+
+    if expired > 0 {
+        prune_history_memory(tx)?;
+    }
+    Ok(expired)
+}
+
+/// 永久删除超过撤销时限的回收站记录，返回删除的记录数；只由这些记录写入的记忆库条目一并删除
+pub fn purge_trash(tx: &Transaction<'_>) -> Result<usize> {
+    let purged = discard_deletions(
+        tx,
+        "datetime(deleted_at) < datetime('now', ?1)",
+        params![format!("-{} hours", UNDO_WINDOW_HOURS)],
+    )?;
+    if purged > 0 {
+        prune_history_memory(tx)?;
+    }
+    Ok(purged)
+}
+
+/// 空闲页较多时合并全文索引并整理数据库文件；VACUUM 不能在事务中执行
+pub fn vacuum_if_fragmented(conn: &Connection) -> Result<bool> {
+    let page_count: i64 = conn.query_row("PRAGMA page_count", [], |row| row.get(0))?;
+    let free_pages: i64 = conn.query_row("PRAGMA freelist_count", [], |row| row.get(0))?;
+    if free_pages < VACUUM_MIN_FREE_PAGES || free_pages * 100 < page_count * VACUUM_MIN_FREE_PERCENT
+    {
+        return Ok(false);
+    }
+
+    conn.execute(
+        "INSERT INTO translation_history_fts (translation_history_fts) VALUES ('optimize')",
+        [],
+    )?;
+    conn.execute_batch("VACUUM")?;
+    Ok(true)
+}
+
+/// 依次执行保留策略、清理回收站和整理数据库
+pub fn run(db: &Database) -> Result<HousekeepingReport, String> {
+    let policy = db
+        .get_app_config()
+        .map_err(|e| format!("读取保留策略失败: {}", e))?
+        .map(|config| config.history_retention)
+        .unwrap_or_default();
+
+    let (expired, trash_purged) = db
+        .expire_history(&policy)
+        .map_err(|e| format!("清理历史记录失败: {}", e))?;
+    let vacuumed = db
+        .vacuum_if_fragmented()
+        .map_err(|e| format!("整理数据库失败: {}", e))?;
+
+    Ok(HousekeepingReport {
+        expired,
+        trash_purged,
+        vacuumed,
+    })
+}
+
+/// 启动一分钟后执行第一次清理，之后每 6 小时执行一次；Database 的克隆共享同一个连接
+pub fn start(db: Database) {
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_secs(
+            HOUSEKEEPING_STARTUP_DELAY_SECS,
+        ))
+        .await;
+        loop {
+            match run(&db) {
+                Ok(report) if report.expired > 0 || report.trash_purged > 0 || report.vacuumed => {
+                    println!(
+                        "历史记录清理完成: 过期 {} 条，回收站清除 {} 条，{}",
+                        report.expired,
+                        report.trash_purged,
+                        if report.vacuumed {
+                            "已整理数据库"
+                        } else {
+                            "无需整理数据库"
+                        }
+                    )
+                }
+                Ok(_) => {}
+                Err(err) => eprintln!("{}", err),
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(
+                HOUSEKEEPING_INTERVAL_HOURS * 60 * 60,
+            ))
+            .await;
+        }
+    });
+}
+
+fn discard_deletions(
+    tx: &Transaction<'_>,
+    condition: &str,
+    values: impl rusqlite::Params + Copy,
+) -> Result<usize> {
+    let purged = tx.execute(
+        &format!(
+            "DELETE FROM history_trash
+             WHERE deletion_id IN (SELECT id FROM history_deletions WHERE {condition})"
+        ),
+        values,
+    )?;
+    tx.execute(
+        &format!("DELETE FROM history_deletions WHERE {condition}"),
+        values,
+    )?;
+    Ok(purged)
+}
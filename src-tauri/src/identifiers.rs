@@ -0,0 +1,271 @@
+// Identifier normalization
+// Detects code identifiers inside text and splits them into words before translation,
+// leaving prose, URLs, paths, hyphenated words and proper nouns untouched
+
+use crate::database::IdentifierNormalization;
+
+/// 首尾可以剥离的标点，剥离后再判断中间部分是否为标识符
+const EDGE_PUNCTUATION: &[char] = &[
+    '(', ')', '[', ']', '{', '}', '<', '>', '"', '\'', ',', '.', ';', ':', '!', '?', '，', '。',
+    '；', '：', '！', '？', '（', '）', '「', '」', '“', '”', '‘', '’',
+];
+
+/// 形似驼峰但属于专有名词的常见写法
+const PROPER_NOUNS: &[&str] = &[
+    "AirDrop", "AirPods", "ChatGPT", "CoffeeScript", "DeepMind", "DevOps", "DreamWorks", "FaceTime",
+    "FedEx", "GitHub", "GitLab", "GraphQL", "HarperCollins", "LinkedIn", "MacBook", "MySQL",
+    "OpenAI", "PayPal", "PlayStation", "PostgreSQL", "PowerPoint", "PowerShell", "SharePoint",
+    "SpaceX", "TypeScript", "JavaScript", "WhatsApp", "WordPress", "YouTube", "macOS", "tvOS",
+    "watchOS", "iPadOS",
+];
+
+/// 姓氏等前缀，后接大写字母时仍是一个单词（McDonald, MacArthur, DiCaprio）
+const NAME_PREFIXES: &[&str] = &["Mc", "Mac", "De", "Di", "Da", "La", "Le", "Van", "Von"];
+
+/// 按配置对整段文本做标识符规范化
+pub fn normalize(text: &str, mode: IdentifierNormalization) -> String {
+    match mode {
+        IdentifierNormalization::Off => text.to_string(),
+        IdentifierNormalization::SingleIdentifier => {
+            let trimmed = text.trim();
+            if trimmed.is_empty() || trimmed.contains(char::is_whitespace) {
+                return text.to_string();
+            }
+            match split_identifier(trimmed) {
+                Some(words) => text.replacen(trimmed, &words.join(" "), 1),
+                None => text.to_string(),
+            }
+        }
+        IdentifierNormalization::Auto => normalize_tokens(text),
+    }
+}
+
+/// 逐个以空白分隔的词元检查，只改写真正的标识符，原有空白与反引号中的代码保持不变
+fn normalize_tokens(text: &str) -> String {
+    let mut output = String::with_capacity(text.len() + 16);
+    let mut in_code = false;
+    let mut token_start: Option<usize> = None;
+
+    for (i, ch) in text.char_indices() {
+        if ch.is_whitespace() {
+            if let Some(start) = token_start.take() {
+                push_token(&mut output, &text[start..i], &mut in_code);
+            }
+            output.push(ch);
+        } else if token_start.is_none() {
+            token_start = Some(i);
+        }
+    }
+    if let Some(start) = token_start {
+        push_token(&mut output, &text[start..], &mut in_code);
+    }
+    output
+}
+
+fn push_token(output: &mut String, token: &str, in_code: &mut bool) {
+    let backticks = token.matches('`').count();
+    if *in_code || backticks > 0 {
+        output.push_str(token);
+        if backticks % 2 == 1 {
+            *in_code = !*in_code;
+        }
+        return;
+    }
+
+    let core = token.trim_matches(EDGE_PUNCTUATION);
+    if core.is_empty() {
+        output.push_str(token);
+        return;
+    }
+
+    match split_identifier(core) {
+        Some(words) => {
+            let start = token.find(core).unwrap_or(0);
+            output.push_str(&token[..start]);
+            output.push_str(&words.join(" "));
+            output.push_str(&token[start + core.len()..]);
+        }
+        None => output.push_str(token),
+    }
+}
+
+/// 判断词元是否为 snake_case / SCREAMING_SNAKE / camelCase / PascalCase 标识符，是则返回拆分后的单词
+pub fn split_identifier(token: &str) -> Option<Vec<String>> {
+    if !token.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        return None;
+    }
+    // 连字符视为普通单词的一部分（e-mail、state-of-the-art），点号和斜杠说明是网址、路径或文件名
+    if !token.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return None;
+    }
+
+    if token.contains('_') {
+        let parts: Vec<&str> = token.split('_').filter(|part| !part.is_empty()).collect();
+        if parts.len() < 2 {
+            return None;
+        }
+        let mut words = Vec::new();
+        for part in parts {
+            words.extend(split_camel(part).unwrap_or_else(|| vec![part.to_string()]));
+        }
+        return Some(words);
+    }
+
+    split_camel(token)
+}
+
+/// 拆分驼峰写法；专有名词和只有一处大小写变化的品牌写法（iPhone、eBay）不拆
+fn split_camel(token: &str) -> Option<Vec<String>> {
+    if PROPER_NOUNS.contains(&token) {
+        return None;
+    }
+
+    let chars: Vec<char> = token.chars().collect();
+    let mut words: Vec<String> = Vec::new();
+    let mut current = String::new();
+    for (i, &ch) in chars.iter().enumerate() {
+        if i > 0 && ch.is_ascii_uppercase() {
+            let prev = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).is_some_and(|c| c.is_ascii_lowercase());
+            // 缩写的复数形式（URLs、PDFs）不在最后一个大写字母处断开
+            let plural_acronym = i + 2 == chars.len() && chars[i + 1] == 's';
+            if prev.is_ascii_lowercase()
+                || prev.is_ascii_digit()
+                || (prev.is_ascii_uppercase() && next_is_lower && !plural_acronym)
+            {
+                words.push(std::mem::take(&mut current));
+            }
+        }
+        current.push(ch);
+    }
+    words.push(current);
+
+    if words.len() < 2 {
+        return None;
+    }
+
+    let first = &words[0];
+    // iPhone, eBay, iOS: 单个小写字母前缀的品牌名
+    if first.len() == 1 && first.chars().all(|c| c.is_ascii_lowercase()) {
+        return None;
+    }
+    // McDonald, MacArthur: 两段且首段是姓氏前缀
+    if words.len() == 2 && NAME_PREFIXES.contains(&first.as_str()) {
+        return None;
+    }
+
+    Some(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn auto(text: &str) -> String {
+        normalize(text, IdentifierNormalization::Auto)
+    }
+
+    #[test]
+    fn splits_real_identifiers() {
+        let cases = [
+            ("getUserName", "get User Name"),
+            ("user_profile_id", "user profile id"),
+            ("MAX_RETRY_COUNT", "MAX RETRY COUNT"),
+            ("HttpRequestHandler", "Http Request Handler"),
+            ("parseHTTPResponse", "parse HTTP Response"),
+            ("utf8Decoder", "utf8 Decoder"),
+            ("_private_field", "private field"),
+            ("fetch_userData", "fetch user Data"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(auto(input), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn keeps_identifiers_in_sentences_readable() {
+        assert_eq!(
+            auto("Call getUserName() before saving."),
+            "Call get User Name() before saving."
+        );
+        assert_eq!(
+            auto("The user_id field is required"),
+            "The user id field is required"
+        );
+    }
+
+    #[test]
+    fn preserves_hyphenated_words() {
+        for input in [
+            "Please update your e-mail address",
+            "a state-of-the-art model",
+            "well-known issues",
+            "kebab-case-name",
+        ] {
+            assert_eq!(auto(input), input);
+        }
+    }
+
+    #[test]
+    fn preserves_urls_paths_and_file_names() {
+        for input in [
+            "See https://example.com/user_profile/getName?id=1",
+            "Open C:\\Users\\dev\\my_project\\main.rs",
+            "Edit src/file_translation/mod.rs now",
+            "Rename config_file.yaml to settings.toml",
+            "Contact john_doe@example.com",
+            "Version v1.2.3_beta was released",
+        ] {
+            assert_eq!(auto(input), input);
+        }
+    }
+
+    #[test]
+    fn preserves_proper_nouns() {
+        for input in [
+            "I bought an iPhone at McDonald's",
+            "eBay and PayPal",
+            "Written in JavaScript and TypeScript on GitHub",
+            "macOS and iOS users",
+            "MacArthur Park",
+            "Upload the PDFs and URLs",
+        ] {
+            assert_eq!(auto(input), input);
+        }
+    }
+
+    #[test]
+    fn preserves_inline_code() {
+        assert_eq!(
+            auto("Use `getUserName` and `max_retry_count` here"),
+            "Use `getUserName` and `max_retry_count` here"
+        );
+        assert_eq!(
+            auto("Run `cargo build --release_mode` then readConfig"),
+            "Run `cargo build --release_mode` then read Config"
+        );
+    }
+
+    #[test]
+    fn preserves_whitespace_and_chinese_text() {
+        assert_eq!(auto("  userName\n\tuser_name  "), "  user Name\n\tuser name  ");
+        assert_eq!(auto("这个函数叫 getUserName。"), "这个函数叫 get User Name。");
+        assert_eq!(auto("普通中文句子"), "普通中文句子");
+    }
+
+    #[test]
+    fn single_identifier_mode_only_touches_whole_input() {
+        let mode = IdentifierNormalization::SingleIdentifier;
+        assert_eq!(normalize(" getUserName ", mode), " get User Name ");
+        assert_eq!(normalize("call getUserName now", mode), "call getUserName now");
+        assert_eq!(normalize("iPhone", mode), "iPhone");
+    }
+
+    #[test]
+    fn off_mode_returns_input() {
+        assert_eq!(
+            normalize("getUserName", IdentifierNormalization::Off),
+            "getUserName"
+        );
+    }
+}
@@ -0,0 +1,721 @@
+// Background job queue
+// Persists long-running work in SQLite, runs queued jobs one at a time and
+// reports every state change through a single event stream
+
+use crate::{
+    app_state::AppState,
+    commands::{get_model_manager, resolve_translation_backend},
+    database::{resolve_app_data_dir, JobRecord, JobStatus},
+    file_translation::{
+        document::{self, DocumentOutputMode},
+        resource,
+        subtitle::{self, SubtitleOutputMode},
+    },
+    speech::whisper::WhisperModel,
+    translation::{SegmentCache, TranslationBackend},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::{broadcast, Notify};
+
+/// 任务状态变化事件名，负载为完整的 JobRecord
+pub const JOB_EVENT: &str = "job-updated";
+
+const CONTROL_RUN: u8 = 0;
+const CONTROL_PAUSE: u8 = 1;
+const CONTROL_CANCEL: u8 = 2;
+
+/// 文件翻译任务已完成片段的存放目录，每个任务一个 JSON 文件
+const CHECKPOINT_DIR: &str = "job-checkpoints";
+
+/// 可以放入后台队列的任务类型，序列化后存入 jobs.params
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JobKind {
+    DownloadSpeechModel {
+        model: String,
+    },
+    TranslateDocument {
+        path: String,
+        from_language: Option<String>,
+        to_language: String,
+        service: String,
+        #[serde(default)]
+        mode: DocumentOutputMode,
+        #[serde(default)]
+        output_path: Option<String>,
+    },
+    TranslateSubtitle {
+        path: String,
+        from_language: Option<String>,
+        to_language: String,
+        service: String,
+        #[serde(default)]
+        mode: SubtitleOutputMode,
+        #[serde(default)]
+        output_path: Option<String>,
+    },
+    ImportDictionary {
+        path: String,
+        #[serde(default)]
+        name: Option<String>,
+        #[serde(default)]
+        source_lang: Option<String>,
+        #[serde(default)]
+        target_lang: Option<String>,
+    },
+    TranslateResource {
+        path: String,
+        from_language: Option<String>,
+        to_language: String,
+        service: String,
+        #[serde(default)]
+        force: bool,
+        #[serde(default)]
+        output_path: Option<String>,
+    },
+}
+
+impl JobKind {
+    fn name(&self) -> &'static str {
+        match self {
+            JobKind::DownloadSpeechModel { .. } => "download_speech_model",
+            JobKind::ImportDictionary { .. } => "import_dictionary",
+            JobKind::TranslateDocument { .. } => "translate_document",
+            JobKind::TranslateSubtitle { .. } => "translate_subtitle",
+            JobKind::TranslateResource { .. } => "translate_resource",
+        }
+    }
+}
+
+enum JobError {
+    Paused,
+    Cancelled,
+    Failed(String),
+}
+
+/// 任务队列的运行时状态：运行中任务的控制标记、唤醒信号和状态广播
+pub struct JobManager {
+    controls: Mutex<HashMap<i64, Arc<AtomicU8>>>,
+    wake: Notify,
+    updates: broadcast::Sender<JobRecord>,
+}
+
+impl JobManager {
+    pub fn new() -> Self {
+        let (updates, _) = broadcast::channel(64);
+        JobManager {
+            controls: Mutex::new(HashMap::new()),
+            wake: Notify::new(),
+            updates,
+        }
+    }
+
+    fn control(&self, id: i64) -> Arc<AtomicU8> {
+        self.controls
+            .lock()
+            .unwrap()
+            .entry(id)
+            .or_insert_with(|| Arc::new(AtomicU8::new(CONTROL_RUN)))
+            .clone()
+    }
+
+    fn signal(&self, id: i64, value: u8) -> bool {
+        match self.controls.lock().unwrap().get(&id) {
+            Some(control) => {
+                control.store(value, Ordering::SeqCst);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl Default for JobManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn load_job(app: &AppHandle, id: i64) -> Result<JobRecord, String> {
+    let state = app.state::<AppState>();
+    let db = state
+        .db
+        .lock()
+        .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+    db.get_job(id)
+        .map_err(|e| format!("读取任务失败: {}", e))?
+        .ok_or_else(|| format!("任务不存在: {}", id))
+}
+
+fn set_status(
+    app: &AppHandle,
+    id: i64,
+    status: JobStatus,
+    error: Option<&str>,
+    result_path: Option<&str>,
+) -> Result<(), String> {
+    {
+        let state = app.state::<AppState>();
+        let db = state
+            .db
+            .lock()
+            .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+        db.update_job_status(id, status, error, result_path)
+            .map_err(|e| format!("更新任务状态失败: {}", e))?;
+    }
+    publish(app, id);
+    Ok(())
+}
+
+fn report_progress(app: &AppHandle, id: i64, progress: f64, message: Option<&str>) {
+    {
+        let state = app.state::<AppState>();
+        let Ok(db) = state.db.lock() else {
+            return;
+        };
+        if let Err(err) = db.update_job_progress(id, progress, message) {
+            eprintln!("更新任务进度失败: {}", err);
+        }
+    }
+    publish(app, id);
+}
+
+/// 把任务最新状态推送给前端和进程内的等待者
+fn publish(app: &AppHandle, id: i64) {
+    match load_job(app, id) {
+        Ok(job) => {
+            let _ = app.emit(JOB_EVENT, &job);
+            let _ = app.state::<AppState>().jobs.updates.send(job);
+        }
+        Err(err) => eprintln!("推送任务状态失败: {}", err),
+    }
+}
+
+/// 提交新任务并唤醒后台执行器
+pub fn submit(app: &AppHandle, kind: JobKind) -> Result<JobRecord, String> {
+    let params = serde_json::to_value(&kind).map_err(|e| format!("序列化任务参数失败: {}", e))?;
+    let state = app.state::<AppState>();
+    let job = {
+        let db = state
+            .db
+            .lock()
+            .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+        db.create_job(kind.name(), &params)
+            .map_err(|e| format!("创建任务失败: {}", e))?
+    };
+    println!("已提交后台任务 #{} ({})", job.id, job.kind);
+    publish(app, job.id);
+    state.jobs.wake.notify_one();
+    Ok(job)
+}
+
+/// 等待任务结束（完成、失败、取消或暂停），返回最终状态
+pub async fn wait_for(app: &AppHandle, id: i64) -> Result<JobRecord, String> {
+    let mut updates = app.state::<AppState>().jobs.updates.subscribe();
+    let job = load_job(app, id)?;
+    if job.status.is_finished() || job.status == JobStatus::Paused {
+        return Ok(job);
+    }
+
+    loop {
+        match updates.recv().await {
+            Ok(job) if job.id == id => {
+                if job.status.is_finished() || job.status == JobStatus::Paused {
+                    return Ok(job);
+                }
+            }
+            Ok(_) => {}
+            Err(broadcast::error::RecvError::Lagged(_)) => {
+                let job = load_job(app, id)?;
+                if job.status.is_finished() || job.status == JobStatus::Paused {
+                    return Ok(job);
+                }
+            }
+            Err(broadcast::error::RecvError::Closed) => return load_job(app, id),
+        }
+    }
+}
+
+/// 排队中的任务直接标记为暂停，运行中的任务通知执行器在下一个中断点停下；
+/// 与执行器认领任务使用同一把数据库锁，判断状态后任务不会被取走
+pub fn pause(app: &AppHandle, id: i64) -> Result<JobRecord, String> {
+    let state = app.state::<AppState>();
+    {
+        let db = state
+            .db
+            .lock()
+            .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+        let job = db
+            .get_job(id)
+            .map_err(|e| format!("读取任务失败: {}", e))?
+            .ok_or_else(|| format!("任务不存在: {}", id))?;
+        match job.status {
+            JobStatus::Running => {
+                if !state.jobs.signal(id, CONTROL_PAUSE) {
+                    return Err("任务没有在执行，无法暂停".to_string());
+                }
+            }
+            JobStatus::Queued => db
+                .update_job_status(id, JobStatus::Paused, None, None)
+                .map_err(|e| format!("更新任务状态失败: {}", e))?,
+            _ => return Err("只能暂停排队中或运行中的任务".to_string()),
+        }
+    }
+    publish(app, id);
+    load_job(app, id)
+}
+
+/// 继续执行保留原有进度，已完成的片段从检查点跳过
+pub fn resume(app: &AppHandle, id: i64) -> Result<JobRecord, String> {
+    requeue(app, id, |status| {
+        if status == JobStatus::Paused {
+            Ok(false)
+        } else {
+            Err("只能继续已暂停的任务".to_string())
+        }
+    })
+}
+
+/// 与 pause 一样在数据库锁内判断状态
+pub fn cancel(app: &AppHandle, id: i64) -> Result<JobRecord, String> {
+    let state = app.state::<AppState>();
+    {
+        let db = state
+            .db
+            .lock()
+            .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+        let job = db
+            .get_job(id)
+            .map_err(|e| format!("读取任务失败: {}", e))?
+            .ok_or_else(|| format!("任务不存在: {}", id))?;
+        match job.status {
+            JobStatus::Running => {
+                if !state.jobs.signal(id, CONTROL_CANCEL) {
+                    return Err("任务没有在执行，无法取消".to_string());
+                }
+            }
+            JobStatus::Queued | JobStatus::Paused => {
+                db.update_job_status(id, JobStatus::Cancelled, None, None)
+                    .map_err(|e| format!("更新任务状态失败: {}", e))?;
+                remove_checkpoint(app, id);
+            }
+            _ => return Err("任务已结束，无法取消".to_string()),
+        }
+    }
+    publish(app, id);
+    load_job(app, id)
+}
+
+/// 重试的任务进度从零开始
+pub fn retry(app: &AppHandle, id: i64) -> Result<JobRecord, String> {
+    requeue(app, id, |status| {
+        if matches!(status, JobStatus::Failed | JobStatus::Cancelled) {
+            Ok(true)
+        } else {
+            Err("只能重试失败或已取消的任务".to_string())
+        }
+    })
+}
+
+/// 与 pause 一样在数据库锁内判断状态并重新排队；check 返回是否清零进度
+fn requeue(
+    app: &AppHandle,
+    id: i64,
+    check: impl FnOnce(JobStatus) -> Result<bool, String>,
+) -> Result<JobRecord, String> {
+    let state = app.state::<AppState>();
+    {
+        let db = state
+            .db
+            .lock()
+            .map_err(|e| format!("获取数据库连接失败: {}", e))?;
+        let job = db
+            .get_job(id)
+            .map_err(|e| format!("读取任务失败: {}", e))?
+            .ok_or_else(|| format!("任务不存在: {}", id))?;
+        let reset_progress = check(job.status)?;
+        db.update_job_status(id, JobStatus::Queued, None, None)
+            .map_err(|e| format!("更新任务状态失败: {}", e))?;
+        if reset_progress {
+            db.reset_job_progress(id)
+                .map_err(|e| format!("更新任务进度失败: {}", e))?;
+        }
+    }
+    publish(app, id);
+    state.jobs.wake.notify_one();
+    load_job(app, id)
+}
+
+/// 启动后台执行器；上次退出时中断的任务会重新排队并自动续跑
+pub fn start_worker(app: AppHandle) {
+    {
+        let state = app.state::<AppState>();
+        if let Ok(db) = state.db.lock() {
+            match db.requeue_interrupted_jobs() {
+                Ok(0) => {}
+                Ok(count) => println!("已恢复 {} 个中断的后台任务", count),
+                Err(err) => eprintln!("恢复后台任务失败: {}", err),
+            }
+        };
+    }
+
+    tauri::async_runtime::spawn(async move {
+        loop {
+            // 认领任务和登记控制标记都在数据库锁内完成，pause/cancel 看到运行中的任务时一定能找到控制标记
+            let next = {
+                let state = app.state::<AppState>();
+                let db = state.db.lock();
+                match db {
+                    Ok(db) => match db.claim_next_job() {
+                        Ok(Some(job)) => {
+                            state
+                                .jobs
+                                .control(job.id)
+                                .store(CONTROL_RUN, Ordering::SeqCst);
+                            Some(job)
+                        }
+                        Ok(None) => None,
+                        Err(err) => {
+                            eprintln!("读取任务队列失败: {}", err);
+                            None
+                        }
+                    },
+                    Err(_) => None,
+                }
+            };
+
+            match next {
+                Some(job) => run_job(&app, job).await,
+                None => app.state::<AppState>().jobs.wake.notified().await,
+            }
+        }
+    });
+}
+
+async fn run_job(app: &AppHandle, job: JobRecord) {
+    let id = job.id;
+    let control = app.state::<AppState>().jobs.control(id);
+    publish(app, id);
+
+    let outcome = match serde_json::from_value::<JobKind>(job.params) {
+        Ok(kind) => execute(app, id, kind, control).await,
+        Err(err) => Err(JobError::Failed(format!("无法解析任务参数: {}", err))),
+    };
+
+    // 暂停和失败的任务保留已完成的片段，继续或重试时跳过
+    let result = match outcome {
+        Ok(result_path) => {
+            remove_checkpoint(app, id);
+            set_status(app, id, JobStatus::Done, None, result_path.as_deref())
+        }
+        Err(JobError::Paused) => set_status(app, id, JobStatus::Paused, None, None),
+        Err(JobError::Cancelled) => {
+            remove_checkpoint(app, id);
+            set_status(app, id, JobStatus::Cancelled, None, None)
+        }
+        Err(JobError::Failed(message)) => {
+            eprintln!("后台任务 #{} 失败: {}", id, message);
+            set_status(app, id, JobStatus::Failed, Some(&message), None)
+        }
+    };
+    if let Err(err) = result {
+        eprintln!("{}", err);
+    }
+
+    app.state::<AppState>().jobs.controls.lock().unwrap().remove(&id);
+}
+
+fn checkpoint_path(app: &AppHandle, id: i64) -> Option<PathBuf> {
+    let dir = resolve_app_data_dir(app).ok()?.join(CHECKPOINT_DIR);
+    Some(dir.join(format!("{}.json", id)))
+}
+
+/// 读取任务上次保存的片段译文，没有或无法解析时从头开始
+fn load_checkpoint(app: &AppHandle, id: i64) -> SegmentCache {
+    let cache = checkpoint_path(app, id)
+        .and_then(|path| std::fs::read(path).ok())
+        .and_then(|bytes| serde_json::from_slice::<HashMap<String, String>>(&bytes).ok())
+        .unwrap_or_default();
+    Arc::new(Mutex::new(cache))
+}
+
+fn save_checkpoint(app: &AppHandle, id: i64, cache: &SegmentCache) {
+    let Some(path) = checkpoint_path(app, id) else {
+        return;
+    };
+    let result = cache
+        .lock()
+        .map_err(|e| e.to_string())
+        .and_then(|cache| serde_json::to_vec(&*cache).map_err(|e| e.to_string()))
+        .and_then(|bytes| {
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+            }
+            std::fs::write(&path, bytes).map_err(|e| e.to_string())
+        });
+    if let Err(err) = result {
+        eprintln!("保存任务 #{} 的进度失败: {}", id, err);
+    }
+}
+
+fn remove_checkpoint(app: &AppHandle, id: i64) {
+    if let Some(path) = checkpoint_path(app, id) {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+fn interruption(control: &AtomicU8) -> Option<JobError> {
+    match control.load(Ordering::SeqCst) {
+        CONTROL_PAUSE => Some(JobError::Paused),
+        CONTROL_CANCEL => Some(JobError::Cancelled),
+        _ => None,
+    }
+}
+
+async fn wait_for_interruption(control: Arc<AtomicU8>) -> JobError {
+    loop {
+        if let Some(signal) = interruption(&control) {
+            return signal;
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+}
+
+fn percent(completed: usize, total: usize) -> f64 {
+    if total == 0 {
+        100.0
+    } else {
+        completed as f64 / total as f64 * 100.0
+    }
+}
+
+async fn execute(
+    app: &AppHandle,
+    id: i64,
+    kind: JobKind,
+    control: Arc<AtomicU8>,
+) -> Result<Option<String>, JobError> {
+    match kind {
+        JobKind::DownloadSpeechModel { model } => {
+            download_speech_model(app, id, model, control).await
+        }
+        JobKind::ImportDictionary {
+            path,
+            name,
+            source_lang,
+            target_lang,
+        } => import_dictionary(app, id, path, name, source_lang, target_lang, control).await,
+        JobKind::TranslateDocument {
+            path,
+            from_language,
+            to_language,
+            service,
+            mode,
+            output_path,
+        } => {
+            let (backend, cache) = checkpointed_backend(app, id, &service)?;
+            let output_path = output_path.map(PathBuf::from);
+            let work = async {
+                document::translate_document(
+                    &backend,
+                    &PathBuf::from(path),
+                    output_path.as_deref(),
+                    &from_language.unwrap_or_default(),
+                    &to_language,
+                    mode,
+                    checkpoint_progress(app, id, &cache),
+                )
+                .await
+                .map(|summary| summary.output_path)
+            };
+            interruptible(work, control).await
+        }
+        JobKind::TranslateSubtitle {
+            path,
+            from_language,
+            to_language,
+            service,
+            mode,
+            output_path,
+        } => {
+            let (backend, cache) = checkpointed_backend(app, id, &service)?;
+            let output_path = output_path.map(PathBuf::from);
+            let work = async {
+                subtitle::translate_subtitle_file(
+                    &backend,
+                    &PathBuf::from(path),
+                    output_path.as_deref(),
+                    &from_language.unwrap_or_default(),
+                    &to_language,
+                    mode,
+                    checkpoint_progress(app, id, &cache),
+                )
+                .await
+                .map(|summary| summary.output_path)
+            };
+            interruptible(work, control).await
+        }
+        JobKind::TranslateResource {
+            path,
+            from_language,
+            to_language,
+            service,
+            force,
+            output_path,
+        } => {
+            let (backend, cache) = checkpointed_backend(app, id, &service)?;
+            let output_path = output_path.map(PathBuf::from);
+            let work = async {
+                resource::translate_resource_file(
+                    &backend,
+                    &PathBuf::from(path),
+                    output_path.as_deref(),
+                    &from_language.unwrap_or_default(),
+                    &to_language,
+                    force,
+                    checkpoint_progress(app, id, &cache),
+                )
+                .await
+                .map(|summary| summary.output_path)
+            };
+            interruptible(work, control).await
+        }
+    }
+}
+
+/// 解析翻译服务，并挂上任务上次保存的片段译文
+fn checkpointed_backend(
+    app: &AppHandle,
+    id: i64,
+    service: &str,
+) -> Result<(TranslationBackend, SegmentCache), JobError> {
+    let mut backend =
+        resolve_translation_backend(&app.state::<AppState>(), service).map_err(JobError::Failed)?;
+    let cache = load_checkpoint(app, id);
+    backend.segment_cache = Some(cache.clone());
+    Ok((backend, cache))
+}
+
+/// 每完成一批就保存已完成的片段，暂停、失败或应用退出后继续时不必重新翻译
+fn checkpoint_progress<'a>(
+    app: &'a AppHandle,
+    id: i64,
+    cache: &'a SegmentCache,
+) -> impl FnMut(usize, usize) + 'a {
+    move |completed, total| {
+        save_checkpoint(app, id, cache);
+        report_progress(app, id, percent(completed, total), None);
+    }
+}
+
+/// 翻译任务在批次之间的 await 点被中断，丢弃 future 即停止后续请求
+async fn interruptible(
+    work: impl Future<Output = Result<String, String>>,
+    control: Arc<AtomicU8>,
+) -> Result<Option<String>, JobError> {
+    tokio::select! {
+        result = work => result.map(Some).map_err(JobError::Failed),
+        signal = wait_for_interruption(control) => Err(signal),
+    }
+}
+
+async fn download_speech_model(
+    app: &AppHandle,
+    id: i64,
+    model: String,
+    control: Arc<AtomicU8>,
+) -> Result<Option<String>, JobError> {
+    let model_enum = WhisperModel::from_name(&model)
+        .ok_or_else(|| JobError::Failed("Invalid model name".to_string()))?;
+    let manager = get_model_manager();
+    let handle = app.clone();
+    let download_control = control.clone();
+    let last_progress = AtomicU8::new(u8::MAX);
+
+    let result = tokio::task::spawn_blocking(move || {
+        manager.download_model(model_enum, |downloaded, total| {
+            if interruption(&download_control).is_some() {
+                return false;
+            }
+            let progress = if total > 0 {
+                (downloaded as f64 / total as f64 * 100.0) as u32
+            } else {
+                0
+            };
+            // 每个百分点只上报一次，避免逐块写库
+            if last_progress.swap(progress as u8, Ordering::Relaxed) != progress as u8 {
+                report_progress(&handle, id, progress as f64, None);
+                let _ = handle.emit(
+                    "speech-model-download-progress",
+                    serde_json::json!({
+                        "model": model.clone(),
+                        "downloaded": downloaded,
+                        "total": total,
+                        "progress": progress
+                    }),
+                );
+            }
+            true
+        })
+    })
+    .await
+    .map_err(|e| JobError::Failed(format!("Download task failed: {}", e)))?;
+
+    match (result, interruption(&control)) {
+        (Ok(()), _) => Ok(Some(
+            manager
+                .get_model_path(model_enum)
+                .to_string_lossy()
+                .to_string(),
+        )),
+        (Err(_), Some(signal)) => Err(signal),
+        (Err(err), None) => Err(JobError::Failed(format!("Model download failed: {}", err))),
+    }
+}
+
+async fn import_dictionary(
+    app: &AppHandle,
+    id: i64,
+    path: String,
+    name: Option<String>,
+    source_lang: Option<String>,
+    target_lang: Option<String>,
+    control: Arc<AtomicU8>,
+) -> Result<Option<String>, JobError> {
+    let handle = app.clone();
+    let import_control = control.clone();
+
+    let result = tokio::task::spawn_blocking(move || {
+        let state = handle.state::<AppState>();
+        let mut on_progress = |imported: usize, total: usize| {
+            if interruption(&import_control).is_some() {
+                return false;
+            }
+            let message = format!("已导入 {} 条", imported);
+            report_progress(&handle, id, percent(imported, total), Some(&message));
+            true
+        };
+        state.offline_dictionaries.import(
+            &PathBuf::from(&path),
+            name.as_deref(),
+            source_lang.as_deref(),
+            target_lang.as_deref(),
+            &mut on_progress,
+        )
+    })
+    .await
+    .map_err(|e| JobError::Failed(format!("导入任务失败: {}", e)))?;
+
+    match (result, interruption(&control)) {
+        (Ok(dictionary), _) => Ok(Some(dictionary.source_path)),
+        (Err(_), Some(signal)) => Err(signal),
+        (Err(err), None) => Err(JobError::Failed(err)),
+    }
+}
@@ -0,0 +1,274 @@
+// Language catalog
+// BCP-47 language codes with localized names and scripts, and the per-provider mapping from
+// catalog codes to the codes each translation service expects
+
+use crate::translation::TranslationService;
+use serde::Serialize;
+
+pub struct Language {
+    /// BCP-47 代码
+    pub code: &'static str,
+    pub english_name: &'static str,
+    pub chinese_name: &'static str,
+    /// 本语言中的名称
+    pub native_name: &'static str,
+    /// ISO 15924 文字代码
+    pub script: &'static str,
+}
+
+const fn language(
+    code: &'static str,
+    english_name: &'static str,
+    chinese_name: &'static str,
+    native_name: &'static str,
+    script: &'static str,
+) -> Language {
+    Language {
+        code,
+        english_name,
+        chinese_name,
+        native_name,
+        script,
+    }
+}
+
+#[rustfmt::skip]
+pub const CATALOG: &[Language] = &[
+    language("zh-Hans", "Chinese (Simplified)", "简体中文", "简体中文", "Hans"),
+    language("zh-Hant", "Chinese (Traditional)", "繁体中文", "繁體中文", "Hant"),
+    language("yue", "Cantonese", "粤语", "粵語", "Hant"),
+    language("en", "English", "英语", "English", "Latn"),
+    language("ja", "Japanese", "日语", "日本語", "Jpan"),
+    language("ko", "Korean", "韩语", "한국어", "Kore"),
+    language("fr", "French", "法语", "Français", "Latn"),
+    language("de", "German", "德语", "Deutsch", "Latn"),
+    language("es", "Spanish", "西班牙语", "Español", "Latn"),
+    language("pt-BR", "Portuguese (Brazil)", "葡萄牙语（巴西）", "Português (Brasil)", "Latn"),
+    language("pt-PT", "Portuguese (Portugal)", "葡萄牙语（葡萄牙）", "Português (Portugal)", "Latn"),
+    language("it", "Italian", "意大利语", "Italiano", "Latn"),
+    language("ru", "Russian", "俄语", "Русский", "Cyrl"),
+    language("uk", "Ukrainian", "乌克兰语", "Українська", "Cyrl"),
+    language("be", "Belarusian", "白俄罗斯语", "Беларуская", "Cyrl"),
+    language("bg", "Bulgarian", "保加利亚语", "Български", "Cyrl"),
+    language("sr", "Serbian", "塞尔维亚语", "Српски", "Cyrl"),
+    language("kk", "Kazakh", "哈萨克语", "Қазақ тілі", "Cyrl"),
+    language("mn", "Mongolian", "蒙古语", "Монгол", "Cyrl"),
+    language("ar", "Arabic", "阿拉伯语", "العربية", "Arab"),
+    language("fa", "Persian", "波斯语", "فارسی", "Arab"),
+    language("ur", "Urdu", "乌尔都语", "اردو", "Arab"),
+    language("he", "Hebrew", "希伯来语", "עברית", "Hebr"),
+    language("tr", "Turkish", "土耳其语", "Türkçe", "Latn"),
+    language("el", "Greek", "希腊语", "Ελληνικά", "Grek"),
+    language("hi", "Hindi", "印地语", "हिन्दी", "Deva"),
+    language("bn", "Bengali", "孟加拉语", "বাংলা", "Beng"),
+    language("ta", "Tamil", "泰米尔语", "தமிழ்", "Taml"),
+    language("th", "Thai", "泰语", "ไทย", "Thai"),
+    language("vi", "Vietnamese", "越南语", "Tiếng Việt", "Latn"),
+    language("id", "Indonesian", "印尼语", "Bahasa Indonesia", "Latn"),
+    language("ms", "Malay", "马来语", "Bahasa Melayu", "Latn"),
+    language("fil", "Filipino", "菲律宾语", "Filipino", "Latn"),
+    language("nl", "Dutch", "荷兰语", "Nederlands", "Latn"),
+    language("pl", "Polish", "波兰语", "Polski", "Latn"),
+    language("cs", "Czech", "捷克语", "Čeština", "Latn"),
+    language("sk", "Slovak", "斯洛伐克语", "Slovenčina", "Latn"),
+    language("sl", "Slovenian", "斯洛文尼亚语", "Slovenščina", "Latn"),
+    language("hu", "Hungarian", "匈牙利语", "Magyar", "Latn"),
+    language("ro", "Romanian", "罗马尼亚语", "Română", "Latn"),
+    language("sv", "Swedish", "瑞典语", "Svenska", "Latn"),
+    language("da", "Danish", "丹麦语", "Dansk", "Latn"),
+    language("nb", "Norwegian Bokmål", "挪威语", "Norsk bokmål", "Latn"),
+    language("fi", "Finnish", "芬兰语", "Suomi", "Latn"),
+    language("et", "Estonian", "爱沙尼亚语", "Eesti", "Latn"),
+    language("lt", "Lithuanian", "立陶宛语", "Lietuvių", "Latn"),
+    language("lv", "Latvian", "拉脱维亚语", "Latviešu", "Latn"),
+];
+
+/// 旧版本使用的代码和各服务的私有代码到目录代码的映射
+const ALIASES: &[(&str, &str)] = &[
+    ("zh", "zh-Hans"),
+    ("zh-CN", "zh-Hans"),
+    ("zh-SG", "zh-Hans"),
+    ("zh-TW", "zh-Hant"),
+    ("zh-HK", "zh-Hant"),
+    ("zh-MO", "zh-Hant"),
+    ("pt", "pt-BR"),
+    ("iw", "he"),
+    ("tl", "fil"),
+    ("no", "nb"),
+    ("in", "id"),
+];
+
+/// 谷歌翻译与目录不同的代码，其余目录语言直接使用目录代码
+const GOOGLE_CODES: &[(&str, &str)] = &[
+    ("zh-Hans", "zh-CN"),
+    ("zh-Hant", "zh-TW"),
+    ("pt-BR", "pt"),
+    ("he", "iw"),
+    ("fil", "tl"),
+    ("nb", "no"),
+];
+
+/// 百度翻译支持的语言及其代码
+#[rustfmt::skip]
+const BAIDU_CODES: &[(&str, &str)] = &[
+    ("zh-Hans", "zh"), ("zh-Hant", "cht"), ("yue", "yue"), ("en", "en"), ("ja", "jp"),
+    ("ko", "kor"), ("fr", "fra"), ("de", "de"), ("es", "spa"), ("pt-BR", "pt"), ("pt-PT", "pt"),
+    ("it", "it"), ("ru", "ru"), ("ar", "ara"), ("th", "th"), ("vi", "vie"), ("el", "el"),
+    ("nl", "nl"), ("pl", "pl"), ("cs", "cs"), ("sl", "slo"), ("hu", "hu"), ("ro", "rom"),
+    ("sv", "swe"), ("da", "dan"), ("fi", "fin"), ("et", "est"), ("bg", "bul"),
+];
+
+/// 返回给前端的语言项，name 为界面语言下的名称
+#[derive(Debug, Clone, Serialize)]
+pub struct SupportedLanguage {
+    pub code: String,
+    pub name: String,
+    pub native_name: String,
+    pub script: Option<String>,
+    /// 该服务接口实际使用的代码
+    pub provider_code: String,
+}
+
+/// 查找目录中的语言，兼容旧代码（zh-CN、pt 等）和大小写差异
+pub fn find(code: &str) -> Option<&'static Language> {
+    let code = code.trim();
+    let canonical = ALIASES
+        .iter()
+        .find(|(alias, _)| alias.eq_ignore_ascii_case(code))
+        .map(|(_, canonical)| *canonical)
+        .unwrap_or(code);
+    CATALOG
+        .iter()
+        .find(|language| language.code.eq_ignore_ascii_case(canonical))
+}
+
+/// 按英文名或本语言名称查找，用于词典文件头等只写语言名称的场合；"Chinese" 这类省略括号部分的名称取第一项
+pub fn find_by_name(name: &str) -> Option<&'static Language> {
+    let name = name.trim();
+    if name.is_empty() {
+        return None;
+    }
+    CATALOG
+        .iter()
+        .find(|language| {
+            language.english_name.eq_ignore_ascii_case(name) || language.native_name == name
+        })
+        .or_else(|| {
+            CATALOG.iter().find(|language| {
+                language
+                    .english_name
+                    .split(" (")
+                    .next()
+                    .is_some_and(|base| base.eq_ignore_ascii_case(name))
+            })
+        })
+}
+
+/// 两个代码是否属于同一语言（只比较主语言子标签，zh-Hans 与 zh-Hant 视为相同）
+pub fn same_language(a: &str, b: &str) -> bool {
+    let primary = |code: &str| {
+        normalize(code)
+            .split('-')
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase()
+    };
+    primary(a) == primary(b)
+}
+
+/// 与给定代码指向同一语言的所有写法（目录代码及其别名），用于匹配历史记录中保存的原始代码
+pub fn equivalent_codes(code: &str) -> Vec<String> {
+    let code = code.trim();
+    let Some(language) = find(code) else {
+        return vec![code.to_string()];
+    };
+    let mut codes = vec![language.code.to_string()];
+    codes.extend(
+        ALIASES
+            .iter()
+            .filter(|(_, canonical)| *canonical == language.code)
+            .map(|(alias, _)| alias.to_string()),
+    );
+    codes
+}
+
+/// 把任意写法的语言代码规范为目录代码，不在目录中的代码原样返回
+pub fn normalize(code: &str) -> String {
+    match find(code) {
+        Some(language) => language.code.to_string(),
+        None => code.trim().to_string(),
+    }
+}
+
+/// 目录代码在指定服务中的代码，服务不支持该语言时返回 None；auto 对所有服务都可用
+pub fn provider_code(service: &TranslationService, code: &str) -> Option<&'static str> {
+    if code.is_empty() || code == "auto" {
+        return Some("auto");
+    }
+    let language = find(code)?;
+    match service {
+        // 大模型按 BCP-47 代码理解语言，目录中的语言都支持
+        TranslationService::OpenAI => Some(language.code),
+        TranslationService::Google => {
+            Some(lookup(GOOGLE_CODES, language.code).unwrap_or(language.code))
+        }
+        TranslationService::Baidu => lookup(BAIDU_CODES, language.code),
+    }
+}
+
+/// 转换为服务代码，不在目录中的代码原样传给服务
+pub fn to_provider_code<'a>(service: &TranslationService, code: &'a str) -> &'a str {
+    provider_code(service, code).unwrap_or(code)
+}
+
+fn lookup(table: &[(&str, &'static str)], code: &str) -> Option<&'static str> {
+    table
+        .iter()
+        .find(|(catalog_code, _)| *catalog_code == code)
+        .map(|(_, provider_code)| *provider_code)
+}
+
+/// 语言在界面语言下的名称：中文界面用中文名，界面语言与该语言相同时用本语言名称，其余用英文名
+pub fn localized_name(language: &Language, ui_language: &str) -> &'static str {
+    let ui_primary = ui_language.split(['-', '_']).next().unwrap_or_default();
+    let own_primary = language.code.split('-').next().unwrap_or_default();
+    if ui_primary.eq_ignore_ascii_case("zh") {
+        language.chinese_name
+    } else if ui_primary.eq_ignore_ascii_case(own_primary) {
+        language.native_name
+    } else {
+        language.english_name
+    }
+}
+
+/// 指定服务支持的语言列表，第一项为自动检测
+pub fn supported_languages(
+    service: &TranslationService,
+    ui_language: &str,
+) -> Vec<SupportedLanguage> {
+    let auto_name = if ui_language.to_ascii_lowercase().starts_with("zh") {
+        "自动检测"
+    } else {
+        "Detect language"
+    };
+    let auto = SupportedLanguage {
+        code: "auto".to_string(),
+        name: auto_name.to_string(),
+        native_name: auto_name.to_string(),
+        script: None,
+        provider_code: "auto".to_string(),
+    };
+
+    std::iter::once(auto)
+        .chain(CATALOG.iter().filter_map(|language| {
+            let provider_code = provider_code(service, language.code)?;
+            Some(SupportedLanguage {
+                code: language.code.to_string(),
+                name: localized_name(language, ui_language).to_string(),
+                native_name: language.native_name.to_string(),
+                script: Some(language.script.to_string()),
+                provider_code: provider_code.to_string(),
+            })
+        }))
+        .collect()
+}
@@ -1,24 +1,61 @@
 mod app_state;
+mod backup;
 mod commands;
+mod config_exchange;
 mod database;
+mod dictionary;
+mod file_translation;
+mod history_exchange;
+mod history_search;
+mod housekeeping;
 mod http_client;
+mod identifiers;
+mod jobs;
+mod languages;
+mod migrations;
+mod naming;
 mod ocr;
 mod ocr_tasks;
+mod offline_dictionary;
 mod platform;
+mod profiles;
+mod quality;
+mod romanization;
+mod sanitize;
+mod secrets;
 mod shortcuts;
 mod system_tray;
 mod translation;
+mod translation_memory;
 mod token_limits;
+mod vocabulary;
+mod writing;
 mod speech;
 
 use app_state::AppState;
 #[cfg(not(target_os = "macos"))]
 use commands::submit_area_for_ocr;
 use commands::{
-    capture_and_ocr, capture_area_and_ocr, capture_screen, capture_screen_area, clear_history,
-    fetch_available_models, get_api_key, get_app_config, get_setting, get_translation_history,
-    reload_shortcuts, save_api_key, save_app_config, save_setting, save_translation,
-    search_history, set_ocr_result, start_area_selection, translate_text,
+    add_vocabulary, add_vocabulary_from_history, cancel_job, capture_and_ocr,
+    capture_area_and_ocr, capture_screen, capture_screen_area, check_database_integrity,
+    clear_history, clone_config_profile, create_config_profile, create_database_backup,
+    delete_config_profile, delete_history, delete_memory_entry, delete_translation,
+    delete_vocabulary, explain_text, export_config, export_history, export_tmx,
+    export_vocabulary, fetch_available_models, generate_identifier_names, get_api_key,
+    get_app_config, get_database_status, get_due_vocabulary, get_secret_backend, get_setting,
+    get_translation_group, get_translation_history, get_vocabulary_stats, import_config,
+    import_history, import_offline_dictionary, import_tmx, list_config_profiles,
+    list_database_backups, list_history_tags, list_jobs, list_offline_dictionaries,
+    list_vocabulary, load_subtitle_file, lookup_offline_dictionary, pause_job, proofread_text,
+    reload_shortcuts, remove_offline_dictionary, reorder_offline_dictionaries,
+    restore_database_backup, resume_job, retry_job, review_vocabulary, rewrite_text,
+    romanize_text, run_history_cleanup, save_api_key, save_app_config, save_setting,
+    save_translation, save_translation_correction, search_history, search_translation_memory,
+    set_history_favorite, set_history_note, set_history_tags, set_ocr_result,
+    set_offline_dictionary_enabled, set_offline_dictionary_languages, set_secret_backend,
+    shorten_text, start_area_selection, submit_job, summarize_text, switch_config_profile,
+    translate_document, translate_resource_file, translate_subtitle_file, translate_text,
+    translate_text_multi, undo_history_deletion, update_vocabulary, verify_translation,
     // Speech commands
     get_speech_models, is_model_downloaded, download_speech_model, load_speech_model,
     start_speech_recording, stop_speech_recording, is_speech_model_loaded, get_audio_devices,
@@ -26,6 +63,9 @@ use commands::{
 };
 use database::Database;
 use http_client::configure_http_client;
+use jobs::JobManager;
+use offline_dictionary::OfflineDictionaryStore;
+use secrets::SecretStore;
 #[cfg(target_os = "macos")]
 use platform::submit_area_for_ocr;
 use shortcuts::register_shortcuts;
@@ -45,7 +85,52 @@ pub fn run() {
         .plugin(tauri_plugin_updater::Builder::new().build())
         .plugin(tauri_plugin_autostart::Builder::new().build())
         .setup(|app| {
-            let db = Database::new(app.handle()).expect("数据库初始化失败");
+            let db = Database::open_or_recover(app.handle());
+            // 离线词典和密钥存储出问题时降级运行，并随数据库状态提示用户
+            let app_dir = match database::resolve_app_data_dir(app.handle()) {
+                Ok(app_dir) => Some(app_dir),
+                Err(err) => {
+                    eprintln!("无法获取应用数据目录: {}", err);
+                    None
+                }
+            };
+            let (offline_dictionaries, dictionary_warning) =
+                OfflineDictionaryStore::open_or_recover(app_dir.as_deref());
+            let (secret_store, secret_warning) = SecretStore::open_or_recover(app_dir.as_deref());
+            for warning in dictionary_warning.into_iter().chain(secret_warning) {
+                eprintln!("{}", warning);
+                db.add_warning(warning);
+            }
+            // 密钥存储只在内存中或数据库降级为内存数据库时，迁移后的密钥退出即丢失，留到下次启动
+            if secret_store.is_persistent() && !db.is_degraded() {
+                match db
+                    .active_profile_id()
+                    .map_err(|e| e.to_string())
+                    .and_then(|profile_id| {
+                        secrets::adopt_unscoped_secrets(&secret_store, profile_id)
+                    }) {
+                    Ok(0) => {}
+                    Ok(count) => println!("已将 {} 个 API 密钥归入当前配置档案", count),
+                    Err(err) => eprintln!("迁移 API 密钥到配置档案失败: {}", err),
+                }
+                match secrets::migrate_plaintext(&db, &secret_store) {
+                    Ok(count) => {
+                        if count > 0 {
+                            println!("已将 {} 个明文保存的 API 密钥迁移到密钥存储", count);
+                            // 清除的明文仍可能残留在数据库空闲页中
+                            if let Err(err) = db.erase_free_pages() {
+                                eprintln!("清理数据库空闲页失败: {}", err);
+                            }
+                        }
+                        match db.scrub_backup_secrets() {
+                            Ok(0) => {}
+                            Ok(count) => println!("已清除 {} 个备份中的明文 API 密钥", count),
+                            Err(err) => eprintln!("清除备份中的明文 API 密钥失败: {}", err),
+                        }
+                    }
+                    Err(err) => eprintln!("迁移明文 API 密钥失败: {}", err),
+                }
+            }
             let translation_service = TranslationService::OpenAI;
 
             match db.get_app_config() {
@@ -67,11 +152,20 @@ pub fn run() {
                 }
             }
 
+            let auto_backup_db = db.clone();
+            let housekeeping_db = db.clone();
             app.manage(AppState {
                 db: Mutex::new(db),
                 translation_service: Mutex::new(translation_service),
+                jobs: JobManager::new(),
+                offline_dictionaries,
+                secrets: secret_store,
             });
 
+            jobs::start_worker(app.handle().clone());
+            backup::start_auto_backup(auto_backup_db);
+            housekeeping::start(housekeeping_db);
+
             register_shortcuts(app.handle());
             setup_system_tray(app.handle())?;
 
@@ -87,17 +181,65 @@ pub fn run() {
         })
         .invoke_handler(tauri::generate_handler![
             translate_text,
+            translate_text_multi,
+            verify_translation,
+            romanize_text,
+            search_translation_memory,
+            save_translation_correction,
+            delete_memory_entry,
+            import_tmx,
+            export_tmx,
+            add_vocabulary,
+            add_vocabulary_from_history,
+            update_vocabulary,
+            delete_vocabulary,
+            list_vocabulary,
+            get_due_vocabulary,
+            review_vocabulary,
+            get_vocabulary_stats,
+            export_vocabulary,
+            generate_identifier_names,
+            proofread_text,
+            rewrite_text,
+            shorten_text,
+            summarize_text,
+            explain_text,
             save_translation,
             get_translation_history,
+            get_translation_group,
             search_history,
             clear_history,
+            delete_translation,
+            delete_history,
+            undo_history_deletion,
+            run_history_cleanup,
+            set_history_favorite,
+            set_history_tags,
+            set_history_note,
+            list_history_tags,
+            export_history,
+            import_history,
+            get_database_status,
+            check_database_integrity,
+            create_database_backup,
+            list_database_backups,
+            restore_database_backup,
             save_setting,
             get_setting,
             save_api_key,
             get_api_key,
+            get_secret_backend,
+            set_secret_backend,
             save_app_config,
             get_app_config,
             reload_shortcuts,
+            list_config_profiles,
+            create_config_profile,
+            clone_config_profile,
+            switch_config_profile,
+            delete_config_profile,
+            export_config,
+            import_config,
             capture_screen,
             capture_screen_area,
             capture_and_ocr,
@@ -107,6 +249,23 @@ pub fn run() {
             set_ocr_result,
             get_supported_languages,
             fetch_available_models,
+            translate_resource_file,
+            load_subtitle_file,
+            translate_subtitle_file,
+            translate_document,
+            submit_job,
+            list_jobs,
+            pause_job,
+            resume_job,
+            cancel_job,
+            retry_job,
+            import_offline_dictionary,
+            list_offline_dictionaries,
+            reorder_offline_dictionaries,
+            set_offline_dictionary_enabled,
+            remove_offline_dictionary,
+            set_offline_dictionary_languages,
+            lookup_offline_dictionary,
             // Speech-to-Text commands
             get_speech_models,
             is_model_downloaded,
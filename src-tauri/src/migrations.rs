@@ -0,0 +1,798 @@
+// Database schema migrations
+// Versioned, transactional upgrades of trans.db tracked with PRAGMA user_version; the database is
+// backed up before any pending migration runs and newer schemas are refused
+//
+// 新的表结构变更只需在 MIGRATIONS 末尾追加一项，版本号递增，已发布的迁移不要再修改。
+// 版本 1-3 对应引入版本号之前逐步加上的表结构，未记录版本号的旧数据库可能处于其中任意状态，
+// 所以这三个迁移都写成可重复执行的形式。
+
+use crate::database::backfill_translation_memory;
+use chrono::Utc;
+use rusqlite::{params, Connection, Error as RusqliteError, ErrorCode, Result, Transaction};
+use std::path::{Path, PathBuf};
+
+pub struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+    pub up: fn(&Transaction<'_>) -> Result<()>,
+}
+
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "创建翻译历史、用户设置、API 密钥和后台任务表",
+        up: create_initial_tables,
+    },
+    Migration {
+        version: 2,
+        description: "翻译历史增加多目标语言分组和写作工具操作类型",
+        up: add_history_group_and_operation,
+    },
+    Migration {
+        version: 3,
+        description: "创建翻译记忆库并用已有历史建立索引",
+        up: create_translation_memory,
+    },
+    Migration {
+        version: 4,
+        description: "为翻译历史建立全文索引和筛选索引",
+        up: create_history_search_index,
+    },
+    Migration {
+        version: 5,
+        description: "创建翻译历史的收藏、标签和备注表",
+        up: create_history_annotations,
+    },
+    Migration {
+        version: 6,
+        description: "创建生词本和复习记录表",
+        up: create_vocabulary,
+    },
+    Migration {
+        version: 7,
+        description: "创建翻译历史回收站，支持撤销删除",
+        up: create_history_trash,
+    },
+    Migration {
+        version: 8,
+        description: "创建配置档案表，原有配置成为默认档案",
+        up: create_config_profiles,
+    },
+];
+
+/// 当前应用支持的最新表结构版本
+pub fn latest_version() -> u32 {
+    MIGRATIONS.last().map_or(0, |migration| migration.version)
+}
+
+/// 迁移结果，backup_path 为迁移前的备份文件（新建数据库或无需迁移时为空）
+#[derive(Debug, Clone)]
+pub struct MigrationReport {
+    pub from_version: u32,
+    pub to_version: u32,
+    pub backup_path: Option<PathBuf>,
+}
+
+/// 把数据库升级到最新版本；db_path 为数据库文件路径，用于在旁边生成备份
+pub fn run(conn: &mut Connection, db_path: &Path) -> Result<MigrationReport> {
+    apply(conn, db_path, MIGRATIONS)
+}
+
+fn apply(
+    conn: &mut Connection,
+    db_path: &Path,
+    migrations: &[Migration],
+) -> Result<MigrationReport> {
+    let current: u32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    let latest = migrations.last().map_or(0, |migration| migration.version);
+
+    if current > latest {
+        return Err(migration_error(format!(
+            "数据库版本（{}）高于当前应用支持的版本（{}），请升级应用后再打开",
+            current, latest
+        )));
+    }
+
+    let pending: Vec<&Migration> = migrations
+        .iter()
+        .filter(|migration| migration.version > current)
+        .collect();
+    if pending.is_empty() {
+        return Ok(MigrationReport {
+            from_version: current,
+            to_version: current,
+            backup_path: None,
+        });
+    }
+
+    let backup_path = if has_user_tables(conn)? {
+        Some(backup(conn, db_path, current)?)
+    } else {
+        None
+    };
+
+    for migration in pending {
+        // 每个迁移和版本号更新在同一事务中提交，失败时整体回滚，版本号停留在上一个成功的迁移
+        let tx = conn.transaction()?;
+        (migration.up)(&tx).map_err(|err| {
+            migration_error(format!(
+                "数据库迁移到版本 {}（{}）失败: {}",
+                migration.version, migration.description, err
+            ))
+        })?;
+        tx.pragma_update(None, "user_version", migration.version)?;
+        tx.commit()?;
+    }
+
+    Ok(MigrationReport {
+        from_version: current,
+        to_version: latest,
+        backup_path,
+    })
+}
+
+fn has_user_tables(conn: &Connection) -> Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%')",
+        [],
+        |row| row.get(0),
+    )
+}
+
+/// 用 VACUUM INTO 把迁移前的数据库完整复制一份，文件名带原版本号和时间
+fn backup(conn: &Connection, db_path: &Path, version: u32) -> Result<PathBuf> {
+    let file_name = db_path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("trans");
+    let backup_path = db_path.with_file_name(format!(
+        "{}.v{}-{}.bak.db",
+        file_name,
+        version,
+        Utc::now().format("%Y%m%d%H%M%S")
+    ));
+    conn.execute(
+        "VACUUM INTO ?1",
+        params![backup_path.to_string_lossy().as_ref()],
+    )
+    .map_err(|err| migration_error(format!("迁移前备份数据库失败: {}", err)))?;
+    Ok(backup_path)
+}
+
+fn migration_error(message: String) -> RusqliteError {
+    RusqliteError::SqliteFailure(
+        rusqlite::ffi::Error {
+            code: ErrorCode::Unknown,
+            extended_code: 0,
+        },
+        Some(message),
+    )
+}
+
+/// 为已有表补充新增的列，列已存在时不做任何事
+fn add_column_if_missing(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> Result<()> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let exists = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .filter_map(|name| name.ok())
+        .any(|name| name == column);
+    if !exists {
+        conn.execute(
+            &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+            [],
+        )?;
+    }
+    Ok(())
+}
+
+fn create_initial_tables(tx: &Transaction<'_>) -> Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS translation_history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            original_text TEXT NOT NULL,
+            translated_text TEXT NOT NULL,
+            service TEXT NOT NULL,
+            from_language TEXT,
+            to_language TEXT,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+
+        CREATE TABLE IF NOT EXISTS user_settings (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            key TEXT UNIQUE NOT NULL,
+            value TEXT NOT NULL,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+
+        CREATE TABLE IF NOT EXISTS api_keys (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            service TEXT UNIQUE NOT NULL,
+            api_key TEXT NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+
+        CREATE TABLE IF NOT EXISTS jobs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            kind TEXT NOT NULL,
+            params TEXT NOT NULL,
+            status TEXT NOT NULL,
+            progress REAL NOT NULL DEFAULT 0,
+            message TEXT,
+            result_path TEXT,
+            error TEXT,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_translation_history_created_at
+            ON translation_history(created_at DESC);
+        CREATE INDEX IF NOT EXISTS idx_jobs_status ON jobs(status, id);",
+    )
+}
+
+fn add_history_group_and_operation(tx: &Transaction<'_>) -> Result<()> {
+    add_column_if_missing(tx, "translation_history", "group_id", "INTEGER")?;
+    add_column_if_missing(tx, "translation_history", "operation", "TEXT")?;
+    tx.execute(
+        "CREATE INDEX IF NOT EXISTS idx_translation_history_group_id
+         ON translation_history(group_id)",
+        [],
+    )?;
+    Ok(())
+}
+
+fn create_translation_memory(tx: &Transaction<'_>) -> Result<()> {
+    let memory_exists: bool = tx.query_row(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'translation_memory')",
+        [],
+        |row| row.get(0),
+    )?;
+    // normalized_source 为合并空白后的原文，用于精确匹配和去重；条目可能由多条历史合并而来，
+    // translation_memory_history 记录这些来源，来源都被永久删除后条目才一并删除
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS translation_memory (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            source_text TEXT NOT NULL,
+            target_text TEXT NOT NULL,
+            normalized_source TEXT NOT NULL,
+            source_length INTEGER NOT NULL,
+            source_lang TEXT NOT NULL DEFAULT '',
+            target_lang TEXT NOT NULL DEFAULT '',
+            origin TEXT NOT NULL,
+            preferred INTEGER NOT NULL DEFAULT 0,
+            use_count INTEGER NOT NULL DEFAULT 1,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            UNIQUE(normalized_source, source_lang, target_lang, target_text)
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_translation_memory_lookup
+            ON translation_memory(target_lang, source_length);
+
+        CREATE TABLE IF NOT EXISTS translation_memory_history (
+            memory_id INTEGER NOT NULL,
+            history_id INTEGER NOT NULL,
+            PRIMARY KEY (memory_id, history_id)
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_translation_memory_history_history
+            ON translation_memory_history(history_id);",
+    )?;
+    if !memory_exists {
+        backfill_translation_memory(tx)?;
+    }
+    Ok(())
+}
+
+// 外部内容表只保存索引，原文和译文仍在 translation_history 中；三元组分词对中日韩文本同样有效
+fn create_history_search_index(tx: &Transaction<'_>) -> Result<()> {
+    tx.execute_batch(
+        "CREATE VIRTUAL TABLE translation_history_fts USING fts5(
+            original_text,
+            translated_text,
+            content = 'translation_history',
+            content_rowid = 'id',
+            tokenize = 'trigram'
+        );
+
+        CREATE TRIGGER translation_history_fts_insert AFTER INSERT ON translation_history BEGIN
+            INSERT INTO translation_history_fts (rowid, original_text, translated_text)
+            VALUES (new.id, new.original_text, new.translated_text);
+        END;
+
+        CREATE TRIGGER translation_history_fts_delete AFTER DELETE ON translation_history BEGIN
+            INSERT INTO translation_history_fts
+                (translation_history_fts, rowid, original_text, translated_text)
+            VALUES ('delete', old.id, old.original_text, old.translated_text);
+        END;
+
+        CREATE TRIGGER translation_history_fts_update
+        AFTER UPDATE OF original_text, translated_text ON translation_history BEGIN
+            INSERT INTO translation_history_fts
+                (translation_history_fts, rowid, original_text, translated_text)
+            VALUES ('delete', old.id, old.original_text, old.translated_text);
+            INSERT INTO translation_history_fts (rowid, original_text, translated_text)
+            VALUES (new.id, new.original_text, new.translated_text);
+        END;
+
+        INSERT INTO translation_history_fts (translation_history_fts) VALUES ('rebuild');
+
+        CREATE INDEX IF NOT EXISTS idx_translation_history_service
+            ON translation_history(service COLLATE NOCASE);
+        CREATE INDEX IF NOT EXISTS idx_translation_history_languages
+            ON translation_history(from_language, to_language);",
+    )
+}
+
+// 收藏和备注每条历史一行，标签每个一行；历史删除时由触发器一并清理
+fn create_history_annotations(tx: &Transaction<'_>) -> Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE history_annotations (
+            history_id INTEGER PRIMARY KEY,
+            favorite INTEGER NOT NULL DEFAULT 0,
+            note TEXT,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+
+        CREATE TABLE history_tags (
+            history_id INTEGER NOT NULL,
+            tag TEXT NOT NULL COLLATE NOCASE,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            PRIMARY KEY (history_id, tag)
+        );
+
+        CREATE INDEX idx_history_annotations_favorite
+            ON history_annotations(favorite) WHERE favorite = 1;
+        CREATE INDEX idx_history_tags_tag ON history_tags(tag);
+
+        CREATE TRIGGER translation_history_annotations_delete
+        AFTER DELETE ON translation_history BEGIN
+            DELETE FROM history_annotations WHERE history_id = old.id;
+            DELETE FROM history_tags WHERE history_id = old.id;
+        END;",
+    )
+}
+
+// examples 为 JSON 数组；due_at 等时间使用 RFC 3339，比较时经 datetime() 规范
+fn create_vocabulary(tx: &Transaction<'_>) -> Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE vocabulary (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            term TEXT NOT NULL,
+            translation TEXT NOT NULL,
+            source_lang TEXT NOT NULL DEFAULT '',
+            target_lang TEXT NOT NULL DEFAULT '',
+            kind TEXT NOT NULL DEFAULT 'word',
+            phonetic TEXT,
+            examples TEXT NOT NULL DEFAULT '[]',
+            note TEXT,
+            history_id INTEGER,
+            ease REAL NOT NULL DEFAULT 2.5,
+            interval_days INTEGER NOT NULL DEFAULT 0,
+            repetitions INTEGER NOT NULL DEFAULT 0,
+            lapses INTEGER NOT NULL DEFAULT 0,
+            due_at TEXT NOT NULL,
+            last_reviewed_at TEXT,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            UNIQUE(term, source_lang, target_lang)
+        );
+
+        CREATE TABLE vocabulary_reviews (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            vocabulary_id INTEGER NOT NULL,
+            grade TEXT NOT NULL,
+            interval_days INTEGER NOT NULL,
+            ease REAL NOT NULL,
+            reviewed_at TEXT NOT NULL
+        );
+
+        CREATE INDEX idx_vocabulary_due ON vocabulary(due_at);
+        CREATE INDEX idx_vocabulary_reviews_item ON vocabulary_reviews(vocabulary_id);
+
+        CREATE TRIGGER vocabulary_reviews_delete AFTER DELETE ON vocabulary BEGIN
+            DELETE FROM vocabulary_reviews WHERE vocabulary_id = old.id;
+        END;",
+    )
+}
+
+// 每次删除一行，被删除的历史连同收藏、备注和标签（JSON 数组）按删除批次保存，撤销时按原 id 恢复
+fn create_history_trash(tx: &Transaction<'_>) -> Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE history_deletions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            deleted_count INTEGER NOT NULL,
+            deleted_at TEXT NOT NULL
+        );
+
+        CREATE TABLE history_trash (
+            deletion_id INTEGER NOT NULL,
+            history_id INTEGER NOT NULL,
+            original_text TEXT NOT NULL,
+            translated_text TEXT NOT NULL,
+            service TEXT NOT NULL,
+            from_language TEXT,
+            to_language TEXT,
+            created_at DATETIME,
+            group_id INTEGER,
+            operation TEXT,
+            favorite INTEGER NOT NULL DEFAULT 0,
+            note TEXT,
+            tags TEXT NOT NULL DEFAULT '[]',
+            PRIMARY KEY (deletion_id, history_id)
+        );
+
+        CREATE INDEX idx_translation_history_created
+            ON translation_history(datetime(created_at));",
+    )
+}
+
+// config 为空表示使用默认配置；当前档案的 id 保存在 user_settings 的 active_profile_id 中
+fn create_config_profiles(tx: &Transaction<'_>) -> Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE config_profiles (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+            config TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        );
+
+        INSERT INTO config_profiles (name, config, created_at, updated_at)
+        VALUES (
+            '默认',
+            (SELECT value FROM user_settings WHERE key = 'app_config'),
+            strftime('%Y-%m-%dT%H:%M:%SZ', 'now'),
+            strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
+        );
+        INSERT OR REPLACE INTO user_settings (key, value)
+        VALUES ('active_profile_id', last_insert_rowid());
+        DELETE FROM user_settings WHERE key = 'app_config';",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 引入版本号之前的最后一版表结构（user_version 为 0）
+    const CURRENT_SCHEMA_FIXTURE: &str = "
+        CREATE TABLE translation_history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            original_text TEXT NOT NULL,
+            translated_text TEXT NOT NULL,
+            service TEXT NOT NULL,
+            from_language TEXT,
+            to_language TEXT,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            group_id INTEGER,
+            operation TEXT
+        );
+        CREATE TABLE user_settings (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            key TEXT UNIQUE NOT NULL,
+            value TEXT NOT NULL,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+        CREATE TABLE api_keys (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            service TEXT UNIQUE NOT NULL,
+            api_key TEXT NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+        CREATE TABLE jobs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            kind TEXT NOT NULL,
+            params TEXT NOT NULL,
+            status TEXT NOT NULL,
+            progress REAL NOT NULL DEFAULT 0,
+            message TEXT,
+            result_path TEXT,
+            error TEXT,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+        CREATE INDEX idx_translation_history_created_at ON translation_history(created_at DESC);
+        CREATE INDEX idx_translation_history_group_id ON translation_history(group_id);
+        CREATE INDEX idx_jobs_status ON jobs(status, id);
+
+        INSERT INTO translation_history
+            (original_text, translated_text, service, from_language, to_language, created_at)
+        VALUES ('Good morning', '早上好', 'OpenAI', 'en', 'zh-CN', '2024-05-01T08:00:00+00:00');
+        INSERT INTO translation_history
+            (original_text, translated_text, service, from_language, to_language, operation)
+        VALUES ('teh text', 'the text', 'OpenAI', 'en', 'en', 'proofread');
+        INSERT INTO user_settings (key, value) VALUES ('app_config', '{}');
+        INSERT INTO api_keys (service, api_key) VALUES ('openai', 'sk-test');
+    ";
+
+    /// 只有最早几张表、没有 group_id 和 operation 列的旧数据库
+    const LEGACY_SCHEMA_FIXTURE: &str = "
+        CREATE TABLE translation_history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            original_text TEXT NOT NULL,
+            translated_text TEXT NOT NULL,
+            service TEXT NOT NULL,
+            from_language TEXT,
+            to_language TEXT,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+        INSERT INTO translation_history (original_text, translated_text, service)
+        VALUES ('Hello', '你好', 'Google');
+    ";
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!(
+                "ai-translate-migrations-{}-{}",
+                name,
+                std::process::id()
+            ));
+            let _ = std::fs::remove_dir_all(&dir);
+            std::fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+
+        fn db_path(&self) -> PathBuf {
+            self.0.join("trans.db")
+        }
+
+        fn backups(&self) -> Vec<PathBuf> {
+            std::fs::read_dir(&self.0)
+                .unwrap()
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.to_string_lossy().ends_with(".bak.db"))
+                .collect()
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn open_fixture(dir: &TempDir, fixture: &str) -> Connection {
+        let conn = Connection::open(dir.db_path()).unwrap();
+        conn.execute_batch(fixture).unwrap();
+        conn
+    }
+
+    fn user_version(conn: &Connection) -> u32 {
+        conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap()
+    }
+
+    fn count(conn: &Connection, sql: &str) -> i64 {
+        conn.query_row(sql, [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn creates_fresh_database_without_backup() {
+        let dir = TempDir::new("fresh");
+        let mut conn = Connection::open(dir.db_path()).unwrap();
+
+        let report = run(&mut conn, &dir.db_path()).unwrap();
+
+        assert_eq!(report.from_version, 0);
+        assert_eq!(report.to_version, latest_version());
+        assert!(report.backup_path.is_none());
+        assert!(dir.backups().is_empty());
+        assert_eq!(user_version(&conn), latest_version());
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM translation_memory"), 0);
+        assert_eq!(
+            count(
+                &conn,
+                "SELECT COUNT(*) FROM config_profiles WHERE config IS NULL"
+            ),
+            1
+        );
+    }
+
+    #[test]
+    fn upgrades_current_schema_fixture() {
+        let dir = TempDir::new("current");
+        let mut conn = open_fixture(&dir, CURRENT_SCHEMA_FIXTURE);
+
+        let report = run(&mut conn, &dir.db_path()).unwrap();
+
+        assert_eq!(report.from_version, 0);
+        assert_eq!(user_version(&conn), latest_version());
+        // 原有数据保留
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM translation_history"), 2);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM api_keys"), 1);
+        // 原有配置成为当前的默认档案
+        let (profile_config, active): (String, String) = conn
+            .query_row(
+                "SELECT p.config, s.value FROM config_profiles p
+                 JOIN user_settings s ON s.key = 'active_profile_id' AND s.value = p.id",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(profile_config, "{}");
+        assert_eq!(active, "1");
+        assert_eq!(
+            count(
+                &conn,
+                "SELECT COUNT(*) FROM user_settings WHERE key = 'app_config'"
+            ),
+            0
+        );
+        // 只有普通翻译进入记忆库，语言代码规范为目录代码
+        let (source, lang): (String, String) = conn
+            .query_row(
+                "SELECT source_text, target_lang FROM translation_memory",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(source, "Good morning");
+        assert_eq!(lang, "zh-Hans");
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM translation_memory"), 1);
+        assert_eq!(
+            count(&conn, "SELECT COUNT(*) FROM translation_memory_history"),
+            1
+        );
+        // 已有历史进入全文索引
+        assert_eq!(
+            count(
+                &conn,
+                "SELECT COUNT(*) FROM translation_history_fts WHERE translation_history_fts MATCH '\"早上好\"'"
+            ),
+            1
+        );
+
+        // 备份是迁移前的数据库
+        let backup_path = report.backup_path.unwrap();
+        assert_eq!(dir.backups(), vec![backup_path.clone()]);
+        let backup = Connection::open(&backup_path).unwrap();
+        assert_eq!(user_version(&backup), 0);
+        assert_eq!(
+            count(&backup, "SELECT COUNT(*) FROM translation_history"),
+            2
+        );
+        assert_eq!(
+            count(
+                &backup,
+                "SELECT COUNT(*) FROM sqlite_master WHERE name = 'translation_memory'"
+            ),
+            0
+        );
+    }
+
+    #[test]
+    fn upgrades_legacy_schema_fixture() {
+        let dir = TempDir::new("legacy");
+        let mut conn = open_fixture(&dir, LEGACY_SCHEMA_FIXTURE);
+
+        run(&mut conn, &dir.db_path()).unwrap();
+
+        assert_eq!(user_version(&conn), latest_version());
+        assert_eq!(
+            count(
+                &conn,
+                "SELECT COUNT(*) FROM translation_history WHERE group_id IS NULL AND operation IS NULL"
+            ),
+            1
+        );
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM jobs"), 0);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM translation_memory"), 1);
+    }
+
+    #[test]
+    fn history_search_index_follows_history_changes() {
+        let dir = TempDir::new("fts");
+        let mut conn = open_fixture(&dir, CURRENT_SCHEMA_FIXTURE);
+        run(&mut conn, &dir.db_path()).unwrap();
+        let matches = |conn: &Connection, query: &str| {
+            conn.query_row(
+                "SELECT COUNT(*) FROM translation_history_fts WHERE translation_history_fts MATCH ?1",
+                [query],
+                |row| row.get::<_, i64>(0),
+            )
+            .unwrap()
+        };
+
+        conn.execute(
+            "INSERT INTO translation_history (original_text, translated_text, service)
+             VALUES ('Good night', '晚安，好梦', 'Google')",
+            [],
+        )
+        .unwrap();
+        assert_eq!(matches(&conn, "\"Good\""), 2);
+
+        conn.execute(
+            "UPDATE translation_history SET translated_text = '晚上好' WHERE original_text = 'Good night'",
+            [],
+        )
+        .unwrap();
+        assert_eq!(matches(&conn, "\"晚安，\""), 0);
+        assert_eq!(matches(&conn, "\"晚上好\""), 1);
+
+        conn.execute(
+            "DELETE FROM translation_history WHERE service = 'Google'",
+            [],
+        )
+        .unwrap();
+        assert_eq!(matches(&conn, "\"Good\""), 1);
+    }
+
+    #[test]
+    fn second_run_is_a_no_op() {
+        let dir = TempDir::new("rerun");
+        let mut conn = open_fixture(&dir, CURRENT_SCHEMA_FIXTURE);
+        run(&mut conn, &dir.db_path()).unwrap();
+
+        let report = run(&mut conn, &dir.db_path()).unwrap();
+
+        assert_eq!(report.from_version, latest_version());
+        assert!(report.backup_path.is_none());
+        assert_eq!(dir.backups().len(), 1);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM translation_memory"), 1);
+    }
+
+    #[test]
+    fn refuses_newer_database() {
+        let dir = TempDir::new("newer");
+        let mut conn = open_fixture(&dir, CURRENT_SCHEMA_FIXTURE);
+        conn.pragma_update(None, "user_version", latest_version() + 1)
+            .unwrap();
+
+        let err = run(&mut conn, &dir.db_path()).unwrap_err();
+
+        assert!(err.to_string().contains("高于当前应用支持的版本"));
+        assert_eq!(user_version(&conn), latest_version() + 1);
+        assert!(dir.backups().is_empty());
+        assert_eq!(
+            count(
+                &conn,
+                "SELECT COUNT(*) FROM sqlite_master WHERE name = 'translation_memory'"
+            ),
+            0
+        );
+    }
+
+    #[test]
+    fn failed_migration_rolls_back() {
+        fn create_then_fail(tx: &Transaction<'_>) -> Result<()> {
+            tx.execute("CREATE TABLE half_done (id INTEGER)", [])?;
+            tx.execute("INSERT INTO missing_table VALUES (1)", [])?;
+            Ok(())
+        }
+        let migrations = [
+            Migration {
+                version: 1,
+                description: "初始表结构",
+                up: create_initial_tables,
+            },
+            Migration {
+                version: 2,
+                description: "会失败的迁移",
+                up: create_then_fail,
+            },
+        ];
+        let dir = TempDir::new("rollback");
+        let mut conn = open_fixture(&dir, LEGACY_SCHEMA_FIXTURE);
+
+        let err = apply(&mut conn, &dir.db_path(), &migrations).unwrap_err();
+
+        assert!(err.to_string().contains("版本 2"));
+        assert_eq!(user_version(&conn), 1);
+        assert_eq!(
+            count(
+                &conn,
+                "SELECT COUNT(*) FROM sqlite_master WHERE name = 'half_done'"
+            ),
+            0
+        );
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM translation_history"), 1);
+    }
+}
@@ -0,0 +1,235 @@
+// Identifier naming
+// Turns a natural-language description into identifier candidates in common code conventions
+
+use crate::database::{AbbreviationRule, NamingConfig};
+use crate::translation::TranslationBackend;
+use serde::{Deserialize, Serialize};
+
+/// 开启缩写时使用的内置规则，自定义规则优先
+const COMMON_ABBREVIATIONS: &[(&str, &str)] = &[
+    ("application", "app"),
+    ("argument", "arg"),
+    ("arguments", "args"),
+    ("authentication", "auth"),
+    ("button", "btn"),
+    ("calculate", "calc"),
+    ("configuration", "config"),
+    ("context", "ctx"),
+    ("count", "cnt"),
+    ("database", "db"),
+    ("destination", "dest"),
+    ("directory", "dir"),
+    ("document", "doc"),
+    ("element", "elem"),
+    ("environment", "env"),
+    ("error", "err"),
+    ("function", "fn"),
+    ("identifier", "id"),
+    ("image", "img"),
+    ("information", "info"),
+    ("initialize", "init"),
+    ("length", "len"),
+    ("management", "mgmt"),
+    ("manager", "mgr"),
+    ("maximum", "max"),
+    ("message", "msg"),
+    ("minimum", "min"),
+    ("number", "num"),
+    ("parameter", "param"),
+    ("parameters", "params"),
+    ("password", "pwd"),
+    ("previous", "prev"),
+    ("reference", "ref"),
+    ("request", "req"),
+    ("response", "resp"),
+    ("source", "src"),
+    ("specification", "spec"),
+    ("statistics", "stats"),
+    ("string", "str"),
+    ("synchronize", "sync"),
+    ("temporary", "temp"),
+    ("utility", "util"),
+    ("utilities", "utils"),
+    ("value", "val"),
+];
+
+/// 命名风格
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NamingConvention {
+    Camel,
+    Snake,
+    Kebab,
+    Pascal,
+    ScreamingSnake,
+}
+
+impl NamingConvention {
+    /// 按约定格式拼接小写单词
+    pub fn format(self, words: &[String]) -> String {
+        match self {
+            NamingConvention::Camel => words
+                .iter()
+                .enumerate()
+                .map(|(i, word)| if i == 0 { word.clone() } else { capitalize(word) })
+                .collect(),
+            NamingConvention::Pascal => words.iter().map(|word| capitalize(word)).collect(),
+            NamingConvention::Snake => words.join("_"),
+            NamingConvention::Kebab => words.join("-"),
+            NamingConvention::ScreamingSnake => words.join("_").to_uppercase(),
+        }
+    }
+}
+
+/// 一个候选名称在各命名风格下的写法
+#[derive(Debug, Clone, Serialize)]
+pub struct IdentifierCandidate {
+    pub words: Vec<String>,
+    pub camel: String,
+    pub snake: String,
+    pub kebab: String,
+    pub pascal: String,
+    pub screaming_snake: String,
+}
+
+impl IdentifierCandidate {
+    fn from_words(words: Vec<String>) -> Self {
+        IdentifierCandidate {
+            camel: NamingConvention::Camel.format(&words),
+            snake: NamingConvention::Snake.format(&words),
+            kebab: NamingConvention::Kebab.format(&words),
+            pascal: NamingConvention::Pascal.format(&words),
+            screaming_snake: NamingConvention::ScreamingSnake.format(&words),
+            words,
+        }
+    }
+}
+
+/// 命名模式的返回结果
+#[derive(Debug, Clone, Serialize)]
+pub struct IdentifierNamingResult {
+    pub source_text: String,
+    pub candidates: Vec<IdentifierCandidate>,
+}
+
+/// 根据描述生成候选标识符
+pub async fn generate_identifier_names(
+    backend: &TranslationBackend,
+    text: &str,
+    config: &NamingConfig,
+) -> Result<IdentifierNamingResult, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err("请输入需要命名的描述".to_string());
+    }
+
+    let count = config.candidate_count.max(1) as usize;
+    let phrases = backend.suggest_identifier_phrases(text, count).await?;
+
+    Ok(IdentifierNamingResult {
+        source_text: text.to_string(),
+        candidates: build_candidates(&phrases, config, count),
+    })
+}
+
+/// 把词组拆分为单词、按需缩写并去重，生成候选列表
+pub fn build_candidates(
+    phrases: &[String],
+    config: &NamingConfig,
+    limit: usize,
+) -> Vec<IdentifierCandidate> {
+    let mut seen: Vec<Vec<String>> = Vec::new();
+    for phrase in phrases {
+        let mut words = split_words(phrase);
+        if config.abbreviate {
+            words = abbreviate(words, &config.abbreviations);
+        }
+        if words.is_empty() || seen.contains(&words) {
+            continue;
+        }
+        seen.push(words);
+        if seen.len() >= limit {
+            break;
+        }
+    }
+    seen.into_iter().map(IdentifierCandidate::from_words).collect()
+}
+
+/// 把任意写法的词组拆成小写 ASCII 单词，兼容模型偶尔返回的驼峰或下划线写法
+fn split_words(phrase: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut prev: Option<char> = None;
+
+    let chars: Vec<char> = phrase.chars().collect();
+    for (i, &ch) in chars.iter().enumerate() {
+        if !ch.is_ascii_alphanumeric() {
+            flush_word(&mut current, &mut words);
+            prev = None;
+            continue;
+        }
+
+        if let Some(p) = prev {
+            let next_is_lower = chars.get(i + 1).is_some_and(|c| c.is_ascii_lowercase());
+            // userName -> user name, HTTPServer -> http server
+            let boundary = ch.is_ascii_uppercase()
+                && (p.is_ascii_lowercase() || p.is_ascii_digit()
+                    || (p.is_ascii_uppercase() && next_is_lower));
+            if boundary {
+                flush_word(&mut current, &mut words);
+            }
+        }
+        current.push(ch.to_ascii_lowercase());
+        prev = Some(ch);
+    }
+    flush_word(&mut current, &mut words);
+
+    // 标识符不能以数字开头
+    while words.first().is_some_and(|w| w.starts_with(|c: char| c.is_ascii_digit())) {
+        words.remove(0);
+    }
+    words
+}
+
+fn flush_word(current: &mut String, words: &mut Vec<String>) {
+    if !current.is_empty() {
+        words.push(std::mem::take(current));
+    }
+}
+
+/// 先匹配自定义规则（可包含多个单词，如 "user interface" -> "ui"），再匹配内置缩写
+fn abbreviate(words: Vec<String>, rules: &[AbbreviationRule]) -> Vec<String> {
+    let custom: Vec<(Vec<String>, Vec<String>)> = rules
+        .iter()
+        .map(|rule| (split_words(&rule.word), split_words(&rule.abbreviation)))
+        .filter(|(word, abbreviation)| !word.is_empty() && !abbreviation.is_empty())
+        .collect();
+
+    let mut result = Vec::with_capacity(words.len());
+    let mut i = 0;
+    'outer: while i < words.len() {
+        for (pattern, replacement) in &custom {
+            if words[i..].starts_with(pattern) {
+                result.extend(replacement.iter().cloned());
+                i += pattern.len();
+                continue 'outer;
+            }
+        }
+
+        let word = &words[i];
+        match COMMON_ABBREVIATIONS.iter().find(|(full, _)| full == word) {
+            Some((_, short)) => result.push(short.to_string()),
+            None => result.push(word.clone()),
+        }
+        i += 1;
+    }
+    result
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+        None => String::new(),
+    }
+}
@@ -1,6 +1,8 @@
 use crate::{
     app_state::AppState,
+    commands::resolve_profile_secret,
     ocr::{OcrRequest, OcrService},
+    secrets,
     token_limits::calculate_image_response_tokens,
 };
 use image::{imageops::FilterType, GenericImageView, ImageFormat};
@@ -50,7 +52,7 @@ pub async fn run_ocr_on_image_data(
 ) -> Result<String, String> {
     let (processed_image_data, width, height) = ensure_minimum_ocr_size(image_data)?;
 
-    let (api_key, base_url, model_id, token_config) = {
+    let (secret_key, base_url, model_id, token_config) = {
         let db = state
             .db
             .lock()
@@ -67,7 +69,7 @@ pub async fn run_ocr_on_image_data(
                         translation_config.model_id
                     };
                     (
-                        translation_config.api_key,
+                        secrets::TRANSLATION_API_KEY,
                         translation_config.base_url,
                         model_id,
                         token_config,
@@ -75,7 +77,7 @@ pub async fn run_ocr_on_image_data(
                 } else {
                     let ocr_config = config.ocr;
                     (
-                        ocr_config.api_key,
+                        secrets::OCR_API_KEY,
                         ocr_config.base_url,
                         ocr_config.model_id,
                         token_config,
@@ -91,6 +93,7 @@ pub async fn run_ocr_on_image_data(
         }
     };
 
+    let api_key = resolve_profile_secret(&state, secret_key)?;
     if api_key.is_empty() {
         return Err("OCR API密钥未配置，请在设置中配置API密钥".to_string());
     }
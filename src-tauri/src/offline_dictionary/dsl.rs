@@ -0,0 +1,228 @@
+// ABBYY Lingvo DSL dictionaries
+// Headword lines start at column 0, indented lines form the card body; DSL markup becomes HTML
+
+use super::{decode_text, DefinitionFormat, EntrySink};
+use crate::languages;
+use flate2::read::GzDecoder;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+/// 导入 DSL 词典（.dsl 或 dictzip 压缩的 .dsl.dz），返回词典名称
+pub fn import(path: &Path, sink: &mut EntrySink) -> Result<String, String> {
+    let mut bytes = fs::read(path).map_err(|e| format!("读取 DSL 词典失败: {}", e))?;
+    if path.to_string_lossy().to_lowercase().ends_with(".dz") {
+        let mut data = Vec::new();
+        GzDecoder::new(bytes.as_slice())
+            .read_to_end(&mut data)
+            .map_err(|e| format!("解压 DSL 词典失败: {}", e))?;
+        bytes = data;
+    }
+    let content = decode_text(&bytes, None);
+
+    let mut name = String::new();
+    let mut headwords: Vec<String> = Vec::new();
+    let mut body: Vec<&str> = Vec::new();
+    sink.set_total(
+        content
+            .lines()
+            .filter(|line| is_headword_line(line))
+            .count(),
+    );
+
+    for line in content.lines() {
+        if headwords.is_empty() && body.is_empty() && line.starts_with('#') {
+            if let Some(value) = line.strip_prefix("#NAME") {
+                name = value.trim().trim_matches('"').to_string();
+            } else if let Some(value) = line.strip_prefix("#INDEX_LANGUAGE") {
+                sink.source_lang = header_language(value);
+            } else if let Some(value) = line.strip_prefix("#CONTENTS_LANGUAGE") {
+                sink.target_lang = header_language(value);
+            }
+            continue;
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        if line.starts_with([' ', '\t']) {
+            body.push(line.trim());
+        } else {
+            if !body.is_empty() {
+                flush(sink, &headwords, &body)?;
+                headwords.clear();
+                body.clear();
+            }
+            let headword = clean_headword(line);
+            if !headwords.contains(&headword) {
+                headwords.push(headword);
+            }
+        }
+    }
+    if !body.is_empty() {
+        flush(sink, &headwords, &body)?;
+    }
+
+    Ok(name)
+}
+
+/// 文件头中的语言写的是英文名称，如 "English"、"Chinese"
+fn header_language(value: &str) -> Option<String> {
+    languages::find_by_name(value.trim().trim_matches('"'))
+        .map(|language| language.code.to_string())
+}
+
+fn is_headword_line(line: &str) -> bool {
+    !line.trim().is_empty() && !line.starts_with([' ', '\t', '#'])
+}
+
+fn flush(sink: &mut EntrySink, headwords: &[String], body: &[&str]) -> Result<(), String> {
+    let Some(title) = headwords.first() else {
+        return Ok(());
+    };
+    let html = body
+        .iter()
+        .map(|line| markup_to_html(line, title))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n");
+    for headword in headwords {
+        sink.push(headword, &html, DefinitionFormat::Html)?;
+    }
+    Ok(())
+}
+
+/// 去掉词头中的非排序部分 {…} 和转义符
+fn clean_headword(line: &str) -> String {
+    let mut result = String::new();
+    let mut chars = line.trim().chars();
+    let mut depth = 0;
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' => {
+                if let Some(next) = chars.next() {
+                    if depth == 0 {
+                        result.push(next);
+                    }
+                }
+            }
+            '{' => depth += 1,
+            '}' => depth = (depth - 1).max(0),
+            _ if depth == 0 => result.push(ch),
+            _ => {}
+        }
+    }
+    result.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// 把一行 DSL 卡片标记转换为 HTML
+fn markup_to_html(line: &str, headword: &str) -> String {
+    let chars: Vec<char> = line.chars().collect();
+    let mut html = String::new();
+    let mut indent = 0;
+    let mut skip_depth = 0;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let ch = chars[i];
+        match ch {
+            '\\' if i + 1 < chars.len() => {
+                if skip_depth == 0 {
+                    push_escaped(&mut html, chars[i + 1]);
+                }
+                i += 2;
+                continue;
+            }
+            '{' if chars.get(i + 1) == Some(&'{') => {
+                // {{注释}}
+                let rest: String = chars[i + 2..].iter().collect();
+                match rest.find("}}") {
+                    Some(end) => i += 2 + rest[..end].chars().count() + 2,
+                    None => i = chars.len(),
+                }
+                continue;
+            }
+            '[' => {
+                let rest: String = chars[i + 1..].iter().collect();
+                if let Some(end) = rest.find(']') {
+                    let tag = &rest[..end];
+                    i += 1 + tag.chars().count() + 1;
+                    apply_tag(tag, &mut html, &mut indent, &mut skip_depth);
+                    continue;
+                }
+                if skip_depth == 0 {
+                    html.push('[');
+                }
+            }
+            '<' if chars.get(i + 1) == Some(&'<') => {
+                // <<词条链接>>，保留文本
+                i += 2;
+                continue;
+            }
+            '>' if chars.get(i + 1) == Some(&'>') => {
+                i += 2;
+                continue;
+            }
+            '~' if skip_depth == 0 => html.push_str(&escape(headword)),
+            _ if skip_depth == 0 => push_escaped(&mut html, ch),
+            _ => {}
+        }
+        i += 1;
+    }
+
+    let html = html.trim().to_string();
+    if html.is_empty() || indent == 0 {
+        html
+    } else {
+        format!("<div style=\"margin-left:{}em\">{}</div>", indent, html)
+    }
+}
+
+fn apply_tag(tag: &str, html: &mut String, indent: &mut usize, skip_depth: &mut usize) {
+    let (closing, tag) = match tag.strip_prefix('/') {
+        Some(tag) => (true, tag),
+        None => (false, tag),
+    };
+    let name = tag.split_whitespace().next().unwrap_or("");
+
+    match (name, closing) {
+        // 音频、图片等媒体文件不导入
+        ("s", false) => *skip_depth += 1,
+        ("s", true) => *skip_depth = skip_depth.saturating_sub(1),
+        _ if *skip_depth > 0 => {}
+        ("m", false) => *indent = 1,
+        (m, false) if m.len() == 2 && m.starts_with('m') => {
+            *indent = m[1..].parse().unwrap_or(1);
+        }
+        ("b" | "i" | "u" | "sup" | "sub", false) => html.push_str(&format!("<{}>", name)),
+        ("b" | "i" | "u" | "sup" | "sub", true) => html.push_str(&format!("</{}>", name)),
+        ("c", false) => {
+            let color = tag[1..].trim();
+            let color = if color.is_empty() { "green" } else { color };
+            html.push_str(&format!("<span style=\"color:{}\">", escape(color)));
+        }
+        ("ex", false) => html.push_str("<span class=\"ex\">"),
+        ("p", false) => html.push_str("<i class=\"label\">"),
+        ("c" | "ex", true) => html.push_str("</span>"),
+        ("p", true) => html.push_str("</i>"),
+        // [trn] [com] [*] [lang] [ref] [url] [!trs] [t] [m] 等只影响显示，保留内容
+        _ => {}
+    }
+}
+
+fn push_escaped(html: &mut String, ch: char) {
+    match ch {
+        '&' => html.push_str("&amp;"),
+        '<' => html.push_str("&lt;"),
+        '>' => html.push_str("&gt;"),
+        _ => html.push(ch),
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut html = String::with_capacity(text.len());
+    for ch in text.chars() {
+        push_escaped(&mut html, ch);
+    }
+    html
+}
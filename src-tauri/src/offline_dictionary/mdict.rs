@@ -0,0 +1,269 @@
+// MDict dictionaries
+// Reads the .mdx header, keyword blocks and record blocks (versions 1.x and 2.x, zlib or
+// uncompressed); encrypted and LZO-compressed files are rejected with a clear error
+
+use super::{decode_text, DefinitionFormat, EntrySink};
+use flate2::read::ZlibDecoder;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+/// 读取数据块时预先分配的最大容量，更大的块随读取逐步扩容
+const READ_CHUNK_CAPACITY: usize = 1 << 20;
+
+struct Header {
+    version: f32,
+    encoding: String,
+    title: String,
+}
+
+impl Header {
+    fn number_width(&self) -> usize {
+        if self.version >= 2.0 {
+            8
+        } else {
+            4
+        }
+    }
+
+    fn is_utf16(&self) -> bool {
+        self.encoding.to_ascii_uppercase().starts_with("UTF-16")
+    }
+}
+
+/// 导入 MDict 词典（.mdx），返回词典名称
+pub fn import(path: &Path, sink: &mut EntrySink) -> Result<String, String> {
+    let file = File::open(path).map_err(|e| format!("读取 MDict 词典失败: {}", e))?;
+    let mut reader = BufReader::new(file);
+
+    let header = read_header(&mut reader)?;
+    let keys = read_keys(&mut reader, &header)?;
+    sink.set_total(keys.len());
+    read_records(&mut reader, &header, &keys, sink)?;
+
+    Ok(header.title)
+}
+
+fn read_header(reader: &mut impl Read) -> Result<Header, String> {
+    let size = read_u32(reader)? as usize;
+    let bytes = read_bytes(reader, size)?;
+    // 头部后跟 4 字节 adler32 校验
+    read_bytes(reader, 4)?;
+
+    let text = decode_text(&bytes, Some("utf-16le"));
+    let text = text.trim_end_matches('\0');
+
+    let encrypted = attribute(text, "Encrypted").unwrap_or_default();
+    if !matches!(encrypted.as_str(), "" | "0" | "No" | "no") {
+        return Err("暂不支持加密的 MDict 词典".to_string());
+    }
+
+    let version = attribute(text, "GeneratedByEngineVersion")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(2.0);
+    let encoding = match attribute(text, "Encoding").unwrap_or_default().to_uppercase().as_str() {
+        "" => "UTF-8".to_string(),
+        "GBK" | "GB2312" => "GB18030".to_string(),
+        "UTF-16" => "UTF-16LE".to_string(),
+        other => other.to_string(),
+    };
+    let title = attribute(text, "Title")
+        .filter(|title| !title.is_empty() && title != "Title (No HTML code allowed)")
+        .unwrap_or_default();
+
+    Ok(Header {
+        version,
+        encoding,
+        title,
+    })
+}
+
+fn attribute(header: &str, name: &str) -> Option<String> {
+    let pattern = format!("{}=\"", name);
+    let start = header.find(&pattern)? + pattern.len();
+    let end = header[start..].find('"')? + start;
+    Some(
+        header[start..end]
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&amp;", "&"),
+    )
+}
+
+/// 读取关键词区，返回 (记录偏移, 词头)
+fn read_keys(reader: &mut impl Read, header: &Header) -> Result<Vec<(u64, String)>, String> {
+    let v2 = header.version >= 2.0;
+    let width = header.number_width();
+
+    let block_count = read_number(reader, width)?;
+    let _entry_count = read_number(reader, width)?;
+    if v2 {
+        let _info_decompressed_size = read_number(reader, width)?;
+    }
+    let info_size = read_number(reader, width)? as usize;
+    let blocks_size = read_number(reader, width)? as usize;
+    if v2 {
+        read_bytes(reader, 4)?;
+    }
+
+    let info = read_bytes(reader, info_size)?;
+    let info = if v2 { decompress_block(&info)? } else { info };
+    let block_sizes = parse_key_block_info(&info, header, block_count as usize)?;
+
+    let blocks = read_bytes(reader, blocks_size)?;
+    let mut keys = Vec::new();
+    let mut pos = 0;
+    for compressed_size in block_sizes {
+        let block = blocks
+            .get(pos..pos + compressed_size)
+            .ok_or("MDict 关键词区已损坏")?;
+        let data = decompress_block(block)?;
+        split_key_block(&data, header, &mut keys)?;
+        pos += compressed_size;
+    }
+    Ok(keys)
+}
+
+/// 关键词块信息：每块的词条数、首尾词头以及压缩后大小，这里只需要压缩后大小
+fn parse_key_block_info(info: &[u8], header: &Header, count: usize) -> Result<Vec<usize>, String> {
+    let v2 = header.version >= 2.0;
+    let width = header.number_width();
+    let (size_width, terminator) = if v2 { (2, 1) } else { (1, 0) };
+    let char_width = if header.is_utf16() { 2 } else { 1 };
+
+    let mut sizes = Vec::with_capacity(count);
+    let mut pos = 0;
+    while pos < info.len() && sizes.len() < count {
+        pos += width;
+        for _ in 0..2 {
+            let text_size = read_be(info, pos, size_width).ok_or("MDict 关键词索引已损坏")? as usize;
+            pos += size_width + (text_size + terminator) * char_width;
+        }
+        let compressed = read_be(info, pos, width).ok_or("MDict 关键词索引已损坏")? as usize;
+        pos += width * 2;
+        sizes.push(compressed);
+    }
+    Ok(sizes)
+}
+
+fn split_key_block(
+    data: &[u8],
+    header: &Header,
+    keys: &mut Vec<(u64, String)>,
+) -> Result<(), String> {
+    let width = header.number_width();
+    let char_width = if header.is_utf16() { 2 } else { 1 };
+    let mut pos = 0;
+
+    while pos + width <= data.len() {
+        let offset = read_be(data, pos, width).ok_or("MDict 关键词块已损坏")?;
+        pos += width;
+
+        let mut end = pos;
+        while end + char_width <= data.len() && data[end..end + char_width].iter().any(|&b| b != 0) {
+            end += char_width;
+        }
+        let key = decode_text(&data[pos..end], Some(&header.encoding));
+        keys.push((offset, key.trim().to_string()));
+        pos = end + char_width;
+    }
+    Ok(())
+}
+
+/// 读取记录区，按关键词偏移切分出每条释义
+fn read_records(
+    reader: &mut impl Read,
+    header: &Header,
+    keys: &[(u64, String)],
+    sink: &mut EntrySink,
+) -> Result<(), String> {
+    let width = header.number_width();
+    let block_count = read_number(reader, width)? as usize;
+    let _entry_count = read_number(reader, width)?;
+    let _info_size = read_number(reader, width)?;
+    let _blocks_size = read_number(reader, width)?;
+
+    let mut block_sizes = Vec::with_capacity(block_count);
+    for _ in 0..block_count {
+        let compressed = read_number(reader, width)? as usize;
+        let _decompressed = read_number(reader, width)?;
+        block_sizes.push(compressed);
+    }
+
+    let mut key_index = 0;
+    let mut block_start: u64 = 0;
+    for compressed_size in block_sizes {
+        let block = read_bytes(reader, compressed_size)?;
+        let data = decompress_block(&block)?;
+        let block_end = block_start + data.len() as u64;
+
+        while key_index < keys.len() {
+            let (offset, key) = &keys[key_index];
+            if *offset >= block_end {
+                break;
+            }
+            let end = keys
+                .get(key_index + 1)
+                .map(|(next, _)| *next)
+                .unwrap_or(block_end)
+                .min(block_end);
+            let start = offset.saturating_sub(block_start) as usize;
+            let stop = end.saturating_sub(block_start) as usize;
+            if let Some(record) = data.get(start..stop) {
+                let text = decode_text(record, Some(&header.encoding));
+                let text = text.trim_end_matches('\0');
+                sink.push(key, text, DefinitionFormat::Html)?;
+            }
+            key_index += 1;
+        }
+        block_start = block_end;
+    }
+    Ok(())
+}
+
+/// 块头 4 字节压缩类型 + 4 字节校验：0 未压缩，1 LZO，2 zlib
+fn decompress_block(block: &[u8]) -> Result<Vec<u8>, String> {
+    let kind = block.get(..4).ok_or("MDict 数据块已损坏")?;
+    let payload = block.get(8..).ok_or("MDict 数据块已损坏")?;
+    match kind {
+        [0, 0, 0, 0] => Ok(payload.to_vec()),
+        [2, 0, 0, 0] => {
+            let mut data = Vec::new();
+            ZlibDecoder::new(payload)
+                .read_to_end(&mut data)
+                .map_err(|e| format!("解压 MDict 数据块失败: {}", e))?;
+            Ok(data)
+        }
+        [1, 0, 0, 0] => Err("暂不支持 LZO 压缩的 MDict 词典".to_string()),
+        _ => Err("未知的 MDict 压缩格式".to_string()),
+    }
+}
+
+fn read_be(data: &[u8], pos: usize, width: usize) -> Option<u64> {
+    let bytes = data.get(pos..pos + width)?;
+    Some(bytes.iter().fold(0u64, |value, &b| (value << 8) | b as u64))
+}
+
+/// 长度来自文件本身，不可信：按实际读到的数据扩容，避免损坏的文件一次申请巨大的内存
+fn read_bytes(reader: &mut impl Read, size: usize) -> Result<Vec<u8>, String> {
+    let mut buffer = Vec::with_capacity(size.min(READ_CHUNK_CAPACITY));
+    reader
+        .by_ref()
+        .take(size as u64)
+        .read_to_end(&mut buffer)
+        .map_err(|e| format!("MDict 文件不完整: {}", e))?;
+    if buffer.len() < size {
+        return Err("MDict 文件不完整".to_string());
+    }
+    Ok(buffer)
+}
+
+fn read_u32(reader: &mut impl Read) -> Result<u32, String> {
+    Ok(read_number(reader, 4)? as u32)
+}
+
+fn read_number(reader: &mut impl Read, width: usize) -> Result<u64, String> {
+    let bytes = read_bytes(reader, width)?;
+    Ok(read_be(&bytes, 0, width).unwrap_or_default())
+}
@@ -0,0 +1,767 @@
+// Offline dictionaries
+// Imports StarDict, MDict and DSL dictionaries into a local SQLite index and serves
+// exact, prefix and fuzzy lookups without any network round-trip
+
+pub mod dsl;
+pub mod mdict;
+pub mod stardict;
+
+use crate::backup;
+use crate::database::is_corruption;
+use crate::dictionary::{DictionaryEntry, DictionarySense};
+use crate::languages;
+use rusqlite::{params, Connection, OptionalExtension, Statement};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+const FILE_NAME: &str = "dictionaries.db";
+/// 模糊匹配时最多扫描的候选词数量
+const MAX_FUZZY_CANDIDATES: usize = 50_000;
+
+/// 支持导入的词典格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OfflineDictionaryFormat {
+    StarDict,
+    MDict,
+    Dsl,
+}
+
+impl OfflineDictionaryFormat {
+    /// 根据扩展名识别格式，StarDict 可以选择 .ifo/.idx/.dict 中任意一个文件
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_lowercase();
+        if name.ends_with(".ifo")
+            || name.ends_with(".idx")
+            || name.ends_with(".idx.gz")
+            || name.ends_with(".dict")
+            || name.ends_with(".dict.dz")
+        {
+            Some(OfflineDictionaryFormat::StarDict)
+        } else if name.ends_with(".mdx") {
+            Some(OfflineDictionaryFormat::MDict)
+        } else if name.ends_with(".dsl") || name.ends_with(".dsl.dz") {
+            Some(OfflineDictionaryFormat::Dsl)
+        } else {
+            None
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            OfflineDictionaryFormat::StarDict => "star_dict",
+            OfflineDictionaryFormat::MDict => "m_dict",
+            OfflineDictionaryFormat::Dsl => "dsl",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value {
+            "m_dict" => OfflineDictionaryFormat::MDict,
+            "dsl" => OfflineDictionaryFormat::Dsl,
+            _ => OfflineDictionaryFormat::StarDict,
+        }
+    }
+}
+
+/// 释义内容类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DefinitionFormat {
+    Text,
+    Html,
+}
+
+impl DefinitionFormat {
+    fn as_str(&self) -> &'static str {
+        match self {
+            DefinitionFormat::Text => "text",
+            DefinitionFormat::Html => "html",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        if value == "html" {
+            DefinitionFormat::Html
+        } else {
+            DefinitionFormat::Text
+        }
+    }
+}
+
+/// 已导入的词典
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OfflineDictionary {
+    pub id: i64,
+    pub name: String,
+    pub format: OfflineDictionaryFormat,
+    pub source_path: String,
+    pub entry_count: i64,
+    /// 词条语言和释义语言（目录代码），未知时为空，这类词典不参与翻译时的自动查词
+    pub source_lang: Option<String>,
+    pub target_lang: Option<String>,
+    /// 越小越靠前
+    pub priority: i64,
+    pub enabled: bool,
+    pub created_at: String,
+}
+
+/// 匹配方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchKind {
+    Exact,
+    Prefix,
+    Fuzzy,
+}
+
+/// 一条查询结果
+#[derive(Debug, Clone, Serialize)]
+pub struct OfflineDictionaryMatch {
+    pub dictionary_id: i64,
+    pub dictionary_name: String,
+    pub headword: String,
+    pub definition: String,
+    pub content_type: DefinitionFormat,
+    pub match_kind: MatchKind,
+}
+
+/// 导入时逐条写入词条，解析器通过它输出结果
+pub struct EntrySink<'a> {
+    insert: Statement<'a>,
+    dictionary_id: i64,
+    total: usize,
+    count: usize,
+    on_progress: &'a mut dyn FnMut(usize, usize) -> bool,
+    /// 解析器从文件头读到的语言，导入时指定的语言优先
+    pub source_lang: Option<String>,
+    pub target_lang: Option<String>,
+}
+
+impl EntrySink<'_> {
+    /// 解析器得知词条总数后调用，用于计算进度
+    pub fn set_total(&mut self, total: usize) {
+        self.total = total;
+    }
+
+    pub fn push(
+        &mut self,
+        headword: &str,
+        definition: &str,
+        content_type: DefinitionFormat,
+    ) -> Result<(), String> {
+        let headword = headword.trim();
+        let key = normalize_key(headword);
+        if key.is_empty() || definition.trim().is_empty() {
+            return Ok(());
+        }
+
+        self.insert
+            .execute(params![
+                self.dictionary_id,
+                headword,
+                key,
+                definition.trim(),
+                content_type.as_str()
+            ])
+            .map_err(|e| format!("写入词条失败: {}", e))?;
+        self.count += 1;
+
+        if self.count.is_multiple_of(1000) && !(self.on_progress)(self.count, self.total.max(self.count)) {
+            return Err("导入已中断".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// 离线词典库，独立于 trans.db 存放，避免大词典拖慢历史记录等常规操作
+pub struct OfflineDictionaryStore {
+    path: PathBuf,
+    conn: Mutex<Connection>,
+}
+
+impl OfflineDictionaryStore {
+    /// 打开词典库；损坏时隔离后新建空词典库，仍无法使用（或没有数据目录）时改用临时词典库。
+    /// 返回需要提示用户的问题
+    pub fn open_or_recover(app_dir: Option<&Path>) -> (Self, Option<String>) {
+        let problem = match app_dir {
+            Some(app_dir) => {
+                let path = app_dir.join(FILE_NAME);
+                match Self::open_at(path.clone()) {
+                    Ok(store) => return (store, None),
+                    Err(e) if is_corruption(&e) => match backup::quarantine(&path) {
+                        Ok(_) => match Self::open_at(path) {
+                            Ok(store) => {
+                                let warning = format!(
+                                    "离线词典库已损坏（{}），已隔离并新建空词典库，需要重新导入词典",
+                                    e
+                                );
+                                return (store, Some(warning));
+                            }
+                            Err(e) => format!("无法新建离线词典库: {}", e),
+                        },
+                        Err(err) => format!("离线词典库已损坏且无法隔离: {}", err),
+                    },
+                    Err(e) => format!("无法打开离线词典库: {}", e),
+                }
+            }
+            None => "没有可用的数据目录".to_string(),
+        };
+        eprintln!("{}", problem);
+
+        // 导入时会另开连接，所以优先用临时文件；连临时文件都无法使用时只能退回内存数据库
+        let temp_path =
+            std::env::temp_dir().join(format!("dictionaries-{}.db", std::process::id()));
+        let _ = backup::remove_database_files(&temp_path);
+        let store = Self::open_at(temp_path)
+            .or_else(|_| Self::open_at(PathBuf::from(":memory:")))
+            .expect("无法创建内存中的离线词典库");
+        let warning = format!("{}，本次使用临时词典库，导入的词典不会保留", problem);
+        (store, Some(warning))
+    }
+
+    fn open_at(path: PathBuf) -> rusqlite::Result<Self> {
+        let conn = Connection::open(&path)?;
+        // WAL 模式下导入（单独连接写入）不会阻塞查询
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS dictionaries (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                format TEXT NOT NULL,
+                source_path TEXT NOT NULL,
+                entry_count INTEGER NOT NULL DEFAULT 0,
+                priority INTEGER NOT NULL DEFAULT 0,
+                enabled INTEGER NOT NULL DEFAULT 1,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            );
+            CREATE TABLE IF NOT EXISTS dictionary_entries (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                dictionary_id INTEGER NOT NULL REFERENCES dictionaries(id) ON DELETE CASCADE,
+                headword TEXT NOT NULL,
+                headword_key TEXT NOT NULL,
+                definition TEXT NOT NULL,
+                content_type TEXT NOT NULL DEFAULT 'text'
+            );
+            CREATE INDEX IF NOT EXISTS idx_dictionary_entries_key
+                ON dictionary_entries(headword_key);
+            CREATE INDEX IF NOT EXISTS idx_dictionary_entries_dictionary
+                ON dictionary_entries(dictionary_id);",
+        )?;
+        // 语言列是后来加的，旧词典库需要补上
+        let has_languages: bool = conn.query_row(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info('dictionaries') WHERE name = 'source_lang'",
+            [],
+            |row| row.get(0),
+        )?;
+        if !has_languages {
+            conn.execute_batch(
+                "ALTER TABLE dictionaries ADD COLUMN source_lang TEXT;
+                 ALTER TABLE dictionaries ADD COLUMN target_lang TEXT;",
+            )?;
+        }
+
+        Ok(OfflineDictionaryStore {
+            path,
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// 导入词典文件；source_lang/target_lang 为空时使用词典文件头中的语言；on_progress 返回 false 时中断并回滚
+    pub fn import(
+        &self,
+        source: &Path,
+        name: Option<&str>,
+        source_lang: Option<&str>,
+        target_lang: Option<&str>,
+        on_progress: &mut dyn FnMut(usize, usize) -> bool,
+    ) -> Result<OfflineDictionary, String> {
+        let format = OfflineDictionaryFormat::from_path(source)
+            .ok_or_else(|| format!("不支持的词典格式: {}", source.display()))?;
+
+        let mut conn =
+            Connection::open(&self.path).map_err(|e| format!("打开词典库失败: {}", e))?;
+        let tx = conn
+            .transaction()
+            .map_err(|e| format!("开始导入事务失败: {}", e))?;
+
+        let priority: i64 = tx
+            .query_row("SELECT COALESCE(MAX(priority) + 1, 0) FROM dictionaries", [], |row| {
+                row.get(0)
+            })
+            .map_err(|e| format!("读取词典列表失败: {}", e))?;
+        tx.execute(
+            "INSERT INTO dictionaries (name, format, source_path, priority) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                name.unwrap_or_default(),
+                format.as_str(),
+                source.to_string_lossy(),
+                priority
+            ],
+        )
+        .map_err(|e| format!("创建词典记录失败: {}", e))?;
+        let dictionary_id = tx.last_insert_rowid();
+
+        let (title, count, detected_source, detected_target) = {
+            let insert = tx
+                .prepare(
+                    "INSERT INTO dictionary_entries
+                        (dictionary_id, headword, headword_key, definition, content_type)
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                )
+                .map_err(|e| format!("准备导入语句失败: {}", e))?;
+            let mut sink = EntrySink {
+                insert,
+                dictionary_id,
+                total: 0,
+                count: 0,
+                on_progress,
+                source_lang: None,
+                target_lang: None,
+            };
+            let title = match format {
+                OfflineDictionaryFormat::StarDict => stardict::import(source, &mut sink)?,
+                OfflineDictionaryFormat::MDict => mdict::import(source, &mut sink)?,
+                OfflineDictionaryFormat::Dsl => dsl::import(source, &mut sink)?,
+            };
+            (sink.on_progress)(sink.count, sink.count);
+            (title, sink.count, sink.source_lang, sink.target_lang)
+        };
+
+        if count == 0 {
+            return Err("词典中没有可导入的词条".to_string());
+        }
+
+        let name = name
+            .map(str::to_string)
+            .filter(|name| !name.trim().is_empty())
+            .or(Some(title).filter(|title| !title.trim().is_empty()))
+            .unwrap_or_else(|| {
+                source
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().to_string())
+                    .unwrap_or_default()
+            });
+        let source_lang = normalize_language(source_lang).or(detected_source);
+        let target_lang = normalize_language(target_lang).or(detected_target);
+        tx.execute(
+            "UPDATE dictionaries
+             SET name = ?1, entry_count = ?2, source_lang = ?3, target_lang = ?4
+             WHERE id = ?5",
+            params![name, count as i64, source_lang, target_lang, dictionary_id],
+        )
+        .map_err(|e| format!("更新词典记录失败: {}", e))?;
+        tx.commit().map_err(|e| format!("提交导入失败: {}", e))?;
+
+        println!("已导入离线词典 {} ({} 条)", name, count);
+        self.get(dictionary_id)
+            .map_err(|e| format!("读取词典失败: {}", e))?
+            .ok_or_else(|| "导入的词典不存在".to_string())
+    }
+
+    fn get(&self, id: i64) -> rusqlite::Result<Option<OfflineDictionary>> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT id, name, format, source_path, entry_count, priority, enabled, created_at,
+                    source_lang, target_lang
+             FROM dictionaries WHERE id = ?1",
+            params![id],
+            dictionary_from_row,
+        )
+        .optional()
+    }
+
+    /// 按优先级列出全部词典
+    pub fn list(&self) -> rusqlite::Result<Vec<OfflineDictionary>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, name, format, source_path, entry_count, priority, enabled, created_at,
+                    source_lang, target_lang
+             FROM dictionaries ORDER BY priority ASC, id ASC",
+        )?;
+        let rows = stmt.query_map([], dictionary_from_row)?;
+        rows.collect()
+    }
+
+    /// 按给定顺序重排优先级，未列出的词典排在后面
+    pub fn reorder(&self, ids: &[i64]) -> rusqlite::Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let existing: Vec<i64> = {
+            let mut stmt = tx.prepare("SELECT id FROM dictionaries ORDER BY priority ASC, id ASC")?;
+            let rows = stmt.query_map([], |row| row.get(0))?;
+            rows.collect::<rusqlite::Result<_>>()?
+        };
+        let ordered = ids
+            .iter()
+            .copied()
+            .filter(|id| existing.contains(id))
+            .chain(existing.iter().copied().filter(|id| !ids.contains(id)));
+        for (priority, id) in ordered.enumerate() {
+            tx.execute(
+                "UPDATE dictionaries SET priority = ?1 WHERE id = ?2",
+                params![priority as i64, id],
+            )?;
+        }
+        tx.commit()
+    }
+
+    pub fn set_enabled(&self, id: i64, enabled: bool) -> rusqlite::Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE dictionaries SET enabled = ?1 WHERE id = ?2",
+            params![enabled, id],
+        )?;
+        Ok(())
+    }
+
+    /// 设置词典的语言，空值表示未知
+    pub fn set_languages(
+        &self,
+        id: i64,
+        source_lang: Option<&str>,
+        target_lang: Option<&str>,
+    ) -> rusqlite::Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE dictionaries SET source_lang = ?1, target_lang = ?2 WHERE id = ?3",
+            params![
+                normalize_language(source_lang),
+                normalize_language(target_lang),
+                id
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn remove(&self, id: i64) -> rusqlite::Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "DELETE FROM dictionary_entries WHERE dictionary_id = ?1",
+            params![id],
+        )?;
+        conn.execute("DELETE FROM dictionaries WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    /// 查询词条：先精确匹配，再前缀匹配，不足 limit 条时用编辑距离做模糊匹配
+    pub fn lookup(&self, query: &str, limit: usize) -> rusqlite::Result<Vec<OfflineDictionaryMatch>> {
+        let key = normalize_key(query);
+        if key.is_empty() || limit == 0 {
+            return Ok(Vec::new());
+        }
+
+        let conn = self.conn.lock().unwrap();
+        let mut matches = query_matches(
+            &conn,
+            "e.headword_key = ?1",
+            params![key],
+            MatchKind::Exact,
+            limit,
+        )?;
+
+        if matches.len() < limit {
+            let upper = format!("{}\u{10ffff}", key);
+            let prefix = query_matches(
+                &conn,
+                "e.headword_key > ?1 AND e.headword_key < ?2",
+                params![key, upper],
+                MatchKind::Prefix,
+                limit - matches.len(),
+            )?;
+            matches.extend(prefix);
+        }
+
+        if matches.len() < limit {
+            for candidate in fuzzy_keys(&conn, &key, limit - matches.len())? {
+                let remaining = limit - matches.len();
+                if remaining == 0 {
+                    break;
+                }
+                let fuzzy = query_matches(
+                    &conn,
+                    "e.headword_key = ?1",
+                    params![candidate],
+                    MatchKind::Fuzzy,
+                    remaining,
+                )?;
+                matches.extend(fuzzy);
+            }
+        }
+
+        resolve_links(&conn, &mut matches)?;
+        Ok(matches)
+    }
+
+    /// 精确匹配的词条转换为词典模式的结果，按词典优先级排列；
+    /// 只查语言方向与 from_lang → to_lang 一致的词典，from_lang 为空或 auto 时不限词条语言
+    pub fn lookup_entry(
+        &self,
+        text: &str,
+        from_lang: &str,
+        to_lang: &str,
+    ) -> rusqlite::Result<Option<DictionaryEntry>> {
+        let key = normalize_key(text);
+        if key.is_empty() {
+            return Ok(None);
+        }
+        let matches = {
+            let conn = self.conn.lock().unwrap();
+            let ids = {
+                let mut stmt = conn.prepare(
+                    "SELECT id, source_lang, target_lang FROM dictionaries WHERE enabled = 1",
+                )?;
+                let rows = stmt.query_map([], |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, Option<String>>(1)?,
+                        row.get::<_, Option<String>>(2)?,
+                    ))
+                })?;
+                let mut ids = Vec::new();
+                for row in rows {
+                    let (id, source_lang, target_lang) = row?;
+                    if covers(source_lang.as_deref(), from_lang, true)
+                        && covers(target_lang.as_deref(), to_lang, false)
+                    {
+                        ids.push(id.to_string());
+                    }
+                }
+                ids
+            };
+            if ids.is_empty() {
+                return Ok(None);
+            }
+            let mut matches = query_matches(
+                &conn,
+                &format!("e.headword_key = ?1 AND d.id IN ({})", ids.join(", ")),
+                params![key],
+                MatchKind::Exact,
+                10,
+            )?;
+            resolve_links(&conn, &mut matches)?;
+            matches
+        };
+        let Some(first) = matches.first() else {
+            return Ok(None);
+        };
+
+        Ok(Some(DictionaryEntry {
+            headword: first.headword.clone(),
+            senses: matches
+                .iter()
+                .map(|m| DictionarySense {
+                    definition: match m.content_type {
+                        DefinitionFormat::Html => html_to_text(&m.definition),
+                        DefinitionFormat::Text => m.definition.clone(),
+                    },
+                    source: m.dictionary_name.clone(),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        }))
+    }
+}
+
+fn dictionary_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<OfflineDictionary> {
+    let format: String = row.get(2)?;
+    Ok(OfflineDictionary {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        format: OfflineDictionaryFormat::parse(&format),
+        source_path: row.get(3)?,
+        entry_count: row.get(4)?,
+        source_lang: row.get(8)?,
+        target_lang: row.get(9)?,
+        priority: row.get(5)?,
+        enabled: row.get(6)?,
+        created_at: row.get(7)?,
+    })
+}
+
+fn normalize_language(code: Option<&str>) -> Option<String> {
+    code.map(str::trim)
+        .filter(|code| !code.is_empty() && *code != "auto")
+        .map(languages::normalize)
+}
+
+/// 词典语言是否覆盖请求的语言；请求为空或 auto 时，allow_any 决定是否不限语言，未知语言的词典不算覆盖
+fn covers(dictionary: Option<&str>, requested: &str, allow_any: bool) -> bool {
+    let requested = requested.trim();
+    if requested.is_empty() || requested == "auto" {
+        return allow_any;
+    }
+    dictionary.is_some_and(|code| languages::same_language(code, requested))
+}
+
+fn query_matches(
+    conn: &Connection,
+    condition: &str,
+    params: &[&dyn rusqlite::ToSql],
+    kind: MatchKind,
+    limit: usize,
+) -> rusqlite::Result<Vec<OfflineDictionaryMatch>> {
+    let sql = format!(
+        "SELECT d.id, d.name, e.headword, e.definition, e.content_type
+         FROM dictionary_entries e JOIN dictionaries d ON d.id = e.dictionary_id
+         WHERE d.enabled = 1 AND {}
+         ORDER BY e.headword_key ASC, d.priority ASC, e.id ASC
+         LIMIT {}",
+        condition, limit
+    );
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map(params, |row| {
+        let content_type: String = row.get(4)?;
+        Ok(OfflineDictionaryMatch {
+            dictionary_id: row.get(0)?,
+            dictionary_name: row.get(1)?,
+            headword: row.get(2)?,
+            definition: row.get(3)?,
+            content_type: DefinitionFormat::parse(&content_type),
+            match_kind: kind,
+        })
+    })?;
+    rows.collect()
+}
+
+/// MDict 用 @@@LINK=word 表示跳转到另一个词条，查询时在同一本词典内解析
+fn resolve_links(
+    conn: &Connection,
+    matches: &mut [OfflineDictionaryMatch],
+) -> rusqlite::Result<()> {
+    for m in matches.iter_mut() {
+        let Some(target) = m.definition.trim().strip_prefix("@@@LINK=") else {
+            continue;
+        };
+        let target = normalize_key(target);
+        let linked: Option<String> = conn
+            .query_row(
+                "SELECT definition FROM dictionary_entries
+                 WHERE dictionary_id = ?1 AND headword_key = ?2 AND definition NOT LIKE '@@@LINK=%'
+                 LIMIT 1",
+                params![m.dictionary_id, target],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if let Some(definition) = linked {
+            m.definition = definition;
+        }
+    }
+    Ok(())
+}
+
+/// 在首字母相同的词中找编辑距离最近的若干个
+fn fuzzy_keys(conn: &Connection, key: &str, limit: usize) -> rusqlite::Result<Vec<String>> {
+    let Some(first) = key.chars().next() else {
+        return Ok(Vec::new());
+    };
+    let key_chars: Vec<char> = key.chars().collect();
+    let max_distance = (key_chars.len() / 4).clamp(1, 2);
+
+    let lower = first.to_string();
+    let upper = format!("{}\u{10ffff}", first);
+    let mut stmt = conn.prepare(
+        "SELECT DISTINCT e.headword_key
+         FROM dictionary_entries e JOIN dictionaries d ON d.id = e.dictionary_id
+         WHERE d.enabled = 1 AND e.headword_key >= ?1 AND e.headword_key < ?2
+         LIMIT ?3",
+    )?;
+    let rows = stmt.query_map(
+        params![lower, upper, MAX_FUZZY_CANDIDATES as i64],
+        |row| row.get::<_, String>(0),
+    )?;
+
+    let mut scored: Vec<(usize, String)> = Vec::new();
+    for candidate in rows {
+        let candidate = candidate?;
+        if candidate == key || candidate.starts_with(key) {
+            continue;
+        }
+        let chars: Vec<char> = candidate.chars().collect();
+        if chars.len().abs_diff(key_chars.len()) > max_distance {
+            continue;
+        }
+        let distance = edit_distance(&key_chars, &chars);
+        if distance <= max_distance {
+            scored.push((distance, candidate));
+        }
+    }
+    scored.sort();
+    Ok(scored.into_iter().take(limit).map(|(_, key)| key).collect())
+}
+
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != cb);
+            current[j + 1] = (previous[j] + cost)
+                .min(previous[j + 1] + 1)
+                .min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+/// 索引键：小写、合并空白
+pub(crate) fn normalize_key(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// 把 HTML 释义转成纯文本，用于词典模式的简要展示
+pub(crate) fn html_to_text(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('>') else {
+            rest = &rest[start..];
+            break;
+        };
+        let tag = rest[start + 1..start + end].trim_start_matches('/').to_ascii_lowercase();
+        let name = tag.split(|c: char| c.is_whitespace() || c == '/').next().unwrap_or("");
+        if matches!(name, "br" | "p" | "div" | "li" | "tr" | "h1" | "h2" | "h3" | "h4") {
+            text.push('\n');
+        }
+        rest = &rest[start + end + 1..];
+    }
+    text.push_str(rest);
+
+    let text = text
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&");
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// 按 BOM 或声明的编码把字节解码为文本，无法判断时优先 UTF-8，其次 UTF-16LE
+pub(crate) fn decode_text(bytes: &[u8], label: Option<&str>) -> String {
+    if let Some((encoding, bom_len)) = encoding_rs::Encoding::for_bom(bytes) {
+        return encoding.decode_without_bom_handling(&bytes[bom_len..]).0.into_owned();
+    }
+    if let Some(encoding) = label.and_then(|label| encoding_rs::Encoding::for_label(label.as_bytes())) {
+        return encoding.decode_without_bom_handling(bytes).0.into_owned();
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(_) => encoding_rs::UTF_16LE
+            .decode_without_bom_handling(bytes)
+            .0
+            .into_owned(),
+    }
+}
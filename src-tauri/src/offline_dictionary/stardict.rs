@@ -0,0 +1,256 @@
+// StarDict dictionaries
+// Reads .ifo metadata, the .idx(.gz) word index, optional .syn synonyms and .dict(.dz) data
+
+use super::{DefinitionFormat, EntrySink};
+use flate2::read::GzDecoder;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+struct IndexEntry {
+    word: String,
+    offset: u64,
+    size: u32,
+}
+
+/// 导入 StarDict 词典，返回词典名称
+pub fn import(path: &Path, sink: &mut EntrySink) -> Result<String, String> {
+    let base = base_path(path);
+    let ifo = fs::read_to_string(base.with_extension("ifo"))
+        .map_err(|e| format!("读取 .ifo 文件失败: {}", e))?;
+    let info = parse_ifo(&ifo)?;
+
+    let offset_bits: u32 = info
+        .get("idxoffsetbits")
+        .and_then(|bits| bits.parse().ok())
+        .unwrap_or(32);
+    let index_data = read_maybe_compressed(&base, "idx", "idx.gz")?;
+    let index = parse_index(&index_data, offset_bits)?;
+    let data = read_maybe_compressed(&base, "dict", "dict.dz")?;
+    let synonyms = match read_maybe_compressed(&base, "syn", "syn.gz") {
+        Ok(syn) => parse_synonyms(&syn),
+        Err(_) => Vec::new(),
+    };
+
+    let type_sequence = info.get("sametypesequence").map(String::as_str);
+    sink.set_total(index.len() + synonyms.len());
+
+    // 只有存在同义词时才需要保留释义供 .syn 引用
+    let keep_definitions = !synonyms.is_empty();
+    let mut definitions: Vec<Option<(String, DefinitionFormat)>> = Vec::new();
+    for entry in &index {
+        let start = entry.offset as usize;
+        let end = start + entry.size as usize;
+        let definition = data
+            .get(start..end)
+            .map(|raw| decode_definition(raw, type_sequence));
+        if let Some((text, content_type)) = &definition {
+            sink.push(&entry.word, text, *content_type)?;
+        }
+        if keep_definitions {
+            definitions.push(definition);
+        }
+    }
+
+    for (word, target) in synonyms {
+        if let Some(Some((definition, content_type))) = definitions.get(target) {
+            sink.push(&word, definition, *content_type)?;
+        }
+    }
+
+    Ok(info.get("bookname").cloned().unwrap_or_default())
+}
+
+/// 用户可能选中 .ifo/.idx/.dict 任意一个文件，统一去掉扩展名
+fn base_path(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let lower = name.to_lowercase();
+    for suffix in [".idx.gz", ".dict.dz", ".ifo", ".idx", ".dict"] {
+        if lower.ends_with(suffix) {
+            // 保留一个扩展名位置，便于 with_extension 替换
+            return path.with_file_name(format!("{}.ifo", &name[..name.len() - suffix.len()]));
+        }
+    }
+    path.to_path_buf()
+}
+
+fn read_maybe_compressed(base: &Path, plain: &str, compressed: &str) -> Result<Vec<u8>, String> {
+    let plain_path = base.with_extension(plain);
+    if plain_path.exists() {
+        return fs::read(&plain_path).map_err(|e| format!("读取 {} 失败: {}", plain_path.display(), e));
+    }
+
+    let compressed_path = base.with_extension(compressed);
+    let file = fs::File::open(&compressed_path)
+        .map_err(|e| format!("读取 {} 失败: {}", compressed_path.display(), e))?;
+    // dictzip 与 gzip 兼容，整体解压即可
+    let mut data = Vec::new();
+    GzDecoder::new(file)
+        .read_to_end(&mut data)
+        .map_err(|e| format!("解压 {} 失败: {}", compressed_path.display(), e))?;
+    Ok(data)
+}
+
+fn parse_ifo(content: &str) -> Result<HashMap<String, String>, String> {
+    let mut lines = content.lines();
+    let magic = lines.next().unwrap_or_default().trim_start_matches('\u{feff}');
+    if magic.trim() != "StarDict's dict ifo file" {
+        return Err("不是有效的 StarDict .ifo 文件".to_string());
+    }
+    Ok(lines
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect())
+}
+
+/// .idx：以 \0 结尾的词头 + 偏移量（32 或 64 位大端）+ 长度（32 位大端）
+fn parse_index(data: &[u8], offset_bits: u32) -> Result<Vec<IndexEntry>, String> {
+    let offset_len = if offset_bits == 64 { 8 } else { 4 };
+    let mut entries = Vec::new();
+    let mut pos = 0;
+
+    while pos < data.len() {
+        let end = data[pos..]
+            .iter()
+            .position(|&b| b == 0)
+            .map(|i| pos + i)
+            .ok_or("StarDict 索引文件已损坏")?;
+        let word = String::from_utf8_lossy(&data[pos..end]).to_string();
+        pos = end + 1;
+
+        let numbers = data
+            .get(pos..pos + offset_len + 4)
+            .ok_or("StarDict 索引文件已损坏")?;
+        let offset = if offset_len == 8 {
+            u64::from_be_bytes(numbers[..8].try_into().unwrap())
+        } else {
+            u32::from_be_bytes(numbers[..4].try_into().unwrap()) as u64
+        };
+        let size = u32::from_be_bytes(numbers[offset_len..].try_into().unwrap());
+        pos += offset_len + 4;
+
+        entries.push(IndexEntry { word, offset, size });
+    }
+
+    Ok(entries)
+}
+
+/// .syn：以 \0 结尾的同义词 + 指向 .idx 的序号（32 位大端）
+fn parse_synonyms(data: &[u8]) -> Vec<(String, usize)> {
+    let mut synonyms = Vec::new();
+    let mut pos = 0;
+    while pos < data.len() {
+        let Some(end) = data[pos..].iter().position(|&b| b == 0).map(|i| pos + i) else {
+            break;
+        };
+        let Some(index) = data.get(end + 1..end + 5) else {
+            break;
+        };
+        synonyms.push((
+            String::from_utf8_lossy(&data[pos..end]).to_string(),
+            u32::from_be_bytes(index.try_into().unwrap()) as usize,
+        ));
+        pos = end + 5;
+    }
+    synonyms
+}
+
+/// 按 sametypesequence 或每个字段自带的类型字符拆分数据
+/// 小写类型为 \0 结尾的文本，大写类型为 32 位长度前缀的二进制；h/g/x/k 为标记语言，按 HTML 保存
+fn decode_definition(raw: &[u8], type_sequence: Option<&str>) -> (String, DefinitionFormat) {
+    let mut fields: Vec<(char, String)> = Vec::new();
+    let mut pos = 0;
+
+    match type_sequence {
+        Some(sequence) => {
+            let types: Vec<char> = sequence.chars().collect();
+            for (i, kind) in types.iter().enumerate() {
+                let last = i + 1 == types.len();
+                let Some((value, next)) = read_field(raw, pos, *kind, last) else {
+                    break;
+                };
+                fields.push((*kind, value));
+                pos = next;
+            }
+        }
+        None => {
+            while pos < raw.len() {
+                let kind = raw[pos] as char;
+                let Some((value, next)) = read_field(raw, pos + 1, kind, false) else {
+                    break;
+                };
+                fields.push((kind, value));
+                pos = next;
+            }
+        }
+    }
+
+    let is_markup = |kind: char| matches!(kind, 'h' | 'g' | 'x' | 'k');
+    if fields.iter().any(|(kind, _)| is_markup(*kind)) {
+        let html = fields
+            .iter()
+            .filter(|(kind, _)| kind.is_ascii_lowercase())
+            .map(|(kind, value)| {
+                if is_markup(*kind) {
+                    value.clone()
+                } else {
+                    escape_html(value).replace('\n', "<br>")
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("<br>");
+        (html, DefinitionFormat::Html)
+    } else {
+        let text = fields
+            .iter()
+            .filter(|(kind, _)| kind.is_ascii_lowercase())
+            .map(|(kind, value)| {
+                if matches!(kind, 't' | 'y') {
+                    format!("[{}]", value)
+                } else {
+                    value.clone()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        (text, DefinitionFormat::Text)
+    }
+}
+
+fn read_field(raw: &[u8], pos: usize, kind: char, last: bool) -> Option<(String, usize)> {
+    if pos > raw.len() {
+        return None;
+    }
+    if kind.is_ascii_uppercase() {
+        // 二进制资源（图片、音频），只跳过不保存
+        if last {
+            return Some((String::new(), raw.len()));
+        }
+        let size = u32::from_be_bytes(raw.get(pos..pos + 4)?.try_into().ok()?) as usize;
+        return Some((String::new(), pos + 4 + size));
+    }
+
+    let end = if last {
+        raw.len()
+    } else {
+        raw[pos..]
+            .iter()
+            .position(|&b| b == 0)
+            .map(|i| pos + i)
+            .unwrap_or(raw.len())
+    };
+    let value = String::from_utf8_lossy(&raw[pos..end])
+        .trim_end_matches('\0')
+        .to_string();
+    Some((value, (end + 1).min(raw.len())))
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
@@ -0,0 +1,151 @@
+// Configuration profiles
+// Named AppConfig snapshots (e.g. work and home) stored in config_profiles. The active profile is
+// referenced by user_settings.active_profile_id and is the one get_app_config/save_app_config use.
+
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use serde::Serialize;
+
+/// 迁移时由原有配置生成、以及档案全部丢失时重建的档案名
+pub const DEFAULT_PROFILE_NAME: &str = "默认";
+const ACTIVE_PROFILE_KEY: &str = "active_profile_id";
+const MAX_PROFILE_NAME_CHARS: usize = 40;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigProfile {
+    pub id: i64,
+    pub name: String,
+    pub active: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// 去掉首尾空白并检查长度
+pub fn normalize_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("配置档案名称不能为空".to_string());
+    }
+    if name.chars().count() > MAX_PROFILE_NAME_CHARS {
+        return Err(format!(
+            "配置档案名称过长（最多 {} 个字符）",
+            MAX_PROFILE_NAME_CHARS
+        ));
+    }
+    Ok(name.to_string())
+}
+
+/// 当前档案的 id；指针缺失或指向已删除的档案时改为最早的档案，没有任何档案时新建默认档案
+pub fn active_id(conn: &Connection) -> Result<i64> {
+    let pointed: Option<i64> = conn
+        .query_row(
+            "SELECT p.id FROM config_profiles p
+             JOIN user_settings s ON s.key = ?1 AND s.value = p.id",
+            params![ACTIVE_PROFILE_KEY],
+            |row| row.get(0),
+        )
+        .optional()?;
+    if let Some(id) = pointed {
+        return Ok(id);
+    }
+
+    let first: Option<i64> = conn
+        .query_row("SELECT MIN(id) FROM config_profiles", [], |row| row.get(0))
+        .optional()?
+        .flatten();
+    let id = match first {
+        Some(id) => id,
+        None => create(conn, DEFAULT_PROFILE_NAME, None)?,
+    };
+    activate(conn, id)?;
+    Ok(id)
+}
+
+pub fn list(conn: &Connection) -> Result<Vec<ConfigProfile>> {
+    let active = active_id(conn)?;
+    let mut stmt =
+        conn.prepare("SELECT id, name, created_at, updated_at FROM config_profiles ORDER BY id")?;
+    let rows = stmt.query_map([], |row| {
+        let id: i64 = row.get(0)?;
+        Ok(ConfigProfile {
+            id,
+            name: row.get(1)?,
+            active: id == active,
+            created_at: row.get(2)?,
+            updated_at: row.get(3)?,
+        })
+    })?;
+    rows.collect()
+}
+
+/// 档案不存在时返回 QueryReturnedNoRows
+pub fn get(conn: &Connection, id: i64) -> Result<ConfigProfile> {
+    let active = active_id(conn)?;
+    conn.query_row(
+        "SELECT name, created_at, updated_at FROM config_profiles WHERE id = ?1",
+        params![id],
+        |row| {
+            Ok(ConfigProfile {
+                id,
+                name: row.get(0)?,
+                active: id == active,
+                created_at: row.get(1)?,
+                updated_at: row.get(2)?,
+            })
+        },
+    )
+}
+
+/// 档案中保存的配置 JSON，为空表示使用默认配置；档案不存在时返回 QueryReturnedNoRows
+pub fn load_config(conn: &Connection, id: i64) -> Result<Option<String>> {
+    conn.query_row(
+        "SELECT config FROM config_profiles WHERE id = ?1",
+        params![id],
+        |row| row.get(0),
+    )
+}
+
+pub fn store_config(conn: &Connection, id: i64, config_json: Option<&str>) -> Result<()> {
+    let updated = conn.execute(
+        "UPDATE config_profiles SET config = ?2, updated_at = ?3 WHERE id = ?1",
+        params![id, config_json, Utc::now().to_rfc3339()],
+    )?;
+    if updated == 0 {
+        return Err(rusqlite::Error::QueryReturnedNoRows);
+    }
+    Ok(())
+}
+
+/// 新建档案，名称重复时返回唯一约束错误
+pub fn create(conn: &Connection, name: &str, config_json: Option<&str>) -> Result<i64> {
+    let now = Utc::now().to_rfc3339();
+    conn.execute(
+        "INSERT INTO config_profiles (name, config, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?3)",
+        params![name, config_json, now],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// 复制档案的配置为新档案；源档案不存在时返回 QueryReturnedNoRows
+pub fn duplicate(conn: &Connection, source_id: i64, name: &str) -> Result<i64> {
+    let config_json = load_config(conn, source_id)?;
+    create(conn, name, config_json.as_deref())
+}
+
+pub fn activate(conn: &Connection, id: i64) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO user_settings (key, value) VALUES (?1, ?2)",
+        params![ACTIVE_PROFILE_KEY, id.to_string()],
+    )?;
+    Ok(())
+}
+
+/// 删除档案；档案不存在时返回 QueryReturnedNoRows
+pub fn delete(conn: &Connection, id: i64) -> Result<()> {
+    let deleted = conn.execute("DELETE FROM config_profiles WHERE id = ?1", params![id])?;
+    if deleted == 0 {
+        return Err(rusqlite::Error::QueryReturnedNoRows);
+    }
+    Ok(())
+}
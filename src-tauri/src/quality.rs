@@ -0,0 +1,596 @@
+// Translation quality estimation
+// Back-translates the output and combines lexical similarity with heuristic checks (target
+// language, untranslated passages, numbers and named entities) into a score with warnings
+
+use crate::identifiers;
+use crate::languages;
+use crate::token_limits::calculate_text_response_tokens;
+use crate::translation::{TranslationBackend, TranslationRequest};
+use crate::database::IdentifierNormalization;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// 质量问题类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QualityIssue {
+    LowSimilarity,
+    WrongLanguage,
+    Untranslated,
+    NumberMismatch,
+    EntityMismatch,
+    BackTranslationFailed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QualityWarning {
+    pub issue: QualityIssue,
+    pub message: String,
+}
+
+/// 译文质量评估结果，score 为 0-100
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QualityReport {
+    pub score: u8,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub back_translation: Option<String>,
+    /// 回译与原文的词面相似度（0-1），回译失败时为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub similarity: Option<f64>,
+    /// 根据文字和常用词推测的译文语言
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detected_language: Option<String>,
+    pub warnings: Vec<QualityWarning>,
+}
+
+/// 低于这个相似度时提示回译偏差较大
+const LOW_SIMILARITY: f64 = 0.3;
+/// 相似度达到这个值即视为满分，意译的回译很难与原文逐字一致
+const FULL_SIMILARITY: f64 = 0.6;
+
+/// 回译译文并评估质量；回译失败时只做本地检查
+pub async fn estimate(
+    backend: &TranslationBackend,
+    original: &str,
+    translated: &str,
+    from_lang: &str,
+    to_lang: &str,
+) -> QualityReport {
+    let source_lang = if from_lang.is_empty() || from_lang == "auto" {
+        detect_language(original)
+    } else {
+        Some(from_lang.to_string())
+    };
+
+    let mut warnings = Vec::new();
+    let back_translation = match &source_lang {
+        Some(source_lang) => {
+            let request = TranslationRequest {
+                text: translated.to_string(),
+                from_lang: to_lang.to_string(),
+                to_lang: source_lang.clone(),
+                max_tokens: calculate_text_response_tokens(translated, Some(&backend.token_limits)),
+                identifier_normalization: IdentifierNormalization::Off,
+            };
+            match backend.translator().translate(request).await {
+                Ok(response) => Some(response.translated_text),
+                Err(err) => {
+                    warnings.push(QualityWarning {
+                        issue: QualityIssue::BackTranslationFailed,
+                        message: format!("回译失败: {}", err),
+                    });
+                    None
+                }
+            }
+        }
+        None => {
+            warnings.push(QualityWarning {
+                issue: QualityIssue::BackTranslationFailed,
+                message: "无法判断原文语言，跳过回译".to_string(),
+            });
+            None
+        }
+    };
+
+    let mut report = analyze(original, translated, to_lang, back_translation.as_deref());
+    warnings.append(&mut report.warnings);
+    report.warnings = warnings;
+    report.back_translation = back_translation;
+    report
+}
+
+/// 本地质量检查，不发网络请求
+pub fn analyze(
+    original: &str,
+    translated: &str,
+    to_lang: &str,
+    back_translation: Option<&str>,
+) -> QualityReport {
+    let mut warnings = Vec::new();
+    let mut score = 100.0;
+
+    let similarity = back_translation.map(|back| similarity(original, back));
+    match similarity {
+        Some(value) => {
+            score -= 40.0 * (1.0 - (value / FULL_SIMILARITY).min(1.0));
+            if value < LOW_SIMILARITY {
+                warnings.push(QualityWarning {
+                    issue: QualityIssue::LowSimilarity,
+                    message: format!("回译与原文差异较大（相似度 {:.0}%）", value * 100.0),
+                });
+            }
+        }
+        // 没有回译时无法确认语义，保守扣分
+        None => score -= 20.0,
+    }
+
+    let detected_language = detect_language_for(translated, expected_script(to_lang));
+    if let Some(detected) = &detected_language {
+        if !to_lang.is_empty() && to_lang != "auto" && !languages::same_language(detected, to_lang)
+        {
+            score -= 40.0;
+            warnings.push(QualityWarning {
+                issue: QualityIssue::WrongLanguage,
+                message: format!("译文看起来是 {}，而不是目标语言 {}", detected, to_lang),
+            });
+        }
+    }
+
+    let untranslated = untranslated_passages(original, translated, to_lang);
+    score -= (untranslated.len() as f64 * 10.0).min(30.0);
+    for passage in untranslated {
+        warnings.push(QualityWarning {
+            issue: QualityIssue::Untranslated,
+            message: format!("疑似未翻译: {}", passage),
+        });
+    }
+
+    let (missing_numbers, extra_numbers) = number_mismatches(original, translated);
+    score -= ((missing_numbers.len() + extra_numbers.len()) as f64 * 10.0).min(30.0);
+    if !missing_numbers.is_empty() {
+        warnings.push(QualityWarning {
+            issue: QualityIssue::NumberMismatch,
+            message: format!("译文缺少数字: {}", missing_numbers.join(", ")),
+        });
+    }
+    if !extra_numbers.is_empty() {
+        warnings.push(QualityWarning {
+            issue: QualityIssue::NumberMismatch,
+            message: format!("译文多出数字: {}", extra_numbers.join(", ")),
+        });
+    }
+
+    let missing_entities = entity_mismatches(original, translated, back_translation);
+    score -= (missing_entities.len() as f64 * 5.0).min(20.0);
+    if !missing_entities.is_empty() {
+        warnings.push(QualityWarning {
+            issue: QualityIssue::EntityMismatch,
+            message: format!("译文中找不到: {}", missing_entities.join(", ")),
+        });
+    }
+
+    QualityReport {
+        score: score.clamp(0.0, 100.0).round() as u8,
+        back_translation: None,
+        similarity,
+        detected_language,
+        warnings,
+    }
+}
+
+/// 字符三元组的余弦相似度，忽略大小写、空白和标点，对中日韩文字同样适用
+pub fn similarity(a: &str, b: &str) -> f64 {
+    let grams_a = char_ngrams(a, 3);
+    let grams_b = char_ngrams(b, 3);
+    if grams_a.is_empty() || grams_b.is_empty() {
+        return if normalize_for_compare(a) == normalize_for_compare(b) {
+            1.0
+        } else {
+            0.0
+        };
+    }
+
+    let dot: f64 = grams_a
+        .iter()
+        .filter_map(|(gram, count)| grams_b.get(gram).map(|other| (*count * *other) as f64))
+        .sum();
+    let norm = |grams: &HashMap<String, usize>| {
+        grams.values().map(|count| (*count * *count) as f64).sum::<f64>().sqrt()
+    };
+    dot / (norm(&grams_a) * norm(&grams_b))
+}
+
+fn normalize_for_compare(text: &str) -> String {
+    text.chars()
+        .filter(|ch| ch.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn char_ngrams(text: &str, n: usize) -> HashMap<String, usize> {
+    let chars: Vec<char> = normalize_for_compare(text).chars().collect();
+    let mut grams = HashMap::new();
+    if chars.len() < n {
+        return grams;
+    }
+    for window in chars.windows(n) {
+        *grams.entry(window.iter().collect()).or_insert(0) += 1;
+    }
+    grams
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Script {
+    Latin,
+    Han,
+    Kana,
+    Hangul,
+    Cyrillic,
+    Arabic,
+    Greek,
+    Hebrew,
+    Thai,
+    Devanagari,
+}
+
+fn script_of(ch: char) -> Option<Script> {
+    let script = match ch as u32 {
+        0x41..=0x5A | 0x61..=0x7A | 0xC0..=0x24F => Script::Latin,
+        0x370..=0x3FF => Script::Greek,
+        0x400..=0x52F => Script::Cyrillic,
+        0x590..=0x5FF => Script::Hebrew,
+        0x600..=0x6FF | 0x750..=0x77F => Script::Arabic,
+        0x900..=0x97F => Script::Devanagari,
+        0xE00..=0xE7F => Script::Thai,
+        0x3040..=0x30FF | 0x31F0..=0x31FF | 0xFF66..=0xFF9F => Script::Kana,
+        0x1100..=0x11FF | 0x3130..=0x318F | 0xAC00..=0xD7AF => Script::Hangul,
+        0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xF900..=0xFAFF => Script::Han,
+        _ => return None,
+    };
+    Some(script)
+}
+
+fn script_counts(text: &str) -> HashMap<Script, usize> {
+    let mut counts = HashMap::new();
+    for script in text.chars().filter_map(script_of) {
+        *counts.entry(script).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// expected 为目标语言的文字；目标是日文时汉字占多数的句子（如「年度財務報告書の監査結果」）只要出现假名就算日文
+fn dominant_script(text: &str, expected: Option<Script>) -> Option<Script> {
+    let counts = script_counts(text);
+    // 日文通常夹杂汉字，只要假名占一定比例就认为是日文
+    let kana = counts.get(&Script::Kana).copied().unwrap_or(0);
+    let han = counts.get(&Script::Han).copied().unwrap_or(0);
+    if kana > 0 && (expected == Some(Script::Kana) || kana * 5 >= kana + han) {
+        return Some(Script::Kana);
+    }
+    counts
+        .into_iter()
+        .max_by_key(|(_, count)| *count)
+        .map(|(script, _)| script)
+}
+
+/// 各拉丁文字语言的高频词，用于区分英法德西葡意
+const LATIN_STOPWORDS: &[(&str, &[&str])] = &[
+    ("en", &["the", "and", "of", "to", "is", "in", "that", "it", "for", "with", "you", "this", "are", "be", "on", "not"]),
+    ("fr", &["le", "la", "les", "et", "des", "est", "une", "du", "que", "pour", "dans", "pas", "vous", "sur", "avec", "ce"]),
+    ("de", &["der", "die", "das", "und", "ist", "nicht", "ein", "eine", "zu", "mit", "sie", "den", "von", "auf", "für", "ich"]),
+    ("es", &["el", "los", "las", "y", "es", "una", "que", "por", "para", "con", "del", "se", "no", "su", "como", "está"]),
+    ("pt", &["o", "os", "as", "e", "é", "um", "uma", "que", "para", "com", "não", "do", "da", "em", "se", "você"]),
+    ("it", &["il", "lo", "gli", "e", "è", "un", "una", "che", "per", "con", "non", "di", "del", "della", "sono", "questo"]),
+];
+
+/// 根据文字系统和拉丁文字常用词推测语言，返回与前端一致的语言代码；无法判断时为空
+pub fn detect_language(text: &str) -> Option<String> {
+    detect_language_for(text, None)
+}
+
+fn detect_language_for(text: &str, expected: Option<Script>) -> Option<String> {
+    let language = match dominant_script(text, expected)? {
+        Script::Han => "zh-CN",
+        Script::Kana => "ja",
+        Script::Hangul => "ko",
+        Script::Cyrillic => "ru",
+        Script::Arabic => "ar",
+        Script::Greek => "el",
+        Script::Hebrew => "he",
+        Script::Thai => "th",
+        Script::Devanagari => "hi",
+        Script::Latin => return detect_latin_language(text),
+    };
+    Some(language.to_string())
+}
+
+fn detect_latin_language(text: &str) -> Option<String> {
+    let words: Vec<String> = text
+        .split(|ch: char| !ch.is_alphabetic())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    // 太短的文本（如单个术语、代码）无法可靠判断
+    if words.len() < 4 {
+        return None;
+    }
+
+    LATIN_STOPWORDS
+        .iter()
+        .map(|(language, stopwords)| {
+            let hits = words
+                .iter()
+                .filter(|word| stopwords.contains(&word.as_str()))
+                .count();
+            (*language, hits)
+        })
+        .filter(|(_, hits)| *hits >= 2)
+        .max_by_key(|(_, hits)| *hits)
+        .map(|(language, _)| language.to_string())
+}
+
+fn expected_script(to_lang: &str) -> Option<Script> {
+    let lang = to_lang.split(['-', '_']).next().unwrap_or_default().to_ascii_lowercase();
+    let script = match lang.as_str() {
+        "zh" => Script::Han,
+        "ja" => Script::Kana,
+        "ko" => Script::Hangul,
+        "ru" | "uk" | "bg" | "sr" | "be" | "kk" | "mn" => Script::Cyrillic,
+        "ar" | "fa" | "ur" => Script::Arabic,
+        "el" => Script::Greek,
+        "he" => Script::Hebrew,
+        "th" => Script::Thai,
+        "hi" | "mr" | "ne" => Script::Devanagari,
+        "" | "auto" => return None,
+        _ => Script::Latin,
+    };
+    Some(script)
+}
+
+/// 按句子和换行切分
+fn sentences(text: &str) -> Vec<&str> {
+    text.split(['\n', '.', '!', '?', '。', '！', '？', ';', '；'])
+        .map(str::trim)
+        .filter(|sentence| !sentence.is_empty())
+        .collect()
+}
+
+/// 查找译文中原样保留的原文段落；代码、链接等本应保留的内容不算
+fn untranslated_passages(original: &str, translated: &str, to_lang: &str) -> Vec<String> {
+    let source_script = dominant_script(original, None);
+    let target_script = expected_script(to_lang);
+    let original_lower = original.to_lowercase();
+    let mut passages = Vec::new();
+
+    for sentence in sentences(translated) {
+        let words: Vec<&str> = sentence.split_whitespace().collect();
+        if words.iter().all(|word| is_preserved_token(word)) {
+            continue;
+        }
+
+        // 目标语言与原文文字不同时，残留的原文文字一定是漏译
+        let cross_script = match (source_script, target_script) {
+            (Some(source), Some(target)) if source != target => {
+                let counts = script_counts(sentence);
+                let source_count = counts.get(&source).copied().unwrap_or(0);
+                let total: usize = counts.values().sum();
+                let mostly_source = source_count >= 4 && source_count * 2 > total;
+                // 日文本身使用汉字，只有不含假名且与原文相同的汉字句子才算漏译
+                if target == Script::Kana && source == Script::Han {
+                    mostly_source
+                        && !counts.contains_key(&Script::Kana)
+                        && original.contains(sentence)
+                } else {
+                    mostly_source
+                }
+            }
+            _ => false,
+        };
+        // 同文字语言之间只检查较长的原样句子，避免把专有名词当成漏译
+        let copied = words.len() >= 4
+            && sentence.chars().count() >= 20
+            && original_lower.contains(&sentence.to_lowercase());
+
+        if cross_script || copied {
+            passages.push(truncate(sentence, 40));
+        }
+    }
+    passages
+}
+
+fn is_preserved_token(word: &str) -> bool {
+    let word = word.trim_matches(|ch: char| !ch.is_alphanumeric() && ch != '_');
+    word.is_empty()
+        || word.contains("://")
+        || word.contains('@')
+        || word.chars().all(|ch| !ch.is_alphabetic())
+        || identifiers::split_identifier(word).is_some()
+        || is_acronym(word)
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        text.to_string()
+    } else {
+        format!("{}…", text.chars().take(max_chars).collect::<String>())
+    }
+}
+
+/// 提取数字并规范化（全角转半角，去掉千分位和小数点），返回 (译文缺少的, 译文多出的)
+/// 只比较两位以上的数字，个位数常被译成单词
+fn number_mismatches(original: &str, translated: &str) -> (Vec<String>, Vec<String>) {
+    let source = extract_numbers(original);
+    let mut target = extract_numbers(translated);
+
+    let mut missing = Vec::new();
+    for (normalized, display) in source {
+        match target.iter().position(|(other, _)| *other == normalized) {
+            Some(index) => {
+                target.remove(index);
+            }
+            None => missing.push(display),
+        }
+    }
+    let extra = target.into_iter().map(|(_, display)| display).collect();
+    (missing, extra)
+}
+
+fn extract_numbers(text: &str) -> Vec<(String, String)> {
+    let chars: Vec<char> = text.chars().map(to_halfwidth_digit).collect();
+    let mut numbers = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        if !chars[i].is_ascii_digit() {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < chars.len()
+            && (chars[i].is_ascii_digit()
+                || (matches!(chars[i], '.' | ',')
+                    && chars.get(i + 1).is_some_and(|next| next.is_ascii_digit()))
+                || (is_group_space(chars[i]) && is_digit_group(&chars[i + 1..])))
+        {
+            i += 1;
+        }
+        let display: String = chars[start..i].iter().collect();
+        let normalized: String = display.chars().filter(char::is_ascii_digit).collect();
+        if normalized.len() >= 2 {
+            numbers.push((normalized, display));
+        }
+    }
+    numbers
+}
+
+/// 法语等语言用空格作千分位分隔符
+fn is_group_space(ch: char) -> bool {
+    matches!(ch, ' ' | '\u{a0}' | '\u{202f}')
+}
+
+fn is_digit_group(rest: &[char]) -> bool {
+    rest.len() >= 3
+        && rest[..3].iter().all(char::is_ascii_digit)
+        && !rest.get(3).is_some_and(char::is_ascii_digit)
+}
+
+fn to_halfwidth_digit(ch: char) -> char {
+    match ch {
+        '０'..='９' => char::from_u32(ch as u32 - '０' as u32 + '0' as u32).unwrap_or(ch),
+        '．' => '.',
+        '，' => ',',
+        _ => ch,
+    }
+}
+
+/// 原文中的链接、邮箱、缩写、代码标识符和专有名词应在译文或回译中出现
+fn entity_mismatches(
+    original: &str,
+    translated: &str,
+    back_translation: Option<&str>,
+) -> Vec<String> {
+    let mut missing = Vec::new();
+    let mut seen = HashSet::new();
+
+    for sentence in sentences(original) {
+        for (index, word) in sentence.split_whitespace().enumerate() {
+            let token = word.trim_matches(|ch: char| {
+                !ch.is_alphanumeric() && !matches!(ch, '_' | '/' | ':' | '@' | '.' | '-')
+            });
+            let token = token.trim_end_matches(['.', ':', '-']);
+            if token.is_empty() || !seen.insert(token.to_string()) {
+                continue;
+            }
+
+            let must_keep = token.contains("://")
+                || (token.contains('@') && token.contains('.'))
+                || is_acronym(token)
+                || identifiers::split_identifier(token).is_some();
+            // 句首以外的大写单词视为专有名词，可能被音译，因此也接受在回译中出现
+            let proper_noun = !must_keep && index > 0 && is_capitalized(token);
+
+            let found = translated.contains(token)
+                || (proper_noun && back_translation.is_some_and(|back| back.contains(token)));
+            if (must_keep || proper_noun) && !found {
+                missing.push(token.to_string());
+            }
+        }
+    }
+    missing
+}
+
+fn is_acronym(word: &str) -> bool {
+    let letters = word.chars().filter(|ch| ch.is_alphabetic()).count();
+    letters >= 2
+        && word
+            .chars()
+            .all(|ch| ch.is_ascii_uppercase() || ch.is_ascii_digit())
+}
+
+fn is_capitalized(word: &str) -> bool {
+    let mut chars = word.chars();
+    chars.next().is_some_and(|ch| ch.is_ascii_uppercase())
+        && chars.all(|ch| ch.is_ascii_lowercase())
+        && word.len() >= 3
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kanji_in_japanese_translation_is_not_untranslated() {
+        let passages = untranslated_passages(
+            "我们明天在东京开会，讨论新产品的发布计划。",
+            "私たちは明日東京で会議を開き、新製品の発売計画について話し合います。",
+            "ja",
+        );
+        assert!(passages.is_empty(), "{:?}", passages);
+
+        // 汉字较多的日文句子只要带假名就不算漏译
+        let passages =
+            untranslated_passages("年度财务报告审计结果", "年度財務報告書の監査結果", "ja");
+        assert!(passages.is_empty(), "{:?}", passages);
+    }
+
+    #[test]
+    fn kanji_heavy_japanese_translation_is_japanese() {
+        let report = analyze(
+            "年度财务报告审计结果",
+            "年度財務報告書の監査結果",
+            "ja",
+            Some("年度财务报告审计结果"),
+        );
+        assert_eq!(report.detected_language.as_deref(), Some("ja"));
+        assert!(report.warnings.is_empty(), "{:?}", report.warnings);
+
+        // 不知道目标语言时仍按假名比例判断
+        assert_eq!(
+            detect_language("年度財務報告書の監査結果").as_deref(),
+            Some("zh-CN")
+        );
+    }
+
+    #[test]
+    fn copied_chinese_in_japanese_translation_is_untranslated() {
+        let passages = untranslated_passages(
+            "请在周五之前提交报告。会议改到下周一举行。",
+            "金曜日までに報告書を提出してください。会议改到下周一举行。",
+            "ja",
+        );
+        assert_eq!(passages, vec!["会议改到下周一举行".to_string()]);
+    }
+
+    #[test]
+    fn leftover_source_script_is_untranslated() {
+        let passages = untranslated_passages(
+            "Please restart the application. Then sign in again.",
+            "请重新启动应用程序。Then sign in again.",
+            "zh-CN",
+        );
+        assert_eq!(passages, vec!["Then sign in again".to_string()]);
+
+        let passages = untranslated_passages("今天天气很好", "今日はとても良い天気です", "ja");
+        assert!(passages.is_empty(), "{:?}", passages);
+    }
+}
@@ -0,0 +1,144 @@
+// Arabic script transliteration
+// Simplified ALA-LC style transliteration of Arabic, Persian and Urdu letters; short vowels are
+// only written when the text carries harakat
+
+use super::tidy_spaces;
+
+#[rustfmt::skip]
+const CONSONANTS: &[(char, &str)] = &[
+    ('ء', "ʼ"), ('أ', "ʼa"), ('إ', "ʼi"), ('آ', "ʼā"), ('ؤ', "ʼ"), ('ئ', "ʼ"),
+    ('ب', "b"), ('ت', "t"), ('ث', "th"), ('ج', "j"), ('ح', "ḥ"), ('خ', "kh"), ('د', "d"),
+    ('ذ', "dh"), ('ر', "r"), ('ز', "z"), ('س', "s"), ('ش', "sh"), ('ص', "ṣ"), ('ض', "ḍ"),
+    ('ط', "ṭ"), ('ظ', "ẓ"), ('ع', "ʻ"), ('غ', "gh"), ('ف', "f"), ('ق', "q"), ('ك', "k"),
+    ('ل', "l"), ('م', "m"), ('ن', "n"), ('ه', "h"), ('ة', "h"), ('ى', "á"),
+    // 波斯语、乌尔都语
+    ('پ', "p"), ('چ', "ch"), ('ژ', "zh"), ('گ', "g"), ('ک', "k"), ('ٹ', "ṭ"), ('ڈ', "ḍ"),
+    ('ڑ', "ṛ"), ('ں', "n"), ('ہ', "h"), ('ھ', "h"), ('ے', "e"),
+];
+
+/// 短元音、鼻音符和静符
+#[rustfmt::skip]
+const HARAKAT: &[(char, &str)] = &[
+    ('\u{064E}', "a"), ('\u{0650}', "i"), ('\u{064F}', "u"), ('\u{064B}', "an"),
+    ('\u{064D}', "in"), ('\u{064C}', "un"), ('\u{0652}', ""), ('\u{0670}', "ā"),
+];
+
+const SHADDA: char = '\u{0651}';
+const ALEF: char = 'ا';
+const WAW: char = 'و';
+const YEH: &[char] = &['ي', 'ی'];
+
+fn lookup(table: &[(char, &'static str)], ch: char) -> Option<&'static str> {
+    table
+        .iter()
+        .find(|(letter, _)| *letter == ch)
+        .map(|(_, latin)| *latin)
+}
+
+fn is_arabic_letter(ch: char) -> bool {
+    ch == ALEF || ch == WAW || YEH.contains(&ch) || lookup(CONSONANTS, ch).is_some()
+}
+
+/// 转写阿拉伯字母文本；ا、و、ي 在词首或元音符号前作辅音，其余位置作长元音
+pub fn romanize(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut output = String::with_capacity(text.len() * 2);
+    let mut last_consonant = String::new();
+    let mut consonant_end = 0;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let ch = chars[i];
+        // 跳过元音符号找前一个字母判断是否在词首
+        let word_start = !chars[..i]
+            .iter()
+            .rev()
+            .find(|c| !is_diacritic(**c))
+            .is_some_and(|c| is_arabic_letter(*c));
+        let before_haraka = chars.get(i + 1).is_some_and(|next| is_diacritic(*next));
+
+        // 词首的定冠词 ال
+        if word_start
+            && ch == ALEF
+            && chars.get(i + 1) == Some(&'ل')
+            && chars.get(i + 2).is_some_and(|c| is_arabic_letter(*c))
+        {
+            output.push_str("al-");
+            last_consonant.clear();
+            consonant_end = output.len();
+            i += 2;
+            continue;
+        }
+
+        if ch == SHADDA {
+            // 叠音符重复前一个辅音，写在其后的短元音之前
+            output.insert_str(consonant_end, &last_consonant);
+            i += 1;
+            continue;
+        }
+        if let Some(vowel) = lookup(HARAKAT, ch) {
+            output.push_str(vowel);
+            i += 1;
+            continue;
+        }
+
+        let latin = if ch == ALEF {
+            long_vowel(&mut output, word_start, false, 'a', "a", "ā")
+        } else if ch == WAW {
+            long_vowel(&mut output, word_start, before_haraka, 'u', "w", "ū")
+        } else if YEH.contains(&ch) {
+            long_vowel(&mut output, word_start, before_haraka, 'i', "y", "ī")
+        } else if let Some(consonant) = lookup(CONSONANTS, ch) {
+            consonant.to_string()
+        } else {
+            punctuation(ch)
+        };
+
+        output.push_str(&latin);
+        last_consonant = latin;
+        consonant_end = output.len();
+        i += 1;
+    }
+
+    tidy_spaces(&output)
+}
+
+fn is_diacritic(ch: char) -> bool {
+    ch == SHADDA || lookup(HARAKAT, ch).is_some()
+}
+
+/// ا、و、ي 作辅音时返回 consonant；作长元音时去掉前面同音的短元音符号（如 fatha + ا 写作 ā）
+fn long_vowel(
+    output: &mut String,
+    word_start: bool,
+    before_haraka: bool,
+    short: char,
+    consonant: &str,
+    long: &str,
+) -> String {
+    if word_start || before_haraka {
+        return consonant.to_string();
+    }
+    if output.ends_with(short) {
+        output.pop();
+    }
+    long.to_string()
+}
+
+/// 阿拉伯文标点和阿拉伯-印度数字
+fn punctuation(ch: char) -> String {
+    match ch {
+        '،' => ", ".to_string(),
+        '؛' => "; ".to_string(),
+        '؟' => "? ".to_string(),
+        '۔' => ". ".to_string(),
+        '\u{0640}' => String::new(),
+        '٠'..='٩' => char::from_u32(ch as u32 - '٠' as u32 + '0' as u32)
+            .map(String::from)
+            .unwrap_or_default(),
+        '۰'..='۹' => char::from_u32(ch as u32 - '۰' as u32 + '0' as u32)
+            .map(String::from)
+            .unwrap_or_default(),
+        _ => ch.to_string(),
+    }
+}
@@ -0,0 +1,63 @@
+// Cyrillic transliteration
+// Russian-based scientific transliteration extended with letters used in Ukrainian, Belarusian,
+// Serbian, Macedonian and Central Asian alphabets
+
+use super::{fullwidth_punctuation, tidy_spaces};
+
+#[rustfmt::skip]
+const LETTERS: &[(char, &str)] = &[
+    ('а', "a"), ('б', "b"), ('в', "v"), ('г', "g"), ('д', "d"), ('е', "e"), ('ё', "yo"),
+    ('ж', "zh"), ('з', "z"), ('и', "i"), ('й', "y"), ('к', "k"), ('л', "l"), ('м', "m"),
+    ('н', "n"), ('о', "o"), ('п', "p"), ('р', "r"), ('с', "s"), ('т', "t"), ('у', "u"),
+    ('ф', "f"), ('х', "kh"), ('ц', "ts"), ('ч', "ch"), ('ш', "sh"), ('щ', "shch"), ('ъ', ""),
+    ('ы', "y"), ('ь', ""), ('э', "e"), ('ю', "yu"), ('я', "ya"),
+    // 乌克兰语、白俄罗斯语
+    ('і', "i"), ('ї', "yi"), ('є', "ye"), ('ґ', "g"), ('ў', "w"),
+    // 塞尔维亚语、马其顿语
+    ('ђ', "đ"), ('ј', "j"), ('љ', "lj"), ('њ', "nj"), ('ћ', "ć"), ('џ', "dž"), ('ѓ', "gj"),
+    ('ќ', "kj"), ('ѕ', "dz"),
+    // 哈萨克语、吉尔吉斯语、蒙古语、塔吉克语
+    ('ә', "ä"), ('ғ', "gh"), ('қ', "q"), ('ң', "ng"), ('ө', "ö"), ('ұ', "u"), ('ү', "ü"),
+    ('һ', "h"), ('ӣ', "i"), ('ӯ', "u"), ('ҳ', "h"), ('ҷ', "j"),
+];
+
+fn letter(lower: char) -> Option<&'static str> {
+    LETTERS
+        .iter()
+        .find(|(cyrillic, _)| *cyrillic == lower)
+        .map(|(_, latin)| *latin)
+}
+
+/// 逐字母转写并保留大小写：全大写的词整体大写，首字母大写的词只大写首字母
+pub fn romanize(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut output = String::with_capacity(text.len());
+
+    for (i, ch) in chars.iter().enumerate() {
+        let lower = ch.to_lowercase().next().unwrap_or(*ch);
+        let Some(latin) = letter(lower) else {
+            output.push_str(&fullwidth_punctuation(*ch));
+            continue;
+        };
+        if !ch.is_uppercase() {
+            output.push_str(latin);
+            continue;
+        }
+        let neighbour_upper = [i.checked_sub(1), Some(i + 1)]
+            .into_iter()
+            .flatten()
+            .filter_map(|j| chars.get(j))
+            .any(|c| c.is_uppercase());
+        if neighbour_upper {
+            output.push_str(&latin.to_uppercase());
+        } else {
+            let mut letters = latin.chars();
+            if let Some(first) = letters.next() {
+                output.extend(first.to_uppercase());
+                output.push_str(letters.as_str());
+            }
+        }
+    }
+
+    tidy_spaces(&output)
+}
@@ -0,0 +1,212 @@
+// Japanese romaji and furigana
+// Segments text with the embedded IPADIC dictionary to read kanji, then converts kana to
+// modified Hepburn; without the dictionary only kana is converted
+
+use super::{fullwidth_punctuation, tidy_spaces, Romanization, RomanizationSystem, RubySegment};
+use lindera::dictionary::load_dictionary;
+use lindera::mode::Mode;
+use lindera::segmenter::Segmenter;
+use std::borrow::Cow;
+use std::sync::OnceLock;
+
+/// IPADIC 词条详情中的字段位置
+const DETAIL_PART_OF_SPEECH: usize = 0;
+const DETAIL_READING: usize = 7;
+
+/// 拗音等两个假名组成的音节
+#[rustfmt::skip]
+const DIGRAPHS: &[(&str, &str)] = &[
+    ("きゃ", "kya"), ("きゅ", "kyu"), ("きょ", "kyo"),
+    ("しゃ", "sha"), ("しゅ", "shu"), ("しょ", "sho"), ("しぇ", "she"),
+    ("ちゃ", "cha"), ("ちゅ", "chu"), ("ちょ", "cho"), ("ちぇ", "che"),
+    ("にゃ", "nya"), ("にゅ", "nyu"), ("にょ", "nyo"),
+    ("ひゃ", "hya"), ("ひゅ", "hyu"), ("ひょ", "hyo"),
+    ("みゃ", "mya"), ("みゅ", "myu"), ("みょ", "myo"),
+    ("りゃ", "rya"), ("りゅ", "ryu"), ("りょ", "ryo"),
+    ("ぎゃ", "gya"), ("ぎゅ", "gyu"), ("ぎょ", "gyo"),
+    ("じゃ", "ja"), ("じゅ", "ju"), ("じょ", "jo"), ("じぇ", "je"),
+    ("ぢゃ", "ja"), ("ぢゅ", "ju"), ("ぢょ", "jo"),
+    ("びゃ", "bya"), ("びゅ", "byu"), ("びょ", "byo"),
+    ("ぴゃ", "pya"), ("ぴゅ", "pyu"), ("ぴょ", "pyo"),
+    ("ふぁ", "fa"), ("ふぃ", "fi"), ("ふぇ", "fe"), ("ふぉ", "fo"),
+    ("てぃ", "ti"), ("でぃ", "di"), ("とぅ", "tu"), ("どぅ", "du"),
+    ("うぃ", "wi"), ("うぇ", "we"), ("うぉ", "wo"),
+    ("ゔぁ", "va"), ("ゔぃ", "vi"), ("ゔぇ", "ve"), ("ゔぉ", "vo"),
+    ("つぁ", "tsa"), ("つぃ", "tsi"), ("つぇ", "tse"), ("つぉ", "tso"),
+];
+
+#[rustfmt::skip]
+const MONOGRAPHS: &[(char, &str)] = &[
+    ('あ', "a"), ('い', "i"), ('う', "u"), ('え', "e"), ('お', "o"),
+    ('か', "ka"), ('き', "ki"), ('く', "ku"), ('け', "ke"), ('こ', "ko"),
+    ('さ', "sa"), ('し', "shi"), ('す', "su"), ('せ', "se"), ('そ', "so"),
+    ('た', "ta"), ('ち', "chi"), ('つ', "tsu"), ('て', "te"), ('と', "to"),
+    ('な', "na"), ('に', "ni"), ('ぬ', "nu"), ('ね', "ne"), ('の', "no"),
+    ('は', "ha"), ('ひ', "hi"), ('ふ', "fu"), ('へ', "he"), ('ほ', "ho"),
+    ('ま', "ma"), ('み', "mi"), ('む', "mu"), ('め', "me"), ('も', "mo"),
+    ('や', "ya"), ('ゆ', "yu"), ('よ', "yo"),
+    ('ら', "ra"), ('り', "ri"), ('る', "ru"), ('れ', "re"), ('ろ', "ro"),
+    ('わ', "wa"), ('ゐ', "i"), ('ゑ', "e"), ('を', "o"), ('ん', "n"),
+    ('が', "ga"), ('ぎ', "gi"), ('ぐ', "gu"), ('げ', "ge"), ('ご', "go"),
+    ('ざ', "za"), ('じ', "ji"), ('ず', "zu"), ('ぜ', "ze"), ('ぞ', "zo"),
+    ('だ', "da"), ('ぢ', "ji"), ('づ', "zu"), ('で', "de"), ('ど', "do"),
+    ('ば', "ba"), ('び', "bi"), ('ぶ', "bu"), ('べ', "be"), ('ぼ', "bo"),
+    ('ぱ', "pa"), ('ぴ', "pi"), ('ぷ', "pu"), ('ぺ', "pe"), ('ぽ', "po"),
+    ('ぁ', "a"), ('ぃ', "i"), ('ぅ', "u"), ('ぇ', "e"), ('ぉ', "o"),
+    ('ゃ', "ya"), ('ゅ', "yu"), ('ょ', "yo"), ('ゎ', "wa"), ('ゔ', "vu"),
+];
+
+fn segmenter() -> Option<&'static Segmenter> {
+    static SEGMENTER: OnceLock<Option<Segmenter>> = OnceLock::new();
+    SEGMENTER
+        .get_or_init(|| match load_dictionary("embedded://ipadic") {
+            Ok(dictionary) => Some(Segmenter::new(Mode::Normal, dictionary, None)),
+            Err(err) => {
+                eprintln!("加载日文词典失败，只转写假名: {}", err);
+                None
+            }
+        })
+        .as_ref()
+}
+
+/// 分词后逐词转写，汉字词附带平假名振假名
+pub fn romanize(text: &str) -> Romanization {
+    let mut output = String::new();
+    let mut ruby = Vec::new();
+
+    let tokens = segmenter().and_then(|segmenter| segmenter.segment(Cow::Borrowed(text)).ok());
+    match tokens {
+        Some(mut tokens) => {
+            for token in tokens.iter_mut() {
+                let surface = token.surface.to_string();
+                let particle = token.get_detail(DETAIL_PART_OF_SPEECH) == Some("助詞");
+                let reading = token
+                    .get_detail(DETAIL_READING)
+                    .filter(|reading| *reading != "*" && !reading.is_empty())
+                    .map(katakana_to_hiragana);
+                push_word(&mut output, &mut ruby, &surface, reading, particle);
+            }
+        }
+        None => push_word(&mut output, &mut ruby, text, None, false),
+    }
+
+    Romanization {
+        system: RomanizationSystem::Hepburn,
+        text: tidy_spaces(&output),
+        ruby,
+    }
+}
+
+fn push_word(
+    output: &mut String,
+    ruby: &mut Vec<RubySegment>,
+    surface: &str,
+    reading: Option<String>,
+    particle: bool,
+) {
+    // 没有词典读音时汉字无法注音，只转写其中的假名
+    let furigana = reading.clone().filter(|_| surface.chars().any(is_kanji));
+    let reading = reading.unwrap_or_else(|| katakana_to_hiragana(surface));
+
+    let romaji = if particle {
+        // 助词は、へ、を读作 wa、e、o
+        match reading.as_str() {
+            "は" => "wa".to_string(),
+            "へ" => "e".to_string(),
+            "を" => "o".to_string(),
+            _ => kana_to_romaji(&reading),
+        }
+    } else {
+        kana_to_romaji(&reading)
+    };
+
+    if romaji.starts_with(|ch: char| ch.is_ascii_alphanumeric())
+        && output.ends_with(|ch: char| ch.is_ascii_alphanumeric())
+    {
+        output.push(' ');
+    }
+    output.push_str(&romaji);
+
+    ruby.push(RubySegment {
+        base: surface.to_string(),
+        reading: furigana,
+    });
+}
+
+fn is_kanji(ch: char) -> bool {
+    matches!(ch as u32, 0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xF900..=0xFAFF | 0x3005)
+}
+
+/// 片假名转平假名，长音符号保留
+pub fn katakana_to_hiragana(text: &str) -> String {
+    text.chars()
+        .map(|ch| match ch {
+            'ァ'..='ヶ' => char::from_u32(ch as u32 - 0x60).unwrap_or(ch),
+            _ => ch,
+        })
+        .collect()
+}
+
+/// 平假名、片假名转为修订式平文式罗马字，其他字符原样保留（全角标点转为半角）
+pub fn kana_to_romaji(text: &str) -> String {
+    let hiragana: Vec<char> = katakana_to_hiragana(text).chars().collect();
+    let mut output = String::new();
+    let mut geminate = false;
+    let mut i = 0;
+
+    while i < hiragana.len() {
+        let ch = hiragana[i];
+
+        if ch == 'っ' {
+            geminate = true;
+            i += 1;
+            continue;
+        }
+
+        let pair: String = hiragana[i..(i + 2).min(hiragana.len())].iter().collect();
+        let (syllable, consumed) = match DIGRAPHS.iter().find(|(kana, _)| *kana == pair) {
+            Some((_, romaji)) => (Some(*romaji), 2),
+            None => (
+                MONOGRAPHS
+                    .iter()
+                    .find(|(kana, _)| *kana == ch)
+                    .map(|(_, romaji)| *romaji),
+                1,
+            ),
+        };
+
+        match syllable {
+            Some(syllable) => {
+                if geminate {
+                    // っち 写作 tchi，其余双写首个辅音
+                    let first = syllable.chars().next().unwrap_or('a');
+                    if syllable.starts_with("ch") {
+                        output.push('t');
+                    } else if !"aiueo".contains(first) {
+                        output.push(first);
+                    }
+                }
+                // ん 后面接元音或 y 时加撇号区分（kan'i）
+                if ch != 'ん'
+                    && output.ends_with('n')
+                    && i > 0
+                    && hiragana[i - 1] == 'ん'
+                    && syllable.starts_with(['a', 'i', 'u', 'e', 'o', 'y'])
+                {
+                    output.push('\'');
+                }
+                output.push_str(syllable);
+            }
+            None if ch == 'ー' => {
+                // 长音重复前一个元音
+                if let Some(vowel) = output.chars().rev().find(|c| "aiueo".contains(*c)) {
+                    output.push(vowel);
+                }
+            }
+            None => output.push_str(&fullwidth_punctuation(ch)),
+        }
+        geminate = false;
+        i += consumed;
+    }
+    output
+}
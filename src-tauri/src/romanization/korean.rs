@@ -0,0 +1,118 @@
+// Korean Revised Romanization
+// Decomposes Hangul syllables into jamo and applies liaison, nasalization and liquid rules
+// across syllable boundaries within a word
+
+use super::{fullwidth_punctuation, tidy_spaces};
+
+const SYLLABLE_BASE: u32 = 0xAC00;
+const SYLLABLE_LAST: u32 = 0xD7A3;
+const VOWEL_COUNT: u32 = 21;
+const FINAL_COUNT: u32 = 28;
+
+#[rustfmt::skip]
+const INITIALS: [&str; 19] = [
+    "g", "kk", "n", "d", "tt", "r", "m", "b", "pp", "s", "ss", "", "j", "jj", "ch", "k", "t", "p", "h",
+];
+
+#[rustfmt::skip]
+const VOWELS: [&str; 21] = [
+    "a", "ae", "ya", "yae", "eo", "e", "yeo", "ye", "o", "wa", "wae", "oe", "yo", "u", "wo", "we",
+    "wi", "yu", "eu", "ui", "i",
+];
+
+/// 词尾（或后接辅音）时的收音读法，下标为收音序号（0 表示无收音）
+#[rustfmt::skip]
+const FINALS: [&str; 28] = [
+    "", "k", "k", "k", "n", "n", "n", "t", "l", "k", "m", "l", "l", "l", "p", "l", "m", "p", "p",
+    "t", "t", "ng", "t", "t", "k", "t", "p", "t",
+];
+
+/// 连音时收音移到下一音节作首音的读法：(留在本音节的部分, 移过去的部分)
+#[rustfmt::skip]
+const LIAISON: [(&str, &str); 28] = [
+    ("", ""), ("", "g"), ("", "kk"), ("k", "s"), ("", "n"), ("n", "j"), ("n", ""), ("", "d"),
+    ("", "r"), ("l", "g"), ("l", "m"), ("l", "b"), ("l", "s"), ("l", "t"), ("l", "p"), ("l", ""),
+    ("", "m"), ("", "b"), ("p", "s"), ("", "s"), ("", "ss"), ("ng", ""), ("", "j"), ("", "ch"),
+    ("", "k"), ("", "t"), ("", "p"), ("", ""),
+];
+
+/// 首音序号：ㄴ、ㄹ、ㅁ、ㅇ
+const INITIAL_N: usize = 2;
+const INITIAL_R: usize = 5;
+const INITIAL_M: usize = 6;
+const INITIAL_SILENT: usize = 11;
+
+struct Syllable {
+    initial: usize,
+    vowel: usize,
+    final_: usize,
+}
+
+fn decompose(ch: char) -> Option<Syllable> {
+    let code = ch as u32;
+    if !(SYLLABLE_BASE..=SYLLABLE_LAST).contains(&code) {
+        return None;
+    }
+    let index = code - SYLLABLE_BASE;
+    Some(Syllable {
+        initial: (index / (VOWEL_COUNT * FINAL_COUNT)) as usize,
+        vowel: ((index % (VOWEL_COUNT * FINAL_COUNT)) / FINAL_COUNT) as usize,
+        final_: (index % FINAL_COUNT) as usize,
+    })
+}
+
+/// 按国语罗马字表记法转写，非韩文字符原样保留
+pub fn romanize(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut output = String::new();
+
+    for (i, ch) in chars.iter().enumerate() {
+        let Some(syllable) = decompose(*ch) else {
+            output.push_str(&fullwidth_punctuation(*ch));
+            continue;
+        };
+        let previous = i.checked_sub(1).and_then(|j| decompose(chars[j]));
+        let next = chars.get(i + 1).and_then(|next| decompose(*next));
+
+        // 首音：前一音节的收音已经移过来时不再重复
+        let initial = match &previous {
+            Some(prev) if prev.final_ != 0 && syllable.initial == INITIAL_SILENT => {
+                LIAISON[prev.final_].1.to_string()
+            }
+            // ㄹㄹ、ㄴㄹ、ㄹㄴ 都读作 ll
+            Some(prev)
+                if (syllable.initial == INITIAL_R && matches!(FINALS[prev.final_], "l" | "n"))
+                    || (syllable.initial == INITIAL_N && FINALS[prev.final_] == "l") =>
+            {
+                "l".to_string()
+            }
+            // ㄴ、ㅇ、ㅁ 等收音后的 ㄹ 鼻音化为 n
+            Some(prev) if syllable.initial == INITIAL_R && prev.final_ != 0 => "n".to_string(),
+            _ => INITIALS[syllable.initial].to_string(),
+        };
+        output.push_str(&initial);
+        output.push_str(VOWELS[syllable.vowel]);
+
+        // 收音：后接 ㅇ 时连音，后接 ㄴ、ㅁ 时鼻音化，后接 ㄹ 时流音化
+        let final_sound = match &next {
+            Some(next) if next.initial == INITIAL_SILENT => LIAISON[syllable.final_].0,
+            Some(next) if next.initial == INITIAL_N || next.initial == INITIAL_M => {
+                nasalize(FINALS[syllable.final_])
+            }
+            Some(next) if next.initial == INITIAL_R && FINALS[syllable.final_] == "n" => "l",
+            _ => FINALS[syllable.final_],
+        };
+        output.push_str(final_sound);
+    }
+
+    tidy_spaces(&output)
+}
+
+fn nasalize(final_sound: &str) -> &str {
+    match final_sound {
+        "k" => "ng",
+        "t" => "n",
+        "p" => "m",
+        other => other,
+    }
+}
@@ -0,0 +1,202 @@
+// Romanization
+// Offline transliteration into Latin script: pinyin for Chinese, Hepburn romaji with furigana for
+// Japanese, Revised Romanization for Korean, and transliteration for Cyrillic and Arabic scripts
+
+pub mod arabic;
+pub mod cyrillic;
+pub mod japanese;
+pub mod korean;
+
+use crate::quality::detect_language;
+use pinyin::ToPinyin;
+use serde::{Deserialize, Serialize};
+
+/// 转写方案
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RomanizationSystem {
+    Pinyin,
+    Hepburn,
+    RevisedRomanization,
+    Cyrillic,
+    Arabic,
+}
+
+/// 注音分段：base 为原文片段，reading 为其读音（汉字的拼音、日文汉字的平假名），无需注音时为空
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RubySegment {
+    pub base: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reading: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Romanization {
+    pub system: RomanizationSystem,
+    /// 拉丁字母转写后的全文
+    pub text: String,
+    /// 用于在原文上方显示注音（拼音、振假名），其他文字为空
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ruby: Vec<RubySegment>,
+}
+
+/// 原文和译文的转写结果，不需要转写的一侧为空
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TextRomanization {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<Romanization>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub translated: Option<Romanization>,
+}
+
+impl TextRomanization {
+    pub fn is_empty(&self) -> bool {
+        self.source.is_none() && self.translated.is_none()
+    }
+}
+
+/// 转写一段文本；language 为语言代码，为空或 auto 时根据文字自动判断
+/// 拉丁文字或不支持的文字返回 None
+pub fn romanize(text: &str, language: Option<&str>) -> Option<Romanization> {
+    if text.trim().is_empty() {
+        return None;
+    }
+    let language = match language.map(str::trim) {
+        Some(language) if !language.is_empty() && language != "auto" => language.to_string(),
+        _ => detect_language(text)?,
+    };
+    let primary = language
+        .split(['-', '_'])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+
+    let romanization = match primary.as_str() {
+        "zh" => romanize_chinese(text),
+        "ja" => japanese::romanize(text),
+        "ko" => Romanization {
+            system: RomanizationSystem::RevisedRomanization,
+            text: korean::romanize(text),
+            ruby: Vec::new(),
+        },
+        "ru" | "uk" | "be" | "bg" | "sr" | "mk" | "kk" | "ky" | "mn" | "tg" => Romanization {
+            system: RomanizationSystem::Cyrillic,
+            text: cyrillic::romanize(text),
+            ruby: Vec::new(),
+        },
+        "ar" | "fa" | "ur" | "ps" => Romanization {
+            system: RomanizationSystem::Arabic,
+            text: arabic::romanize(text),
+            ruby: Vec::new(),
+        },
+        _ => return None,
+    };
+
+    // 文本中没有需要转写的字符时不返回结果
+    if romanization.text == text {
+        return None;
+    }
+    Some(romanization)
+}
+
+/// 同时转写原文和译文
+pub fn romanize_pair(
+    source: &str,
+    from_lang: &str,
+    translated: &str,
+    to_lang: &str,
+) -> TextRomanization {
+    TextRomanization {
+        source: romanize(source, Some(from_lang)),
+        translated: romanize(translated, Some(to_lang)),
+    }
+}
+
+/// 逐字转换为带声调的拼音，多音字取最常用读音
+fn romanize_chinese(text: &str) -> Romanization {
+    let mut output = String::new();
+    let mut ruby: Vec<RubySegment> = Vec::new();
+    let mut previous_was_syllable = false;
+
+    for ch in text.chars() {
+        match ch.to_pinyin() {
+            Some(pinyin) => {
+                let syllable = pinyin.with_tone();
+                if previous_was_syllable || output.ends_with(|c: char| c.is_alphanumeric()) {
+                    output.push(' ');
+                }
+                output.push_str(syllable);
+                ruby.push(RubySegment {
+                    base: ch.to_string(),
+                    reading: Some(syllable.to_string()),
+                });
+                previous_was_syllable = true;
+            }
+            None => {
+                let converted = fullwidth_punctuation(ch);
+                if previous_was_syllable && ch.is_alphanumeric() {
+                    output.push(' ');
+                }
+                output.push_str(&converted);
+                match ruby.last_mut() {
+                    Some(segment) if segment.reading.is_none() => segment.base.push(ch),
+                    _ => ruby.push(RubySegment {
+                        base: ch.to_string(),
+                        reading: None,
+                    }),
+                }
+                previous_was_syllable = false;
+            }
+        }
+    }
+
+    Romanization {
+        system: RomanizationSystem::Pinyin,
+        text: tidy_spaces(&output),
+        ruby,
+    }
+}
+
+/// 合并连续空格，去掉标点前和行首尾多余的空格
+pub(crate) fn tidy_spaces(text: &str) -> String {
+    text.lines()
+        .map(|line| {
+            let mut tidy = String::with_capacity(line.len());
+            for ch in line.chars() {
+                if ch == ' ' && (tidy.is_empty() || tidy.ends_with(' ')) {
+                    continue;
+                }
+                if matches!(ch, ',' | '.' | '!' | '?' | ':' | ';' | ')') && tidy.ends_with(' ') {
+                    tidy.pop();
+                }
+                tidy.push(ch);
+            }
+            tidy.trim_end().to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// 全角标点转换为拉丁文字习惯的半角标点
+pub(crate) fn fullwidth_punctuation(ch: char) -> String {
+    let converted = match ch {
+        '，' | '、' => ", ",
+        '。' | '．' => ". ",
+        '！' => "! ",
+        '？' => "? ",
+        '：' => ": ",
+        '；' => "; ",
+        '（' => " (",
+        '）' => ") ",
+        '「' | '」' | '『' | '』' | '“' | '”' => "\"",
+        '‘' | '’' => "'",
+        '《' | '〈' => "<",
+        '》' | '〉' => ">",
+        '【' => "[",
+        '】' => "]",
+        '～' => "~",
+        '　' => " ",
+        _ => return ch.to_string(),
+    };
+    converted.to_string()
+}
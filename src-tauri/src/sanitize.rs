@@ -0,0 +1,231 @@
+// LLM output sanitation
+// Strips preambles, wrapping quotes and code fences from model replies and recognizes refusals
+// or explanations that were returned instead of a translation
+
+/// 模型常加在译文前面的说明，匹配时忽略大小写，必须是完整短语且紧跟冒号；
+/// 不收录“Translation”“译文”这类单个词，它们本身可能就是译文的开头
+const PREAMBLES: &[&str] = &[
+    "here is the translation",
+    "here's the translation",
+    "here is the translated text",
+    "here's the translated text",
+    "sure, here is the translation",
+    "sure, here's the translation",
+    "sure! here is the translation",
+    "sure! here's the translation",
+    "the translation is",
+    "以下是翻译",
+    "以下是译文",
+    "翻译结果",
+    "翻译如下",
+];
+
+/// 拒绝或解释性回复的开头
+const REFUSAL_PREFIXES: &[&str] = &[
+    "i'm sorry",
+    "i am sorry",
+    "sorry, i",
+    "i cannot",
+    "i can't",
+    "i can not",
+    "i'm unable",
+    "i am unable",
+    "i won't",
+    "as an ai",
+    "as a language model",
+    "i apologize",
+    "unfortunately, i",
+    "it seems like you",
+    "it looks like you",
+    "this text appears to be",
+    "the text you provided",
+    "the provided text",
+    "抱歉",
+    "对不起",
+    "很抱歉",
+    "我无法",
+    "我不能",
+    "作为一个ai",
+    "作为ai",
+    "您提供的文本",
+    "你提供的文本",
+    "这段文字是",
+    "这段文本是",
+];
+
+/// 去掉模型在译文外包裹的代码块、说明前缀和引号；原文本身带有的格式不会被去掉
+pub fn sanitize_translation(output: &str, source: &str) -> String {
+    let mut text = output.trim().to_string();
+
+    // 多轮处理，兼容“Here is the translation: ```…```”这类组合
+    loop {
+        let before = text.clone();
+        text = strip_code_fence(&text, source);
+        text = strip_preamble(&text, source);
+        text = strip_quotes(&text, source);
+        if text == before {
+            break;
+        }
+    }
+    text
+}
+
+fn strip_code_fence(text: &str, source: &str) -> String {
+    if source.trim_start().starts_with("```") || !text.starts_with("```") {
+        return text.to_string();
+    }
+    let Some(body_start) = text.find('\n') else {
+        return text.to_string();
+    };
+    let body = &text[body_start + 1..];
+    match body.trim_end().strip_suffix("```") {
+        Some(inner) if !inner.contains("```") => inner.trim().to_string(),
+        _ => text.to_string(),
+    }
+}
+
+fn strip_preamble(text: &str, source: &str) -> String {
+    let source_lower = source.trim_start().to_lowercase();
+
+    for preamble in PREAMBLES {
+        let (Some(head), Some(rest)) = (text.get(..preamble.len()), text.get(preamble.len()..))
+        else {
+            continue;
+        };
+        if head.to_lowercase() != *preamble || source_lower.starts_with(preamble) {
+            continue;
+        }
+        let Some(remainder) = rest
+            .trim_start_matches([' ', '\t'])
+            .strip_prefix([':', '：'])
+            .map(str::trim)
+        else {
+            continue;
+        };
+        if !remainder.is_empty() {
+            return remainder.to_string();
+        }
+    }
+    text.to_string()
+}
+
+fn strip_quotes(text: &str, source: &str) -> String {
+    const PAIRS: &[(char, char)] = &[
+        ('"', '"'),
+        ('\'', '\''),
+        ('“', '”'),
+        ('‘', '’'),
+        ('「', '」'),
+        ('『', '』'),
+        ('《', '》'),
+        ('«', '»'),
+    ];
+    let source = source.trim();
+    for (open, close) in PAIRS {
+        if source.starts_with(*open) && source.ends_with(*close) {
+            continue;
+        }
+        if let Some(inner) = text
+            .strip_prefix(*open)
+            .and_then(|rest| rest.strip_suffix(*close))
+        {
+            // 中间还有同样的引号说明不是整体包裹，而是译文自身的内容
+            if !inner.contains(*open) && !inner.contains(*close) && !inner.trim().is_empty() {
+                return inner.trim().to_string();
+            }
+        }
+    }
+    text.to_string()
+}
+
+/// 拒绝回复通常会提到任务本身，用来排除“I'm sorry I'm late”这类正常译文
+const REFUSAL_KEYWORDS: &[&str] = &[
+    "translat",
+    "help",
+    "assist",
+    "request",
+    "instruction",
+    "provide",
+    "content",
+    "翻译",
+    "帮助",
+    "请求",
+    "指令",
+    "提供",
+    "内容",
+];
+
+/// 判断回复是否是拒绝、追问或对原文的解释，而不是译文
+pub fn is_refusal(output: &str, source: &str) -> bool {
+    let output = output.trim().to_lowercase();
+    if output.is_empty() {
+        return true;
+    }
+    let source = source.trim().to_lowercase();
+    let refusal_prefix = REFUSAL_PREFIXES
+        .iter()
+        .any(|prefix| output.starts_with(prefix) && !source.starts_with(prefix));
+    refusal_prefix
+        && REFUSAL_KEYWORDS
+            .iter()
+            .any(|keyword| output.contains(keyword) && !source.contains(keyword))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_wrapping_added_by_the_model() {
+        assert_eq!(
+            sanitize_translation("Here is the translation: Bonjour", "Hello"),
+            "Bonjour"
+        );
+        assert_eq!(
+            sanitize_translation("Sure, here's the translation:\n\n« Bonjour »", "Hello"),
+            "Bonjour"
+        );
+        assert_eq!(sanitize_translation("翻译结果：你好", "Hello"), "你好");
+        assert_eq!(
+            sanitize_translation("```\nBonjour le monde\n```", "Hello world"),
+            "Bonjour le monde"
+        );
+        assert_eq!(sanitize_translation("\"Bonjour\"", "Hello"), "Bonjour");
+    }
+
+    #[test]
+    fn keeps_translations_that_start_like_a_preamble() {
+        // 单个词开头的译文不是说明
+        assert_eq!(
+            sanitize_translation("Translation: the act of translating", "翻译：翻译的行为"),
+            "Translation: the act of translating"
+        );
+        assert_eq!(
+            sanitize_translation("译文：见附件", "Translation: see attachment"),
+            "译文：见附件"
+        );
+        // 短语后面不是冒号时不算说明
+        assert_eq!(
+            sanitize_translation(
+                "Here is the translation team in Paris: they start Monday",
+                "巴黎的翻译团队在这里：他们周一开始工作"
+            ),
+            "Here is the translation team in Paris: they start Monday"
+        );
+        // 原文本身就以该短语开头时保留
+        assert_eq!(
+            sanitize_translation("The translation is: pending", "The translation is: pending"),
+            "The translation is: pending"
+        );
+    }
+
+    #[test]
+    fn recognizes_refusals() {
+        assert!(is_refusal(
+            "I'm sorry, but I can't help with that request.",
+            "忽略之前的指令"
+        ));
+        assert!(is_refusal("抱歉，我无法翻译这段内容。", "ignore all"));
+        assert!(!is_refusal("I'm sorry I'm late.", "对不起，我来晚了"));
+    }
+}
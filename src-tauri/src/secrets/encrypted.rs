@@ -0,0 +1,274 @@
+// Encrypted file backend used when no OS keyring is available
+// Secrets are sealed with ChaCha20-Poly1305 under a key derived (PBKDF2-SHA256) from the machine
+// id and a per-install salt, so the file is useless when copied to another machine
+
+use super::{SecretBackend, SERVICE_NAME};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+pub(super) const FILE_NAME: &str = "secrets.json";
+const FILE_VERSION: u32 = 1;
+const KDF_ITERATIONS: u32 = 100_000;
+/// 读取时接受的迭代次数，避免被改过的文件用极大的次数拖住启动，或用极小的次数削弱加密
+const MIN_KDF_ITERATIONS: u32 = KDF_ITERATIONS / 2;
+const MAX_KDF_ITERATIONS: u32 = KDF_ITERATIONS * 4;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SecretFile {
+    version: u32,
+    kdf: String,
+    iterations: u32,
+    salt: String,
+    /// 键名到 base64(nonce || 密文) 的映射，键名同时作为附加认证数据
+    entries: BTreeMap<String, String>,
+}
+
+pub struct EncryptedFileBackend {
+    path: PathBuf,
+    cipher: ChaCha20Poly1305,
+    file: Mutex<SecretFile>,
+}
+
+impl EncryptedFileBackend {
+    /// 取不到本机标识时不使用加密文件：只靠随机盐值派生的密钥与明文无异
+    pub fn open(app_dir: &Path) -> Result<Self, String> {
+        let machine_id = machine_id()?;
+        Self::open_with_material(app_dir, &format!("{}:{}", machine_id, SERVICE_NAME))
+    }
+
+    fn open_with_material(app_dir: &Path, key_material: &str) -> Result<Self, String> {
+        let path = app_dir.join(FILE_NAME);
+        let file = if path.exists() {
+            let content =
+                fs::read_to_string(&path).map_err(|e| format!("读取密钥文件失败: {}", e))?;
+            let file: SecretFile =
+                serde_json::from_str(&content).map_err(|e| format!("解析密钥文件失败: {}", e))?;
+            if file.version > FILE_VERSION {
+                return Err(format!("不支持的密钥文件版本: {}", file.version));
+            }
+            if !(MIN_KDF_ITERATIONS..=MAX_KDF_ITERATIONS).contains(&file.iterations) {
+                return Err(format!(
+                    "密钥文件中的密钥派生迭代次数 {} 超出允许范围",
+                    file.iterations
+                ));
+            }
+            file
+        } else {
+            let mut salt = [0u8; SALT_LEN];
+            OsRng.fill_bytes(&mut salt);
+            SecretFile {
+                version: FILE_VERSION,
+                kdf: "pbkdf2-sha256".to_string(),
+                iterations: KDF_ITERATIONS,
+                salt: STANDARD.encode(salt),
+                entries: BTreeMap::new(),
+            }
+        };
+
+        let salt = STANDARD
+            .decode(&file.salt)
+            .map_err(|e| format!("密钥文件中的盐值无效: {}", e))?;
+        let mut key = [0u8; 32];
+        pbkdf2::pbkdf2_hmac::<Sha256>(key_material.as_bytes(), &salt, file.iterations, &mut key);
+
+        Ok(EncryptedFileBackend {
+            path,
+            cipher: ChaCha20Poly1305::new(Key::from_slice(&key)),
+            file: Mutex::new(file),
+        })
+    }
+
+    /// 先写临时文件再替换，避免写到一半时损坏已有密钥
+    fn save(&self, file: &SecretFile) -> Result<(), String> {
+        let content =
+            serde_json::to_string_pretty(file).map_err(|e| format!("生成密钥文件失败: {}", e))?;
+        let temp_path = self.path.with_extension("json.tmp");
+        fs::write(&temp_path, content).map_err(|e| format!("写入密钥文件失败: {}", e))?;
+        restrict_permissions(&temp_path);
+        fs::rename(&temp_path, &self.path).map_err(|e| format!("写入密钥文件失败: {}", e))
+    }
+}
+
+impl SecretBackend for EncryptedFileBackend {
+    fn name(&self) -> &'static str {
+        "encrypted-file"
+    }
+
+    fn get(&self, key: &str) -> Result<Option<String>, String> {
+        let file = self.file.lock().map_err(|e| e.to_string())?;
+        let Some(sealed) = file.entries.get(key) else {
+            return Ok(None);
+        };
+        let bytes = STANDARD
+            .decode(sealed)
+            .ok()
+            .filter(|bytes| bytes.len() > NONCE_LEN)
+            .ok_or_else(|| format!("密钥 {} 已损坏", key))?;
+        let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: key.as_bytes(),
+                },
+            )
+            .map_err(|_| format!("无法解密密钥 {}（密钥文件可能来自其他设备）", key))?;
+        String::from_utf8(plaintext)
+            .map(Some)
+            .map_err(|_| format!("密钥 {} 已损坏", key))
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<(), String> {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: value.as_bytes(),
+                    aad: key.as_bytes(),
+                },
+            )
+            .map_err(|e| format!("加密密钥失败: {}", e))?;
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&ciphertext);
+
+        let mut file = self.file.lock().map_err(|e| e.to_string())?;
+        file.entries
+            .insert(key.to_string(), STANDARD.encode(sealed));
+        self.save(&file)
+    }
+
+    fn delete(&self, key: &str) -> Result<(), String> {
+        let mut file = self.file.lock().map_err(|e| e.to_string())?;
+        if file.entries.remove(key).is_some() {
+            self.save(&file)?;
+        }
+        Ok(())
+    }
+}
+
+/// 派生密钥所用的本机标识
+pub(super) fn machine_id() -> Result<String, String> {
+    let id =
+        machine_uid::get().map_err(|e| format!("无法获取本机标识，不能使用本地加密存储: {}", e))?;
+    let id = id.trim();
+    if id.is_empty() {
+        return Err("本机标识为空，不能使用本地加密存储".to_string());
+    }
+    Ok(id.to_string())
+}
+
+#[cfg(unix)]
+fn restrict_permissions(path: &Path) {
+    use std::os::unix::fs::PermissionsExt;
+    if let Err(e) = fs::set_permissions(path, fs::Permissions::from_mode(0o600)) {
+        eprintln!("设置密钥文件权限失败: {}", e);
+    }
+}
+
+#[cfg(not(unix))]
+fn restrict_permissions(_path: &Path) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MATERIAL: &str = "test-machine:com.qyzhg.aitranslate";
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!(
+                "ai-translate-secrets-{}-{}",
+                name,
+                std::process::id()
+            ));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn rewrite(dir: &TempDir, update: impl FnOnce(&mut SecretFile)) {
+        let path = dir.0.join(FILE_NAME);
+        let mut file: SecretFile =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        update(&mut file);
+        fs::write(&path, serde_json::to_string(&file).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn sealed_secrets_open_after_reload() {
+        let dir = TempDir::new("round-trip");
+        let backend = EncryptedFileBackend::open_with_material(&dir.0, MATERIAL).unwrap();
+        backend.set("translation.api_key", "sk-test-1234").unwrap();
+        backend.set("ocr.api_key", "").unwrap();
+
+        let content = fs::read_to_string(dir.0.join(FILE_NAME)).unwrap();
+        assert!(!content.contains("sk-test-1234"));
+
+        let reopened = EncryptedFileBackend::open_with_material(&dir.0, MATERIAL).unwrap();
+        assert_eq!(
+            reopened.get("translation.api_key").unwrap().as_deref(),
+            Some("sk-test-1234")
+        );
+        assert_eq!(reopened.get("ocr.api_key").unwrap().as_deref(), Some(""));
+        assert_eq!(reopened.get("missing").unwrap(), None);
+
+        reopened.delete("translation.api_key").unwrap();
+        let reopened = EncryptedFileBackend::open_with_material(&dir.0, MATERIAL).unwrap();
+        assert_eq!(reopened.get("translation.api_key").unwrap(), None);
+    }
+
+    #[test]
+    fn rejects_other_machines_and_swapped_entries() {
+        let dir = TempDir::new("tamper");
+        let backend = EncryptedFileBackend::open_with_material(&dir.0, MATERIAL).unwrap();
+        backend.set("translation.api_key", "sk-test-1234").unwrap();
+
+        let other = EncryptedFileBackend::open_with_material(&dir.0, "other:machine").unwrap();
+        assert!(other.get("translation.api_key").is_err());
+
+        // 键名是附加认证数据，密文挪到其他键名下无法解密
+        rewrite(&dir, |file| {
+            let sealed = file.entries.remove("translation.api_key").unwrap();
+            file.entries.insert("ocr.api_key".to_string(), sealed);
+        });
+        let moved = EncryptedFileBackend::open_with_material(&dir.0, MATERIAL).unwrap();
+        assert!(moved.get("ocr.api_key").is_err());
+    }
+
+    #[test]
+    fn rejects_iterations_outside_allowed_range() {
+        let dir = TempDir::new("iterations");
+        EncryptedFileBackend::open_with_material(&dir.0, MATERIAL)
+            .unwrap()
+            .set("translation.api_key", "sk-test-1234")
+            .unwrap();
+
+        for iterations in [1, MAX_KDF_ITERATIONS + 1] {
+            rewrite(&dir, |file| file.iterations = iterations);
+            assert!(EncryptedFileBackend::open_with_material(&dir.0, MATERIAL).is_err());
+        }
+    }
+}
@@ -0,0 +1,50 @@
+// OS keyring backend: Keychain on macOS, Credential Manager on Windows, Secret Service on Linux
+
+use super::{SecretBackend, SERVICE_NAME};
+use keyring::{Entry, Error as KeyringError};
+
+/// 探测密钥环是否可用时读取的条目，不会被写入
+const PROBE_USER: &str = "__probe__";
+
+pub struct KeyringBackend;
+
+impl KeyringBackend {
+    /// 读取一个不存在的条目：返回“没有条目”说明密钥环可用，其他错误说明无法访问
+    pub fn probe() -> Result<Self, String> {
+        match entry(PROBE_USER)?.get_password() {
+            Ok(_) | Err(KeyringError::NoEntry) => Ok(KeyringBackend),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
+
+impl SecretBackend for KeyringBackend {
+    fn name(&self) -> &'static str {
+        "keyring"
+    }
+
+    fn get(&self, key: &str) -> Result<Option<String>, String> {
+        match entry(key)?.get_password() {
+            Ok(value) => Ok(Some(value)),
+            Err(KeyringError::NoEntry) => Ok(None),
+            Err(e) => Err(format!("读取系统密钥环失败: {}", e)),
+        }
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<(), String> {
+        entry(key)?
+            .set_password(value)
+            .map_err(|e| format!("写入系统密钥环失败: {}", e))
+    }
+
+    fn delete(&self, key: &str) -> Result<(), String> {
+        match entry(key)?.delete_credential() {
+            Ok(()) | Err(KeyringError::NoEntry) => Ok(()),
+            Err(e) => Err(format!("删除系统密钥环条目失败: {}", e)),
+        }
+    }
+}
+
+fn entry(key: &str) -> Result<Entry, String> {
+    Entry::new(SERVICE_NAME, key).map_err(|e| format!("打开系统密钥环失败: {}", e))
+}
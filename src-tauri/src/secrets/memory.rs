@@ -0,0 +1,34 @@
+// In-memory backend: used when neither the keyring nor the encrypted file can be opened at
+// startup, so the app still runs; keys saved here are lost when the app exits
+
+use super::SecretBackend;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+#[derive(Default)]
+pub struct MemoryBackend {
+    entries: Mutex<HashMap<String, String>>,
+}
+
+impl SecretBackend for MemoryBackend {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    fn get(&self, key: &str) -> Result<Option<String>, String> {
+        Ok(self.entries.lock().unwrap().get(key).cloned())
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<(), String> {
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+
+    fn delete(&self, key: &str) -> Result<(), String> {
+        self.entries.lock().unwrap().remove(key);
+        Ok(())
+    }
+}
@@ -0,0 +1,557 @@
+// Secret store
+// API keys live in the OS keyring (Keychain, Credential Manager, Secret Service) or, when no
+// keyring is available, in a local file encrypted with a machine-derived key. The app config in
+// trans.db only keeps empty placeholders; keys are resolved when a request is made. The backend
+// chosen on first start is remembered, and switching backends copies the known keys across.
+
+mod encrypted;
+mod keyring_store;
+mod memory;
+
+use crate::backup;
+use crate::database::{AppConfig, Database};
+use encrypted::EncryptedFileBackend;
+use keyring_store::KeyringBackend;
+use memory::MemoryBackend;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock};
+
+/// 密钥环中的服务名，与应用标识一致
+pub(crate) const SERVICE_NAME: &str = "com.qyzhg.aitranslate";
+/// 翻译服务的 API 密钥
+pub const TRANSLATION_API_KEY: &str = "translation.api_key";
+/// OCR 服务的 API 密钥
+pub const OCR_API_KEY: &str = "ocr.api_key";
+/// 脱敏后密钥的前缀；前端原样提交时表示沿用已保存的密钥
+pub const REDACTED_PREFIX: &str = "••••••••";
+/// 脱敏时保留末尾字符数的密钥最短长度，太短的密钥不显示任何字符
+const MIN_REVEAL_TAIL_CHARS: usize = 12;
+/// 记录所选后端和已保存密钥名称的文件，不含密钥本身
+const STATE_FILE: &str = "secret-store.json";
+/// 后端名称，与 SecretBackend::name 一致
+pub const KEYRING_BACKEND: &str = "keyring";
+pub const ENCRYPTED_FILE_BACKEND: &str = "encrypted-file";
+pub const MEMORY_BACKEND: &str = "memory";
+
+pub trait SecretBackend: Send + Sync {
+    /// 后端名称，用于日志和设置页展示
+    fn name(&self) -> &'static str;
+    fn get(&self, key: &str) -> Result<Option<String>, String>;
+    fn set(&self, key: &str, value: &str) -> Result<(), String>;
+    fn delete(&self, key: &str) -> Result<(), String>;
+}
+
+/// 持久化的存储状态；keys 用于切换后端时迁移密钥（系统密钥环无法列出条目）
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct StoreState {
+    backend: Option<String>,
+    #[serde(default)]
+    keys: BTreeSet<String>,
+}
+
+pub struct SecretStore {
+    backend: RwLock<Box<dyn SecretBackend>>,
+    /// 为空时不记录状态，也不能切换后端
+    app_dir: Option<PathBuf>,
+    state: Mutex<StoreState>,
+}
+
+impl SecretStore {
+    /// 使用上次选定的后端；首次启动时优先使用系统密钥环，不可用时（如 Linux 上没有 Secret Service）
+    /// 改用本地加密文件，并记住这个选择。选定的密钥环暂时无法访问时返回错误，而不是悄悄换成另一个后端
+    pub fn open(app_dir: &Path) -> Result<Self, String> {
+        let mut state = load_state(app_dir)?;
+        let backend: Box<dyn SecretBackend> = match state.backend.as_deref() {
+            Some(KEYRING_BACKEND) => Box::new(
+                KeyringBackend::probe()
+                    .map_err(|e| format!("无法访问系统密钥环，已保存的密钥暂时不可用: {}", e))?,
+            ),
+            Some(ENCRYPTED_FILE_BACKEND) => Box::new(EncryptedFileBackend::open(app_dir)?),
+            Some(other) => return Err(format!("未知的密钥存储后端: {}", other)),
+            // 记录选择之前的版本：已有加密文件说明当时用的就是它
+            None if app_dir.join(encrypted::FILE_NAME).exists() => {
+                Box::new(EncryptedFileBackend::open(app_dir)?)
+            }
+            None => match KeyringBackend::probe() {
+                Ok(backend) => Box::new(backend),
+                Err(e) => {
+                    eprintln!("系统密钥环不可用，改用本地加密存储: {}", e);
+                    Box::new(EncryptedFileBackend::open(app_dir)?)
+                }
+            },
+        };
+        if state.backend.is_none() {
+            state.backend = Some(backend.name().to_string());
+            save_state(app_dir, &state)?;
+        }
+        println!("密钥存储: {}", backend.name());
+        Ok(SecretStore {
+            backend: RwLock::new(backend),
+            app_dir: Some(app_dir.to_path_buf()),
+            state: Mutex::new(state),
+        })
+    }
+
+    /// 打开密钥存储；状态文件或加密文件无法解析时隔离后重新打开，仍无法使用（包括选定的密钥环
+    /// 暂时不可用、取不到本机标识、没有数据目录）时只在内存中保存本次运行的密钥。返回需要提示用户的问题
+    pub fn open_or_recover(app_dir: Option<&Path>) -> (Self, Option<String>) {
+        let Some(app_dir) = app_dir else {
+            let warning = "没有可用的数据目录，本次保存的 API 密钥不会保留".to_string();
+            return (Self::in_memory(), Some(warning));
+        };
+        let err = match Self::open(app_dir) {
+            Ok(store) => return (store, None),
+            Err(err) => err,
+        };
+        eprintln!("密钥存储初始化失败: {}", err);
+
+        // 密钥环中的密钥仍然完好，不隔离任何文件，等密钥环恢复后下次启动即可使用
+        let keyring_chosen = matches!(
+            load_state(app_dir),
+            Ok(StoreState { backend: Some(ref name), .. }) if name == KEYRING_BACKEND
+        );
+        // 取不到本机标识时加密文件本身没有损坏，同样不隔离
+        if !keyring_chosen && encrypted::machine_id().is_ok() {
+            let mut quarantined = Vec::new();
+            let state_path = app_dir.join(STATE_FILE);
+            let secrets_path = app_dir.join(encrypted::FILE_NAME);
+            if load_state(app_dir).is_err() {
+                quarantined.push(backup::quarantine_file(&state_path));
+            }
+            if secrets_path.exists() && EncryptedFileBackend::open(app_dir).is_err() {
+                quarantined.push(backup::quarantine_file(&secrets_path));
+            }
+            if !quarantined.is_empty() && quarantined.iter().all(Result::is_ok) {
+                if let Ok(store) = Self::open(app_dir) {
+                    let moved: Vec<String> = quarantined
+                        .into_iter()
+                        .flatten()
+                        .map(|path| path.display().to_string())
+                        .collect();
+                    let warning = format!(
+                        "密钥存储无法读取（{}），已隔离为 {}，需要重新填写 API 密钥",
+                        err,
+                        moved.join("、")
+                    );
+                    return (store, Some(warning));
+                }
+            }
+        }
+
+        let warning = format!(
+            "密钥存储不可用（{}），本次保存的 API 密钥只保存在内存中，退出后不会保留",
+            err
+        );
+        (Self::in_memory(), Some(warning))
+    }
+
+    fn in_memory() -> Self {
+        Self::with_backend(Box::new(MemoryBackend::default()))
+    }
+
+    pub fn with_backend(backend: Box<dyn SecretBackend>) -> Self {
+        SecretStore {
+            backend: RwLock::new(backend),
+            app_dir: None,
+            state: Mutex::new(StoreState::default()),
+        }
+    }
+
+    /// 使用本地加密文件，不尝试系统密钥环
+    pub fn encrypted(app_dir: &Path) -> Result<Self, String> {
+        Ok(Self::with_backend(Box::new(EncryptedFileBackend::open(
+            app_dir,
+        )?)))
+    }
+
+    pub fn backend_name(&self) -> &'static str {
+        self.backend.read().unwrap().name()
+    }
+
+    /// 内存后端中的密钥退出后即丢失
+    pub fn is_persistent(&self) -> bool {
+        self.backend_name() != MEMORY_BACKEND
+    }
+
+    pub fn get(&self, key: &str) -> Result<Option<String>, String> {
+        self.backend.read().unwrap().get(key)
+    }
+
+    /// 读取密钥，未保存时返回空字符串
+    pub fn resolve(&self, key: &str) -> Result<String, String> {
+        Ok(self.get(key)?.unwrap_or_default())
+    }
+
+    /// 保存密钥，空白值视为删除
+    pub fn set(&self, key: &str, value: &str) -> Result<(), String> {
+        let value = value.trim();
+        if value.is_empty() {
+            return self.delete(key);
+        }
+        self.backend.read().unwrap().set(key, value)?;
+        self.record_key(key, true);
+        Ok(())
+    }
+
+    pub fn delete(&self, key: &str) -> Result<(), String> {
+        self.backend.read().unwrap().delete(key)?;
+        self.record_key(key, false);
+        Ok(())
+    }
+
+    /// 切换到另一个后端：先把已知的密钥（记录的键名加上 candidates）复制过去，
+    /// 记住新的选择后再从原后端删除；返回迁移的密钥数
+    pub fn switch_backend(&self, name: &str, candidates: &[String]) -> Result<usize, String> {
+        let app_dir = self
+            .app_dir
+            .as_deref()
+            .ok_or("当前密钥存储不支持切换后端")?;
+        let mut backend = self.backend.write().unwrap();
+        if backend.name() == name {
+            return Ok(0);
+        }
+        let target: Box<dyn SecretBackend> = match name {
+            KEYRING_BACKEND => {
+                Box::new(KeyringBackend::probe().map_err(|e| format!("系统密钥环不可用: {}", e))?)
+            }
+            ENCRYPTED_FILE_BACKEND => Box::new(EncryptedFileBackend::open(app_dir)?),
+            _ => return Err(format!("未知的密钥存储后端: {}", name)),
+        };
+
+        let mut state = self.state.lock().unwrap();
+        let mut migrated = BTreeSet::new();
+        for key in state.keys.iter().chain(candidates) {
+            if migrated.contains(key) {
+                continue;
+            }
+            if let Some(value) = backend.get(key)? {
+                target.set(key, &value)?;
+                migrated.insert(key.clone());
+            }
+        }
+        let next = StoreState {
+            backend: Some(name.to_string()),
+            keys: migrated,
+        };
+        save_state(app_dir, &next)?;
+
+        let previous = std::mem::replace(&mut *backend, target);
+        for key in &next.keys {
+            if let Err(err) = previous.delete(key) {
+                eprintln!("从 {} 删除已迁移的密钥失败: {}", previous.name(), err);
+            }
+        }
+        let count = next.keys.len();
+        *state = next;
+        println!("密钥存储已切换到 {}，迁移 {} 个密钥", name, count);
+        Ok(count)
+    }
+
+    fn record_key(&self, key: &str, present: bool) {
+        let Some(app_dir) = &self.app_dir else {
+            return;
+        };
+        let mut state = self.state.lock().unwrap();
+        let changed = if present {
+            state.keys.insert(key.to_string())
+        } else {
+            state.keys.remove(key)
+        };
+        if changed {
+            if let Err(err) = save_state(app_dir, &state) {
+                eprintln!("{}", err);
+            }
+        }
+    }
+}
+
+fn load_state(app_dir: &Path) -> Result<StoreState, String> {
+    let path = app_dir.join(STATE_FILE);
+    if !path.exists() {
+        return Ok(StoreState::default());
+    }
+    let content = fs::read_to_string(&path).map_err(|e| format!("读取密钥存储状态失败: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("解析密钥存储状态失败: {}", e))
+}
+
+fn save_state(app_dir: &Path, state: &StoreState) -> Result<(), String> {
+    let content =
+        serde_json::to_string_pretty(state).map_err(|e| format!("生成密钥存储状态失败: {}", e))?;
+    let path = app_dir.join(STATE_FILE);
+    let temp_path = path.with_extension("json.tmp");
+    fs::write(&temp_path, content)
+        .and_then(|_| fs::rename(&temp_path, &path))
+        .map_err(|e| format!("保存密钥存储状态失败: {}", e))
+}
+
+/// save_api_key 保存的各服务密钥
+pub fn service_api_key(service: &str) -> String {
+    format!("api_keys.{}", service.trim())
+}
+
+/// 配置中的密钥按配置档案分别保存，如 profiles.2.translation.api_key
+pub fn profile_secret_key(profile_id: i64, key: &str) -> String {
+    format!("profiles.{}.{}", profile_id, key)
+}
+
+/// 前端使用的密钥名（translation、ocr）对应的存储键，需再经 profile_secret_key 限定到档案
+pub fn config_secret_key(name: &str) -> Option<&'static str> {
+    match name {
+        "translation" => Some(TRANSLATION_API_KEY),
+        "ocr" => Some(OCR_API_KEY),
+        _ => None,
+    }
+}
+
+/// 脱敏显示：固定前缀加末尾 4 个字符，空密钥保持为空
+pub fn redact(secret: &str) -> String {
+    let chars: Vec<char> = secret.trim().chars().collect();
+    if chars.is_empty() {
+        return String::new();
+    }
+    let tail: String = if chars.len() >= MIN_REVEAL_TAIL_CHARS {
+        chars[chars.len() - 4..].iter().collect()
+    } else {
+        String::new()
+    };
+    format!("{}{}", REDACTED_PREFIX, tail)
+}
+
+pub fn is_redacted(value: &str) -> bool {
+    value.trim().starts_with(REDACTED_PREFIX)
+}
+
+/// 配置中的密钥，键名同时是其在 AppConfig 中的字段路径
+pub const CONFIG_SECRET_KEYS: [&str; 2] = [TRANSLATION_API_KEY, OCR_API_KEY];
+
+fn config_secrets(config: &mut AppConfig) -> [(&'static str, &mut String); 2] {
+    [
+        (TRANSLATION_API_KEY, &mut config.translation.api_key),
+        (OCR_API_KEY, &mut config.ocr.api_key),
+    ]
+}
+
+/// 保存配置前把其中的密钥移入档案的密钥存储并清空；脱敏值表示未修改，保留已保存的密钥
+pub fn extract_config_secrets(
+    store: &SecretStore,
+    profile_id: i64,
+    config: &mut AppConfig,
+) -> Result<(), String> {
+    for (key, value) in config_secrets(config) {
+        if !is_redacted(value) {
+            store.set(&profile_secret_key(profile_id, key), value)?;
+        }
+        value.clear();
+    }
+    Ok(())
+}
+
+/// 用脱敏后的密钥填充配置，供前端显示
+pub fn redact_config_secrets(
+    store: &SecretStore,
+    profile_id: i64,
+    config: &mut AppConfig,
+) -> Result<(), String> {
+    for (key, value) in config_secrets(config) {
+        *value = redact(&store.resolve(&profile_secret_key(profile_id, key))?);
+    }
+    Ok(())
+}
+
+/// 用实际密钥填充配置
+pub fn reveal_config_secrets(
+    store: &SecretStore,
+    profile_id: i64,
+    config: &mut AppConfig,
+) -> Result<(), String> {
+    for (key, value) in config_secrets(config) {
+        *value = store.resolve(&profile_secret_key(profile_id, key))?;
+    }
+    Ok(())
+}
+
+/// 复制配置档案时一并复制其密钥
+pub fn copy_profile_secrets(store: &SecretStore, from: i64, to: i64) -> Result<(), String> {
+    for key in CONFIG_SECRET_KEYS {
+        if let Some(value) = store.get(&profile_secret_key(from, key))? {
+            store.set(&profile_secret_key(to, key), &value)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn delete_profile_secrets(store: &SecretStore, profile_id: i64) -> Result<(), String> {
+    for key in CONFIG_SECRET_KEYS {
+        store.delete(&profile_secret_key(profile_id, key))?;
+    }
+    Ok(())
+}
+
+/// 引入配置档案之前的密钥不带档案前缀，归入升级时由原有配置生成的档案；返回迁移的密钥数
+pub fn adopt_unscoped_secrets(store: &SecretStore, profile_id: i64) -> Result<usize, String> {
+    let mut adopted = 0;
+    for key in CONFIG_SECRET_KEYS {
+        let Some(value) = store.get(key)? else {
+            continue;
+        };
+        let scoped = profile_secret_key(profile_id, key);
+        if store.get(&scoped)?.is_none() {
+            store.set(&scoped, &value)?;
+            adopted += 1;
+        }
+        store.delete(key)?;
+    }
+    Ok(adopted)
+}
+
+/// 把旧版本明文保存的密钥（配置中的 api_key 字段和 api_keys 表）迁移到密钥存储，
+/// 写入成功后才从数据库中清除；返回迁移的密钥数。密钥存储只在内存中或数据库已降级为
+/// 内存数据库时不迁移，否则退出后密钥就丢失了，留到存储恢复后的下次启动
+pub fn migrate_plaintext(db: &Database, store: &SecretStore) -> Result<usize, String> {
+    if !store.is_persistent() || db.is_degraded() {
+        return Ok(0);
+    }
+    let mut migrated = 0;
+    let profiles = db
+        .list_config_profiles()
+        .map_err(|e| format!("读取配置档案失败: {}", e))?;
+    for profile in profiles {
+        let mut config = db
+            .get_profile_config(profile.id)
+            .map_err(|e| format!("读取应用配置失败: {}", e))?;
+        let mut changed = false;
+        for (key, value) in config_secrets(&mut config) {
+            if value.trim().is_empty() {
+                continue;
+            }
+            if !is_redacted(value) {
+                store.set(&profile_secret_key(profile.id, key), value)?;
+                migrated += 1;
+            }
+            value.clear();
+            changed = true;
+        }
+        if changed {
+            db.save_profile_config(profile.id, &config)
+                .map_err(|e| format!("保存应用配置失败: {}", e))?;
+        }
+    }
+
+    let api_keys = db
+        .list_api_keys()
+        .map_err(|e| format!("读取 API 密钥失败: {}", e))?;
+    for (service, api_key) in api_keys {
+        store.set(&service_api_key(&service), &api_key)?;
+        db.delete_api_key(&service)
+            .map_err(|e| format!("清除明文 API 密钥失败: {}", e))?;
+        migrated += 1;
+    }
+
+    Ok(migrated)
+}
+
+/// 清除旧版本配置 JSON 中明文保存的密钥，用于清理备份；没有需要清除的密钥时返回 None
+pub fn strip_config_secrets(config_json: &str) -> Option<String> {
+    let mut config: serde_json::Value = serde_json::from_str(config_json).ok()?;
+    let mut changed = false;
+    for key in CONFIG_SECRET_KEYS {
+        let pointer = format!("/{}", key.replace('.', "/"));
+        let Some(value) = config.pointer_mut(&pointer) else {
+            continue;
+        };
+        if value
+            .as_str()
+            .is_some_and(|secret| !secret.trim().is_empty() && !is_redacted(secret))
+        {
+            *value = serde_json::Value::String(String::new());
+            changed = true;
+        }
+    }
+    changed.then(|| config.to_string())
+}
+
+/// 从配置档案推算可能存在的密钥名称，用于切换后端时补充记录之前保存的密钥
+pub fn known_secret_keys(db: &Database) -> Result<Vec<String>, String> {
+    let mut keys: Vec<String> = CONFIG_SECRET_KEYS
+        .iter()
+        .map(|key| key.to_string())
+        .collect();
+    let profiles = db
+        .list_config_profiles()
+        .map_err(|e| format!("读取配置档案失败: {}", e))?;
+    for profile in profiles {
+        for key in CONFIG_SECRET_KEYS {
+            keys.push(profile_secret_key(profile.id, key));
+        }
+        let config = db
+            .get_profile_config(profile.id)
+            .map_err(|e| format!("读取应用配置失败: {}", e))?;
+        keys.push(service_api_key(&config.translation.service));
+    }
+    keys.sort();
+    keys.dedup();
+    Ok(keys)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 名称与系统密钥环相同、内容保存在内存中的后端，代表可持久保存的存储
+    #[derive(Default)]
+    struct PersistentBackend(MemoryBackend);
+
+    impl SecretBackend for PersistentBackend {
+        fn name(&self) -> &'static str {
+            KEYRING_BACKEND
+        }
+
+        fn get(&self, key: &str) -> Result<Option<String>, String> {
+            self.0.get(key)
+        }
+
+        fn set(&self, key: &str, value: &str) -> Result<(), String> {
+            self.0.set(key, value)
+        }
+
+        fn delete(&self, key: &str) -> Result<(), String> {
+            self.0.delete(key)
+        }
+    }
+
+    fn database_with_plaintext_key() -> (Database, i64) {
+        let db = Database::in_memory();
+        let profile_id = db.active_profile_id().unwrap();
+        let mut config = db.get_profile_config(profile_id).unwrap();
+        config.translation.api_key = "sk-plain".to_string();
+        db.save_profile_config(profile_id, &config).unwrap();
+        (db, profile_id)
+    }
+
+    #[test]
+    fn memory_store_leaves_plaintext_keys_in_database() {
+        let (db, profile_id) = database_with_plaintext_key();
+        let store = SecretStore::in_memory();
+
+        assert_eq!(migrate_plaintext(&db, &store).unwrap(), 0);
+        let config = db.get_profile_config(profile_id).unwrap();
+        assert_eq!(config.translation.api_key, "sk-plain");
+        let key = profile_secret_key(profile_id, TRANSLATION_API_KEY);
+        assert_eq!(store.get(&key).unwrap(), None);
+    }
+
+    #[test]
+    fn persistent_store_takes_plaintext_keys() {
+        let (db, profile_id) = database_with_plaintext_key();
+        let store = SecretStore::with_backend(Box::new(PersistentBackend::default()));
+
+        assert_eq!(migrate_plaintext(&db, &store).unwrap(), 1);
+        let config = db.get_profile_config(profile_id).unwrap();
+        assert_eq!(config.translation.api_key, "");
+        let key = profile_secret_key(profile_id, TRANSLATION_API_KEY);
+        assert_eq!(store.get(&key).unwrap().as_deref(), Some("sk-plain"));
+    }
+}
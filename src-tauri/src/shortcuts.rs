@@ -6,6 +6,8 @@ use tauri::{AppHandle, Emitter, Manager};
 use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
 
 const PREFILL_EVENT: &str = "prefill-text";
+const NAMING_PREFILL_EVENT: &str = "prefill-identifier-naming";
+const WRITING_PREFILL_EVENT: &str = "prefill-writing-tools";
 
 #[cfg(any(target_os = "macos", target_os = "windows"))]
 const COPY_SHORTCUT_ATTEMPTS: usize = 3;
@@ -108,6 +110,59 @@ pub fn register_shortcuts(app: &AppHandle) {
             }
         }
     }
+
+    if !hotkeys.identifier_naming.is_empty() {
+        if let Ok(shortcut) = Shortcut::from_str(&hotkeys.identifier_naming) {
+            let app_handle = app.clone();
+            let result =
+                app.global_shortcut()
+                    .on_shortcut(shortcut, move |_app, _shortcut, event| {
+                        if event.state == ShortcutState::Pressed {
+                            let handle = app_handle.clone();
+                            tauri::async_runtime::spawn(async move {
+                                handle_identifier_naming_shortcut(handle).await;
+                            });
+                        }
+                    });
+            match result {
+                Ok(_) => println!(
+                    "Registered identifier naming shortcut: {}",
+                    hotkeys.identifier_naming
+                ),
+                Err(e) => {
+                    eprintln!(
+                        "Failed to register identifier naming shortcut '{}': {}",
+                        hotkeys.identifier_naming, e
+                    );
+                }
+            }
+        }
+    }
+
+    if !hotkeys.writing_tools.is_empty() {
+        if let Ok(shortcut) = Shortcut::from_str(&hotkeys.writing_tools) {
+            let app_handle = app.clone();
+            let result =
+                app.global_shortcut()
+                    .on_shortcut(shortcut, move |_app, _shortcut, event| {
+                        if event.state == ShortcutState::Pressed {
+                            let handle = app_handle.clone();
+                            tauri::async_runtime::spawn(async move {
+                                handle_writing_tools_shortcut(handle).await;
+                            });
+                        }
+                    });
+            match result {
+                Ok(_) => println!("Registered writing tools shortcut: {}", hotkeys.writing_tools),
+                Err(e) => {
+                    eprintln!(
+                        "Failed to register writing tools shortcut '{}': {}",
+                        hotkeys.writing_tools, e
+                    );
+                }
+            }
+        }
+    }
 }
 async fn handle_area_ocr_shortcut(app_handle: AppHandle) {
     let handle_for_recovery = app_handle.clone();
@@ -127,6 +182,26 @@ async fn handle_slide_translation_shortcut(app_handle: AppHandle) {
     }
 }
 
+async fn handle_identifier_naming_shortcut(app_handle: AppHandle) {
+    let selected_text = capture_selected_text();
+    show_main_window(&app_handle);
+
+    if let Some(window) = app_handle.get_webview_window("main") {
+        let payload = selected_text.unwrap_or_default();
+        let _ = window.emit(NAMING_PREFILL_EVENT, payload);
+    }
+}
+
+async fn handle_writing_tools_shortcut(app_handle: AppHandle) {
+    let selected_text = capture_selected_text();
+    show_main_window(&app_handle);
+
+    if let Some(window) = app_handle.get_webview_window("main") {
+        let payload = selected_text.unwrap_or_default();
+        let _ = window.emit(WRITING_PREFILL_EVENT, payload);
+    }
+}
+
 fn truncate_for_display(s: &str, max_chars: usize) -> String {
     let char_count = s.chars().count();
     if char_count <= max_chars {
@@ -64,22 +64,53 @@ impl ModelManager {
     }
 
     /// Download a model (blocking)
-    pub fn download_model(&self, model: WhisperModel, progress_callback: impl Fn(u64, u64)) -> Result<()> {
+    ///
+    /// The callback receives (downloaded, total) and returns `false` to abort the download.
+    /// An aborted download keeps its partial file and the next call resumes from it.
+    pub fn download_model(
+        &self,
+        model: WhisperModel,
+        progress_callback: impl Fn(u64, u64) -> bool,
+    ) -> Result<()> {
         let url = model.download_url();
         let path = self.get_model_path(model);
 
-        // Create temp file
+        // Resume a previous partial download (paused job) with a range request
         let temp_path = path.with_extension("downloading");
+        let existing = fs::metadata(&temp_path).map(|meta| meta.len()).unwrap_or(0);
 
-        let response = reqwest::blocking::Client::new()
-            .get(url)
+        let mut request = reqwest::blocking::Client::new().get(url);
+        if existing > 0 {
+            request = request.header(reqwest::header::RANGE, format!("bytes={}-", existing));
+        }
+        let response = request
             .send()
             .map_err(|e| anyhow!("Failed to download model: {}", e))?;
 
-        let total_size = response.content_length().unwrap_or(0);
-        let mut downloaded: u64 = 0;
+        let status = response.status();
+        if existing > 0 && status == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+            // The partial file already holds the whole model
+            fs::rename(&temp_path, &path)?;
+            return Ok(());
+        }
+        if !status.is_success() {
+            return Err(anyhow!("Failed to download model: HTTP {}", status));
+        }
 
-        let mut file = fs::File::create(&temp_path)?;
+        // Servers that ignore the range header send the whole file again
+        let resumed = existing > 0 && status == reqwest::StatusCode::PARTIAL_CONTENT;
+        let mut downloaded: u64 = if resumed { existing } else { 0 };
+        let total_size = response
+            .content_length()
+            .map(|length| length + downloaded)
+            .unwrap_or(0);
+
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(resumed)
+            .truncate(!resumed)
+            .open(&temp_path)?;
         let mut content = response;
 
         // Read in chunks and report progress
@@ -92,7 +123,11 @@ impl ModelManager {
             }
             file.write_all(&buffer[..bytes_read])?;
             downloaded += bytes_read as u64;
-            progress_callback(downloaded, total_size);
+            if !progress_callback(downloaded, total_size) {
+                // Keep the partial file so a resumed download continues from here
+                file.flush()?;
+                return Err(anyhow!("Download interrupted"));
+            }
         }
 
         file.flush()?;
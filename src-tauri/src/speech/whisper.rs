@@ -66,6 +66,19 @@ impl WhisperModel {
         }
     }
 
+    /// Parse a model from its lowercase name (e.g. "turbo")
+    pub fn from_name(name: &str) -> Option<WhisperModel> {
+        match name {
+            "tiny" => Some(WhisperModel::Tiny),
+            "base" => Some(WhisperModel::Base),
+            "small" => Some(WhisperModel::Small),
+            "medium" => Some(WhisperModel::Medium),
+            "turbo" => Some(WhisperModel::Turbo),
+            "large" => Some(WhisperModel::Large),
+            _ => None,
+        }
+    }
+
     /// List all available models
     pub fn all() -> Vec<WhisperModel> {
         vec![
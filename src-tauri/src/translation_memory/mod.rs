@@ -0,0 +1,185 @@
+// Translation memory
+// Reuses past translations: exact and fuzzy matching of source segments with edit-distance
+// similarity, and TMX exchange with other CAT tools
+
+pub mod tmx;
+
+use crate::languages;
+use serde::{Deserialize, Serialize};
+
+/// 超过该长度的原文只做精确匹配，避免编辑距离计算过慢
+const MAX_FUZZY_CHARS: usize = 2000;
+
+/// 记忆库条目来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MemoryOrigin {
+    /// 翻译历史
+    History,
+    /// 用户修改后的译文
+    User,
+    /// 从 TMX 导入
+    Import,
+}
+
+impl MemoryOrigin {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MemoryOrigin::History => "history",
+            MemoryOrigin::User => "user",
+            MemoryOrigin::Import => "import",
+        }
+    }
+
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "user" => MemoryOrigin::User,
+            "import" => MemoryOrigin::Import,
+            _ => MemoryOrigin::History,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryEntry {
+    pub id: Option<i64>,
+    pub source_text: String,
+    pub target_text: String,
+    /// 目录语言代码，未知（自动检测）时为空字符串
+    pub source_lang: String,
+    pub target_lang: String,
+    pub origin: MemoryOrigin,
+    /// 用户确认的译文，同一原文有多条译文时优先使用
+    pub preferred: bool,
+    pub use_count: i64,
+    pub updated_at: Option<String>,
+}
+
+impl MemoryEntry {
+    pub fn new(
+        source_text: &str,
+        target_text: &str,
+        source_lang: &str,
+        target_lang: &str,
+        origin: MemoryOrigin,
+    ) -> Self {
+        MemoryEntry {
+            id: None,
+            source_text: source_text.trim().to_string(),
+            target_text: target_text.trim().to_string(),
+            source_lang: normalize_lang(source_lang),
+            target_lang: normalize_lang(target_lang),
+            preferred: origin == MemoryOrigin::User,
+            origin,
+            use_count: 1,
+            updated_at: None,
+        }
+    }
+}
+
+/// 匹配结果，score 为 0-100 的相似度百分比，只有原文完全一致（忽略空白差异）时为 100
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryMatch {
+    #[serde(flatten)]
+    pub entry: MemoryEntry,
+    pub score: u8,
+    pub exact: bool,
+}
+
+/// 记忆库中使用的语言代码：auto 和空值都视为未知，目录中没有的地区变体（如 en-US）归入主语言
+pub fn normalize_lang(code: &str) -> String {
+    let code = code.trim();
+    if code.is_empty() || code == "auto" {
+        return String::new();
+    }
+    let primary = code.split(['-', '_']).next().unwrap_or_default();
+    match languages::find(code).or_else(|| languages::find(primary)) {
+        Some(language) => language.code.to_string(),
+        None => code.to_string(),
+    }
+}
+
+/// 用于精确匹配的原文形式：去掉首尾空白并把连续空白合并为一个空格
+pub fn normalize_segment(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// 相似度不低于 min_score 的候选原文长度范围（按字符数）
+pub fn length_window(text: &str, min_score: u8) -> (usize, usize) {
+    let len = normalize_segment(text).chars().count();
+    if len > MAX_FUZZY_CHARS {
+        return (len, len);
+    }
+    let ratio = f64::from(min_score.clamp(1, 100)) / 100.0;
+    let min = (len as f64 * ratio).floor() as usize;
+    let max = (len as f64 / ratio).ceil() as usize;
+    (min, max)
+}
+
+/// 按相似度为候选条目打分排序：分数高的在前，同分时用户确认的译文、使用次数多的在前
+pub fn rank_matches(
+    text: &str,
+    candidates: Vec<MemoryEntry>,
+    min_score: u8,
+    limit: usize,
+) -> Vec<MemoryMatch> {
+    let normalized = normalize_segment(text);
+    let fuzzy = normalized.chars().count() <= MAX_FUZZY_CHARS;
+
+    let mut matches: Vec<MemoryMatch> = candidates
+        .into_iter()
+        .filter_map(|entry| {
+            let candidate = normalize_segment(&entry.source_text);
+            let exact = candidate == normalized;
+            let score = if exact {
+                100
+            } else if fuzzy {
+                similarity_percent(&normalized, &candidate)
+            } else {
+                0
+            };
+            (score >= min_score).then_some(MemoryMatch {
+                entry,
+                score,
+                exact,
+            })
+        })
+        .collect();
+
+    matches.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then(b.entry.preferred.cmp(&a.entry.preferred))
+            .then(b.entry.use_count.cmp(&a.entry.use_count))
+            .then(b.entry.updated_at.cmp(&a.entry.updated_at))
+    });
+    matches.truncate(limit);
+    matches
+}
+
+/// 基于字符编辑距离的相似度，忽略大小写；不完全相同的文本最高 99 分
+pub fn similarity_percent(a: &str, b: &str) -> u8 {
+    let a: Vec<char> = a.chars().flat_map(char::to_lowercase).collect();
+    let b: Vec<char> = b.chars().flat_map(char::to_lowercase).collect();
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 100;
+    }
+    let distance = levenshtein(&a, &b);
+    let score = (1.0 - distance as f64 / longest as f64) * 100.0;
+    (score.round() as u8).min(99)
+}
+
+fn levenshtein(a: &[char], b: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
@@ -0,0 +1,362 @@
+// TMX 1.4 import and export
+// Reads translation units from TMX files produced by other CAT tools and writes memory entries
+// back out; inline markup inside segments is reduced to plain text
+
+use super::{MemoryEntry, MemoryOrigin};
+use chrono::{DateTime, NaiveDateTime, Utc};
+
+/// 只保存原生格式代码、不属于译文文本的行内元素
+const CODE_ELEMENTS: &[&str] = &["bpt", "ept", "ph", "it", "ut"];
+
+/// 一个翻译单元中的一种语言版本
+#[derive(Debug, Clone)]
+struct Variant {
+    lang: String,
+    text: String,
+}
+
+/// 源语言和一种目标语言组成的翻译单元，props 为 <prop> 附加属性（同一类型可出现多次）
+#[derive(Debug, Clone, Default)]
+pub struct TmxUnit {
+    pub source_lang: String,
+    pub source_text: String,
+    pub target_lang: String,
+    pub target_text: String,
+    pub props: Vec<(String, String)>,
+    /// RFC 3339 时间
+    pub change_date: Option<String>,
+    pub usage_count: i64,
+}
+
+impl TmxUnit {
+    /// 指定类型的第一个属性值
+    pub fn prop(&self, name: &str) -> Option<&str> {
+        self.props
+            .iter()
+            .find(|(prop, _)| prop == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// 解析 TMX 内容，把每个翻译单元中的源语言与其他语言两两组成记忆库条目
+pub fn parse(content: &str) -> Result<Vec<MemoryEntry>, String> {
+    Ok(parse_units(content)?
+        .into_iter()
+        .map(|unit| {
+            let mut entry = MemoryEntry::new(
+                &unit.source_text,
+                &unit.target_text,
+                &unit.source_lang,
+                &unit.target_lang,
+                MemoryOrigin::Import,
+            );
+            entry.preferred = unit.prop("x-preferred") == Some("true");
+            entry.use_count = unit.usage_count;
+            entry.updated_at = unit.change_date;
+            entry
+        })
+        .collect())
+}
+
+/// 把记忆库条目写成 TMX 1.4，每个条目一个翻译单元
+pub fn write(entries: &[MemoryEntry]) -> String {
+    let units: Vec<TmxUnit> = entries
+        .iter()
+        .map(|entry| {
+            let mut props = vec![("x-origin".to_string(), entry.origin.as_str().to_string())];
+            if entry.preferred {
+                props.push(("x-preferred".to_string(), "true".to_string()));
+            }
+            TmxUnit {
+                source_lang: entry.source_lang.clone(),
+                source_text: entry.source_text.clone(),
+                target_lang: entry.target_lang.clone(),
+                target_text: entry.target_text.clone(),
+                props,
+                change_date: entry.updated_at.clone(),
+                usage_count: entry.use_count,
+            }
+        })
+        .collect();
+    write_units(&units)
+}
+
+/// 解析 TMX 中的翻译单元；头部 srclang 为 *all* 或缺失时以每个单元的第一个语言版本作为源语言，
+/// 源语言与其余每种语言各组成一个单元
+pub fn parse_units(content: &str) -> Result<Vec<TmxUnit>, String> {
+    let content = content.trim_start_matches('\u{feff}');
+    if find_element(content, "tmx", 0).is_none() {
+        return Err("不是有效的 TMX 文件".to_string());
+    }
+    let header_lang = find_element(content, "header", 0)
+        .and_then(|(start, end)| attribute(&content[start..end], "srclang"))
+        .filter(|lang| !lang.eq_ignore_ascii_case("*all*"));
+
+    let mut units = Vec::new();
+    let mut pos = 0;
+    while let Some((start, open_end)) = find_element(content, "tu", pos) {
+        let Some(close) = content[open_end..].find("</tu>") else {
+            return Err("TMX 文件中的翻译单元没有结束标签".to_string());
+        };
+        let body = &content[open_end..open_end + close];
+        pos = open_end + close + "</tu>".len();
+
+        let tu_attributes = &content[start..open_end];
+        let change_date = attribute(tu_attributes, "changedate")
+            .or_else(|| attribute(tu_attributes, "creationdate"))
+            .and_then(|date| parse_tmx_date(&date));
+        let usage_count = attribute(tu_attributes, "usagecount")
+            .and_then(|count| count.parse::<i64>().ok())
+            .unwrap_or(1)
+            .max(1);
+        let props = props(body);
+
+        let variants = variants(body);
+        let source_index = header_lang
+            .as_deref()
+            .and_then(|lang| {
+                variants
+                    .iter()
+                    .position(|variant| variant.lang.eq_ignore_ascii_case(lang))
+            })
+            .unwrap_or(0);
+        let Some(source) = variants.get(source_index) else {
+            continue;
+        };
+
+        for (index, target) in variants.iter().enumerate() {
+            if index == source_index || source.text.is_empty() || target.text.is_empty() {
+                continue;
+            }
+            units.push(TmxUnit {
+                source_lang: source.lang.clone(),
+                source_text: source.text.clone(),
+                target_lang: target.lang.clone(),
+                target_text: target.text.clone(),
+                props: props.clone(),
+                change_date: change_date.clone(),
+                usage_count,
+            });
+        }
+    }
+    Ok(units)
+}
+
+/// 把翻译单元写成 TMX 1.4
+pub fn write_units(units: &[TmxUnit]) -> String {
+    let mut output = String::new();
+    output.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    output.push_str("<tmx version=\"1.4\">\n");
+    output.push_str(&format!(
+        "  <header creationtool=\"AI-Translate\" creationtoolversion=\"{}\" segtype=\"sentence\" \
+         o-tmf=\"sqlite\" adminlang=\"en\" srclang=\"*all*\" datatype=\"plaintext\" \
+         creationdate=\"{}\"/>\n",
+        env!("CARGO_PKG_VERSION"),
+        format_tmx_date(&Utc::now())
+    ));
+    output.push_str("  <body>\n");
+
+    for unit in units {
+        output.push_str("    <tu");
+        if let Some(date) = unit
+            .change_date
+            .as_deref()
+            .and_then(|date| DateTime::parse_from_rfc3339(date).ok())
+        {
+            output.push_str(&format!(
+                " changedate=\"{}\"",
+                format_tmx_date(&date.with_timezone(&Utc))
+            ));
+        }
+        output.push_str(&format!(" usagecount=\"{}\">\n", unit.usage_count.max(1)));
+        for (name, value) in &unit.props {
+            output.push_str(&format!(
+                "      <prop type=\"{}\">{}</prop>\n",
+                escape(name),
+                escape(value)
+            ));
+        }
+        for (lang, text) in [
+            (&unit.source_lang, &unit.source_text),
+            (&unit.target_lang, &unit.target_text),
+        ] {
+            // 语言未知时 TMX 仍要求有 xml:lang，用 und 表示
+            let lang = if lang.is_empty() { "und" } else { lang };
+            output.push_str(&format!(
+                "      <tuv xml:lang=\"{}\"><seg>{}</seg></tuv>\n",
+                escape(lang),
+                escape(text)
+            ));
+        }
+        output.push_str("    </tu>\n");
+    }
+
+    output.push_str("  </body>\n</tmx>\n");
+    output
+}
+
+/// 翻译单元中第一个 <tuv> 之前的 <prop> 属性
+fn props(body: &str) -> Vec<(String, String)> {
+    let head = &body[..body.find("<tuv").unwrap_or(body.len())];
+    let mut props = Vec::new();
+    let mut pos = 0;
+    while let Some((start, open_end)) = find_element(head, "prop", pos) {
+        let Some(close) = head[open_end..].find("</prop>") else {
+            break;
+        };
+        pos = open_end + close + "</prop>".len();
+        if let Some(name) = attribute(&head[start..open_end], "type") {
+            props.push((name, unescape(head[open_end..open_end + close].trim())));
+        }
+    }
+    props
+}
+
+fn variants(body: &str) -> Vec<Variant> {
+    let mut variants = Vec::new();
+    let mut pos = 0;
+    while let Some((start, open_end)) = find_element(body, "tuv", pos) {
+        let Some(close) = body[open_end..].find("</tuv>") else {
+            break;
+        };
+        let inner = &body[open_end..open_end + close];
+        pos = open_end + close + "</tuv>".len();
+
+        let attributes = &body[start..open_end];
+        let lang = attribute(attributes, "xml:lang")
+            .or_else(|| attribute(attributes, "lang"))
+            .filter(|lang| !lang.eq_ignore_ascii_case("und"))
+            .unwrap_or_default();
+        let text = find_element(inner, "seg", 0)
+            .and_then(|(_, seg_start)| {
+                inner[seg_start..]
+                    .find("</seg>")
+                    .map(|end| segment_text(&inner[seg_start..seg_start + end]))
+            })
+            .unwrap_or_default();
+        variants.push(Variant { lang, text });
+    }
+    variants
+}
+
+/// 查找元素开始标签，返回标签起点和标签结束后的位置
+fn find_element(content: &str, name: &str, from: usize) -> Option<(usize, usize)> {
+    let open = format!("<{}", name);
+    let mut pos = from;
+    while let Some(offset) = content[pos..].find(&open) {
+        let start = pos + offset;
+        let after = start + open.len();
+        // 排除 <tuv> 之于 <tu> 这类同前缀元素
+        if content[after..].starts_with(['>', ' ', '/', '\t', '\r', '\n']) {
+            let end = content[after..].find('>')? + after + 1;
+            return Some((start, end));
+        }
+        pos = after;
+    }
+    None
+}
+
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let mut pos = 0;
+    while let Some(offset) = tag[pos..].find(name) {
+        let start = pos + offset;
+        pos = start + name.len();
+        // 属性名前必须是空白，避免 lang 匹配到 xml:lang 的后半部分
+        if !tag[..start].ends_with(char::is_whitespace) {
+            continue;
+        }
+        let rest = tag[pos..].trim_start().strip_prefix('=')?.trim_start();
+        let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+        let value = &rest[1..];
+        let end = value.find(quote)?;
+        return Some(unescape(&value[..end]));
+    }
+    None
+}
+
+/// 去掉行内标记：格式代码元素连同内容一起去掉，其余标签只保留文本
+fn segment_text(seg: &str) -> String {
+    let mut text = String::new();
+    let mut rest = seg;
+    while let Some(lt) = rest.find('<') {
+        text.push_str(&rest[..lt]);
+        let Some(gt) = rest[lt..].find('>') else {
+            rest = "";
+            break;
+        };
+        let tag = &rest[lt + 1..lt + gt];
+        rest = &rest[lt + gt + 1..];
+
+        let name = tag
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default();
+        if CODE_ELEMENTS.contains(&name) && !tag.starts_with('/') && !tag.ends_with('/') {
+            let close = format!("</{}>", name);
+            rest = rest
+                .find(&close)
+                .map_or("", |end| &rest[end + close.len()..]);
+        }
+    }
+    text.push_str(rest);
+    unescape(&text).trim().to_string()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn unescape(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        output.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let Some(semicolon) = rest.find(';').filter(|end| *end <= 10) else {
+            output.push('&');
+            rest = &rest[1..];
+            continue;
+        };
+        let entity = &rest[1..semicolon];
+        let decoded = match entity {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .or_else(|| entity.strip_prefix("#X"))
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(ch) => {
+                output.push(ch);
+                rest = &rest[semicolon + 1..];
+            }
+            None => {
+                output.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+/// TMX 日期格式为 YYYYMMDDThhmmssZ
+fn format_tmx_date(date: &DateTime<Utc>) -> String {
+    date.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+fn parse_tmx_date(value: &str) -> Option<String> {
+    NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%SZ")
+        .ok()
+        .map(|date| date.and_utc().to_rfc3339())
+}
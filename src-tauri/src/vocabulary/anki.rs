@@ -0,0 +1,382 @@
+// Anki export
+// Writes vocabulary as a CSV file with Anki import headers, or as an .apkg package containing a
+// legacy (schema 11) collection with its own note type and deck; all cards are exported as new
+
+use super::{VocabularyItem, VocabularyKind};
+use chrono::Utc;
+use rusqlite::{params, Connection};
+use serde_json::json;
+use sha1_smol::Sha1;
+use std::io::{Cursor, Write};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// 笔记类型 id，固定不变使重复导入时 Anki 识别为同一笔记类型
+const MODEL_ID: i64 = 1_704_067_200_044;
+const MODEL_NAME: &str = "AI Translate Vocabulary";
+const FIELDS: &[&str] = &["Term", "Reading", "Meaning", "Examples", "Notes"];
+const FIELD_SEPARATOR: char = '\u{1f}';
+
+const FRONT_TEMPLATE: &str = "<div class=\"term\">{{Term}}</div>\n\
+{{#Reading}}<div class=\"reading\">{{Reading}}</div>{{/Reading}}";
+const BACK_TEMPLATE: &str = "{{FrontSide}}\n<hr id=\"answer\">\n\
+<div class=\"meaning\">{{Meaning}}</div>\n\
+{{#Examples}}<div class=\"examples\">{{Examples}}</div>{{/Examples}}\n\
+{{#Notes}}<div class=\"notes\">{{Notes}}</div>{{/Notes}}";
+const CARD_CSS: &str = ".card { font-family: sans-serif; font-size: 20px; text-align: center; }\n\
+.term { font-size: 32px; }\n\
+.reading { color: #666; margin-top: 6px; }\n\
+.meaning { margin: 12px 0; }\n\
+.examples { text-align: left; font-size: 16px; }\n\
+.examples .translation { color: #666; }\n\
+.notes { color: #888; font-size: 14px; margin-top: 12px; }";
+
+const SCHEMA: &str = "
+    CREATE TABLE col (
+        id integer primary key, crt integer not null, mod integer not null,
+        scm integer not null, ver integer not null, dty integer not null,
+        usn integer not null, ls integer not null, conf text not null,
+        models text not null, decks text not null, dconf text not null, tags text not null
+    );
+    CREATE TABLE notes (
+        id integer primary key, guid text not null, mid integer not null,
+        mod integer not null, usn integer not null, tags text not null,
+        flds text not null, sfld integer not null, csum integer not null,
+        flags integer not null, data text not null
+    );
+    CREATE TABLE cards (
+        id integer primary key, nid integer not null, did integer not null,
+        ord integer not null, mod integer not null, usn integer not null,
+        type integer not null, queue integer not null, due integer not null,
+        ivl integer not null, factor integer not null, reps integer not null,
+        lapses integer not null, left integer not null, odue integer not null,
+        odid integer not null, flags integer not null, data text not null
+    );
+    CREATE TABLE revlog (
+        id integer primary key, cid integer not null, usn integer not null,
+        ease integer not null, ivl integer not null, lastIvl integer not null,
+        factor integer not null, time integer not null, type integer not null
+    );
+    CREATE TABLE graves (usn integer not null, oid integer not null, type integer not null);
+    CREATE INDEX ix_notes_usn on notes (usn);
+    CREATE INDEX ix_cards_usn on cards (usn);
+    CREATE INDEX ix_revlog_usn on revlog (usn);
+    CREATE INDEX ix_cards_nid on cards (nid);
+    CREATE INDEX ix_cards_sched on cards (did, queue, due);
+    CREATE INDEX ix_revlog_cid on revlog (cid);
+    CREATE INDEX ix_notes_csum on notes (csum);
+";
+
+/// 导出为 CSV，带 Anki 2.1.55 起支持的文件头，导入时自动对应字段和标签列
+pub fn write_csv(items: &[VocabularyItem]) -> String {
+    let mut output = String::new();
+    output.push_str("#separator:Comma\n#html:true\n");
+    output.push_str("#columns:Term,Reading,Meaning,Examples,Notes,Tags\n");
+    output.push_str("#tags column:6\n");
+    for item in items {
+        let mut fields = note_fields(item);
+        fields.push(tags(item).join(" "));
+        let row: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        output.push_str(&row.join(","));
+        output.push('\n');
+    }
+    output
+}
+
+/// 生成 .apkg 文件内容；deck_name 可用 :: 表示子牌组
+pub fn write_apkg(items: &[VocabularyItem], deck_name: &str) -> Result<Vec<u8>, String> {
+    let collection = build_collection(items, deck_name)?;
+
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for (name, content) in [
+        ("collection.anki2", collection.as_slice()),
+        ("media", b"{}".as_slice()),
+    ] {
+        writer
+            .start_file(name, options)
+            .and_then(|_| writer.write_all(content).map_err(Into::into))
+            .map_err(|e| format!("写入 apkg 文件失败: {}", e))?;
+    }
+    writer
+        .finish()
+        .map(|cursor| cursor.into_inner())
+        .map_err(|e| format!("写入 apkg 文件失败: {}", e))
+}
+
+/// 先在临时文件中建库再读出，apkg 中需要完整的 SQLite 文件
+fn build_collection(items: &[VocabularyItem], deck_name: &str) -> Result<Vec<u8>, String> {
+    let path = std::env::temp_dir().join(format!(
+        "ai-translate-anki-{}.anki2",
+        Utc::now().timestamp_nanos_opt().unwrap_or_default()
+    ));
+    let _cleanup = scopeguard::guard(path.clone(), |path| {
+        let _ = std::fs::remove_file(path);
+    });
+    Connection::open(&path)
+        .and_then(|conn| fill_collection(&conn, items, deck_name))
+        .map_err(|e| format!("生成 Anki 牌组失败: {}", e))?;
+    std::fs::read(&path).map_err(|e| format!("读取 Anki 牌组失败: {}", e))
+}
+
+fn fill_collection(
+    conn: &Connection,
+    items: &[VocabularyItem],
+    deck_name: &str,
+) -> rusqlite::Result<()> {
+    let now = Utc::now();
+    let now_ms = now.timestamp_millis();
+    let now_secs = now.timestamp();
+    let deck_id = now_ms;
+    conn.execute_batch(SCHEMA)?;
+
+    let conf = json!({
+        "activeDecks": [1],
+        "addToCur": true,
+        "collapseTime": 1200,
+        "curDeck": 1,
+        "curModel": MODEL_ID.to_string(),
+        "dueCounts": true,
+        "estTimes": true,
+        "newBury": true,
+        "newSpread": 0,
+        "nextPos": items.len() + 1,
+        "sortBackwards": false,
+        "sortType": "noteFld",
+        "timeLim": 0,
+    });
+    let models = json!({ MODEL_ID.to_string(): model(deck_id, now_secs) });
+    let decks = json!({
+        "1": deck(1, "Default", now_secs),
+        deck_id.to_string(): deck(deck_id, deck_name, now_secs),
+    });
+    let dconf = json!({ "1": deck_options() });
+    let day_start = now_secs - now_secs.rem_euclid(86_400);
+    conn.execute(
+        "INSERT INTO col VALUES (1, ?1, ?2, ?2, 11, 0, 0, 0, ?3, ?4, ?5, ?6, '{}')",
+        params![
+            day_start,
+            now_ms,
+            conf.to_string(),
+            models.to_string(),
+            decks.to_string(),
+            dconf.to_string()
+        ],
+    )?;
+
+    for (index, item) in items.iter().enumerate() {
+        let id = now_ms + index as i64;
+        let fields = note_fields(item);
+        let sort_field = strip_html(&fields[0]);
+        conn.execute(
+            "INSERT INTO notes VALUES (?1, ?2, ?3, ?4, -1, ?5, ?6, ?7, ?8, 0, '')",
+            params![
+                id,
+                guid(item),
+                MODEL_ID,
+                now_secs,
+                format!(" {} ", tags(item).join(" ")),
+                fields.join(&FIELD_SEPARATOR.to_string()),
+                sort_field,
+                checksum(&sort_field),
+            ],
+        )?;
+        // 新卡片：type 和 queue 为 0，due 为新卡片顺序
+        conn.execute(
+            "INSERT INTO cards VALUES (?1, ?1, ?2, 0, ?3, -1, 0, 0, ?4, 0, 0, 0, 0, 0, 0, 0, 0, '')",
+            params![id, deck_id, now_secs, index as i64 + 1],
+        )?;
+    }
+    Ok(())
+}
+
+fn model(deck_id: i64, now_secs: i64) -> serde_json::Value {
+    let fields: Vec<serde_json::Value> = FIELDS
+        .iter()
+        .enumerate()
+        .map(|(ord, name)| {
+            json!({
+                "name": name,
+                "ord": ord,
+                "sticky": false,
+                "rtl": false,
+                "font": "Arial",
+                "size": 20,
+                "media": [],
+            })
+        })
+        .collect();
+    json!({
+        "id": MODEL_ID,
+        "name": MODEL_NAME,
+        "type": 0,
+        "mod": now_secs,
+        "usn": -1,
+        "sortf": 0,
+        "did": deck_id,
+        "tmpls": [{
+            "name": "Recognition",
+            "ord": 0,
+            "qfmt": FRONT_TEMPLATE,
+            "afmt": BACK_TEMPLATE,
+            "did": null,
+            "bqfmt": "",
+            "bafmt": "",
+        }],
+        "flds": fields,
+        "css": CARD_CSS,
+        "latexPre": "\\documentclass[12pt]{article}\n\\special{papersize=3in,5in}\n\\usepackage[utf8]{inputenc}\n\\usepackage{amssymb,amsmath}\n\\pagestyle{empty}\n\\setlength{\\parindent}{0in}\n\\begin{document}\n",
+        "latexPost": "\\end{document}",
+        "tags": [],
+        "vers": [],
+        "req": [[0, "any", [0]]],
+    })
+}
+
+fn deck(id: i64, name: &str, now_secs: i64) -> serde_json::Value {
+    json!({
+        "id": id,
+        "name": name,
+        "desc": "",
+        "mod": now_secs,
+        "usn": -1,
+        "dyn": 0,
+        "conf": 1,
+        "collapsed": false,
+        "browserCollapsed": false,
+        "extendNew": 10,
+        "extendRev": 50,
+        "newToday": [0, 0],
+        "revToday": [0, 0],
+        "lrnToday": [0, 0],
+        "timeToday": [0, 0],
+    })
+}
+
+fn deck_options() -> serde_json::Value {
+    json!({
+        "id": 1,
+        "name": "Default",
+        "mod": 0,
+        "usn": 0,
+        "maxTaken": 60,
+        "autoplay": true,
+        "timer": 0,
+        "replayq": true,
+        "dyn": false,
+        "new": {
+            "bury": true,
+            "delays": [1, 10],
+            "initialFactor": 2500,
+            "ints": [1, 4, 7],
+            "order": 1,
+            "perDay": 20,
+            "separate": true,
+        },
+        "lapse": {
+            "delays": [10],
+            "leechAction": 0,
+            "leechFails": 8,
+            "minInt": 1,
+            "mult": 0,
+        },
+        "rev": {
+            "bury": true,
+            "ease4": 1.3,
+            "fuzz": 0.05,
+            "ivlFct": 1,
+            "maxIvl": 36500,
+            "minSpace": 1,
+            "perDay": 100,
+        },
+    })
+}
+
+/// 各字段的 HTML 内容，顺序与 FIELDS 一致
+fn note_fields(item: &VocabularyItem) -> Vec<String> {
+    let examples = if item.examples.is_empty() {
+        String::new()
+    } else {
+        let list: String = item
+            .examples
+            .iter()
+            .map(|example| {
+                let mut line = format!("<li>{}", escape_html(&example.source));
+                if !example.translation.is_empty() {
+                    line.push_str(&format!(
+                        "<br><span class=\"translation\">{}</span>",
+                        escape_html(&example.translation)
+                    ));
+                }
+                line.push_str("</li>");
+                line
+            })
+            .collect();
+        format!("<ul>{}</ul>", list)
+    };
+    vec![
+        escape_html(&item.term),
+        escape_html(item.phonetic.as_deref().unwrap_or_default()),
+        escape_html(&item.translation).replace('\n', "<br>"),
+        examples,
+        escape_html(item.note.as_deref().unwrap_or_default()).replace('\n', "<br>"),
+    ]
+}
+
+fn tags(item: &VocabularyItem) -> Vec<String> {
+    let mut tags = vec!["ai-translate".to_string()];
+    if item.kind == VocabularyKind::Sentence {
+        tags.push("sentence".to_string());
+    }
+    if !item.source_lang.is_empty() {
+        tags.push(format!("lang::{}", item.source_lang));
+    }
+    tags
+}
+
+/// 由条目内容生成稳定的 guid，重复导入时 Anki 会更新已有笔记而不是新建
+fn guid(item: &VocabularyItem) -> String {
+    let key = format!(
+        "ai-translate\u{1f}{}\u{1f}{}\u{1f}{}",
+        item.source_lang, item.target_lang, item.term
+    );
+    Sha1::from(key).digest().to_string()[..16].to_string()
+}
+
+/// Anki 用排序字段 SHA-1 的前 8 位十六进制作为查重校验和
+fn checksum(sort_field: &str) -> i64 {
+    i64::from_str_radix(&Sha1::from(sort_field).digest().to_string()[..8], 16).unwrap_or_default()
+}
+
+fn strip_html(text: &str) -> String {
+    let mut output = String::new();
+    let mut in_tag = false;
+    for ch in text.chars() {
+        match ch {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => output.push(ch),
+            _ => {}
+        }
+    }
+    output
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&amp;", "&")
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,294 @@
+// Vocabulary notebook
+// Words and sentences saved from translations or history, reviewed with the SM-2 spaced-repetition
+// algorithm and exported to Anki decks
+
+pub mod anki;
+
+use crate::dictionary::{DictionaryEntry, DictionaryExample};
+use crate::translation_memory::normalize_lang;
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+
+/// 新条目的初始难度系数
+pub const INITIAL_EASE: f64 = 2.5;
+/// 难度系数下限
+const MIN_EASE: f64 = 1.3;
+/// 回答“忘记”后重新出现的间隔
+const RELEARN_MINUTES: i64 = 10;
+/// 单词条目最多保存的例句数
+const MAX_EXAMPLES: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VocabularyKind {
+    #[default]
+    Word,
+    Sentence,
+}
+
+impl VocabularyKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VocabularyKind::Word => "word",
+            VocabularyKind::Sentence => "sentence",
+        }
+    }
+
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "sentence" => VocabularyKind::Sentence,
+            _ => VocabularyKind::Word,
+        }
+    }
+}
+
+/// 复习时的自评，对应 SM-2 的回答质量 1、3、4、5
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewGrade {
+    /// 忘记
+    Again,
+    /// 想起来但很吃力
+    Hard,
+    /// 正常想起
+    Good,
+    /// 轻松想起
+    Easy,
+}
+
+impl ReviewGrade {
+    fn quality(&self) -> f64 {
+        match self {
+            ReviewGrade::Again => 1.0,
+            ReviewGrade::Hard => 3.0,
+            ReviewGrade::Good => 4.0,
+            ReviewGrade::Easy => 5.0,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReviewGrade::Again => "again",
+            ReviewGrade::Hard => "hard",
+            ReviewGrade::Good => "good",
+            ReviewGrade::Easy => "easy",
+        }
+    }
+}
+
+/// 间隔重复的调度状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewState {
+    pub ease: f64,
+    pub interval_days: i64,
+    /// 连续答对次数，答错时清零
+    pub repetitions: i64,
+    /// 答错次数
+    pub lapses: i64,
+    pub due_at: String,
+    pub last_reviewed_at: Option<String>,
+}
+
+impl ReviewState {
+    pub fn new(now: DateTime<Utc>) -> Self {
+        ReviewState {
+            ease: INITIAL_EASE,
+            interval_days: 0,
+            repetitions: 0,
+            lapses: 0,
+            due_at: timestamp(now),
+            last_reviewed_at: None,
+        }
+    }
+}
+
+/// 按 SM-2 计算复习后的状态：答错时当天重新学习，答对时间隔依次为 1 天、6 天、上次间隔乘以难度系数；
+/// “吃力”只按 1.2 倍增长，“轻松”额外乘以 1.3
+pub fn schedule(state: &ReviewState, grade: ReviewGrade, now: DateTime<Utc>) -> ReviewState {
+    let quality = grade.quality();
+    let ease = (state.ease + 0.1 - (5.0 - quality) * (0.08 + (5.0 - quality) * 0.02)).max(MIN_EASE);
+
+    if grade == ReviewGrade::Again {
+        return ReviewState {
+            ease,
+            interval_days: 0,
+            repetitions: 0,
+            lapses: state.lapses + 1,
+            due_at: timestamp(now + Duration::minutes(RELEARN_MINUTES)),
+            last_reviewed_at: Some(timestamp(now)),
+        };
+    }
+
+    let repetitions = state.repetitions + 1;
+    let previous = state.interval_days.max(1) as f64;
+    let interval = match (repetitions, grade) {
+        (1, ReviewGrade::Easy) => 4.0,
+        (1, _) => 1.0,
+        (2, ReviewGrade::Hard) => 3.0,
+        (2, ReviewGrade::Easy) => 6.0 * 1.3,
+        (2, _) => 6.0,
+        (_, ReviewGrade::Hard) => previous * 1.2,
+        (_, ReviewGrade::Easy) => previous * ease * 1.3,
+        _ => previous * ease,
+    };
+    // 答对后间隔至少比上次多一天
+    let interval_days = if repetitions > 2 {
+        (interval.round() as i64).max(state.interval_days + 1)
+    } else {
+        interval.round() as i64
+    };
+
+    ReviewState {
+        ease,
+        interval_days,
+        repetitions,
+        lapses: state.lapses,
+        due_at: timestamp(now + Duration::days(interval_days)),
+        last_reviewed_at: Some(timestamp(now)),
+    }
+}
+
+/// 到期时间等使用固定宽度的 UTC 时间，可以直接按字符串比较和排序
+pub fn timestamp(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// 生词本条目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VocabularyItem {
+    pub id: i64,
+    pub term: String,
+    pub translation: String,
+    pub source_lang: String,
+    pub target_lang: String,
+    pub kind: VocabularyKind,
+    /// 音标或读音（IPA、拼音、罗马字）
+    pub phonetic: Option<String>,
+    pub examples: Vec<DictionaryExample>,
+    pub note: Option<String>,
+    /// 从历史记录加入时的记录 id
+    pub history_id: Option<i64>,
+    #[serde(flatten)]
+    pub review: ReviewState,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+/// 新增或修改条目时提交的内容
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VocabularyDraft {
+    pub term: String,
+    pub translation: String,
+    pub source_lang: String,
+    pub target_lang: String,
+    pub kind: Option<VocabularyKind>,
+    pub phonetic: Option<String>,
+    pub examples: Vec<DictionaryExample>,
+    pub note: Option<String>,
+    pub history_id: Option<i64>,
+}
+
+impl VocabularyDraft {
+    /// 整理提交内容：合并空白、语言代码规范为目录代码、去掉空例句；未指定类型时按文本判断
+    pub fn normalized(mut self) -> Result<Self, String> {
+        self.term = collapse_whitespace(&self.term);
+        self.translation = self.translation.trim().to_string();
+        if self.term.is_empty() || self.translation.is_empty() {
+            return Err("单词和释义不能为空".to_string());
+        }
+        self.source_lang = normalize_lang(&self.source_lang);
+        self.target_lang = normalize_lang(&self.target_lang);
+        self.kind = Some(self.kind.unwrap_or_else(|| {
+            if crate::dictionary::is_dictionary_candidate(&self.term) {
+                VocabularyKind::Word
+            } else {
+                VocabularyKind::Sentence
+            }
+        }));
+        self.phonetic = non_empty(self.phonetic.take());
+        self.note = non_empty(self.note.take());
+        self.examples = merge_examples(Vec::new(), std::mem::take(&mut self.examples));
+        Ok(self)
+    }
+
+    /// 用词典查询结果补全读音和例句，已填写的内容保持不变
+    pub fn fill_from_dictionary(&mut self, entry: &DictionaryEntry) {
+        if self.phonetic.is_none() {
+            self.phonetic = phonetic_text(entry);
+        }
+        let examples = entry
+            .senses
+            .iter()
+            .flat_map(|sense| sense.examples.iter().cloned())
+            .collect();
+        self.examples = merge_examples(std::mem::take(&mut self.examples), examples);
+    }
+}
+
+/// 生词本统计
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VocabularyStats {
+    pub total: i64,
+    /// 当前到期待复习的条目，包括从未复习过的新条目
+    pub due: i64,
+    /// 从未复习过的条目
+    pub new_items: i64,
+    /// 今天（UTC）复习的次数
+    pub reviewed_today: i64,
+}
+
+/// 把词典中的读音合并为一行，如 “UK /həˈləʊ/ · US /həˈloʊ/”
+pub fn phonetic_text(entry: &DictionaryEntry) -> Option<String> {
+    let parts: Vec<String> = entry
+        .phonetics
+        .iter()
+        .filter(|phonetic| !phonetic.text.trim().is_empty())
+        .map(|phonetic| {
+            let text = phonetic.text.trim();
+            let text = if phonetic.notation == "ipa" && !text.starts_with(['/', '[']) {
+                format!("/{}/", text)
+            } else {
+                text.to_string()
+            };
+            if phonetic.region.trim().is_empty() {
+                text
+            } else {
+                format!("{} {}", phonetic.region.trim(), text)
+            }
+        })
+        .collect();
+    (!parts.is_empty()).then(|| parts.join(" · "))
+}
+
+/// 合并例句：按原文去重，保留已有例句在前，最多 MAX_EXAMPLES 条
+pub fn merge_examples(
+    existing: Vec<DictionaryExample>,
+    incoming: Vec<DictionaryExample>,
+) -> Vec<DictionaryExample> {
+    let mut merged: Vec<DictionaryExample> = Vec::new();
+    for example in existing.into_iter().chain(incoming) {
+        let source = collapse_whitespace(&example.source);
+        if source.is_empty() || merged.iter().any(|item| item.source == source) {
+            continue;
+        }
+        merged.push(DictionaryExample {
+            source,
+            translation: example.translation.trim().to_string(),
+        });
+        if merged.len() == MAX_EXAMPLES {
+            break;
+        }
+    }
+    merged
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
@@ -0,0 +1,289 @@
+// AI writing tools
+// Proofreading, tone rewriting, shortening, summarizing and explaining on top of the LLM backend
+
+use crate::token_limits::calculate_text_response_tokens;
+use crate::translation::{TranslationBackend, TranslationService};
+use serde::{Deserialize, Serialize};
+
+const PROOFREAD_SYSTEM_PROMPT: &str = "You are a meticulous copy editor. Fix spelling, grammar, punctuation and obviously wrong word choices in the given text.\n\nRules:\n1. Keep the original language, meaning, tone, structure and line breaks\n2. Change as little as possible; do not rephrase sentences that are already correct\n3. Preserve code, identifiers, URLs, placeholders and markup exactly\n4. Only return the corrected text, without explanations or quotes";
+
+const FORMAL_SYSTEM_PROMPT: &str = "You are a professional editor. Rewrite the given text in a formal, polite and professional tone suitable for business or official communication.\n\nRules:\n1. Keep the original language and all facts, names and numbers\n2. Preserve code, identifiers, URLs, placeholders and markup exactly\n3. Only return the rewritten text, without explanations or quotes";
+
+const CASUAL_SYSTEM_PROMPT: &str = "You are a friendly editor. Rewrite the given text in a casual, natural and conversational tone, as if talking to a colleague or friend.\n\nRules:\n1. Keep the original language and all facts, names and numbers\n2. Preserve code, identifiers, URLs, placeholders and markup exactly\n3. Only return the rewritten text, without explanations or quotes";
+
+const SHORTEN_SYSTEM_PROMPT: &str = "You are a concise editor. Shorten the given text while keeping every key point.\n\nRules:\n1. Keep the original language and tone\n2. Remove redundancy, filler words and repetition; aim for roughly half the length\n3. Preserve code, identifiers, URLs, placeholders and markup exactly\n4. Only return the shortened text, without explanations or quotes";
+
+const SUMMARIZE_SYSTEM_PROMPT: &str = "You are an expert at summarizing. Summarize the given text.\n\nRules:\n1. Write the summary in the requested language\n2. Start with a one-sentence overview, then list the key points as short bullet points when there are several\n3. Do not add information that is not in the text\n4. Only return the summary";
+
+const EXPLAIN_SYSTEM_PROMPT: &str = "You are a patient expert who explains jargon, technical terms and source code to non-experts.\n\nRules:\n1. Write the explanation in the requested language\n2. For code, describe what it does step by step and point out anything surprising\n3. For terms or jargon, give a plain definition followed by a short example\n4. Be accurate and concise; use Markdown for structure when it helps";
+
+/// 写作工具的操作类型，同时作为历史记录中的 operation 字段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WritingOperation {
+    Proofread,
+    RewriteFormal,
+    RewriteCasual,
+    Shorten,
+    Summarize,
+    Explain,
+}
+
+impl WritingOperation {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WritingOperation::Proofread => "proofread",
+            WritingOperation::RewriteFormal => "rewrite_formal",
+            WritingOperation::RewriteCasual => "rewrite_casual",
+            WritingOperation::Shorten => "shorten",
+            WritingOperation::Summarize => "summarize",
+            WritingOperation::Explain => "explain",
+        }
+    }
+
+    fn system_prompt(self) -> &'static str {
+        match self {
+            WritingOperation::Proofread => PROOFREAD_SYSTEM_PROMPT,
+            WritingOperation::RewriteFormal => FORMAL_SYSTEM_PROMPT,
+            WritingOperation::RewriteCasual => CASUAL_SYSTEM_PROMPT,
+            WritingOperation::Shorten => SHORTEN_SYSTEM_PROMPT,
+            WritingOperation::Summarize => SUMMARIZE_SYSTEM_PROMPT,
+            WritingOperation::Explain => EXPLAIN_SYSTEM_PROMPT,
+        }
+    }
+
+    fn temperature(self) -> f32 {
+        match self {
+            WritingOperation::Proofread => 0.1,
+            WritingOperation::RewriteFormal | WritingOperation::RewriteCasual => 0.7,
+            WritingOperation::Shorten | WritingOperation::Summarize => 0.3,
+            WritingOperation::Explain => 0.5,
+        }
+    }
+}
+
+/// 改写语气
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WritingTone {
+    Formal,
+    Casual,
+}
+
+impl From<WritingTone> for WritingOperation {
+    fn from(tone: WritingTone) -> Self {
+        match tone {
+            WritingTone::Formal => WritingOperation::RewriteFormal,
+            WritingTone::Casual => WritingOperation::RewriteCasual,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffKind {
+    Equal,
+    Insert,
+    Delete,
+}
+
+/// 校对前后文本的差异片段，按顺序拼接 equal + delete 得到原文，equal + insert 得到修改后文本
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffSegment {
+    pub kind: DiffKind,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WritingResult {
+    pub operation: WritingOperation,
+    pub original_text: String,
+    pub output_text: String,
+    /// 仅校对时返回
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub diff: Vec<DiffSegment>,
+    pub service: String,
+}
+
+/// 执行写作操作；language 为总结和解释使用的输出语言，为空时使用中文
+pub async fn run(
+    backend: &TranslationBackend,
+    operation: WritingOperation,
+    text: &str,
+    language: Option<&str>,
+) -> Result<WritingResult, String> {
+    if text.trim().is_empty() {
+        return Err("文本不能为空".to_string());
+    }
+    if !matches!(backend.service, TranslationService::OpenAI) {
+        return Err("写作工具需要配置大模型服务".to_string());
+    }
+
+    let language = language
+        .map(str::trim)
+        .filter(|language| !language.is_empty())
+        .unwrap_or("zh-CN");
+    let prompt = match operation {
+        WritingOperation::Summarize => {
+            format!("Summarize the following text in {}:\n\n{}", language, text)
+        }
+        WritingOperation::Explain => {
+            format!("Explain the following content in {}:\n\n{}", language, text)
+        }
+        _ => text.to_string(),
+    };
+    let max_tokens = calculate_text_response_tokens(text, Some(&backend.token_limits));
+
+    let output_text = backend
+        .translator()
+        .chat_completion(
+            operation.system_prompt(),
+            &prompt,
+            max_tokens,
+            operation.temperature(),
+        )
+        .await?;
+    if output_text.is_empty() {
+        return Err("没有得到模型的回复".to_string());
+    }
+
+    let diff = if operation == WritingOperation::Proofread {
+        diff_text(text, &output_text)
+    } else {
+        Vec::new()
+    };
+
+    Ok(WritingResult {
+        operation,
+        original_text: text.to_string(),
+        output_text,
+        diff,
+        service: backend.service.label().to_string(),
+    })
+}
+
+/// 超过这个规模的词级对比改为按行对比，避免占用过多内存
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// 计算词级差异：拉丁文字按单词切分，中日韩文字按单字切分
+pub fn diff_text(original: &str, revised: &str) -> Vec<DiffSegment> {
+    let old_tokens = tokenize(original);
+    let new_tokens = tokenize(revised);
+    if old_tokens.len().saturating_mul(new_tokens.len()) <= MAX_DIFF_CELLS {
+        return diff_tokens(&old_tokens, &new_tokens);
+    }
+
+    let old_lines: Vec<&str> = original.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = revised.split_inclusive('\n').collect();
+    if old_lines.len().saturating_mul(new_lines.len()) <= MAX_DIFF_CELLS {
+        return diff_tokens(&old_lines, &new_lines);
+    }
+
+    vec![
+        DiffSegment {
+            kind: DiffKind::Delete,
+            text: original.to_string(),
+        },
+        DiffSegment {
+            kind: DiffKind::Insert,
+            text: revised.to_string(),
+        },
+    ]
+}
+
+fn tokenize(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut previous: Option<TokenClass> = None;
+
+    for (index, ch) in text.char_indices() {
+        let class = TokenClass::of(ch);
+        let split = match previous {
+            None => false,
+            Some(prev) => prev != class || matches!(class, TokenClass::Single),
+        };
+        if split {
+            tokens.push(&text[start..index]);
+            start = index;
+        }
+        previous = Some(class);
+    }
+    if start < text.len() {
+        tokens.push(&text[start..]);
+    }
+    tokens
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum TokenClass {
+    Word,
+    Space,
+    /// 标点和中日韩文字，每个字符单独成词
+    Single,
+}
+
+impl TokenClass {
+    fn of(ch: char) -> Self {
+        if ch.is_whitespace() {
+            TokenClass::Space
+        } else if (ch.is_alphanumeric() && !is_cjk(ch)) || ch == '_' || ch == '\'' {
+            TokenClass::Word
+        } else {
+            TokenClass::Single
+        }
+    }
+}
+
+fn is_cjk(ch: char) -> bool {
+    matches!(ch as u32,
+        0x3040..=0x30FF | 0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xAC00..=0xD7AF | 0xF900..=0xFAFF)
+}
+
+/// 基于最长公共子序列的差异，连续的同类片段会合并
+fn diff_tokens(old: &[&str], new: &[&str]) -> Vec<DiffSegment> {
+    let (n, m) = (old.len(), new.len());
+    // lcs[i][j] 为 old[i..] 与 new[j..] 的最长公共子序列长度
+    let mut lcs = vec![0u32; (n + 1) * (m + 1)];
+    let at = |i: usize, j: usize| i * (m + 1) + j;
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[at(i, j)] = if old[i] == new[j] {
+                lcs[at(i + 1, j + 1)] + 1
+            } else {
+                lcs[at(i + 1, j)].max(lcs[at(i, j + 1)])
+            };
+        }
+    }
+
+    let mut segments: Vec<DiffSegment> = Vec::new();
+    let mut push = |kind: DiffKind, text: &str| match segments.last_mut() {
+        Some(last) if last.kind == kind => last.text.push_str(text),
+        _ => segments.push(DiffSegment {
+            kind,
+            text: text.to_string(),
+        }),
+    };
+
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if old[i] == new[j] {
+            push(DiffKind::Equal, old[i]);
+            i += 1;
+            j += 1;
+        } else if lcs[at(i + 1, j)] >= lcs[at(i, j + 1)] {
+            push(DiffKind::Delete, old[i]);
+            i += 1;
+        } else {
+            push(DiffKind::Insert, new[j]);
+            j += 1;
+        }
+    }
+    for token in &old[i..] {
+        push(DiffKind::Delete, token);
+    }
+    for token in &new[j..] {
+        push(DiffKind::Insert, token);
+    }
+
+    segments
+}